@@ -0,0 +1,306 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::path::{Component, Path, PathBuf};
+
+/// Most files published for a single execution.
+pub const MAX_ARTIFACTS: usize = 100;
+
+/// How deep `**` descends below the directory it starts in.
+const MAX_RECURSIVE_DEPTH: usize = 16;
+
+#[derive(Debug, Snafu)]
+pub enum ArtifactError {
+    #[snafu(display(
+        "Invalid output pattern '{}': it must be relative to the working directory and must not contain '..'",
+        pattern
+    ))]
+    InvalidPattern { pattern: String },
+}
+
+pub type Result<T> = std::result::Result<T, ArtifactError>;
+
+/// A file produced by a recipe, published as a resource.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Artifact {
+    pub uri: String,
+    /// Path relative to the working directory
+    pub path: String,
+    pub size: u64,
+    pub mime_type: String,
+}
+
+/// Output patterns are relative paths with `*` and `?` wildcards inside a
+/// path component and `**` for any number of directories.
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    let path = Path::new(pattern);
+    let valid = !pattern.is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    ensure!(valid, InvalidPatternSnafu { pattern });
+    Ok(())
+}
+
+/// Files below `working_dir` matching any of `patterns`, sorted and without
+/// duplicates, at most `MAX_ARTIFACTS`.
+pub fn find_artifacts(working_dir: &Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        validate_pattern(pattern)?;
+        let components: Vec<&str> = pattern
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .collect();
+        collect_matches(working_dir, &components, 0, &mut files);
+    }
+    files.sort();
+    files.dedup();
+    files.truncate(MAX_ARTIFACTS);
+    Ok(files)
+}
+
+fn collect_matches(dir: &Path, pattern: &[&str], depth: usize, files: &mut Vec<PathBuf>) {
+    let Some((first, rest)) = pattern.split_first() else {
+        return;
+    };
+
+    if *first == "**" {
+        // Zero directories, then one more level for each match of the rest
+        collect_matches(dir, rest, depth, files);
+        if depth < MAX_RECURSIVE_DEPTH {
+            let subdirs = entries(dir).filter(|path| {
+                path.is_dir()
+                    && !path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .starts_with('.')
+            });
+            for subdir in subdirs {
+                collect_matches(&subdir, pattern, depth + 1, files);
+            }
+        }
+        return;
+    }
+
+    if !has_wildcard(first) {
+        let path = dir.join(first);
+        if rest.is_empty() {
+            if path.is_file() {
+                files.push(path);
+            }
+        } else if path.is_dir() {
+            collect_matches(&path, rest, depth, files);
+        }
+        return;
+    }
+
+    for path in entries(dir) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if !component_matches(first, &name) {
+            continue;
+        }
+        if rest.is_empty() {
+            if path.is_file() {
+                files.push(path);
+            }
+        } else if path.is_dir() {
+            collect_matches(&path, rest, depth, files);
+        }
+    }
+}
+
+fn entries(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+}
+
+fn has_wildcard(component: &str) -> bool {
+    component.contains(['*', '?'])
+}
+
+/// Match one path component against a pattern with `*` and `?`. Like a
+/// shell, wildcards do not match a leading `.`.
+pub(crate) fn component_matches(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Backtracking match remembering the position of the last `*`
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+/// `file://` URI of an absolute path, percent-encoding reserved characters.
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.display().to_string().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+/// Whether the start of a file looks like text: valid UTF-8, allowing a
+/// character cut off at the end, and no NUL bytes.
+pub fn looks_like_text(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(error) => error.error_len().is_none(),
+    }
+}
+
+/// MIME type from the file extension; unknown extensions are `text/plain`
+/// for UTF-8 content and `application/octet-stream` otherwise.
+pub fn mime_type(path: &Path, is_text: bool) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("txt" | "log") => "text/plain",
+        Some("md") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("csv") => "text/csv",
+        Some("xml") => "application/xml",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz" | "tgz") => "application/gzip",
+        _ if is_text => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Standard base64 with padding, for binary resource contents.
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk.iter().enumerate().fold(0u32, |triple, (i, &byte)| {
+            triple | ((byte as u32) << (16 - 8 * i))
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_component_matches() {
+        assert!(component_matches("*.log", "build.log"));
+        assert!(component_matches("report-?.txt", "report-1.txt"));
+        assert!(component_matches("a*b*c", "axxbyyc"));
+        assert!(!component_matches("*.log", "build.txt"));
+        assert!(!component_matches("*", ".hidden"));
+        assert!(component_matches(".*", ".hidden"));
+    }
+
+    #[test]
+    fn test_find_artifacts() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        for sub in ["dist/js", "logs", "target/debug"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        for file in [
+            "dist/app.js",
+            "dist/js/vendor.js",
+            "logs/build.log",
+            "logs/test.log",
+            "target/debug/app",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let found = find_artifacts(
+            root,
+            &[
+                "logs/*.log".to_string(),
+                "dist/**/*.js".to_string(),
+                "target/debug/app".to_string(),
+                "missing/*".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                root.join("dist/app.js"),
+                root.join("dist/js/vendor.js"),
+                root.join("logs/build.log"),
+                root.join("logs/test.log"),
+                root.join("target/debug/app"),
+            ]
+        );
+
+        assert!(find_artifacts(root, &["../secrets".to_string()]).is_err());
+        assert!(find_artifacts(root, &["/etc/passwd".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_file_uri() {
+        assert_eq!(
+            file_uri(Path::new("/srv/my app/out.log")),
+            "file:///srv/my%20app/out.log"
+        );
+    }
+
+    #[test]
+    fn test_looks_like_text() {
+        assert!(looks_like_text(b"build ok\n"));
+        // A multi-byte character cut off by the read limit
+        assert!(looks_like_text(&"caf\u{e9}".as_bytes()[..4]));
+        assert!(!looks_like_text(&[0x89, b'P', b'N', b'G', 0]));
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(&[0xff, 0x00, 0x10, 0x80]), "/wAQgA==");
+    }
+}
@@ -0,0 +1,270 @@
+use serde_json::Value;
+use snafu::prelude::*;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::environment::{self, MASKED_VALUE};
+use crate::history::unix_millis;
+
+/// Longest string kept in an audit entry, in bytes, before it is cut.
+pub const DEFAULT_MAX_STRING: usize = 4096;
+
+#[derive(Debug, Snafu)]
+pub enum AuditError {
+    #[snafu(display("Failed to open audit log {}: {}", path.display(), source))]
+    Open {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, AuditError>;
+
+/// Which way a message went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From the client: requests, notifications and replies to the server's requests
+    Received,
+    /// To the client
+    Sent,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Received => "received",
+            Direction::Sent => "sent",
+        }
+    }
+}
+
+/// Append-only JSON Lines file of every JSON-RPC message the server
+/// receives and sends. Values of secret-looking keys are masked and long
+/// strings, such as recipe output, are cut short.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+    max_string: usize,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(OpenSnafu { path })?;
+        Ok(Self {
+            file: Mutex::new(file),
+            max_string: DEFAULT_MAX_STRING,
+        })
+    }
+
+    /// Cut strings longer than `max_string` bytes.
+    pub fn with_max_string(self, max_string: usize) -> Self {
+        Self { max_string, ..self }
+    }
+
+    /// Append one message of `session`. Lines that are not JSON are kept
+    /// as strings.
+    pub fn record(&self, session: &str, direction: Direction, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        let mut message =
+            serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string()));
+        redact(&mut message, self.max_string);
+        let entry = serde_json::json!({
+            "timestamp_ms": unix_millis(SystemTime::now()),
+            "session": session,
+            "direction": direction.as_str(),
+            "message": message,
+        });
+        let mut file = self.file.lock().unwrap();
+        if let Err(error) = writeln!(file, "{entry}") {
+            tracing::warn!(%error, "failed to write the audit log");
+        }
+    }
+}
+
+/// Mask the values of secret-looking keys and cut long strings, in place.
+pub fn redact(value: &mut Value, max_string: usize) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if value.is_string() && environment::is_secret_name(key) {
+                    *value = Value::String(MASKED_VALUE.to_string());
+                } else {
+                    redact(value, max_string);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                redact(value, max_string);
+            }
+        }
+        Value::String(text) => cut_string(text, max_string),
+        _ => {}
+    }
+}
+
+/// Cut `text` to at most `max` bytes, on a character boundary, noting how
+/// much was cut.
+pub(crate) fn cut_string(text: &mut String, max: usize) {
+    if text.len() <= max {
+        return;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let cut = text.len() - end;
+    text.truncate(end);
+    text.push_str(&format!("… [{cut} bytes truncated]"));
+}
+
+/// A transport stream, or one half of one, that copies each
+/// newline-delimited message passing through it to an `AuditLog`.
+pub struct AuditedStream<S> {
+    inner: S,
+    log: Arc<AuditLog>,
+    session: String,
+    /// Partial lines not yet recorded
+    received: Vec<u8>,
+    sent: Vec<u8>,
+}
+
+impl<S> AuditedStream<S> {
+    pub fn new(inner: S, log: Arc<AuditLog>, session: impl Into<String>) -> Self {
+        Self {
+            inner,
+            log,
+            session: session.into(),
+            received: Vec::new(),
+            sent: Vec::new(),
+        }
+    }
+}
+
+/// Record the complete lines in `pending`, keeping the rest.
+fn record_lines(log: &AuditLog, session: &str, direction: Direction, pending: &mut Vec<u8>) {
+    while let Some(end) = pending.iter().position(|byte| *byte == b'\n') {
+        let line: Vec<u8> = pending.drain(..=end).collect();
+        log.record(session, direction, &line);
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for AuditedStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            this.received.extend_from_slice(&buf.filled()[filled..]);
+            record_lines(
+                &this.log,
+                &this.session,
+                Direction::Received,
+                &mut this.received,
+            );
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for AuditedStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            this.sent.extend_from_slice(&buf[..written]);
+            record_lines(&this.log, &this.session, Direction::Sent, &mut this.sent);
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_redact() {
+        let mut message = serde_json::json!({
+            "method": "tools/call",
+            "params": {
+                "name": "set_session_environment",
+                "arguments": { "variables": { "GITHUB_TOKEN": "ghp_123", "REGION": "eu" } },
+            },
+            "result": { "stdout": "x".repeat(40) },
+        });
+        redact(&mut message, 32);
+        let variables = &message["params"]["arguments"]["variables"];
+        assert_eq!(variables["GITHUB_TOKEN"], MASKED_VALUE);
+        assert_eq!(variables["REGION"], "eu");
+        assert_eq!(message["params"]["name"], "set_session_environment");
+        assert_eq!(
+            message["result"]["stdout"],
+            format!("{}… [8 bytes truncated]", "x".repeat(32))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audited_stream_records_each_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let log = Arc::new(AuditLog::open(&path).unwrap());
+        let (client, server) = tokio::io::duplex(1024);
+        let mut server = AuditedStream::new(server, log, "7");
+        let mut client = client;
+
+        client
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n{\"jsonrpc\"")
+            .await
+            .unwrap();
+        let mut buffer = [0; 64];
+        let _ = server.read(&mut buffer).await.unwrap();
+        server
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n")
+            .await
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let entries: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["session"], "7");
+        assert_eq!(entries[0]["direction"], "received");
+        assert_eq!(entries[0]["message"]["method"], "ping");
+        assert_eq!(entries[1]["direction"], "sent");
+        assert_eq!(entries[1]["message"]["id"], 1);
+    }
+}
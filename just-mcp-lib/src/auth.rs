@@ -0,0 +1,48 @@
+/// Environment variable holding the token network clients must present.
+pub const AUTH_TOKEN_VAR: &str = "MCP_AUTH_TOKEN";
+
+/// The token in an `Authorization` header value such as `Bearer abc123`.
+/// The scheme is matched case-insensitively.
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+/// Whether `authorization`, a header value, carries `expected`. Compares in
+/// time independent of where the tokens differ.
+pub fn is_authorized(expected: &str, authorization: Option<&str>) -> bool {
+    let Some(presented) = authorization.and_then(bearer_token) else {
+        return false;
+    };
+    let (expected, presented) = (expected.as_bytes(), presented.as_bytes());
+    let difference = expected
+        .iter()
+        .zip(presented)
+        .fold(0, |difference, (a, b)| difference | (a ^ b));
+    difference == 0 && expected.len() == presented.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bearer_token() {
+        assert_eq!(bearer_token("Bearer abc123"), Some("abc123"));
+        assert_eq!(bearer_token("bearer  abc123 "), Some("abc123"));
+        assert_eq!(bearer_token("Basic YWJj"), None);
+        assert_eq!(bearer_token("Bearer "), None);
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized("secret", Some("Bearer secret")));
+        assert!(!is_authorized("secret", Some("Bearer secret2")));
+        assert!(!is_authorized("secret", Some("Bearer secre")));
+        assert!(!is_authorized("secret", Some("secret")));
+        assert!(!is_authorized("secret", None));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::mcp_server::RECIPE_TOOL_PREFIX;
+use crate::{Justfile, ParameterType, Recipe};
+
+/// Most values returned for a single completion request, per the MCP spec.
+pub const MAX_COMPLETIONS: usize = 100;
+
+/// Arguments whose values are recipe names: `recipe_name` of `run_recipe`
+/// and `get_recipe_info`, and `name` of the `explain_recipe` prompt.
+const RECIPE_NAME_ARGUMENTS: &[&str] = &["recipe_name", "name"];
+
+/// Candidate values for `argument` of the tool or prompt `reference`,
+/// keeping those that start with what the user typed so far.
+///
+/// `just_<recipe>` references complete the recipe's parameter values from
+/// their declared type or default.
+pub fn complete(justfile: &Justfile, reference: &str, argument: &str, value: &str) -> Vec<String> {
+    let candidates = match reference.strip_prefix(RECIPE_TOOL_PREFIX) {
+        Some(recipe_name) => justfile
+            .recipes
+            .iter()
+            .find(|recipe| recipe.name == recipe_name)
+            .map(|recipe| parameter_values(recipe, argument))
+            .unwrap_or_default(),
+        None if RECIPE_NAME_ARGUMENTS.contains(&argument) => justfile
+            .recipes
+            .iter()
+            .map(|recipe| recipe.name.clone())
+            .collect(),
+        None => Vec::new(),
+    };
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(value))
+        .collect()
+}
+
+/// Known values of a recipe parameter: enum choices, booleans, or its default.
+pub fn parameter_values(recipe: &Recipe, parameter: &str) -> Vec<String> {
+    match recipe.parameter_types.get(parameter) {
+        Some(ParameterType::Enum(values)) => values.clone(),
+        Some(ParameterType::Bool) => vec!["true".to_string(), "false".to_string()],
+        _ => recipe
+            .parameters
+            .iter()
+            .find(|param| param.name == parameter)
+            .and_then(|param| param.default_value.clone())
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    const JUSTFILE: &str = "# @param env: enum(dev,staging,prod)\n# @param dry_run: bool\ndeploy env dry_run=\"false\" region=\"us-east-1\":\n    ./deploy {{ env }}\n\ndev:\n    cargo run\n\ntest:\n    cargo test\n";
+
+    #[test]
+    fn test_complete_recipe_names() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        assert_eq!(
+            complete(&justfile, "run_recipe", "recipe_name", "de"),
+            vec!["deploy", "dev"]
+        );
+        assert_eq!(
+            complete(&justfile, "explain_recipe", "name", ""),
+            vec!["deploy", "dev", "test"]
+        );
+        assert!(complete(&justfile, "run_recipe", "args", "").is_empty());
+    }
+
+    #[test]
+    fn test_complete_parameter_values() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        assert_eq!(
+            complete(&justfile, "just_deploy", "env", "s"),
+            vec!["staging"]
+        );
+        assert_eq!(
+            complete(&justfile, "just_deploy", "dry_run", ""),
+            vec!["true", "false"]
+        );
+        assert_eq!(
+            complete(&justfile, "just_deploy", "region", ""),
+            vec!["us-east-1"]
+        );
+        assert!(complete(&justfile, "just_missing", "env", "").is_empty());
+    }
+}
@@ -0,0 +1,534 @@
+use serde::Deserialize;
+use snafu::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::limits::SessionLimits;
+use crate::lints::LintConfig;
+use crate::policy::{EnvPolicy, RecipePolicy};
+use crate::secrets::SecretsConfig;
+use crate::timeouts::ToolTimeouts;
+
+#[derive(Debug, Snafu)]
+pub enum ConfigError {
+    #[snafu(display("Failed to read config file {}: {}", path.display(), source))]
+    ConfigRead {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Invalid config: {}", source))]
+    ConfigParse { source: toml::de::Error },
+
+    #[snafu(display("Failed to load env file {}: {}", path.display(), source))]
+    EnvFileRead {
+        path: PathBuf,
+        source: dotenvy::Error,
+    },
+
+    #[snafu(display("Unknown profile '{}' (known: {})", name, known))]
+    UnknownProfile { name: String, known: String },
+}
+
+pub type Result<T> = std::result::Result<T, ConfigError>;
+
+/// Each profile's variables, by profile name.
+pub type ProfileEnvironments = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Config file looked for in the working directory.
+pub const PROJECT_CONFIG_NAME: &str = "just-mcp.toml";
+
+/// Keys holding paths, which are relative to the file that sets them.
+const PATH_KEYS: &[&str] = &[
+    "directory",
+    "justfile",
+    "justfiles",
+    "search-boundary",
+    "env-files",
+    "tls-cert",
+    "tls-key",
+    "audit-log",
+];
+
+/// Server configuration read from TOML files.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ServerConfig {
+    /// Working directory of the server
+    pub directory: Option<PathBuf>,
+    /// Justfile to use instead of looking for one in the working directory
+    pub justfile: Option<PathBuf>,
+    /// Justfiles to register; when empty every justfile is accessible
+    pub justfiles: Vec<PathBuf>,
+    /// Highest directory searched for a justfile when the working directory
+    /// has none; the filesystem root when absent
+    pub search_boundary: Option<PathBuf>,
+    /// `stdio`, `unix`, `tcp`, `http` or `sse`
+    pub transport: Option<String>,
+    /// Socket path or address to listen on
+    pub bind: Option<String>,
+    /// TCP address for the HTTP `/healthz` and `/readyz` endpoints
+    pub http_bind: Option<String>,
+    /// PEM certificate chain for TLS on the TCP transport and HTTP endpoints
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for `tls-cert`
+    pub tls_key: Option<PathBuf>,
+    /// JSON Lines file every JSON-RPC message is appended to
+    pub audit_log: Option<PathBuf>,
+    /// Seconds between justfile change checks; 0 disables watching
+    pub watch_interval: Option<u64>,
+    /// Seconds running recipes get to finish on SIGINT/SIGTERM before
+    /// they are killed
+    pub shutdown_grace_period: Option<u64>,
+    /// Dotenv files whose variables every recipe run gets
+    pub env_files: Vec<PathBuf>,
+    /// Variables every recipe run gets, overriding the env files
+    pub env: BTreeMap<String, String>,
+    /// Load `.env`, `.env.local`, `.env.<profile>` and
+    /// `.env.<profile>.local` from the working directory for recipe runs
+    pub dotenv_stack: bool,
+    /// Profile applied when no `--profile` is given
+    pub profile: Option<String>,
+    /// Named sets of env files and variables, such as `[profiles.staging]`
+    pub profiles: BTreeMap<String, Profile>,
+    pub lints: LintConfig,
+    pub recipes: RecipePolicy,
+    /// Which of the server's environment variables recipes inherit
+    pub inherit_env: EnvPolicy,
+    /// Per-session tool call rate and concurrent execution limits
+    pub limits: SessionLimits,
+    /// Seconds each tool call may take, by tool name or `default`
+    pub tool_timeouts: ToolTimeouts,
+    /// Names of variables whose values are masked in everything the server
+    /// sends, e.g. `*_TOKEN`; a built-in list when absent
+    pub secret_patterns: Option<Vec<String>>,
+    /// Keyring service and secrets every recipe run gets
+    pub secrets: SecretsConfig,
+    /// Ask the user for required parameters `run_recipe` was called without
+    pub elicit_parameters: bool,
+    /// Like `serve --daemon`: default to a Unix socket in the working directory
+    pub daemon: bool,
+}
+
+/// A named environment, such as `dev`, `staging` or `prod`, applied on top
+/// of `env-files` and `[env]` when selected.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Profile {
+    /// Dotenv files loaded in order
+    pub env_files: Vec<PathBuf>,
+    /// Variables overriding the profile's env files
+    pub env: BTreeMap<String, String>,
+}
+
+impl Profile {
+    /// Variables from `env-files`, in order, then from `[env]`.
+    pub fn environment(&self) -> Result<BTreeMap<String, String>> {
+        load_environment(&self.env_files, &self.env)
+    }
+}
+
+/// The config's `env-files`, `[env]` and profiles, kept so their variables
+/// can be read again when one of the files changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvSources {
+    pub env_files: Vec<PathBuf>,
+    pub env: BTreeMap<String, String>,
+    pub profiles: BTreeMap<String, Profile>,
+    /// Variables set unless the config sets them, such as `TMPDIR` from
+    /// `MCP_TEMP_DIR`
+    pub defaults: BTreeMap<String, String>,
+}
+
+impl EnvSources {
+    /// Every dotenv file the variables are read from, the profiles' included.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = self.env_files.clone();
+        for profile in self.profiles.values() {
+            for path in &profile.env_files {
+                if !files.contains(path) {
+                    files.push(path.clone());
+                }
+            }
+        }
+        files
+    }
+
+    /// The config's variables and those of each profile, by name.
+    pub fn load(&self) -> Result<(BTreeMap<String, String>, ProfileEnvironments)> {
+        let mut environment = load_environment(&self.env_files, &self.env)?;
+        for (name, value) in &self.defaults {
+            environment
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+        let profiles = self
+            .profiles
+            .iter()
+            .map(|(name, profile)| Ok((name.clone(), profile.environment()?)))
+            .collect::<Result<_>>()?;
+        Ok((environment, profiles))
+    }
+}
+
+impl ServerConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::load_layered(&[path.to_path_buf()])
+    }
+
+    /// Load several config files, later files overriding earlier ones key by
+    /// key. Tables such as `[lints]` are merged; other values, including
+    /// arrays, are replaced.
+    pub fn load_layered(paths: &[PathBuf]) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        for path in paths {
+            let content = fs::read_to_string(path).context(ConfigReadSnafu { path })?;
+            let mut table: toml::Table = toml::from_str(&content).context(ConfigParseSnafu)?;
+            if let Some(dir) = path.parent() {
+                resolve_paths(&mut table, dir);
+            }
+            merge_tables(&mut merged, table);
+        }
+        toml::Value::Table(merged)
+            .try_into()
+            .context(ConfigParseSnafu)
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        toml::from_str(content).context(ConfigParseSnafu)
+    }
+
+    /// The user config, `$XDG_CONFIG_HOME/just-mcp/config.toml` or
+    /// `~/.config/just-mcp/config.toml`.
+    pub fn user_config_path() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("just-mcp").join("config.toml"))
+    }
+
+    /// Config files in effect without `--config`, lowest precedence first:
+    /// the user config, then `just-mcp.toml` in `dir`.
+    pub fn discover(dir: &Path) -> Vec<PathBuf> {
+        Self::user_config_path()
+            .into_iter()
+            .chain([dir.join(PROJECT_CONFIG_NAME)])
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Variables from `env-files`, in order, then from `[env]`.
+    pub fn environment(&self) -> Result<BTreeMap<String, String>> {
+        load_environment(&self.env_files, &self.env)
+    }
+
+    /// Where `environment` and `profile_environments` come from.
+    pub fn env_sources(&self) -> EnvSources {
+        EnvSources {
+            env_files: self.env_files.clone(),
+            env: self.env.clone(),
+            profiles: self.profiles.clone(),
+            defaults: BTreeMap::new(),
+        }
+    }
+
+    /// The variables of every profile, by name.
+    pub fn profile_environments(&self) -> Result<ProfileEnvironments> {
+        self.profiles
+            .iter()
+            .map(|(name, profile)| Ok((name.clone(), profile.environment()?)))
+            .collect()
+    }
+
+    /// Check that `name` is one of `profiles`.
+    pub fn check_profile(&self, name: &str) -> Result<()> {
+        ensure!(
+            self.profiles.contains_key(name),
+            UnknownProfileSnafu {
+                name,
+                known: self.profiles.keys().cloned().collect::<Vec<_>>().join(", "),
+            }
+        );
+        Ok(())
+    }
+}
+
+fn load_environment(
+    env_files: &[PathBuf],
+    env: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut variables = BTreeMap::new();
+    for path in env_files {
+        let entries = dotenvy::from_path_iter(path).context(EnvFileReadSnafu { path })?;
+        for entry in entries {
+            let (name, value) = entry.context(EnvFileReadSnafu { path })?;
+            variables.insert(name, value);
+        }
+    }
+    variables.extend(env.clone());
+    Ok(variables)
+}
+
+fn resolve_paths(table: &mut toml::Table, dir: &Path) {
+    let resolve = |value: &mut toml::Value| {
+        if let toml::Value::String(path) = value {
+            *path = dir.join(&*path).display().to_string();
+        }
+    };
+    for key in PATH_KEYS {
+        match table.get_mut(*key) {
+            Some(toml::Value::Array(paths)) => paths.iter_mut().for_each(resolve),
+            Some(value) => resolve(value),
+            None => {}
+        }
+    }
+    if let Some(toml::Value::Table(profiles)) = table.get_mut("profiles") {
+        for (_, profile) in profiles.iter_mut() {
+            if let toml::Value::Table(profile) = profile {
+                resolve_paths(profile, dir);
+            }
+        }
+    }
+}
+
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_tables(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lints::LintLevel;
+    use crate::secrets::SecretProvider;
+
+    #[test]
+    fn test_parse_lints_section() {
+        let config = ServerConfig::from_toml_str(
+            r#"
+[lints]
+missing-documentation = "allow"
+empty-recipe = "deny"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.lints.levels.get("missing-documentation"),
+            Some(&LintLevel::Allow)
+        );
+        assert_eq!(
+            config.lints.levels.get("empty-recipe"),
+            Some(&LintLevel::Deny)
+        );
+    }
+
+    #[test]
+    fn test_parse_elicit_parameters() {
+        let config = ServerConfig::from_toml_str("elicit-parameters = true\n").unwrap();
+        assert!(config.elicit_parameters);
+    }
+
+    #[test]
+    fn test_parse_recipes_section() {
+        let config = ServerConfig::from_toml_str(
+            r#"
+[recipes]
+allow = ["build", "test", "lint-*"]
+deny = ["deploy*"]
+hide-blocked = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.recipes.allow, vec!["build", "test", "lint-*"]);
+        assert_eq!(config.recipes.deny, vec!["deploy*"]);
+        assert!(config.recipes.hide_blocked);
+    }
+
+    #[test]
+    fn test_parse_inherit_env_section() {
+        let config = ServerConfig::from_toml_str(
+            "[inherit-env]\ndeny = [\"OPENAI_API_KEY\", \"*_TOKEN\"]\n",
+        )
+        .unwrap();
+        assert!(!config.inherit_env.inherits("OPENAI_API_KEY"));
+        assert!(config.inherit_env.inherits("PATH"));
+    }
+
+    #[test]
+    fn test_parse_secrets_section() {
+        let config = ServerConfig::from_toml_str(
+            "[secrets]\nkeyring-service = \"acme\"\nenv = [\"DEPLOY_KEY\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.secrets.keyring_service.as_deref(), Some("acme"));
+        assert_eq!(config.secrets.env, vec!["DEPLOY_KEY"]);
+        assert_eq!(
+            ServerConfig::default().secrets.provider().describe(),
+            "keyring service 'just-mcp'"
+        );
+    }
+
+    #[test]
+    fn test_parse_limits_section() {
+        let config = ServerConfig::from_toml_str(
+            "[limits]\nmax-concurrent-executions = 2\ntool-calls-per-minute = 60\n",
+        )
+        .unwrap();
+        assert_eq!(config.limits.max_concurrent_executions, Some(2));
+        assert_eq!(config.limits.tool_calls_per_minute, Some(60));
+        assert_eq!(ServerConfig::default().limits, SessionLimits::default());
+    }
+
+    #[test]
+    fn test_parse_tool_timeouts_section() {
+        let config = ServerConfig::from_toml_str(
+            "[tool-timeouts]
+run_recipe = 300
+validate_justfile = 10
+",
+        )
+        .unwrap();
+        assert_eq!(
+            config.tool_timeouts.get("run_recipe"),
+            Some(std::time::Duration::from_secs(300))
+        );
+        assert_eq!(config.tool_timeouts.get("list_recipes"), None);
+    }
+
+    #[test]
+    fn test_layered_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let user = dir.path().join("user.toml");
+        fs::write(
+            &user,
+            "watch-interval = 5\ntransport = \"tcp\"\n[lints]\nempty-recipe = \"deny\"\n[env]\nA = \"user\"\n",
+        )
+        .unwrap();
+        let project = dir.path().join("project").join(PROJECT_CONFIG_NAME);
+        fs::create_dir(project.parent().unwrap()).unwrap();
+        fs::write(
+            &project,
+            "watch-interval = 0\nenv-files = [\".env.mcp\"]\n[lints]\nmissing-documentation = \"allow\"\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("project/.env.mcp"), "A=file\nB=file\n").unwrap();
+
+        let config = ServerConfig::load_layered(&[user, project]).unwrap();
+        assert_eq!(config.watch_interval, Some(0));
+        assert_eq!(config.transport.as_deref(), Some("tcp"));
+        assert_eq!(config.lints.levels.len(), 2);
+        assert_eq!(
+            config.env_files,
+            vec![dir.path().join("project").join(".env.mcp")]
+        );
+
+        // [env] overrides the env files
+        let environment = config.environment().unwrap();
+        assert_eq!(environment["A"], "user");
+        assert_eq!(environment["B"], "file");
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(PROJECT_CONFIG_NAME);
+        fs::write(
+            &path,
+            r#"
+profile = "dev"
+
+[profiles.dev.env]
+API_URL = "http://localhost:8080"
+
+[profiles.staging]
+env-files = [".env.staging"]
+env = { REGION = "eu" }
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(".env.staging"),
+            "API_URL=https://staging.example.com\nREGION=us\n",
+        )
+        .unwrap();
+
+        let config = ServerConfig::load(&path).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("dev"));
+        assert_eq!(
+            config.profiles["staging"].env_files,
+            vec![dir.path().join(".env.staging")]
+        );
+        let profiles = config.profile_environments().unwrap();
+        assert_eq!(profiles["dev"]["API_URL"], "http://localhost:8080");
+        assert_eq!(
+            profiles["staging"]["API_URL"],
+            "https://staging.example.com"
+        );
+        assert_eq!(profiles["staging"]["REGION"], "eu");
+
+        assert!(config.check_profile("staging").is_ok());
+        let error = config.check_profile("prod").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown profile 'prod' (known: dev, staging)"
+        );
+    }
+
+    #[test]
+    fn test_env_sources_reload() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(PROJECT_CONFIG_NAME);
+        fs::write(
+            &path,
+            "env-files = [\".env.mcp\"]\n[profiles.prod]\nenv-files = [\".env.prod\", \".env.mcp\"]\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join(".env.mcp"), "API_KEY=old\n").unwrap();
+        fs::write(dir.path().join(".env.prod"), "REGION=eu\n").unwrap();
+
+        let config = ServerConfig::load(&path).unwrap();
+        let mut sources = config.env_sources();
+        sources
+            .defaults
+            .insert("TMPDIR".to_string(), "/var/tmp".to_string());
+        assert_eq!(
+            sources.files(),
+            vec![dir.path().join(".env.mcp"), dir.path().join(".env.prod")]
+        );
+
+        let (environment, _) = sources.load().unwrap();
+        assert_eq!(environment["API_KEY"], "old");
+        assert_eq!(environment["TMPDIR"], "/var/tmp");
+
+        fs::write(dir.path().join(".env.mcp"), "API_KEY=rotated\n").unwrap();
+        let (environment, profiles) = sources.load().unwrap();
+        assert_eq!(environment["API_KEY"], "rotated");
+        assert_eq!(profiles["prod"]["API_KEY"], "rotated");
+        assert_eq!(profiles["prod"]["REGION"], "eu");
+    }
+
+    #[test]
+    fn test_empty_config_uses_defaults() {
+        assert_eq!(
+            ServerConfig::from_toml_str("").unwrap(),
+            ServerConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_invalid_level_is_rejected() {
+        let result = ServerConfig::from_toml_str("[lints]\nempty-recipe = \"loud\"\n");
+        assert!(result.is_err());
+    }
+}
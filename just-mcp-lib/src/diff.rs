@@ -0,0 +1,176 @@
+/// Lines of unchanged context shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Unified diff between two texts, labelled `a/<path>` and `b/<path>`.
+/// Returns an empty string when the texts are identical.
+pub fn unified_diff(old: &str, new: &str, path: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old_lines, &new_lines);
+    if lines.iter().all(|line| matches!(line, Line::Same(_))) {
+        return String::new();
+    }
+
+    let mut output = format!("--- a/{path}\n+++ b/{path}\n");
+    for hunk in hunks(&lines) {
+        output.push_str(&render_hunk(&lines, hunk));
+    }
+    output
+}
+
+/// Line-level edit script from the longest common subsequence.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals come before additions, as in diff(1)
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Index ranges into the edit script, each covering a run of changes plus
+/// surrounding context. Changes closer than twice the context are merged.
+fn hunks(lines: &[Line]) -> Vec<std::ops::Range<usize>> {
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if matches!(line, Line::Same(_)) {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
+
+fn render_hunk(lines: &[Line], hunk: std::ops::Range<usize>) -> String {
+    // 1-based line numbers where the hunk starts in the old and new text
+    let (mut old_start, mut new_start) = (1, 1);
+    for line in &lines[..hunk.start] {
+        match line {
+            Line::Same(_) => {
+                old_start += 1;
+                new_start += 1;
+            }
+            Line::Removed(_) => old_start += 1,
+            Line::Added(_) => new_start += 1,
+        }
+    }
+
+    let mut body = String::new();
+    let (mut old_count, mut new_count) = (0, 0);
+    for line in &lines[hunk] {
+        match line {
+            Line::Same(text) => {
+                body.push_str(&format!(" {text}\n"));
+                old_count += 1;
+                new_count += 1;
+            }
+            Line::Removed(text) => {
+                body.push_str(&format!("-{text}\n"));
+                old_count += 1;
+            }
+            Line::Added(text) => {
+                body.push_str(&format!("+{text}\n"));
+                new_count += 1;
+            }
+        }
+    }
+
+    // An empty side is reported as starting at the line before the hunk
+    if old_count == 0 {
+        old_start -= 1;
+    }
+    if new_count == 0 {
+        new_start -= 1;
+    }
+    format!("@@ -{old_start},{old_count} +{new_start},{new_count} @@\n{body}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_texts_have_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "justfile"), "");
+    }
+
+    #[test]
+    fn test_appended_lines() {
+        let diff = unified_diff("a\nb\n", "a\nb\nc\n", "justfile");
+        assert_eq!(
+            diff,
+            "--- a/justfile\n+++ b/justfile\n@@ -1,2 +1,3 @@\n a\n b\n+c\n"
+        );
+    }
+
+    #[test]
+    fn test_changed_line_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+        let diff = unified_diff(old, new, "justfile");
+        assert_eq!(
+            diff,
+            "--- a/justfile\n+++ b/justfile\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn test_distant_changes_use_separate_hunks() {
+        let old: String = (1..=20).map(|n| format!("{n}\n")).collect();
+        let new: String = (1..=20)
+            .map(|n| match n {
+                2 => "two\n".to_string(),
+                19 => "nineteen\n".to_string(),
+                n => format!("{n}\n"),
+            })
+            .collect();
+        let diff = unified_diff(&old, &new, "justfile");
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n"));
+        assert!(diff.contains("-19\n+nineteen\n 20\n"));
+    }
+
+    #[test]
+    fn test_removed_everything() {
+        let diff = unified_diff("a\n", "", "justfile");
+        assert_eq!(
+            diff,
+            "--- a/justfile\n+++ b/justfile\n@@ -1,1 +0,0 @@\n-a\n"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use ignore::WalkBuilder;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::Recipe;
+use crate::parser::parse_justfile_str;
+use crate::roots::{self, JUSTFILE_NAMES, MAX_DEPTH, SKIPPED_DIRS};
+use crate::watcher::{self, Stamp};
+
+/// Which justfiles below a root are indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryOptions {
+    /// Directory levels below the root to search
+    pub max_depth: usize,
+    /// Directory names to skip, in addition to hidden ones, `target` and
+    /// `node_modules`
+    pub ignore: Vec<String>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            ignore: Vec::new(),
+        }
+    }
+}
+
+/// Justfiles in `root` and up to `options.max_depth` levels below it,
+/// shallowest first. Unlike `roots::discover_justfiles`, files and
+/// directories excluded by `.gitignore`, `.ignore` or the git excludes are
+/// skipped too, whether or not `root` is in a git repository.
+pub fn discover(root: &Path, options: &DiscoveryOptions) -> Vec<PathBuf> {
+    let ignore = options.ignore.clone();
+    let walker = WalkBuilder::new(root)
+        .max_depth(Some(options.max_depth + 1))
+        // `.justfile` is hidden; hidden directories are skipped below
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_dir());
+            if entry.depth() == 0 || !is_dir {
+                return true;
+            }
+            let name = entry.file_name().to_string_lossy();
+            !name.starts_with('.')
+                && !SKIPPED_DIRS.contains(&name.as_ref())
+                && !ignore.iter().any(|ignored| *ignored == name)
+        })
+        .build();
+
+    let dirs: BTreeSet<PathBuf> = walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .filter(|entry| JUSTFILE_NAMES.contains(&entry.file_name().to_string_lossy().as_ref()))
+        .filter_map(|entry| entry.path().parent().map(Path::to_path_buf))
+        .collect();
+    let mut justfiles: Vec<PathBuf> = dirs
+        .iter()
+        .filter_map(|dir| roots::find_justfile(dir))
+        .collect();
+    justfiles.sort_by_key(|path| (path.components().count(), path.clone()));
+    justfiles
+}
+
+#[derive(Debug)]
+struct IndexedJustfile {
+    stamp: Stamp,
+    /// `None` when the justfile could not be read or parsed
+    recipes: Option<Vec<Recipe>>,
+}
+
+impl IndexedJustfile {
+    fn load(path: &Path) -> Self {
+        let recipes = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| parse_justfile_str(&content).ok())
+            .map(|justfile| justfile.recipes);
+        Self {
+            stamp: watcher::stamp(path),
+            recipes,
+        }
+    }
+}
+
+/// What a refresh of a `WorkspaceIndex` found.
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexChanges {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    /// Justfiles parsed again because they were modified
+    pub modified: Vec<PathBuf>,
+}
+
+impl IndexChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Every justfile below a root, such as a monorepo, and the recipes each
+/// one defines. Refreshing parses only the justfiles that are new or were
+/// modified since, so large trees stay cheap to keep current.
+#[derive(Debug)]
+pub struct WorkspaceIndex {
+    root: PathBuf,
+    options: DiscoveryOptions,
+    /// Shallowest first, as discovered
+    order: Vec<PathBuf>,
+    justfiles: BTreeMap<PathBuf, IndexedJustfile>,
+}
+
+impl WorkspaceIndex {
+    pub fn build(root: &Path, options: DiscoveryOptions) -> Self {
+        let mut index = Self {
+            root: root.to_path_buf(),
+            options,
+            order: Vec::new(),
+            justfiles: BTreeMap::new(),
+        };
+        index.refresh();
+        index
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn options(&self) -> &DiscoveryOptions {
+        &self.options
+    }
+
+    /// Look for justfiles again and parse the new and modified ones.
+    pub fn refresh(&mut self) -> IndexChanges {
+        let mut changes = IndexChanges::default();
+        let order = discover(&self.root, &self.options);
+        let mut justfiles = BTreeMap::new();
+        for path in &order {
+            let indexed = match self.justfiles.remove(path) {
+                Some(indexed) if indexed.stamp == watcher::stamp(path) => indexed,
+                Some(_) => {
+                    changes.modified.push(path.clone());
+                    IndexedJustfile::load(path)
+                }
+                None => {
+                    changes.added.push(path.clone());
+                    IndexedJustfile::load(path)
+                }
+            };
+            justfiles.insert(path.clone(), indexed);
+        }
+        // Whatever was not visited above is gone
+        changes.removed = std::mem::take(&mut self.justfiles).into_keys().collect();
+
+        self.order = order;
+        self.justfiles = justfiles;
+        changes
+    }
+
+    /// Each justfile with its recipes, `None` when it does not parse.
+    pub fn justfiles(&self) -> impl Iterator<Item = (&Path, Option<&[Recipe]>)> {
+        self.order.iter().map(|path| {
+            let recipes = self.justfiles[path].recipes.as_deref();
+            (path.as_path(), recipes)
+        })
+    }
+
+    /// Every recipe of every justfile that parses, with its justfile.
+    pub fn recipes(&self) -> impl Iterator<Item = (&Path, &Recipe)> {
+        self.justfiles().flat_map(|(path, recipes)| {
+            recipes
+                .unwrap_or_default()
+                .iter()
+                .map(move |recipe| (path, recipe))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discover_respects_gitignore() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        for sub in ["api", "web", "vendor/lib", "node_modules/pkg", ".cache"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(root.join(".gitignore"), "vendor/\n").unwrap();
+        fs::write(root.join("justfile"), "").unwrap();
+        fs::write(root.join("api/Justfile"), "").unwrap();
+        fs::write(root.join("web/.justfile"), "").unwrap();
+        fs::write(root.join("vendor/lib/justfile"), "").unwrap();
+        fs::write(root.join("node_modules/pkg/justfile"), "").unwrap();
+        fs::write(root.join(".cache/justfile"), "").unwrap();
+
+        assert_eq!(
+            discover(root, &DiscoveryOptions::default()),
+            vec![
+                root.join("justfile"),
+                root.join("api/Justfile"),
+                root.join("web/.justfile"),
+            ]
+        );
+        let options = DiscoveryOptions {
+            max_depth: 0,
+            ignore: Vec::new(),
+        };
+        assert_eq!(discover(root, &options), vec![root.join("justfile")]);
+    }
+
+    #[test]
+    fn test_index_refreshes_incrementally() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("api")).unwrap();
+        fs::write(root.join("justfile"), "build:\n    cargo build\n").unwrap();
+        fs::write(root.join("api/justfile"), "serve:\n    cargo run\n").unwrap();
+
+        let mut index = WorkspaceIndex::build(root, DiscoveryOptions::default());
+        let recipes: Vec<(PathBuf, String)> = index
+            .recipes()
+            .map(|(path, recipe)| (path.to_path_buf(), recipe.name.clone()))
+            .collect();
+        assert_eq!(
+            recipes,
+            vec![
+                (root.join("justfile"), "build".to_string()),
+                (root.join("api/justfile"), "serve".to_string()),
+            ]
+        );
+        assert!(index.refresh().is_empty());
+
+        let api = root.join("api/justfile");
+        fs::write(&api, "serve:\n    cargo run\n\ntest:\n    cargo test\n").unwrap();
+        let file = fs::File::options().write(true).open(&api).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("web/justfile"), "broken recipe\n").unwrap();
+        fs::remove_file(root.join("justfile")).unwrap();
+
+        let changes = index.refresh();
+        assert_eq!(changes.modified, vec![api.clone()]);
+        assert_eq!(changes.added, vec![root.join("web/justfile")]);
+        assert_eq!(changes.removed, vec![root.join("justfile")]);
+        let justfiles: Vec<(&Path, Option<usize>)> = index
+            .justfiles()
+            .map(|(path, recipes)| (path, recipes.map(<[Recipe]>::len)))
+            .collect();
+        assert_eq!(
+            justfiles,
+            vec![
+                (api.as_path(), Some(2)),
+                (root.join("web/justfile").as_path(), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,571 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::graph::DependencyGraph;
+use crate::{Justfile, Parameter, Recipe};
+
+/// File Markdown documentation is written to next to the justfile.
+pub const DOCS_FILE_NAME: &str = "JUSTFILE.md";
+
+/// File HTML documentation is written to next to the justfile.
+pub const HTML_DOCS_FILE_NAME: &str = "JUSTFILE.html";
+
+/// Heading for recipes without a `[group]` when other recipes have one.
+const UNGROUPED: &str = "Other";
+
+/// Renders the `<pre class="mermaid">` dependency diagram in HTML pages.
+const MERMAID_SCRIPT: &str = r#"<script type="module">
+import mermaid from "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+mermaid.initialize({ startOnLoad: true });
+</script>
+"#;
+
+/// Stylesheet of HTML pages, kept inline so they stand alone.
+const HTML_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; max-width: 60rem; margin: 2rem auto; padding: 0 1rem; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.25rem 0.75rem; text-align: left; }
+pre { background: #f5f5f5; padding: 0.5rem 1rem; overflow-x: auto; }
+";
+
+/// Format of generated documentation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DocFormat {
+    /// GitHub-flavored Markdown with a Mermaid diagram
+    #[default]
+    Markdown,
+    /// A standalone HTML page
+    Html,
+}
+
+impl DocFormat {
+    pub const ALL: [Self; 2] = [Self::Markdown, Self::Html];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.as_str() == name)
+    }
+
+    /// File the documentation is written to next to the justfile.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Markdown => DOCS_FILE_NAME,
+            Self::Html => HTML_DOCS_FILE_NAME,
+        }
+    }
+}
+
+/// Documentation for `justfile` in `format`; see `document_justfile` for
+/// what it contains.
+pub fn generate(
+    justfile: &Justfile,
+    title: &str,
+    include_private: bool,
+    format: DocFormat,
+) -> String {
+    match format {
+        DocFormat::Markdown => document_justfile(justfile, title, include_private),
+        DocFormat::Html => document_justfile_html(justfile, title, include_private),
+    }
+}
+
+/// Title used for the justfile at `path`, e.g. `api recipes`.
+pub fn default_title(path: &Path) -> String {
+    match path.parent().and_then(Path::file_name) {
+        Some(name) => format!("{} recipes", name.to_string_lossy()),
+        None => "Recipes".to_string(),
+    }
+}
+
+/// What both formats document, in order.
+struct Outline<'a> {
+    recipes: Vec<&'a Recipe>,
+    /// Recipes by `[group]`; a single untitled group when none has one
+    groups: Vec<(Option<&'a str>, Vec<&'a Recipe>)>,
+    /// Dependencies between the documented recipes
+    graph: DependencyGraph,
+    /// Name, value and whether it is exported, by name
+    variables: Vec<(&'a str, &'a str, bool)>,
+}
+
+impl<'a> Outline<'a> {
+    fn new(justfile: &'a Justfile, include_private: bool) -> Self {
+        let recipes: Vec<&Recipe> = justfile
+            .recipes
+            .iter()
+            .filter(|recipe| include_private || !recipe.is_private())
+            .collect();
+
+        let mut named: BTreeMap<&str, Vec<&Recipe>> = BTreeMap::new();
+        let mut ungrouped = Vec::new();
+        for &recipe in &recipes {
+            let recipe_groups = recipe.groups();
+            if recipe_groups.is_empty() {
+                ungrouped.push(recipe);
+            }
+            for group in recipe_groups {
+                named.entry(group).or_default().push(recipe);
+            }
+        }
+        let groups = if named.is_empty() {
+            vec![(None, ungrouped)]
+        } else {
+            let mut groups: Vec<_> = named
+                .into_iter()
+                .map(|(group, recipes)| (Some(group), recipes))
+                .collect();
+            if !ungrouped.is_empty() {
+                groups.push((Some(UNGROUPED), ungrouped));
+            }
+            groups
+        };
+
+        let mut graph = DependencyGraph::new(justfile);
+        let documented = |name: &String| recipes.iter().any(|recipe| recipe.name == *name);
+        graph.nodes.retain(|node| documented(&node.name));
+        graph.edges.retain(|edge| documented(&edge.from));
+
+        let variables = justfile
+            .variables
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str(),
+                    value.as_str(),
+                    justfile.exported.contains(name),
+                )
+            })
+            .collect();
+
+        Self {
+            recipes,
+            groups,
+            graph,
+            variables,
+        }
+    }
+}
+
+/// Markdown reference for a justfile: a recipe index grouped by `[group]`
+/// attributes, a Mermaid dependency diagram, the variables, and a section
+/// per recipe with its usage, parameters, dependencies and documentation.
+/// Private recipes are left out unless `include_private` is set.
+pub fn document_justfile(justfile: &Justfile, title: &str, include_private: bool) -> String {
+    let outline = Outline::new(justfile, include_private);
+    let mut markdown = format!("# {title}\n");
+
+    markdown.push_str("\n## Recipes\n");
+    for (group, recipes) in &outline.groups {
+        if let Some(group) = group {
+            markdown.push_str(&format!("\n### {group}\n"));
+        }
+        markdown.push_str(&recipe_index(recipes));
+    }
+
+    if !outline.graph.edges.is_empty() {
+        markdown.push_str("\n## Dependencies\n\n```mermaid\n");
+        markdown.push_str(&outline.graph.to_mermaid());
+        markdown.push_str("```\n");
+    }
+
+    if !outline.variables.is_empty() {
+        markdown.push_str("\n## Variables\n\n| Variable | Value |\n| --- | --- |\n");
+        for (name, value, exported) in &outline.variables {
+            let export = if *exported { " (exported)" } else { "" };
+            markdown.push_str(&format!("| `{name}`{export} | `{}` |\n", table_cell(value)));
+        }
+    }
+
+    markdown.push_str("\n## Reference\n");
+    for recipe in &outline.recipes {
+        markdown.push_str(&recipe_section(recipe));
+    }
+    markdown
+}
+
+fn recipe_index(recipes: &[&Recipe]) -> String {
+    let mut index = String::from("\n| Recipe | Description |\n| --- | --- |\n");
+    for recipe in recipes {
+        index.push_str(&format!(
+            "| [`{}`](#{}) | {} |\n",
+            recipe.name,
+            anchor(&recipe.name),
+            table_cell(recipe.documentation.as_deref().unwrap_or(""))
+        ));
+    }
+    index
+}
+
+fn recipe_section(recipe: &Recipe) -> String {
+    let code = |text: &str| format!("`{text}`");
+    let mut section = format!("\n### `{}`\n", recipe.name);
+    if let Some(documentation) = &recipe.documentation {
+        section.push_str(&format!("\n{documentation}\n"));
+    }
+    section.push_str(&format!("\n```sh\n{}\n```\n", usage(recipe)));
+
+    if !recipe.parameters.is_empty() {
+        section.push_str("\n**Parameters:**\n\n");
+        for param in &recipe.parameters {
+            section.push_str(&format!(
+                "- `{}` ({})\n",
+                param.name,
+                parameter_details(recipe, param, code)
+            ));
+        }
+    }
+
+    if !recipe.dependencies.is_empty() {
+        let dependencies: Vec<String> = recipe
+            .dependencies
+            .iter()
+            .map(|name| format!("[`{name}`](#{})", anchor(name)))
+            .collect();
+        section.push_str(&format!("\n**Runs first:** {}\n", dependencies.join(", ")));
+    }
+
+    if !recipe.attributes.is_empty() {
+        let attributes: Vec<String> = recipe
+            .attributes
+            .iter()
+            .map(|attribute| format!("`[{attribute}]`"))
+            .collect();
+        section.push_str(&format!("\n**Attributes:** {}\n", attributes.join(" ")));
+    }
+    section
+}
+
+/// The same reference as `document_justfile`, as a standalone HTML page.
+/// The dependency diagram is rendered by Mermaid, loaded from a CDN; without
+/// it the diagram's source is shown.
+pub fn document_justfile_html(justfile: &Justfile, title: &str, include_private: bool) -> String {
+    let outline = Outline::new(justfile, include_private);
+    let title = escape_html(title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    );
+
+    html.push_str("<h2>Recipes</h2>\n");
+    for (group, recipes) in &outline.groups {
+        if let Some(group) = group {
+            html.push_str(&format!("<h3>{}</h3>\n", escape_html(group)));
+        }
+        html.push_str(&html_recipe_index(recipes));
+    }
+
+    let diagram = !outline.graph.edges.is_empty();
+    if diagram {
+        html.push_str("<h2>Dependencies</h2>\n<pre class=\"mermaid\">\n");
+        html.push_str(&escape_html(&outline.graph.to_mermaid()));
+        html.push_str("</pre>\n");
+    }
+
+    if !outline.variables.is_empty() {
+        html.push_str("<h2>Variables</h2>\n<table>\n<thead><tr><th>Variable</th><th>Value</th></tr></thead>\n<tbody>\n");
+        for (name, value, exported) in &outline.variables {
+            let export = if *exported { " (exported)" } else { "" };
+            html.push_str(&format!(
+                "<tr><td><code>{}</code>{export}</td><td><code>{}</code></td></tr>\n",
+                escape_html(name),
+                escape_html(value)
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n");
+    }
+
+    html.push_str("<h2>Reference</h2>\n");
+    for recipe in &outline.recipes {
+        html.push_str(&html_recipe_section(recipe));
+    }
+    if diagram {
+        html.push_str(MERMAID_SCRIPT);
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn html_recipe_index(recipes: &[&Recipe]) -> String {
+    let mut index = String::from(
+        "<table>\n<thead><tr><th>Recipe</th><th>Description</th></tr></thead>\n<tbody>\n",
+    );
+    for recipe in recipes {
+        index.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            html_link(&recipe.name),
+            escape_html(recipe.documentation.as_deref().unwrap_or(""))
+        ));
+    }
+    index.push_str("</tbody>\n</table>\n");
+    index
+}
+
+fn html_recipe_section(recipe: &Recipe) -> String {
+    let code = |text: &str| format!("<code>{}</code>", escape_html(text));
+    let mut section = format!(
+        "<section id=\"{}\">\n<h3>{}</h3>\n",
+        anchor(&recipe.name),
+        code(&recipe.name)
+    );
+    if let Some(documentation) = &recipe.documentation {
+        let documentation = escape_html(documentation).replace('\n', "<br>\n");
+        section.push_str(&format!("<p>{documentation}</p>\n"));
+    }
+    section.push_str(&format!(
+        "<pre><code>{}</code></pre>\n",
+        escape_html(&usage(recipe))
+    ));
+
+    if !recipe.parameters.is_empty() {
+        section.push_str("<p><strong>Parameters:</strong></p>\n<ul>\n");
+        for param in &recipe.parameters {
+            section.push_str(&format!(
+                "<li>{} ({})</li>\n",
+                code(&param.name),
+                parameter_details(recipe, param, code)
+            ));
+        }
+        section.push_str("</ul>\n");
+    }
+
+    if !recipe.dependencies.is_empty() {
+        let dependencies: Vec<String> = recipe
+            .dependencies
+            .iter()
+            .map(|name| html_link(name))
+            .collect();
+        section.push_str(&format!(
+            "<p><strong>Runs first:</strong> {}</p>\n",
+            dependencies.join(", ")
+        ));
+    }
+
+    if !recipe.attributes.is_empty() {
+        let attributes: Vec<String> = recipe
+            .attributes
+            .iter()
+            .map(|attribute| code(&format!("[{attribute}]")))
+            .collect();
+        section.push_str(&format!(
+            "<p><strong>Attributes:</strong> {}</p>\n",
+            attributes.join(" ")
+        ));
+    }
+    section.push_str("</section>\n");
+    section
+}
+
+fn html_link(name: &str) -> String {
+    format!(
+        "<a href=\"#{}\"><code>{}</code></a>",
+        anchor(name),
+        escape_html(name)
+    )
+}
+
+/// `text` with `&`, `<`, `>` and quotes escaped for HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Type, default and whether `param` is required, with code spans made by
+/// `code`, e.g. `` `int`, default `8080` ``.
+fn parameter_details(recipe: &Recipe, param: &Parameter, code: impl Fn(&str) -> String) -> String {
+    let mut details = Vec::new();
+    if let Some(param_type) = recipe.parameter_types.get(&param.name) {
+        details.push(code(&param_type.to_string()));
+    }
+    match param.default_value.as_deref() {
+        Some("") => details.push("optional".to_string()),
+        Some(default) => details.push(format!("default {}", code(default))),
+        None => details.push("required".to_string()),
+    }
+    details.join(", ")
+}
+
+/// Plain-text table of every recipe for `just-mcp list`: its name,
+/// parameters and the first line of its documentation.
+pub fn recipe_table(justfile: &Justfile) -> String {
+    let rows: Vec<[String; 3]> = justfile
+        .recipes
+        .iter()
+        .map(|recipe| {
+            let documentation = recipe.documentation.as_deref().unwrap_or("");
+            [
+                recipe.name.clone(),
+                parameter_list(recipe),
+                documentation.lines().next().unwrap_or("").to_string(),
+            ]
+        })
+        .collect();
+    let header = ["RECIPE", "PARAMETERS", "DESCRIPTION"].map(str::to_string);
+    let name_width = rows.iter().chain([&header]).map(|row| row[0].len()).max();
+    let params_width = rows.iter().chain([&header]).map(|row| row[1].len()).max();
+
+    let mut table = String::new();
+    for [name, params, description] in [header].iter().chain(&rows) {
+        let line = format!(
+            "{name:name_width$}  {params:params_width$}  {description}",
+            name_width = name_width.unwrap_or(0),
+            params_width = params_width.unwrap_or(0)
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// How to run the recipe, e.g. `just deploy <env> [port=8080]`.
+fn usage(recipe: &Recipe) -> String {
+    match parameter_list(recipe).as_str() {
+        "" => format!("just {}", recipe.name),
+        params => format!("just {} {params}", recipe.name),
+    }
+}
+
+/// Parameters in usage form, e.g. `<env> [port=8080]`.
+fn parameter_list(recipe: &Recipe) -> String {
+    let params: Vec<String> = recipe
+        .parameters
+        .iter()
+        .map(|param| match param.default_value.as_deref() {
+            Some("") => format!("[{}]", param.name),
+            Some(default) => format!("[{}={default}]", param.name),
+            None => format!("<{}>", param.name),
+        })
+        .collect();
+    params.join(" ")
+}
+
+/// GitHub-style anchor of a `### \`name\`` heading.
+fn anchor(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_'))
+        .collect()
+}
+
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    const JUSTFILE: &str = "version := \"1.0\"\n\n# Build the project\nbuild:\n    cargo build\n\n# Deploy to an environment\n# @param env: enum(dev,prod)\n[group('ops')]\ndeploy env port=\"8080\": build\n    ./deploy {{ env }} {{ port }}\n\n[private]\n_cleanup:\n    rm -rf tmp\n";
+
+    #[test]
+    fn test_document_justfile() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let markdown = document_justfile(&justfile, "Justfile reference", false);
+
+        assert!(markdown.starts_with("# Justfile reference\n\n## Recipes\n"));
+        assert!(markdown.contains(
+            "\n### ops\n\n| Recipe | Description |\n| --- | --- |\n| [`deploy`](#deploy) | Deploy to an environment |\n"
+        ));
+        assert!(markdown.contains("\n### Other\n"));
+        assert!(markdown.contains("| [`build`](#build) | Build the project |\n"));
+        assert!(markdown.contains("```mermaid\nflowchart LR\n"));
+        assert!(markdown.contains("| `version` | `\"1.0\"` |\n"));
+        assert!(markdown.contains("```sh\njust deploy <env> [port=8080]\n```"));
+        assert!(markdown.contains("- `env` (`enum(dev,prod)`, required)\n"));
+        assert!(markdown.contains("- `port` (default `8080`)\n"));
+        assert!(markdown.contains("**Runs first:** [`build`](#build)\n"));
+        assert!(!markdown.contains("_cleanup"));
+
+        let markdown = document_justfile(&justfile, "Justfile reference", true);
+        assert!(markdown.contains("### `_cleanup`"));
+        assert!(markdown.contains("**Attributes:** `[private]`"));
+    }
+
+    #[test]
+    fn test_without_groups_or_dependencies() {
+        let justfile =
+            parse_justfile_str("# Say hi\nhi name=\"\":\n    echo hi {{ name }}\n").unwrap();
+        let markdown = document_justfile(&justfile, "Recipes", false);
+
+        assert!(markdown.contains("## Recipes\n\n| Recipe | Description |"));
+        assert!(!markdown.contains("### Other"));
+        assert!(!markdown.contains("mermaid"));
+        assert!(!markdown.contains("## Variables"));
+        assert!(markdown.contains("just hi [name]"));
+        assert!(markdown.contains("- `name` (optional)\n"));
+    }
+
+    #[test]
+    fn test_document_justfile_html() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let html = document_justfile_html(&justfile, "Tools & <scripts>", false);
+
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<title>Tools &amp; &lt;scripts&gt;</title>"));
+        assert!(html.contains("<h3>ops</h3>\n<table>\n"));
+        assert!(html.contains(
+            "<tr><td><a href=\"#deploy\"><code>deploy</code></a></td><td>Deploy to an environment</td></tr>\n"
+        ));
+        assert!(html.contains("<h3>Other</h3>\n"));
+        assert!(html.contains("<pre class=\"mermaid\">\nflowchart LR\n"));
+        assert!(html.contains(" --&gt; "));
+        assert!(
+            html.contains("<td><code>version</code></td><td><code>&quot;1.0&quot;</code></td>")
+        );
+        assert!(html.contains("<section id=\"deploy\">\n<h3><code>deploy</code></h3>\n"));
+        assert!(html.contains("<pre><code>just deploy &lt;env&gt; [port=8080]</code></pre>\n"));
+        assert!(
+            html.contains("<li><code>env</code> (<code>enum(dev,prod)</code>, required)</li>\n")
+        );
+        assert!(html.contains("<li><code>port</code> (default <code>8080</code>)</li>\n"));
+        assert!(html.contains(
+            "<p><strong>Runs first:</strong> <a href=\"#build\"><code>build</code></a></p>\n"
+        ));
+        assert!(html.contains("mermaid.initialize"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+        assert!(!html.contains("_cleanup"));
+
+        let justfile = parse_justfile_str("hi:\n    echo hi\n").unwrap();
+        let html = generate(&justfile, "Recipes", false, DocFormat::Html);
+        assert!(!html.contains("mermaid"));
+        assert!(!html.contains("<h3>Other</h3>"));
+    }
+
+    #[test]
+    fn test_doc_format() {
+        assert_eq!(DocFormat::parse("html"), Some(DocFormat::Html));
+        assert_eq!(DocFormat::parse("pdf"), None);
+        assert_eq!(DocFormat::Markdown.file_name(), "JUSTFILE.md");
+        assert_eq!(DocFormat::Html.file_name(), "JUSTFILE.html");
+        assert_eq!(
+            default_title(Path::new("/work/api/justfile")),
+            "api recipes"
+        );
+        assert_eq!(default_title(Path::new("justfile")), "Recipes");
+    }
+
+    #[test]
+    fn test_recipe_table() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        assert_eq!(
+            recipe_table(&justfile),
+            "RECIPE    PARAMETERS         DESCRIPTION\n\
+             build                        Build the project\n\
+             deploy    <env> [port=8080]  Deploy to an environment\n\
+             _cleanup\n"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::ServerConfig;
+use crate::environment;
+use crate::parser::parse_justfile_str;
+use crate::roots::find_justfile_upward;
+use crate::{tls, validator};
+
+/// Outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    /// Works, but something may surprise the user
+    Warn,
+    /// The server cannot work as configured
+    Fail,
+}
+
+/// One finding of `just-mcp doctor`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub message: String,
+    /// What to do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Ok,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Warn,
+            hint: Some(hint.into()),
+            ..Self::ok(name, message)
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Fail,
+            ..Self::warn(name, message, hint)
+        }
+    }
+}
+
+/// Check the setup the server would run with: the config files, the
+/// justfile in `working_dir` or `justfile`, the shell recipes run in, the
+/// `just` binary, dotenv files and TLS files.
+pub fn diagnose(
+    config_files: &[PathBuf],
+    working_dir: &Path,
+    justfile: Option<&Path>,
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let config = match ServerConfig::load_layered(config_files) {
+        Ok(config) => {
+            checks.push(Check::ok("config", config_message(config_files)));
+            config
+        }
+        Err(error) => {
+            checks.push(Check::fail(
+                "config",
+                error.to_string(),
+                "Fix the file, or pass --config with another one; defaults are used meanwhile",
+            ));
+            ServerConfig::default()
+        }
+    };
+    for rule in config.lints.unknown_rules() {
+        checks.push(Check::warn(
+            "config",
+            format!("unknown lint rule '{rule}'"),
+            "Remove it from [lints]; `just-mcp validate` lists the rules it reports",
+        ));
+    }
+
+    let justfile = justfile.map(Path::to_path_buf).or(config.justfile.clone());
+    checks.extend(check_justfile(
+        justfile.as_deref(),
+        working_dir,
+        config.search_boundary.as_deref(),
+    ));
+    checks.push(check_shell());
+    checks.push(check_just());
+    checks.extend(check_env_files(&config));
+    if let (Some(cert), Some(key)) = (&config.tls_cert, &config.tls_key) {
+        checks.push(match tls::load_acceptor(cert, key) {
+            Ok(_) => Check::ok("tls", format!("certificate {} loads", cert.display())),
+            Err(error) => Check::fail(
+                "tls",
+                error.to_string(),
+                "tls-cert needs a PEM certificate chain, leaf first, and tls-key its PEM private key",
+            ),
+        });
+    }
+    checks
+}
+
+/// True when no check failed.
+pub fn is_healthy(checks: &[Check]) -> bool {
+    checks.iter().all(|check| check.status != Status::Fail)
+}
+
+/// The checks as text, one `[status] name: message` line each with its
+/// hint indented below.
+pub fn to_text(checks: &[Check]) -> String {
+    let mut text = String::new();
+    for check in checks {
+        let status = match check.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        text.push_str(&format!("[{status}] {}: {}\n", check.name, check.message));
+        if let Some(hint) = &check.hint {
+            text.push_str(&format!("       {hint}\n"));
+        }
+    }
+    text
+}
+
+fn config_message(config_files: &[PathBuf]) -> String {
+    if config_files.is_empty() {
+        return "no config file, using defaults".to_string();
+    }
+    let names: Vec<String> = config_files
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    format!("loaded {}", names.join(", "))
+}
+
+fn check_justfile(
+    explicit: Option<&Path>,
+    working_dir: &Path,
+    boundary: Option<&Path>,
+) -> Vec<Check> {
+    let path = match explicit {
+        Some(path) if path.is_file() => path.to_path_buf(),
+        Some(path) => {
+            return vec![Check::fail(
+                "justfile",
+                format!("{} not found", path.display()),
+                "Check the --justfile flag or the config's `justfile` key",
+            )];
+        }
+        None => match find_justfile_upward(working_dir, boundary) {
+            Some(path) => path,
+            None => {
+                return vec![Check::fail(
+                    "justfile",
+                    format!("no justfile in {} or above it", working_dir.display()),
+                    "Create one, e.g. with the init_justfile tool, or point --directory or --justfile at a project",
+                )];
+            }
+        },
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(error) => {
+            return vec![Check::fail(
+                "justfile",
+                format!("cannot read {}: {error}", path.display()),
+                "Make the file readable by the user the server runs as",
+            )];
+        }
+    };
+    let justfile = match parse_justfile_str(&content) {
+        Ok(justfile) => justfile,
+        Err(error) => {
+            return vec![Check::fail(
+                "justfile",
+                format!("{}: {error}", path.display()),
+                "Fix the syntax error; `just --fmt --check` shows what just makes of it",
+            )];
+        }
+    };
+
+    let mut checks = Vec::new();
+    let errors = validator::validate_justfile(&justfile).errors;
+    checks.push(match errors.first() {
+        None => Check::ok(
+            "justfile",
+            format!(
+                "{} parses, {} recipe(s)",
+                path.display(),
+                justfile.recipes.len()
+            ),
+        ),
+        Some(first) => Check::fail(
+            "justfile",
+            format!(
+                "{} has {} error(s), first: {}",
+                path.display(),
+                errors.len(),
+                first.message
+            ),
+            format!(
+                "Run `just-mcp validate {}` for every diagnostic",
+                path.display()
+            ),
+        ),
+    });
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let dotenv = environment::dotenv_status(&justfile, dir);
+    if dotenv.load_enabled && !dotenv.exists {
+        checks.push(Check::warn(
+            "dotenv",
+            format!("`set dotenv-load` is on but {} does not exist", dotenv.path),
+            "Create the file, or drop the setting if the variables come from elsewhere",
+        ));
+    }
+    checks
+}
+
+fn check_shell() -> Check {
+    match Command::new("sh").args(["-c", "true"]).status() {
+        Ok(status) if status.success() => Check::ok("shell", "sh runs recipe lines"),
+        Ok(status) => Check::fail(
+            "shell",
+            format!("`sh -c true` exited with {status}"),
+            "Recipes run through `sh -c`; check the shell installed as sh",
+        ),
+        Err(error) => Check::fail(
+            "shell",
+            format!("cannot run sh: {error}"),
+            "Recipes run through `sh -c`; install a POSIX shell and put it on PATH",
+        ),
+    }
+}
+
+fn check_just() -> Check {
+    match Command::new("just").arg("--version").output() {
+        Ok(output) if output.status.success() => Check::ok(
+            "just",
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        _ => Check::warn(
+            "just",
+            "just is not installed",
+            "just-mcp runs recipes without it, but install just to compare results with `just <recipe>`",
+        ),
+    }
+}
+
+fn check_env_files(config: &ServerConfig) -> Vec<Check> {
+    config
+        .env_files
+        .iter()
+        .map(|path| match dotenvy::from_path_iter(path) {
+            Ok(entries) => match entries.collect::<Result<Vec<_>, _>>() {
+                Ok(entries) => Check::ok(
+                    "env-files",
+                    format!("{} defines {} variables", path.display(), entries.len()),
+                ),
+                Err(error) => Check::fail(
+                    "env-files",
+                    format!("{}: {error}", path.display()),
+                    "Fix the line; dotenv files hold NAME=value lines",
+                ),
+            },
+            Err(error) => Check::fail(
+                "env-files",
+                format!("cannot read {}: {error}", path.display()),
+                "Create the file or remove it from env-files",
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(checks: &'a [Check], name: &str) -> &'a Check {
+        checks.iter().find(|check| check.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_healthy_project() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("justfile"), "build:\n    cargo build\n").unwrap();
+        let checks = diagnose(&[], dir.path(), None);
+        assert_eq!(find(&checks, "config").status, Status::Ok);
+        assert_eq!(find(&checks, "justfile").status, Status::Ok);
+        assert!(find(&checks, "justfile").message.contains("1 recipe(s)"));
+        assert_eq!(find(&checks, "shell").status, Status::Ok);
+        assert!(is_healthy(&checks));
+    }
+
+    #[test]
+    fn test_problems_are_reported() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = dir.path().join("just-mcp.toml");
+        std::fs::write(&config, "env-files = [\"missing.env\"]\n").unwrap();
+        std::fs::write(
+            dir.path().join("justfile"),
+            "set dotenv-load\n\nbuild: generate\n    cargo build\n",
+        )
+        .unwrap();
+
+        let checks = diagnose(&[config], dir.path(), None);
+        assert_eq!(find(&checks, "justfile").status, Status::Fail);
+        assert_eq!(find(&checks, "dotenv").status, Status::Warn);
+        assert_eq!(find(&checks, "env-files").status, Status::Fail);
+        assert!(!is_healthy(&checks));
+        assert!(to_text(&checks).contains("[FAIL] env-files: cannot read"));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = dir.path().join("just-mcp.toml");
+        std::fs::write(&config, "watch-interval = \"often\"\n").unwrap();
+        let checks = diagnose(&[config], dir.path(), None);
+        assert_eq!(find(&checks, "config").status, Status::Fail);
+        assert_eq!(find(&checks, "justfile").status, Status::Fail);
+        assert!(find(&checks, "justfile").hint.is_some());
+    }
+}
@@ -0,0 +1,911 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::Deserialize;
+use snafu::prelude::*;
+
+use crate::parser::{ParserError, parse_justfile_str};
+use crate::{Justfile, ParameterType, Recipe};
+
+#[derive(Debug, Snafu)]
+pub enum EditError {
+    #[snafu(display("Justfile does not parse: {}", source))]
+    SourceInvalid { source: ParserError },
+
+    #[snafu(display("Edited justfile does not parse: {}", source))]
+    ResultInvalid { source: ParserError },
+
+    #[snafu(display(
+        "Invalid name '{}': use letters, digits, '_' and '-', not starting with a digit or '-'",
+        name
+    ))]
+    InvalidName { name: String },
+
+    #[snafu(display("A recipe or alias named '{}' already exists", name))]
+    AlreadyExists { name: String },
+
+    #[snafu(display("Recipe '{}' not found", name))]
+    UnknownRecipe { name: String },
+
+    #[snafu(display(
+        "Recipe '{}' is a dependency of {}; pass force to delete it anyway",
+        name,
+        dependents.join(", ")
+    ))]
+    HasDependents {
+        name: String,
+        dependents: Vec<String>,
+    },
+
+    #[snafu(display("Value of '{}' must be a single line", name))]
+    MultilineValue { name: String },
+
+    #[snafu(display("Dependency '{}' is not a recipe or alias", name))]
+    UnknownDependency { name: String },
+
+    #[snafu(display("Unrecognized type '{}' for parameter '{}'", annotation, parameter))]
+    InvalidParameterType {
+        parameter: String,
+        annotation: String,
+    },
+
+    #[snafu(display(
+        "Recipe '{}' does not read back as written; check parameter defaults",
+        name
+    ))]
+    NotRoundTripped { name: String },
+}
+
+pub type Result<T> = std::result::Result<T, EditError>;
+
+/// Indentation used for recipe bodies when the justfile has none yet.
+const DEFAULT_INDENT: &str = "    ";
+
+/// A recipe to write to a justfile.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecipeDefinition {
+    pub name: String,
+    pub parameters: Vec<ParameterDefinition>,
+    /// Written as a `# ...` comment above the recipe
+    pub documentation: Option<String>,
+    pub dependencies: Vec<String>,
+    /// Commands, one per line; indentation is normalized
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
+pub struct ParameterDefinition {
+    pub name: String,
+    /// Default value; quoted unless already a quoted string or expression
+    pub default: Option<String>,
+    /// Declared type, written as `# @param NAME: TYPE`, e.g. `int` or `enum(dev,prod)`
+    #[serde(rename = "type")]
+    pub parameter_type: Option<String>,
+}
+
+/// Changes to an existing recipe; `None` keeps the current value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecipeUpdate {
+    /// Replaces all parameters and their `@param` annotations
+    pub parameters: Option<Vec<ParameterDefinition>>,
+    /// An empty string removes the doc comment
+    pub documentation: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+    pub body: Option<String>,
+}
+
+/// Append `recipe` to the justfile `source`, returning the new source.
+/// The result is checked to parse and to contain the recipe as written.
+pub fn create_recipe(source: &str, recipe: &RecipeDefinition) -> Result<String> {
+    let justfile = parse_justfile_str(source).context(SourceInvalidSnafu)?;
+    check_name(&recipe.name)?;
+    ensure!(
+        !justfile.recipes.iter().any(|r| r.name == recipe.name)
+            && !justfile.aliases.contains_key(&recipe.name),
+        AlreadyExistsSnafu { name: &recipe.name }
+    );
+    check_dependencies(&justfile, &recipe.dependencies)?;
+
+    let mut rendered: Vec<String> = doc_comment(recipe.documentation.as_deref())
+        .into_iter()
+        .collect();
+    rendered.extend(parameter_annotations(&recipe.parameters)?);
+    rendered.push(format!(
+        "{}:{}",
+        header_prefix(&recipe.name, &recipe.parameters),
+        dependency_list(&recipe.dependencies)
+    ));
+    rendered.extend(reindent(&recipe.body, &indentation(&justfile, None)));
+
+    let mut edited = source.trim_end().to_string();
+    if !edited.is_empty() {
+        edited.push_str("\n\n");
+    }
+    for line in rendered {
+        edited.push_str(&line);
+        edited.push('\n');
+    }
+
+    check_result(
+        &edited,
+        &recipe.name,
+        &parameter_names(&recipe.parameters),
+        &recipe.dependencies,
+    )?;
+    Ok(edited)
+}
+
+/// Apply `update` to the recipe `name` in place. Lines that are not
+/// affected, including attributes, comments and other recipes, are kept
+/// byte for byte.
+pub fn update_recipe(source: &str, name: &str, update: &RecipeUpdate) -> Result<String> {
+    let justfile = parse_justfile_str(source).context(SourceInvalidSnafu)?;
+    let recipe = justfile
+        .recipes
+        .iter()
+        .find(|recipe| recipe.name == name)
+        .context(UnknownRecipeSnafu { name })?;
+    if let Some(dependencies) = &update.dependencies {
+        check_dependencies(&justfile, dependencies)?;
+    }
+
+    let lines: Vec<&str> = source.lines().collect();
+    let header_index = recipe.span.start_line - 1;
+    let preamble_start = preamble_start(&lines, header_index);
+
+    // Doc comment and `@param` lines that are being replaced are dropped
+    // and their replacements written just above the header
+    let doc_index = (preamble_start..header_index)
+        .rev()
+        .find(|&index| is_doc_comment(lines[index]));
+    let mut edited: Vec<String> = Vec::new();
+    for (index, line) in lines.iter().enumerate().take(header_index) {
+        let replaced = index >= preamble_start
+            && ((update.documentation.is_some() && Some(index) == doc_index)
+                || (update.parameters.is_some() && is_param_annotation(line)));
+        if !replaced {
+            edited.push(line.to_string());
+        }
+    }
+    if let Some(documentation) = &update.documentation {
+        edited.extend(doc_comment(Some(documentation)));
+    }
+    if let Some(parameters) = &update.parameters {
+        edited.extend(parameter_annotations(parameters)?);
+    }
+
+    // Header: `[@]name params: dependencies`, split at the first colon as
+    // the parser does
+    let header = lines[header_index];
+    let (prefix, dependencies) = header.split_once(':').unwrap_or((header, ""));
+    let prefix = match &update.parameters {
+        Some(parameters) => {
+            let quiet = if recipe.quiet { "@" } else { "" };
+            format!("{quiet}{}", header_prefix(name, parameters))
+        }
+        None => prefix.to_string(),
+    };
+    let dependencies = match &update.dependencies {
+        Some(dependencies) => dependency_list(dependencies),
+        None => dependencies.to_string(),
+    };
+    edited.push(format!("{prefix}:{dependencies}"));
+
+    let body_end = recipe.span.end_line;
+    match &update.body {
+        Some(body) => edited.extend(reindent(body, &indentation(&justfile, Some(recipe)))),
+        None => edited.extend(
+            lines[header_index + 1..body_end]
+                .iter()
+                .map(|line| line.to_string()),
+        ),
+    }
+    edited.extend(lines[body_end..].iter().map(|line| line.to_string()));
+
+    let edited = join_lines(edited, source);
+
+    let parameters = match &update.parameters {
+        Some(parameters) => parameter_names(parameters),
+        None => recipe
+            .parameters
+            .iter()
+            .map(|param| param.name.clone())
+            .collect(),
+    };
+    let dependencies = update
+        .dependencies
+        .clone()
+        .unwrap_or_else(|| recipe.dependencies.clone());
+    check_result(&edited, name, &parameters, &dependencies)?;
+    Ok(edited)
+}
+
+/// Remove the recipe `name` with its doc comment and attributes. Aliases
+/// of it are removed too. Recipes that depend on it make this fail unless
+/// `force` is set, in which case the dependency is dropped from them.
+pub fn delete_recipe(source: &str, name: &str, force: bool) -> Result<String> {
+    let justfile = parse_justfile_str(source).context(SourceInvalidSnafu)?;
+    let recipe = justfile
+        .recipes
+        .iter()
+        .find(|recipe| recipe.name == name)
+        .context(UnknownRecipeSnafu { name })?;
+
+    // A dependency may name the recipe or one of its aliases
+    let mut names: Vec<&str> = justfile
+        .aliases
+        .iter()
+        .filter(|(_, target)| target.as_str() == name)
+        .map(|(alias, _)| alias.as_str())
+        .collect();
+    names.push(name);
+    let dependents: Vec<&Recipe> = justfile
+        .recipes
+        .iter()
+        .filter(|other| other.name != name)
+        .filter(|other| {
+            other
+                .dependencies
+                .iter()
+                .any(|dependency| names.contains(&dependency_name(dependency)))
+        })
+        .collect();
+    ensure!(
+        dependents.is_empty() || force,
+        HasDependentsSnafu {
+            name,
+            dependents: dependents
+                .iter()
+                .map(|recipe| recipe.name.clone())
+                .collect::<Vec<_>>(),
+        }
+    );
+
+    let lines: Vec<&str> = source.lines().collect();
+    let mut edited: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    for dependent in &dependents {
+        let index = dependent.span.start_line - 1;
+        edited[index] = map_dependencies(&edited[index], |dependency, group| {
+            (!names.contains(&dependency)).then(|| group.to_string())
+        });
+    }
+
+    // Take the blank lines after the recipe with it, or those before it
+    // when it is the last thing in the file
+    let mut start = preamble_start(&lines, recipe.span.start_line - 1);
+    let mut end = recipe.span.end_line;
+    while end < lines.len() && lines[end].trim().is_empty() {
+        end += 1;
+    }
+    if end == lines.len() {
+        while start > 0 && lines[start - 1].trim().is_empty() {
+            start -= 1;
+        }
+    }
+    let edited: Vec<String> = edited
+        .into_iter()
+        .enumerate()
+        .filter(|(index, line)| {
+            !(start..end).contains(index) && alias_target(line).is_none_or(|t| t != name)
+        })
+        .map(|(_, line)| line)
+        // Removed aliases may leave a blank first line behind
+        .skip_while(|line| line.trim().is_empty())
+        .collect();
+    let edited = join_lines(edited, source);
+
+    let justfile = parse_justfile_str(&edited).context(ResultInvalidSnafu)?;
+    ensure!(
+        !justfile.recipes.iter().any(|recipe| recipe.name == name),
+        NotRoundTrippedSnafu { name }
+    );
+    Ok(edited)
+}
+
+/// Rename the recipe `name` to `new_name`, updating the dependencies of
+/// other recipes and aliases that point at it.
+pub fn rename_recipe(source: &str, name: &str, new_name: &str) -> Result<String> {
+    let justfile = parse_justfile_str(source).context(SourceInvalidSnafu)?;
+    let recipe = justfile
+        .recipes
+        .iter()
+        .find(|recipe| recipe.name == name)
+        .context(UnknownRecipeSnafu { name })?;
+    check_name(new_name)?;
+    ensure!(
+        !justfile.recipes.iter().any(|r| r.name == new_name)
+            && !justfile.aliases.contains_key(new_name),
+        AlreadyExistsSnafu { name: new_name }
+    );
+
+    let mut edited: Vec<String> = source.lines().map(str::to_string).collect();
+    for other in &justfile.recipes {
+        let index = other.span.start_line - 1;
+        edited[index] = map_dependencies(&edited[index], |dependency, group| {
+            Some(if dependency == name {
+                group.replacen(name, new_name, 1)
+            } else {
+                group.to_string()
+            })
+        });
+    }
+
+    let header = &mut edited[recipe.span.start_line - 1];
+    let quiet = if recipe.quiet { "@" } else { "" };
+    let rest = &header[quiet.len() + name.len()..];
+    *header = format!("{quiet}{new_name}{rest}");
+
+    for line in &mut edited {
+        if alias_target(line).is_some_and(|target| target == name)
+            && let Some((alias, _)) = line.split_once(":=")
+        {
+            *line = format!("{alias}:= {new_name}");
+        }
+    }
+    let edited = join_lines(edited, source);
+
+    let parameters: Vec<String> = recipe
+        .parameters
+        .iter()
+        .map(|param| param.name.clone())
+        .collect();
+    let dependencies: Vec<String> = recipe
+        .dependencies
+        .iter()
+        .map(|dependency| {
+            if dependency_name(dependency) == name {
+                dependency.replacen(name, new_name, 1)
+            } else {
+                dependency.clone()
+            }
+        })
+        .collect();
+    check_result(&edited, new_name, &parameters, &dependencies)?;
+    Ok(edited)
+}
+
+/// Set the top-level variable `name` to the expression `value`, e.g.
+/// `"1.2.0"`. An existing assignment is replaced in place; a new one is
+/// added after the last variable, or at the top of the file. `export`
+/// adds or removes the `export` keyword; `None` keeps it as it is.
+pub fn set_variable(source: &str, name: &str, value: &str, export: Option<bool>) -> Result<String> {
+    let justfile = parse_justfile_str(source).context(SourceInvalidSnafu)?;
+    check_name(name)?;
+    let value = value.trim();
+    ensure!(!value.contains('\n'), MultilineValueSnafu { name });
+
+    let exported = export.unwrap_or_else(|| justfile.exported.contains(name));
+    let keyword = if exported { "export " } else { "" };
+    let assignment = format!("{keyword}{name} := {value}");
+
+    let mut edited: Vec<String> = source.lines().map(str::to_string).collect();
+    match justfile.variable_spans.get(name) {
+        Some(span) => edited[span.start_line - 1] = assignment,
+        None => {
+            let last_variable = justfile
+                .variable_spans
+                .values()
+                .map(|span| span.start_line)
+                .max();
+            match last_variable {
+                Some(line) => edited.insert(line, assignment),
+                None if edited.is_empty() => edited.push(assignment),
+                None => {
+                    edited.insert(0, assignment);
+                    if !edited[1].trim().is_empty() {
+                        edited.insert(1, String::new());
+                    }
+                }
+            }
+        }
+    }
+    let mut edited = join_lines(edited, source);
+    if !edited.ends_with('\n') {
+        edited.push('\n');
+    }
+
+    let justfile = parse_justfile_str(&edited).context(ResultInvalidSnafu)?;
+    ensure!(
+        justfile.variables.get(name).map(String::as_str) == Some(value)
+            && justfile.exported.contains(name) == exported,
+        NotRoundTrippedSnafu { name }
+    );
+    Ok(edited)
+}
+
+/// Recipe named by a dependency as the parser splits it: `build`,
+/// `(build)`, or `(build` for the first word of `(build "release")`.
+fn dependency_name(dependency: &str) -> &str {
+    let name = dependency.strip_prefix('(').unwrap_or(dependency);
+    name.strip_suffix(')').unwrap_or(name)
+}
+
+/// Target of an `alias NAME := RECIPE` line.
+fn alias_target(line: &str) -> Option<&str> {
+    let (_, target) = line.trim().strip_prefix("alias ")?.split_once(":=")?;
+    Some(target.trim())
+}
+
+/// Rewrite the dependencies of a recipe header. `rewrite` receives each
+/// dependency's recipe name and its full text, e.g. `(build "release")`,
+/// and returns the replacement or `None` to drop it. Headers with nothing
+/// to change are returned as they are.
+fn map_dependencies(header: &str, rewrite: impl Fn(&str, &str) -> Option<String>) -> String {
+    let Some((prefix, dependencies)) = header.split_once(':') else {
+        return header.to_string();
+    };
+
+    // Group `(name args...)` calls into a single dependency
+    let mut groups: Vec<String> = Vec::new();
+    let mut open = false;
+    for word in dependencies.split_whitespace() {
+        if open {
+            let group = groups.last_mut().expect("open group");
+            group.push(' ');
+            group.push_str(word);
+        } else {
+            groups.push(word.to_string());
+            open = word.starts_with('(');
+        }
+        if word.ends_with(')') {
+            open = false;
+        }
+    }
+
+    let rewritten: Vec<String> = groups
+        .iter()
+        .filter_map(|group| {
+            let name = dependency_name(group.split_whitespace().next().unwrap_or(group));
+            rewrite(name, group)
+        })
+        .collect();
+    if rewritten == groups {
+        return header.to_string();
+    }
+    format!("{prefix}:{}", dependency_list(&rewritten))
+}
+
+/// Lines joined back into a source, keeping the original trailing newline.
+fn join_lines(lines: Vec<String>, source: &str) -> String {
+    let mut text = lines.join("\n");
+    if source.ends_with('\n') && !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+/// First line of the comments and attributes directly above a header.
+fn preamble_start(lines: &[&str], header_index: usize) -> usize {
+    let mut start = header_index;
+    while start > 0 {
+        let line = lines[start - 1].trim();
+        if !(line.starts_with('#') || line.starts_with('[')) {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+fn is_param_annotation(line: &str) -> bool {
+    line.trim()
+        .strip_prefix('#')
+        .is_some_and(|comment| comment.trim().starts_with("@param "))
+}
+
+fn is_doc_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with('#') && !is_param_annotation(line) && !line[1..].trim().is_empty()
+}
+
+/// The parser keeps a single comment line as documentation, so multi-line
+/// text is joined. Empty text yields no comment.
+fn doc_comment(documentation: Option<&str>) -> Option<String> {
+    let documentation: Vec<&str> = documentation?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    (!documentation.is_empty()).then(|| format!("# {}", documentation.join(" ")))
+}
+
+fn parameter_annotations(parameters: &[ParameterDefinition]) -> Result<Vec<String>> {
+    let mut annotations = Vec::new();
+    for param in parameters {
+        check_name(&param.name)?;
+        if let Some(annotation) = &param.parameter_type {
+            let parameter_type =
+                ParameterType::parse(annotation).with_context(|| InvalidParameterTypeSnafu {
+                    parameter: &param.name,
+                    annotation,
+                })?;
+            annotations.push(format!("# @param {}: {parameter_type}", param.name));
+        }
+    }
+    Ok(annotations)
+}
+
+/// Recipe name followed by its parameters, e.g. `deploy env region="us"`.
+fn header_prefix(name: &str, parameters: &[ParameterDefinition]) -> String {
+    let mut prefix = name.to_string();
+    for param in parameters {
+        prefix.push(' ');
+        prefix.push_str(&param.name);
+        if let Some(default) = &param.default {
+            prefix.push('=');
+            prefix.push_str(&quote_value(default));
+        }
+    }
+    prefix
+}
+
+/// Dependencies as written after the header colon, with a leading space.
+fn dependency_list(dependencies: &[String]) -> String {
+    dependencies
+        .iter()
+        .map(|dependency| format!(" {dependency}"))
+        .collect()
+}
+
+fn parameter_names(parameters: &[ParameterDefinition]) -> Vec<String> {
+    parameters.iter().map(|param| param.name.clone()).collect()
+}
+
+/// Body lines with their common leading spaces and tabs replaced by
+/// `indent`. Trailing blank lines are dropped; inner blank lines are kept
+/// empty.
+fn reindent(body: &str, indent: &str) -> Vec<String> {
+    let lines: Vec<&str> = body.trim_end().lines().map(str::trim_end).collect();
+    // Other whitespace, such as a non-breaking space, is part of the line,
+    // so cutting the indentation never splits a character
+    let common = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .skip_while(|line| line.is_empty())
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{indent}{}", &line[common..])
+            }
+        })
+        .collect()
+}
+
+/// A value as a just string literal, unless it already is a quoted string,
+/// backtick or parenthesized expression.
+pub fn quote_value(value: &str) -> String {
+    let already_quoted = ['"', '\'', '`', '(']
+        .iter()
+        .any(|&quote| value.starts_with(quote));
+    if already_quoted {
+        value.to_string()
+    } else if value.contains('"') {
+        format!("'{value}'")
+    } else {
+        format!("\"{value}\"")
+    }
+}
+
+/// Indentation of the first indented body line of `recipe`, or else of any
+/// recipe, so edits match the file's existing style.
+fn indentation(justfile: &Justfile, recipe: Option<&Recipe>) -> String {
+    recipe
+        .into_iter()
+        .chain(&justfile.recipes)
+        .flat_map(|recipe| recipe.body.lines())
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+        .filter(|indent| !indent.is_empty())
+        .unwrap_or_else(|| DEFAULT_INDENT.to_string())
+}
+
+/// Recipe and parameter names as just accepts them.
+fn check_name(name: &str) -> Result<()> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    ensure!(valid, InvalidNameSnafu { name });
+    Ok(())
+}
+
+fn check_dependencies(justfile: &Justfile, dependencies: &[String]) -> Result<()> {
+    for dependency in dependencies {
+        ensure!(
+            justfile.recipes.iter().any(|r| &r.name == dependency)
+                || justfile.aliases.contains_key(dependency),
+            UnknownDependencySnafu { name: dependency }
+        );
+    }
+    Ok(())
+}
+
+/// The edited source parses and declares `name` with the expected
+/// parameters and dependencies.
+fn check_result(
+    edited: &str,
+    name: &str,
+    parameters: &[String],
+    dependencies: &[String],
+) -> Result<()> {
+    let justfile = parse_justfile_str(edited).context(ResultInvalidSnafu)?;
+    let written = justfile.recipes.iter().find(|r| r.name == name);
+    let round_tripped = written.is_some_and(|written| {
+        written
+            .parameters
+            .iter()
+            .map(|param| &param.name)
+            .eq(parameters)
+            && written.dependencies == dependencies
+    });
+    ensure!(round_tripped, NotRoundTrippedSnafu { name });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deploy() -> RecipeDefinition {
+        RecipeDefinition {
+            name: "deploy".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "env".to_string(),
+                    default: None,
+                    parameter_type: Some("enum(dev,prod)".to_string()),
+                },
+                ParameterDefinition {
+                    name: "region".to_string(),
+                    default: Some("us-east-1".to_string()),
+                    parameter_type: None,
+                },
+            ],
+            documentation: Some("Deploy the app".to_string()),
+            dependencies: vec!["build".to_string()],
+            body: "./deploy.sh {{ env }}\necho done".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_create_recipe_appends_formatted_recipe() {
+        let source = "build:\n  cargo build\n";
+        let edited = create_recipe(source, &deploy()).unwrap();
+        assert_eq!(
+            edited,
+            "build:\n  cargo build\n\n# Deploy the app\n# @param env: enum(dev,prod)\ndeploy env region=\"us-east-1\": build\n  ./deploy.sh {{ env }}\n  echo done\n"
+        );
+
+        let justfile = parse_justfile_str(&edited).unwrap();
+        let recipe = &justfile.recipes[1];
+        assert_eq!(recipe.documentation.as_deref(), Some("Deploy the app"));
+        assert_eq!(
+            recipe.parameters[1].default_value.as_deref(),
+            Some("us-east-1")
+        );
+        assert!(recipe.parameter_types.contains_key("env"));
+    }
+
+    #[test]
+    fn test_create_recipe_in_empty_justfile() {
+        let recipe = RecipeDefinition {
+            name: "hello".to_string(),
+            body: "    echo hello\n\n    echo again\n".to_string(),
+            ..RecipeDefinition::default()
+        };
+        let edited = create_recipe("", &recipe).unwrap();
+        assert_eq!(edited, "hello:\n    echo hello\n\n    echo again\n");
+
+        let recipe = RecipeDefinition {
+            name: "hello".to_string(),
+            body: "  echo a\n \u{a0}echo b\n".to_string(),
+            ..RecipeDefinition::default()
+        };
+        let edited = create_recipe("", &recipe).unwrap();
+        assert_eq!(edited, "hello:\n     echo a\n    \u{a0}echo b\n");
+    }
+
+    #[test]
+    fn test_create_recipe_rejects_bad_definitions() {
+        let source = "build:\n    cargo build\n\nalias b := build\n";
+
+        let mut recipe = deploy();
+        recipe.name = "build".to_string();
+        assert!(matches!(
+            create_recipe(source, &recipe),
+            Err(EditError::AlreadyExists { .. })
+        ));
+
+        recipe.name = "b".to_string();
+        assert!(matches!(
+            create_recipe(source, &recipe),
+            Err(EditError::AlreadyExists { .. })
+        ));
+
+        recipe.name = "1st".to_string();
+        assert!(matches!(
+            create_recipe(source, &recipe),
+            Err(EditError::InvalidName { .. })
+        ));
+
+        let mut recipe = deploy();
+        recipe.dependencies = vec!["missing".to_string()];
+        assert!(matches!(
+            create_recipe(source, &recipe),
+            Err(EditError::UnknownDependency { .. })
+        ));
+
+        let mut recipe = deploy();
+        recipe.parameters[0].parameter_type = Some("uuid".to_string());
+        assert!(matches!(
+            create_recipe(source, &recipe),
+            Err(EditError::InvalidParameterType { .. })
+        ));
+
+        let mut recipe = deploy();
+        recipe.parameters[1].default = Some("a: b".to_string());
+        assert!(matches!(
+            create_recipe(source, &recipe),
+            Err(EditError::NotRoundTripped { .. })
+        ));
+    }
+
+    const SOURCE: &str = "# Build it\n[linux]\nbuild target=\"debug\":\n    cargo build --profile {{ target }}\n\n    echo built\n\n# @param env: enum(dev,prod)\n@deploy env: build\n  ./deploy.sh {{ env }}\n";
+
+    #[test]
+    fn test_update_recipe_body_keeps_everything_else() {
+        let update = RecipeUpdate {
+            body: Some("cargo build\ncargo doc".to_string()),
+            ..RecipeUpdate::default()
+        };
+        let edited = update_recipe(SOURCE, "build", &update).unwrap();
+        assert_eq!(
+            edited,
+            SOURCE.replace(
+                "    cargo build --profile {{ target }}\n\n    echo built\n",
+                "    cargo build\n    cargo doc\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_update_recipe_signature_and_docs() {
+        let update = RecipeUpdate {
+            parameters: Some(vec![ParameterDefinition {
+                name: "env".to_string(),
+                default: Some("dev".to_string()),
+                parameter_type: Some("enum(dev,staging,prod)".to_string()),
+            }]),
+            documentation: Some("Ship it".to_string()),
+            dependencies: Some(Vec::new()),
+            body: None,
+        };
+        let edited = update_recipe(SOURCE, "deploy", &update).unwrap();
+        assert!(edited.ends_with(
+            "    echo built\n\n# Ship it\n# @param env: enum(dev,staging,prod)\n@deploy env=\"dev\":\n  ./deploy.sh {{ env }}\n"
+        ));
+
+        // An empty description removes the doc comment; attributes stay
+        let update = RecipeUpdate {
+            documentation: Some(String::new()),
+            ..RecipeUpdate::default()
+        };
+        let edited = update_recipe(SOURCE, "build", &update).unwrap();
+        assert!(edited.starts_with("[linux]\nbuild target=\"debug\":\n"));
+        let justfile = parse_justfile_str(&edited).unwrap();
+        assert_eq!(justfile.recipes[0].documentation, None);
+        assert_eq!(justfile.recipes[0].attributes, vec!["linux"]);
+    }
+
+    #[test]
+    fn test_update_recipe_errors() {
+        assert!(matches!(
+            update_recipe(SOURCE, "missing", &RecipeUpdate::default()),
+            Err(EditError::UnknownRecipe { .. })
+        ));
+        let update = RecipeUpdate {
+            dependencies: Some(vec!["missing".to_string()]),
+            ..RecipeUpdate::default()
+        };
+        assert!(matches!(
+            update_recipe(SOURCE, "deploy", &update),
+            Err(EditError::UnknownDependency { .. })
+        ));
+    }
+
+    const DEPENDENCIES: &str = "alias b := build\n\n# Build it\nbuild:\n    cargo build\n\ntest: b\n    cargo test\n\nrelease: (build) test\n    echo release\n";
+
+    #[test]
+    fn test_delete_recipe() {
+        let source = "build:\n    cargo build\n\n# Lint it\n[group('ci')]\nlint:\n    cargo clippy\n\ntest:\n    cargo test\n";
+        let edited = delete_recipe(source, "lint", false).unwrap();
+        assert_eq!(edited, "build:\n    cargo build\n\ntest:\n    cargo test\n");
+
+        // The last recipe takes the blank lines before it
+        let edited = delete_recipe(source, "test", false).unwrap();
+        assert!(edited.ends_with("    cargo clippy\n"));
+
+        assert!(matches!(
+            delete_recipe(source, "missing", false),
+            Err(EditError::UnknownRecipe { .. })
+        ));
+    }
+
+    #[test]
+    fn test_delete_recipe_with_dependents() {
+        match delete_recipe(DEPENDENCIES, "build", false) {
+            Err(EditError::HasDependents { dependents, .. }) => {
+                assert_eq!(dependents, vec!["test", "release"]);
+            }
+            other => panic!("Expected HasDependents, got {other:?}"),
+        }
+
+        let edited = delete_recipe(DEPENDENCIES, "build", true).unwrap();
+        assert_eq!(
+            edited,
+            "test:\n    cargo test\n\nrelease: test\n    echo release\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_recipe() {
+        let edited = rename_recipe(DEPENDENCIES, "build", "compile").unwrap();
+        assert_eq!(
+            edited,
+            DEPENDENCIES
+                .replace("alias b := build", "alias b := compile")
+                .replace("build:", "compile:")
+                .replace("(build)", "(compile)")
+        );
+
+        assert!(matches!(
+            rename_recipe(DEPENDENCIES, "build", "test"),
+            Err(EditError::AlreadyExists { .. })
+        ));
+        assert!(matches!(
+            rename_recipe(DEPENDENCIES, "build", "b"),
+            Err(EditError::AlreadyExists { .. })
+        ));
+    }
+
+    #[test]
+    fn test_set_variable() {
+        let source = "# Release settings\nversion := \"1.0.0\"\nexport TOKEN := `cat token`\n\nbuild:\n    echo {{ version }}\n";
+
+        let edited = set_variable(source, "version", "\"1.2.0\"", None).unwrap();
+        assert_eq!(edited, source.replace("1.0.0", "1.2.0"));
+
+        // Export is kept unless asked otherwise
+        let edited = set_variable(source, "TOKEN", "\"abc\"", None).unwrap();
+        assert!(edited.contains("\nexport TOKEN := \"abc\"\n"));
+        let edited = set_variable(source, "TOKEN", "\"abc\"", Some(false)).unwrap();
+        assert!(edited.contains("\nTOKEN := \"abc\"\n"));
+
+        // New variables go after the last one
+        let edited = set_variable(source, "target", "\"x86_64\"", Some(true)).unwrap();
+        assert!(edited.contains("`cat token`\nexport target := \"x86_64\"\n\nbuild:"));
+
+        let edited = set_variable("build:\n    cargo build\n", "profile", "\"dev\"", None).unwrap();
+        assert_eq!(edited, "profile := \"dev\"\n\nbuild:\n    cargo build\n");
+        assert_eq!(
+            set_variable("", "a", "\"b\"", None).unwrap(),
+            "a := \"b\"\n"
+        );
+
+        assert!(matches!(
+            set_variable(source, "version", "\"1\"\n\"2\"", None),
+            Err(EditError::MultilineValue { .. })
+        ));
+        assert!(matches!(
+            set_variable(source, "bad name", "\"1\"", None),
+            Err(EditError::InvalidName { .. })
+        ));
+    }
+}
@@ -1,8 +1,14 @@
 use dotenvy;
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use snafu::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 
+use crate::Justfile;
+
 /// MCP-specific environment variables that may be relevant for server operation
 pub const MCP_ENVIRONMENT_VARIABLES: &[&str] = &[
     "MCP_SERVER_NAME",
@@ -15,21 +21,201 @@ pub const MCP_ENVIRONMENT_VARIABLES: &[&str] = &[
     "MCP_TIMEOUT_SECONDS",
 ];
 
+/// Levels `MCP_LOG_LEVEL` accepts, compared without regard to case.
+pub const MCP_LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// Parts of variable names whose values are masked in reports.
+pub(crate) const SECRET_NAME_PARTS: &[&str] = &[
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "KEY",
+    "CREDENTIAL",
+    "AUTH",
+    "PRIVATE",
+    "COOKIE",
+    "SESSION",
+];
+
+/// Shown instead of a secret value.
+pub const MASKED_VALUE: &str = "****";
+
+/// What a recipe run by the server sees of the environment, with secret
+/// values masked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EnvironmentReport {
+    /// Where variables were loaded from, e.g. `ProcessEnv`
+    pub sources: Vec<String>,
+    pub variable_count: usize,
+    /// `MCP_*` variables that are set
+    pub mcp_variables: BTreeMap<String, String>,
+    /// Requested variables; `null` when unset
+    pub variables: BTreeMap<String, Option<String>>,
+    /// Source that set each requested variable, e.g. `EnvFile(.env)`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub origins: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dotenv: Option<DotenvStatus>,
+    /// What the config, profile and session variables change in the
+    /// server's own environment
+    #[serde(default, skip_serializing_if = "EnvDiff::is_empty")]
+    pub changes: EnvDiff,
+}
+
+/// How one environment differs from an earlier one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EnvDiff {
+    /// Variables that were not set before
+    pub added: BTreeMap<String, String>,
+    /// Variables that are no longer set, with their old values
+    pub removed: BTreeMap<String, String>,
+    /// Variables whose value changed
+    pub changed: BTreeMap<String, ValueChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ValueChange {
+    pub old: String,
+    pub new: String,
+}
+
+impl EnvDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// The same diff with the values of secret-looking names masked.
+    pub fn masked(self) -> Self {
+        let mask = |values: BTreeMap<String, String>| {
+            values
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = masked(&name, &value);
+                    (name, value)
+                })
+                .collect()
+        };
+        let changed = self
+            .changed
+            .into_iter()
+            .map(|(name, change)| {
+                let change = ValueChange {
+                    old: masked(&name, &change.old),
+                    new: masked(&name, &change.new),
+                };
+                (name, change)
+            })
+            .collect();
+        Self {
+            added: mask(self.added),
+            removed: mask(self.removed),
+            changed,
+        }
+    }
+}
+
+fn diff_variables(old: &HashMap<String, String>, new: &HashMap<String, String>) -> EnvDiff {
+    let mut diff = EnvDiff::default();
+    for (name, value) in new {
+        match old.get(name) {
+            None => {
+                diff.added.insert(name.clone(), value.clone());
+            }
+            Some(old) if old != value => {
+                let change = ValueChange {
+                    old: old.clone(),
+                    new: value.clone(),
+                };
+                diff.changed.insert(name.clone(), change);
+            }
+            Some(_) => {}
+        }
+    }
+    for (name, value) in old {
+        if !new.contains_key(name) {
+            diff.removed.insert(name.clone(), value.clone());
+        }
+    }
+    diff
+}
+
+/// The `.env` file a justfile refers to. The server does not load it, so
+/// its variables only reach recipes if they are also in the server's
+/// environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DotenvStatus {
+    /// `set dotenv-load` is enabled
+    pub load_enabled: bool,
+    /// `dotenv-path`, or `dotenv-filename` (default `.env`) next to the justfile
+    pub path: String,
+    pub exists: bool,
+    /// Variables the file defines
+    pub variables: Vec<String>,
+    /// Variables the file defines that the environment does not have
+    pub missing_from_environment: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct McpEnvironment {
     pub variables: HashMap<String, String>,
     pub sources: Vec<EnvironmentSource>,
+    /// Where each variable's value came from
+    pub origins: HashMap<String, Origin>,
     pub snapshot: Option<HashMap<String, String>>,
+    snapshot_origins: Option<HashMap<String, Origin>>,
+}
+
+/// Precedence of variables set directly with `McpEnvironment::set`, which
+/// nothing loaded later overrides.
+pub const SET_PRECEDENCE: u8 = u8::MAX;
+
+/// The source that defined a variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    /// Name of the source as listed in reports, e.g. `EnvFile(.env)`, or
+    /// `Set` for `McpEnvironment::set`
+    pub source: String,
+    pub precedence: u8,
+}
+
+impl Origin {
+    fn of(source: &EnvironmentSource) -> Self {
+        Origin {
+            source: source_name(source),
+            precedence: source.precedence(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum EnvironmentSource {
     EnvFile(PathBuf),
+    /// TOML, or YAML for `.yaml` and `.yml` files, with nested tables
+    /// flattened to names such as `DATABASE_HOST`
+    ConfigFile(PathBuf),
     ProcessEnv,
     ServerConfig(String),
     Custom(HashMap<String, String>),
 }
 
+impl EnvironmentSource {
+    /// A variable is only replaced by a source of equal or higher
+    /// precedence, whatever order they are loaded in, so loading the
+    /// process environment last does not clobber the server config. From
+    /// lowest: the process environment, env files, config files, server
+    /// config, custom variables.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            EnvironmentSource::ProcessEnv => 0,
+            EnvironmentSource::EnvFile(_) => 1,
+            EnvironmentSource::ConfigFile(_) => 2,
+            EnvironmentSource::ServerConfig(_) => 3,
+            EnvironmentSource::Custom(_) => 4,
+        }
+    }
+}
+
 #[derive(Debug, Snafu)]
 pub enum EnvironmentError {
     #[snafu(display("Failed to load .env file {}: {}", path.display(), source))]
@@ -38,6 +224,24 @@ pub enum EnvironmentError {
         source: dotenvy::Error,
     },
 
+    #[snafu(display("Failed to read config file {}: {}", path.display(), source))]
+    ConfigFileRead {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Invalid TOML in {}: {}", path.display(), source))]
+    ConfigFileToml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[snafu(display("Invalid YAML in {}: {}", path.display(), source))]
+    ConfigFileYaml {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+
     #[snafu(display("Missing required MCP environment variable: {}", var_name))]
     MissingMcpVariable { var_name: String },
 
@@ -58,7 +262,9 @@ impl McpEnvironment {
         McpEnvironment {
             variables: HashMap::new(),
             sources: Vec::new(),
+            origins: HashMap::new(),
             snapshot: None,
+            snapshot_origins: None,
         }
     }
 
@@ -69,44 +275,88 @@ impl McpEnvironment {
     }
 
     pub fn load_process_env(&mut self) {
-        for (key, value) in std::env::vars() {
+        self.load(EnvironmentSource::ProcessEnv, std::env::vars());
+    }
+
+    /// Add `source` and its `vars`, keeping variables that a source of
+    /// higher precedence already set.
+    fn load(
+        &mut self,
+        source: EnvironmentSource,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) {
+        let origin = Origin::of(&source);
+        for (key, value) in vars {
+            self.insert(key, value, &origin);
+        }
+        self.sources.push(source);
+    }
+
+    fn insert(&mut self, key: String, value: String, origin: &Origin) {
+        let overridden = self
+            .origins
+            .get(&key)
+            .is_some_and(|existing| existing.precedence > origin.precedence);
+        if !overridden {
+            self.origins.insert(key.clone(), origin.clone());
             self.variables.insert(key, value);
         }
-        self.sources.push(EnvironmentSource::ProcessEnv);
     }
 
-    pub fn load_env_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+    /// Load a `.env` file, returning what it changed. Only this
+    /// environment changes, not the server process's, so loading one
+    /// justfile's `.env` leaves other sessions alone.
+    pub fn load_env_file<P: AsRef<Path>>(&mut self, path: P) -> Result<EnvDiff> {
         let path = path.as_ref();
+        let before = self.variables.clone();
+        let entries = dotenvy::from_path_iter(path).context(EnvFileLoadSnafu { path })?;
+        let variables = entries
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context(EnvFileLoadSnafu { path })?;
+        self.load(EnvironmentSource::EnvFile(path.to_path_buf()), variables);
+        Ok(diff_variables(&before, &self.variables))
+    }
 
-        // Use dotenvy to load the .env file
-        match dotenvy::from_path(path) {
-            Ok(_) => {
-                // Reload process environment to pick up the new variables
-                self.load_process_env();
-                self.sources
-                    .push(EnvironmentSource::EnvFile(path.to_path_buf()));
-                Ok(())
+    /// Load the stacked dotenv files of `dir` that exist, later files
+    /// overriding earlier ones; see `dotenv_stack`. Returns what they
+    /// changed.
+    pub fn load_dotenv_stack(&mut self, dir: &Path, profile: Option<&str>) -> Result<EnvDiff> {
+        let before = self.variables.clone();
+        for path in dotenv_stack(dir, profile) {
+            if path.is_file() {
+                self.load_env_file(&path)?;
             }
-            Err(e) => Err(EnvironmentError::EnvFileLoad {
-                path: path.to_path_buf(),
-                source: e,
-            }),
         }
+        Ok(diff_variables(&before, &self.variables))
+    }
+
+    /// Load the variables of a TOML or YAML config file; see
+    /// `flatten_config` for how keys become names.
+    pub fn load_config_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).context(ConfigFileReadSnafu { path })?;
+        let is_yaml = path
+            .extension()
+            .is_some_and(|extension| extension == "yaml" || extension == "yml");
+        let value: Value = if is_yaml {
+            serde_yaml::from_str(&content).context(ConfigFileYamlSnafu { path })?
+        } else {
+            toml::from_str(&content).context(ConfigFileTomlSnafu { path })?
+        };
+        self.load(
+            EnvironmentSource::ConfigFile(path.to_path_buf()),
+            flatten_config(&value),
+        );
+        Ok(())
     }
 
     pub fn set_server_config(&mut self, config_name: String, vars: HashMap<String, String>) {
-        for (key, value) in &vars {
-            self.variables.insert(key.clone(), value.clone());
-        }
-        self.sources
-            .push(EnvironmentSource::ServerConfig(config_name));
+        self.load(EnvironmentSource::ServerConfig(config_name), vars);
     }
 
     pub fn set_custom(&mut self, vars: HashMap<String, String>) {
-        for (key, value) in &vars {
-            self.variables.insert(key.clone(), value.clone());
-        }
-        self.sources.push(EnvironmentSource::Custom(vars));
+        let source = EnvironmentSource::Custom(vars.clone());
+        self.load(source, vars);
     }
 
     pub fn get(&self, key: &str) -> Option<&String> {
@@ -114,20 +364,73 @@ impl McpEnvironment {
     }
 
     pub fn set(&mut self, key: String, value: String) {
-        self.variables.insert(key, value);
+        let origin = Origin {
+            source: "Set".to_string(),
+            precedence: SET_PRECEDENCE,
+        };
+        self.insert(key, value, &origin);
     }
 
-    /// Create a snapshot of the current environment state
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.origins.remove(key);
+        self.variables.remove(key)
+    }
+
+    /// Where the value of `key` came from.
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.origins.get(key)
+    }
+
+    pub fn variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
+
+    /// What changed from `earlier` to this environment.
+    pub fn diff(&self, earlier: &McpEnvironment) -> EnvDiff {
+        diff_variables(&earlier.variables, &self.variables)
+    }
+
+    /// Layer `other` on top: its variables override these unless they come
+    /// from a source of lower precedence, and its sources are added after
+    /// these.
+    pub fn merge(&mut self, mut other: McpEnvironment) {
+        for (key, value) in other.variables {
+            let origin = other.origins.remove(&key).unwrap_or(Origin {
+                source: "Set".to_string(),
+                precedence: SET_PRECEDENCE,
+            });
+            self.insert(key, value, &origin);
+        }
+        self.sources.extend(other.sources);
+    }
+
+    /// Start temporary changes: everything done through the returned guard
+    /// is undone when it is dropped, including on early returns and panics.
+    pub fn scope(&mut self) -> EnvScope<'_> {
+        EnvScope {
+            variables: self.variables.clone(),
+            sources: self.sources.clone(),
+            origins: self.origins.clone(),
+            env: self,
+        }
+    }
+
+    /// Create a snapshot of the current environment state. Prefer `scope`
+    /// for temporary changes, which cannot forget to restore it.
     pub fn create_snapshot(&mut self) {
         self.snapshot = Some(self.variables.clone());
+        self.snapshot_origins = Some(self.origins.clone());
     }
 
-    /// Restore environment from snapshot
-    pub fn restore_from_snapshot(&mut self) -> Result<()> {
+    /// Restore environment from snapshot, returning what the restore
+    /// changed
+    pub fn restore_from_snapshot(&mut self) -> Result<EnvDiff> {
         match &self.snapshot {
             Some(snapshot) => {
+                let diff = diff_variables(&self.variables, snapshot);
                 self.variables = snapshot.clone();
-                Ok(())
+                self.origins = self.snapshot_origins.clone().unwrap_or_default();
+                Ok(diff)
             }
             None => Err(EnvironmentError::SnapshotError {
                 message: "No snapshot available to restore from".to_string(),
@@ -138,6 +441,7 @@ impl McpEnvironment {
     /// Clear the snapshot
     pub fn clear_snapshot(&mut self) {
         self.snapshot = None;
+        self.snapshot_origins = None;
     }
 
     /// Get environment info for MCP introspection
@@ -156,16 +460,7 @@ impl McpEnvironment {
         );
 
         // Add source types
-        let source_types: Vec<String> = self
-            .sources
-            .iter()
-            .map(|s| match s {
-                EnvironmentSource::ProcessEnv => "ProcessEnv".to_string(),
-                EnvironmentSource::EnvFile(path) => format!("EnvFile({})", path.display()),
-                EnvironmentSource::ServerConfig(name) => format!("ServerConfig({name})"),
-                EnvironmentSource::Custom(_) => "Custom".to_string(),
-            })
-            .collect();
+        let source_types: Vec<String> = self.sources.iter().map(source_name).collect();
         info.insert("sources".to_string(), source_types.join(", "));
 
         // Add MCP-specific variables if present
@@ -175,9 +470,59 @@ impl McpEnvironment {
             }
         }
 
+        // Where every variable not inherited from the process came from
+        for (name, origin) in &self.origins {
+            if origin.precedence > EnvironmentSource::ProcessEnv.precedence() {
+                info.insert(format!("origin.{name}"), origin.source.clone());
+            }
+        }
+
         info
     }
 
+    /// Report for MCP introspection: sources, `MCP_*` variables, the
+    /// requested `names`, and the justfile's `.env` status when given.
+    /// Values of secret-looking names are masked.
+    pub fn report(&self, names: &[String], dotenv: Option<DotenvStatus>) -> EnvironmentReport {
+        let mcp_variables = MCP_ENVIRONMENT_VARIABLES
+            .iter()
+            .filter_map(|name| {
+                let value = self.variables.get(*name)?;
+                Some((name.to_string(), masked(name, value)))
+            })
+            .collect();
+        let variables = names
+            .iter()
+            .map(|name| {
+                let value = self.variables.get(name).map(|value| masked(name, value));
+                (name.clone(), value)
+            })
+            .collect();
+        let origins = names
+            .iter()
+            .filter_map(|name| Some((name.clone(), self.origin(name)?.source.clone())))
+            .collect();
+        let dotenv = dotenv.map(|mut status| {
+            status.missing_from_environment = status
+                .variables
+                .iter()
+                .filter(|name| !self.variables.contains_key(*name))
+                .cloned()
+                .collect();
+            status
+        });
+
+        EnvironmentReport {
+            sources: self.sources.iter().map(source_name).collect(),
+            variable_count: self.variables.len(),
+            mcp_variables,
+            variables,
+            origins,
+            dotenv,
+            changes: EnvDiff::default(),
+        }
+    }
+
     pub fn expand_variables(&self, text: &str) -> Result<String> {
         let mut result = text.to_string();
 
@@ -190,16 +535,12 @@ impl McpEnvironment {
             changed = false;
             iterations += 1;
 
-            // Handle ${VAR} syntax
+            // Handle ${VAR}, ${VAR:-default} and ${VAR:+alternative} syntax
             while let Some(start) = result.find("${") {
-                if let Some(end) = result[start..].find('}') {
-                    let var_name = &result[start + 2..start + end];
-                    let replacement = self.variables.get(var_name).cloned().unwrap_or_else(|| {
-                        // Check system environment as fallback
-                        std::env::var(var_name).unwrap_or_default()
-                    });
+                if let Some(end) = closing_brace(&result[start + 2..]) {
+                    let replacement = self.expand_braced(&result[start + 2..start + 2 + end]);
 
-                    result.replace_range(start..start + end + 1, &replacement);
+                    result.replace_range(start..start + end + 3, &replacement);
                     changed = true;
                 } else {
                     break;
@@ -252,12 +593,222 @@ impl McpEnvironment {
     }
 }
 
+/// Guard returned by `McpEnvironment::scope`; restores the variables and
+/// sources the environment had when it was created once dropped.
+pub struct EnvScope<'a> {
+    env: &'a mut McpEnvironment,
+    variables: HashMap<String, String>,
+    sources: Vec<EnvironmentSource>,
+    origins: HashMap<String, Origin>,
+}
+
+impl EnvScope<'_> {
+    /// What has changed since the scope started.
+    pub fn changes(&self) -> EnvDiff {
+        diff_variables(&self.variables, &self.env.variables)
+    }
+}
+
+impl Deref for EnvScope<'_> {
+    type Target = McpEnvironment;
+
+    fn deref(&self) -> &McpEnvironment {
+        self.env
+    }
+}
+
+impl DerefMut for EnvScope<'_> {
+    fn deref_mut(&mut self) -> &mut McpEnvironment {
+        self.env
+    }
+}
+
+impl Drop for EnvScope<'_> {
+    fn drop(&mut self) {
+        self.env.variables = std::mem::take(&mut self.variables);
+        self.env.sources = std::mem::take(&mut self.sources);
+        self.env.origins = std::mem::take(&mut self.origins);
+    }
+}
+
+impl McpEnvironment {
+    /// The value of `name`, falling back to the process environment.
+    fn lookup(&self, name: &str) -> Option<String> {
+        self.variables
+            .get(name)
+            .cloned()
+            .or_else(|| std::env::var(name).ok())
+    }
+
+    /// The value of what is inside `${...}`: `VAR`, `VAR:-default` (used
+    /// when VAR is unset or empty) or `VAR:+alternative` (used when VAR is
+    /// set and not empty, else nothing), as in a shell.
+    fn expand_braced(&self, expression: &str) -> String {
+        let operator = expression.find(":-").into_iter();
+        let operator = operator.chain(expression.find(":+")).min();
+        let Some(at) = operator else {
+            return self.lookup(expression).unwrap_or_default();
+        };
+        let value = self
+            .lookup(&expression[..at])
+            .filter(|value| !value.is_empty());
+        let word = expression[at + 2..].to_string();
+        if expression[at..].starts_with(":-") {
+            value.unwrap_or(word)
+        } else {
+            value.map(|_| word).unwrap_or_default()
+        }
+    }
+}
+
+/// Offset of the `}` closing a `${` whose contents start `text`, skipping
+/// nested `${...}` in defaults.
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, character) in text.char_indices() {
+        match character {
+            '{' if text[..offset].ends_with('$') => depth += 1,
+            '}' if depth == 0 => return Some(offset),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
 impl Default for McpEnvironment {
     fn default() -> Self {
         Self::new()
     }
 }
 
+fn source_name(source: &EnvironmentSource) -> String {
+    match source {
+        EnvironmentSource::ProcessEnv => "ProcessEnv".to_string(),
+        EnvironmentSource::EnvFile(path) => format!("EnvFile({})", path.display()),
+        EnvironmentSource::ConfigFile(path) => format!("ConfigFile({})", path.display()),
+        EnvironmentSource::ServerConfig(name) => format!("ServerConfig({name})"),
+        EnvironmentSource::Custom(_) => "Custom".to_string(),
+    }
+}
+
+/// The conventional dotenv files of `dir`, lowest precedence first: `.env`,
+/// `.env.local`, then with a profile such as `staging`, `.env.staging` and
+/// `.env.staging.local`. The `.local` files are meant to stay out of
+/// version control.
+pub fn dotenv_stack(dir: &Path, profile: Option<&str>) -> Vec<PathBuf> {
+    let mut names = vec![".env".to_string(), ".env.local".to_string()];
+    if let Some(profile) = profile {
+        names.push(format!(".env.{profile}"));
+        names.push(format!(".env.{profile}.local"));
+    }
+    names.iter().map(|name| dir.join(name)).collect()
+}
+
+/// Structured config as environment variables: nested keys are joined with
+/// `_` and upper-cased, with `-` and `.` becoming `_`, so
+/// `[database] max-connections = 10` becomes `DATABASE_MAX_CONNECTIONS=10`.
+/// Arrays of plain values are joined with commas and other arrays are
+/// numbered from 0; nulls are skipped.
+pub fn flatten_config(value: &Value) -> BTreeMap<String, String> {
+    let mut variables = BTreeMap::new();
+    flatten_into(String::new(), value, &mut variables);
+    variables
+}
+
+fn flatten_into(name: String, value: &Value, variables: &mut BTreeMap<String, String>) {
+    let child = |key: &str| {
+        let key = key.to_uppercase().replace(['-', '.'], "_");
+        if name.is_empty() {
+            key
+        } else {
+            format!("{name}_{key}")
+        }
+    };
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten_into(child(key), value, variables);
+            }
+        }
+        Value::Array(values) if values.iter().all(|value| scalar(value).is_some()) => {
+            let values: Vec<String> = values.iter().filter_map(scalar).collect();
+            variables.insert(name, values.join(","));
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                flatten_into(child(&index.to_string()), value, variables);
+            }
+        }
+        value => {
+            if let Some(value) = scalar(value) {
+                variables.insert(name, value);
+            }
+        }
+    }
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// True for names that usually hold credentials, such as `GITHUB_TOKEN`.
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// Whether `name` can be set in a child process environment: not empty and
+/// without `=` or NUL.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\0'])
+}
+
+/// `value`, or `MASKED_VALUE` when `name` looks like a secret.
+pub fn masked(name: &str, value: &str) -> String {
+    if is_secret_name(name) && !value.is_empty() {
+        MASKED_VALUE.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// The `.env` file of a justfile in `dir`, following `dotenv-path` and
+/// `dotenv-filename` settings, and the variables it defines.
+pub fn dotenv_status(justfile: &Justfile, dir: &Path) -> DotenvStatus {
+    let setting = |name: &str| {
+        justfile
+            .settings
+            .get(name)
+            .map(|value| value.trim().trim_matches(['"', '\'']).to_string())
+    };
+    let path = match setting("dotenv-path") {
+        Some(path) => dir.join(path),
+        None => dir.join(setting("dotenv-filename").unwrap_or_else(|| ".env".to_string())),
+    };
+
+    let variables = dotenvy::from_path_iter(&path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|(name, _)| name)
+                .collect()
+        })
+        .unwrap_or_default();
+    DotenvStatus {
+        load_enabled: justfile.setting_enabled("dotenv-load"),
+        path: path.display().to_string(),
+        exists: path.is_file(),
+        variables,
+        missing_from_environment: Vec::new(),
+    }
+}
+
 /// Load MCP environment from multiple sources
 pub fn load_mcp_environment(sources: &[EnvironmentSource]) -> Result<McpEnvironment> {
     let mut env = McpEnvironment::new();
@@ -270,6 +821,9 @@ pub fn load_mcp_environment(sources: &[EnvironmentSource]) -> Result<McpEnvironm
             EnvironmentSource::EnvFile(path) => {
                 env.load_env_file(path)?;
             }
+            EnvironmentSource::ConfigFile(path) => {
+                env.load_config_file(path)?;
+            }
             EnvironmentSource::Custom(vars) => {
                 env.set_custom(vars.clone());
             }
@@ -286,7 +840,9 @@ pub fn load_mcp_environment(sources: &[EnvironmentSource]) -> Result<McpEnvironm
     Ok(env)
 }
 
-/// Validate MCP environment has required variables
+/// Validate MCP environment has required variables, and that the `MCP_*`
+/// variables it sets have values of the right type, e.g. a positive
+/// integer for `MCP_TIMEOUT_SECONDS`. Empty values count as unset.
 pub fn validate_mcp_environment(environment: &McpEnvironment, requirements: &[&str]) -> Result<()> {
     let missing_vars: Vec<&str> = requirements
         .iter()
@@ -300,9 +856,45 @@ pub fn validate_mcp_environment(environment: &McpEnvironment, requirements: &[&s
         });
     }
 
+    let invalid: Vec<String> = MCP_ENVIRONMENT_VARIABLES
+        .iter()
+        .filter_map(|&name| {
+            let value = environment.get(name)?;
+            let expected = mcp_variable_expectation(name, value.trim())?;
+            Some(format!("{name} must be {expected}, got '{value}'"))
+        })
+        .collect();
+
+    if !invalid.is_empty() {
+        return Err(EnvironmentError::McpValidationFailed {
+            message: invalid.join("; "),
+        });
+    }
+
     Ok(())
 }
 
+/// What `value` of the MCP variable `name` should have been, when it is
+/// not valid.
+fn mcp_variable_expectation(name: &str, value: &str) -> Option<String> {
+    if value.is_empty() {
+        return None;
+    }
+    let positive = matches!(value.parse::<u64>(), Ok(number) if number > 0);
+    match name {
+        "MCP_TIMEOUT_SECONDS" if !positive => Some("a positive integer".to_string()),
+        "MCP_MAX_MESSAGE_SIZE" if !positive => Some("a positive number of bytes".to_string()),
+        "MCP_LOG_LEVEL"
+            if !MCP_LOG_LEVELS
+                .iter()
+                .any(|level| level.eq_ignore_ascii_case(value)) =>
+        {
+            Some(format!("one of {}", MCP_LOG_LEVELS.join(", ")))
+        }
+        _ => None,
+    }
+}
+
 /// Get environment info for MCP introspection
 pub fn get_environment_info() -> HashMap<String, String> {
     let env = McpEnvironment::with_process_env();
@@ -344,8 +936,15 @@ mod tests {
         assert_eq!(env.get("MCP_SERVER_NAME"), Some(&"modified".to_string()));
 
         // Restore from snapshot
-        env.restore_from_snapshot().unwrap();
+        let diff = env.restore_from_snapshot().unwrap();
         assert_eq!(env.get("MCP_SERVER_NAME"), Some(&"just-mcp".to_string()));
+        assert_eq!(
+            diff.changed["MCP_SERVER_NAME"],
+            ValueChange {
+                old: "modified".to_string(),
+                new: "just-mcp".to_string(),
+            }
+        );
 
         // Clear snapshot
         env.clear_snapshot();
@@ -355,6 +954,32 @@ mod tests {
         assert!(env.restore_from_snapshot().is_err());
     }
 
+    #[test]
+    fn test_env_scope_restores_on_drop() {
+        let mut env = McpEnvironment::new();
+        env.set("MCP_LOG_LEVEL".to_string(), "info".to_string());
+
+        {
+            let mut scope = env.scope();
+            scope.set("MCP_LOG_LEVEL".to_string(), "debug".to_string());
+            scope.set_custom(HashMap::from([("RUN_ID".to_string(), "7".to_string())]));
+            assert_eq!(scope.get("RUN_ID"), Some(&"7".to_string()));
+            assert_eq!(scope.changes().changed["MCP_LOG_LEVEL"].new, "debug");
+        }
+        assert_eq!(env.get("MCP_LOG_LEVEL"), Some(&"info".to_string()));
+        assert_eq!(env.get("RUN_ID"), None);
+        assert!(env.sources.is_empty());
+
+        // A panic inside the scope does not leak its changes either
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut scope = env.scope();
+            scope.remove("MCP_LOG_LEVEL");
+            panic!("recipe failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(env.get("MCP_LOG_LEVEL"), Some(&"info".to_string()));
+    }
+
     #[test]
     fn test_mcp_environment_server_config() {
         let mut env = McpEnvironment::new();
@@ -370,6 +995,71 @@ mod tests {
         assert_eq!(env.sources.len(), 1);
     }
 
+    #[test]
+    fn test_dotenv_stack() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join(".env"), "A=env\nB=env\nC=env\n").unwrap();
+        std::fs::write(dir.path().join(".env.local"), "B=local\n").unwrap();
+        std::fs::write(dir.path().join(".env.staging"), "C=staging\n").unwrap();
+        std::fs::write(dir.path().join(".env.prod"), "C=prod\n").unwrap();
+
+        let mut env = McpEnvironment::new();
+        let diff = env.load_dotenv_stack(dir.path(), Some("staging")).unwrap();
+        assert_eq!(diff.added.len(), 3);
+        assert_eq!(env.get("A"), Some(&"env".to_string()));
+        assert_eq!(env.get("B"), Some(&"local".to_string()));
+        assert_eq!(env.get("C"), Some(&"staging".to_string()));
+        assert_eq!(
+            env.origin("B").unwrap().source,
+            format!("EnvFile({})", dir.path().join(".env.local").display())
+        );
+        // Missing files, like .env.staging.local, are skipped
+        assert_eq!(env.sources.len(), 3);
+
+        let mut env = McpEnvironment::new();
+        env.load_dotenv_stack(dir.path(), None).unwrap();
+        assert_eq!(env.get("C"), Some(&"env".to_string()));
+    }
+
+    #[test]
+    fn test_precedence_and_origins() {
+        let mut env = McpEnvironment::new();
+        env.set_server_config(
+            "production".to_string(),
+            HashMap::from([("MCP_LOG_LEVEL".to_string(), "warn".to_string())]),
+        );
+        // The process environment, loaded later, does not clobber the config
+        env.load(
+            EnvironmentSource::ProcessEnv,
+            [
+                ("MCP_LOG_LEVEL".to_string(), "trace".to_string()),
+                ("HOME".to_string(), "/home/me".to_string()),
+            ],
+        );
+        assert_eq!(env.get("MCP_LOG_LEVEL"), Some(&"warn".to_string()));
+        assert_eq!(
+            env.origin("MCP_LOG_LEVEL").unwrap().source,
+            "ServerConfig(production)"
+        );
+        assert_eq!(env.origin("HOME").unwrap().source, "ProcessEnv");
+
+        // Custom variables outrank the config, and later equals win
+        env.set_custom(HashMap::from([(
+            "MCP_LOG_LEVEL".to_string(),
+            "debug".to_string(),
+        )]));
+        assert_eq!(env.get("MCP_LOG_LEVEL"), Some(&"debug".to_string()));
+        assert_eq!(env.origin("MCP_LOG_LEVEL").unwrap().source, "Custom");
+
+        let info = env.get_environment_info();
+        assert_eq!(info["origin.MCP_LOG_LEVEL"], "Custom");
+        assert!(!info.contains_key("origin.HOME"));
+
+        let report = env.report(&["MCP_LOG_LEVEL".to_string(), "UNSET".to_string()], None);
+        assert_eq!(report.origins["MCP_LOG_LEVEL"], "Custom");
+        assert!(!report.origins.contains_key("UNSET"));
+    }
+
     #[test]
     fn test_mcp_environment_info() {
         let mut env = McpEnvironment::new();
@@ -407,6 +1097,28 @@ mod tests {
         assert_eq!(result, "just-mcp running");
     }
 
+    #[test]
+    fn test_default_value_expansion() {
+        let mut env = McpEnvironment::new();
+        env.set("HOST".to_string(), "db.internal".to_string());
+        env.set("EMPTY".to_string(), String::new());
+
+        let expand = |text: &str| env.expand_variables(text).unwrap();
+        assert_eq!(expand("${HOST:-localhost}"), "db.internal");
+        assert_eq!(expand("${JUST_MCP_UNSET_PORT:-5432}"), "5432");
+        assert_eq!(expand("${EMPTY:-fallback}"), "fallback");
+        assert_eq!(expand("${HOST:+--host=$HOST}"), "--host=db.internal");
+        assert_eq!(expand("${EMPTY:+--verbose}"), "");
+        assert_eq!(
+            expand("${JUST_MCP_UNSET_PORT:-${HOST}}:80"),
+            "db.internal:80"
+        );
+        assert_eq!(
+            expand("${JUST_MCP_UNSET_URL:-http://x:8080/a-b}"),
+            "http://x:8080/a-b"
+        );
+    }
+
     #[test]
     fn test_validate_mcp_environment() {
         let mut env = McpEnvironment::new();
@@ -423,6 +1135,30 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("MCP_MISSING_VAR"));
     }
 
+    #[test]
+    fn test_validate_mcp_variable_types() {
+        let mut env = McpEnvironment::new();
+        env.set("MCP_LOG_LEVEL".to_string(), "WARN".to_string());
+        env.set("MCP_TIMEOUT_SECONDS".to_string(), "30".to_string());
+        env.set("MCP_MAX_MESSAGE_SIZE".to_string(), String::new());
+        assert!(validate_mcp_environment(&env, &[]).is_ok());
+
+        env.set("MCP_LOG_LEVEL".to_string(), "verbose".to_string());
+        env.set("MCP_TIMEOUT_SECONDS".to_string(), "0".to_string());
+        let error = validate_mcp_environment(&env, &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "MCP environment validation failed: \
+             MCP_LOG_LEVEL must be one of trace, debug, info, warn, error, got 'verbose'; \
+             MCP_TIMEOUT_SECONDS must be a positive integer, got '0'"
+        );
+
+        env.set("MCP_TIMEOUT_SECONDS".to_string(), "-5".to_string());
+        env.set("MCP_LOG_LEVEL".to_string(), "info".to_string());
+        let error = validate_mcp_environment(&env, &[]).unwrap_err();
+        assert!(error.to_string().contains("MCP_TIMEOUT_SECONDS"));
+    }
+
     #[test]
     fn test_load_mcp_environment_multiple_sources() {
         let mut custom_vars = HashMap::new();
@@ -446,6 +1182,135 @@ mod tests {
         assert_eq!(env.sources.len(), 3);
     }
 
+    #[test]
+    fn test_load_config_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let toml_path = dir.path().join("app.toml");
+        std::fs::write(
+            &toml_path,
+            r#"
+log-level = "debug"
+features = ["a", "b"]
+
+[database]
+host = "localhost"
+port = 5432
+tls = true
+
+[[servers]]
+name = "eu"
+"#,
+        )
+        .unwrap();
+        let yaml_path = dir.path().join("app.yml");
+        std::fs::write(
+            &yaml_path,
+            "database:\n  host: db.internal\n  replica: ~\ncache.ttl: 60\n",
+        )
+        .unwrap();
+
+        let env = load_mcp_environment(&[
+            EnvironmentSource::ConfigFile(toml_path.clone()),
+            EnvironmentSource::ConfigFile(yaml_path),
+        ])
+        .unwrap();
+        assert_eq!(env.get("LOG_LEVEL"), Some(&"debug".to_string()));
+        assert_eq!(env.get("FEATURES"), Some(&"a,b".to_string()));
+        assert_eq!(env.get("DATABASE_PORT"), Some(&"5432".to_string()));
+        assert_eq!(env.get("DATABASE_TLS"), Some(&"true".to_string()));
+        assert_eq!(env.get("SERVERS_0_NAME"), Some(&"eu".to_string()));
+        // Later files override earlier ones
+        assert_eq!(env.get("DATABASE_HOST"), Some(&"db.internal".to_string()));
+        assert_eq!(env.get("DATABASE_REPLICA"), None);
+        assert_eq!(env.get("CACHE_TTL"), Some(&"60".to_string()));
+        assert_eq!(
+            env.report(&[], None).sources[0],
+            format!("ConfigFile({})", toml_path.display())
+        );
+
+        std::fs::write(&toml_path, "[database\n").unwrap();
+        let error = McpEnvironment::new()
+            .load_config_file(&toml_path)
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Invalid TOML in"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut before = McpEnvironment::new();
+        before.set("KEEP".to_string(), "1".to_string());
+        before.set("CHANGE".to_string(), "old".to_string());
+        before.set("DROP".to_string(), "gone".to_string());
+        let mut after = before.clone();
+        after.set("CHANGE".to_string(), "new".to_string());
+        after.remove("DROP");
+        after.set("API_TOKEN".to_string(), "s3cret".to_string());
+
+        let diff = after.diff(&before);
+        assert_eq!(diff.added.keys().collect::<Vec<_>>(), vec!["API_TOKEN"]);
+        assert_eq!(diff.removed["DROP"], "gone");
+        assert_eq!(diff.changed["CHANGE"].old, "old");
+        assert_eq!(diff.changed["CHANGE"].new, "new");
+        assert!(!diff.changed.contains_key("KEEP"));
+        assert_eq!(diff.masked().added["API_TOKEN"], MASKED_VALUE);
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_report_masks_secrets() {
+        let mut env = McpEnvironment::new();
+        env.set("MCP_LOG_LEVEL".to_string(), "debug".to_string());
+        env.set("GITHUB_TOKEN".to_string(), "ghp_abc".to_string());
+        env.set("DATABASE_URL".to_string(), "postgres://db".to_string());
+
+        let report = env.report(
+            &[
+                "GITHUB_TOKEN".to_string(),
+                "DATABASE_URL".to_string(),
+                "FOO".to_string(),
+            ],
+            None,
+        );
+        assert_eq!(report.mcp_variables["MCP_LOG_LEVEL"], "debug");
+        assert_eq!(
+            report.variables["GITHUB_TOKEN"].as_deref(),
+            Some(MASKED_VALUE)
+        );
+        assert_eq!(
+            report.variables["DATABASE_URL"].as_deref(),
+            Some("postgres://db")
+        );
+        assert_eq!(report.variables["FOO"], None);
+        assert!(is_secret_name("aws_secret_access_key"));
+        assert!(is_valid_name("FOO_1"));
+        assert!(!is_valid_name("A=B") && !is_valid_name(""));
+        assert!(!is_secret_name("HOME"));
+    }
+
+    #[test]
+    fn test_dotenv_status() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("ci.env"), "FOO=1\nBAR=2\n").unwrap();
+        let justfile = crate::parser::parse_justfile_str(
+            "set dotenv-load\nset dotenv-filename := \"ci.env\"\n\nbuild:\n    echo $FOO\n",
+        )
+        .unwrap();
+
+        let status = dotenv_status(&justfile, dir.path());
+        assert!(status.load_enabled);
+        assert!(status.exists);
+        assert_eq!(status.variables, vec!["FOO", "BAR"]);
+
+        let mut env = McpEnvironment::new();
+        env.set("FOO".to_string(), "1".to_string());
+        let report = env.report(&[], Some(status));
+        assert_eq!(report.dotenv.unwrap().missing_from_environment, vec!["BAR"]);
+
+        let status = dotenv_status(&crate::Justfile::default(), dir.path());
+        assert!(!status.exists && !status.load_enabled);
+        assert!(status.path.ends_with(".env"));
+    }
+
     #[test]
     fn test_get_environment_info_function() {
         // This test will depend on the actual process environment
@@ -1,17 +1,225 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
 use std::time::Instant;
 
+use crate::environment::McpEnvironment;
+use crate::policy::EnvPolicy;
+use crate::secrets;
+use crate::telemetry::args_hash;
 use crate::{Justfile, Recipe};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct ExecutionResult {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
     pub duration_ms: u64,
+    /// Interleaved stdout/stderr lines; empty unless `ExecutionOptions::transcript` is set
+    pub transcript: Vec<TranscriptEntry>,
+    /// Every command that exited non-zero, including ones that did not stop execution
+    pub failures: Vec<CommandFailure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CommandFailure {
+    pub recipe_name: String,
+    pub command: String,
+    pub exit_code: i32,
+    /// The failure did not affect the overall exit code (`-` prefix under `ExitStrategy::Just`)
+    pub ignored: bool,
+}
+
+/// How a failing command line affects the rest of the execution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStrategy {
+    /// Stop at the first failing line or dependency
+    #[default]
+    FailFast,
+    /// Run every line and dependency, collecting all failures
+    KeepGoing,
+    /// Stop at the first failure unless the line is prefixed with `-`, as just does
+    Just,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+    /// A command line echoed before it runs (see `ExecutionOptions::echo`)
+    Command,
+}
+
+/// A single output line in the order it was received from the child process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TranscriptEntry {
+    /// Microseconds since the top-level recipe started (monotonic)
+    pub elapsed_us: u64,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ExecutionOptions {
+    /// Capture a merged, timestamped transcript of stdout and stderr
+    pub transcript: bool,
+    /// Echo each command to stderr before running it, as `just` does,
+    /// honoring `@` line prefixes, `@recipe` quiet recipes and `set quiet`
+    pub echo: bool,
+    pub exit_strategy: ExitStrategy,
+    /// Variables added to the environment of every command
+    pub env: BTreeMap<String, String>,
+    /// Which of the server's own variables commands inherit
+    pub inherit_env: EnvPolicy,
+}
+
+impl ExecutionOptions {
+    /// Add the variables of `environment` to those commands run with,
+    /// overriding any of the same name.
+    pub fn with_environment(mut self, environment: &McpEnvironment) -> Self {
+        self.env.extend(
+            environment
+                .variables()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        self
+    }
+}
+
+/// What running a recipe would do, worked out without running anything.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionPlan {
+    pub recipe_name: String,
+    /// Directory the commands run in; they inherit the server's environment
+    pub working_dir: String,
+    /// Program and arguments each command line is passed to
+    pub shell: Vec<String>,
+    /// Recipes in the order they would run, dependencies first
+    pub order: Vec<String>,
+    pub steps: Vec<PlannedRecipe>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PlannedRecipe {
+    pub recipe_name: String,
+    pub commands: Vec<PlannedCommand>,
+}
+
+/// A command line with parameters and variables substituted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PlannedCommand {
+    pub command: String,
+    /// Output is suppressed (`@` prefix)
+    pub quiet: bool,
+    /// Failure is ignored under `ExitStrategy::Just` (`-` prefix)
+    pub ignore_errors: bool,
+}
+
+/// A recipe body with its `{{ }}` interpolations resolved for a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandedRecipe {
+    /// Value of every parameter: the given argument or its default
+    pub arguments: HashMap<String, String>,
+    pub body: String,
+}
+
+/// Shell each command line is run with.
+const SHELL: &[&str] = &["sh", "-c"];
+
+/// Stops a running execution from another thread. The running command's
+/// process group is killed (on Unix) and no further commands or
+/// dependencies start; the execution returns `ExecutionError::Cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    state: Arc<Mutex<CancelState>>,
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: bool,
+    /// Process group of the command currently running
+    process_group: Option<u32>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        if let Some(process_group) = state.process_group {
+            kill_process_group(process_group);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    /// A guard that cancels the execution when dropped, unless disarmed
+    /// first; for executions awaited by a future that may be dropped.
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(Some(self.clone()))
+    }
+
+    /// Track the running command, killing it at once if already cancelled.
+    fn set_process_group(&self, process_group: Option<u32>) {
+        let mut state = self.state.lock().unwrap();
+        state.process_group = process_group;
+        if let (true, Some(process_group)) = (state.cancelled, process_group) {
+            kill_process_group(process_group);
+        }
+    }
+}
+
+/// Cancels a `CancelToken` on drop; see `CancelToken::cancel_on_drop`.
+#[derive(Debug)]
+pub struct CancelOnDrop(Option<CancelToken>);
+
+impl CancelOnDrop {
+    /// Keep the execution running when the guard is dropped.
+    pub fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(cancel) = &self.0 {
+            cancel.cancel();
+        }
+    }
+}
+
+#[cfg(unix)]
+fn kill_process_group(process_group: u32) {
+    // SAFETY: kill(2) has no memory-safety preconditions; a negative pid
+    // addresses the process group
+    unsafe {
+        libc::kill(-(process_group as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_process_group: u32) {}
+
+/// State shared by every recipe and command of one execution.
+struct Run<'a> {
+    options: &'a ExecutionOptions,
+    started: Instant,
+    on_output: &'a dyn Fn(&TranscriptEntry),
+    cancel: &'a CancelToken,
 }
 
 #[derive(Debug, Snafu)]
@@ -45,6 +253,9 @@ pub enum ExecutionError {
 
     #[snafu(display("Parameter substitution failed: {}", message))]
     SubstitutionFailed { message: String },
+
+    #[snafu(display("Execution of recipe '{}' was cancelled", recipe_name))]
+    Cancelled { recipe_name: String },
 }
 
 pub type Result<T> = std::result::Result<T, ExecutionError>;
@@ -54,6 +265,96 @@ pub fn execute_recipe(
     recipe_name: &str,
     args: &[String],
     working_dir: &Path,
+) -> Result<ExecutionResult> {
+    execute_recipe_with_options(
+        justfile,
+        recipe_name,
+        args,
+        working_dir,
+        &ExecutionOptions::default(),
+    )
+}
+
+pub fn execute_recipe_with_options(
+    justfile: &Justfile,
+    recipe_name: &str,
+    args: &[String],
+    working_dir: &Path,
+    options: &ExecutionOptions,
+) -> Result<ExecutionResult> {
+    execute_recipe_with_output(justfile, recipe_name, args, working_dir, options, &|_| {})
+}
+
+/// Like `execute_recipe_with_options`, also passing each output line (and
+/// echoed command) to `on_output` as soon as it is received, whether or not
+/// a transcript is requested.
+pub fn execute_recipe_with_output(
+    justfile: &Justfile,
+    recipe_name: &str,
+    args: &[String],
+    working_dir: &Path,
+    options: &ExecutionOptions,
+    on_output: &dyn Fn(&TranscriptEntry),
+) -> Result<ExecutionResult> {
+    execute_recipe_cancellable(
+        justfile,
+        recipe_name,
+        args,
+        working_dir,
+        options,
+        on_output,
+        &CancelToken::new(),
+    )
+}
+
+/// Like `execute_recipe_with_output`, stopping when `cancel` is cancelled.
+pub fn execute_recipe_cancellable(
+    justfile: &Justfile,
+    recipe_name: &str,
+    args: &[String],
+    working_dir: &Path,
+    options: &ExecutionOptions,
+    on_output: &dyn Fn(&TranscriptEntry),
+    cancel: &CancelToken,
+) -> Result<ExecutionResult> {
+    let span = tracing::info_span!(
+        "execution",
+        recipe = recipe_name,
+        args_hash = tracing::field::Empty,
+        exit_code = tracing::field::Empty,
+        otel.status_message = tracing::field::Empty
+    )
+    .entered();
+    if let Some(hash) = args_hash(args) {
+        span.record("args_hash", hash);
+    }
+    let run = Run {
+        options,
+        started: Instant::now(),
+        on_output,
+        cancel,
+    };
+    let result = execute_recipe_inner(justfile, recipe_name, args, working_dir, &run);
+    match &result {
+        Ok(result) => {
+            span.record("exit_code", result.exit_code);
+            tracing::info!(duration_ms = result.duration_ms, "recipe finished")
+        }
+        Err(error) => {
+            // Marks the exported span failed
+            span.record("otel.status_message", error.to_string());
+            tracing::warn!(%error, "recipe failed")
+        }
+    }
+    result
+}
+
+fn execute_recipe_inner(
+    justfile: &Justfile,
+    recipe_name: &str,
+    args: &[String],
+    working_dir: &Path,
+    run: &Run,
 ) -> Result<ExecutionResult> {
     let recipe = find_recipe(justfile, recipe_name)?;
 
@@ -61,21 +362,18 @@ pub fn execute_recipe(
     let param_values = validate_arguments(recipe, args)?;
 
     // Execute dependencies first and collect their output
-    let mut dependency_output = ExecutionResult {
-        stdout: String::new(),
-        stderr: String::new(),
-        exit_code: 0,
-        duration_ms: 0,
-    };
+    let mut dependency_output = ExecutionResult::default();
 
     for dep in &recipe.dependencies {
-        let dep_result = execute_recipe(justfile, dep, &[], working_dir).map_err(|e| {
-            ExecutionError::DependencyFailed {
-                recipe_name: recipe_name.to_string(),
-                dependency: dep.clone(),
-                source: Box::new(e),
-            }
-        })?;
+        let dep_result =
+            execute_recipe_inner(justfile, dep, &[], working_dir, run).map_err(|e| match e {
+                ExecutionError::Cancelled { .. } => e,
+                e => ExecutionError::DependencyFailed {
+                    recipe_name: recipe_name.to_string(),
+                    dependency: dep.clone(),
+                    source: Box::new(e),
+                },
+            })?;
 
         // Accumulate dependency output
         if !dependency_output.stdout.is_empty() && !dep_result.stdout.is_empty() {
@@ -89,8 +387,14 @@ pub fn execute_recipe(
         dependency_output.stderr.push_str(&dep_result.stderr);
 
         dependency_output.duration_ms += dep_result.duration_ms;
+        dependency_output.transcript.extend(dep_result.transcript);
+        dependency_output.failures.extend(dep_result.failures);
         if dep_result.exit_code != 0 {
             dependency_output.exit_code = dep_result.exit_code;
+            // A failed dependency prevents the recipe from running unless keeping going
+            if run.options.exit_strategy != ExitStrategy::KeepGoing {
+                return Ok(dependency_output);
+            }
         }
     }
 
@@ -98,7 +402,14 @@ pub fn execute_recipe(
     let substituted_body = substitute_parameters(&recipe.body, &param_values, &justfile.variables)?;
 
     // Execute the recipe
-    let mut recipe_result = execute_commands(&substituted_body, working_dir, recipe_name)?;
+    let recipe_quiet = recipe.quiet || justfile.setting_enabled("quiet");
+    let mut recipe_result = execute_commands(
+        &substituted_body,
+        working_dir,
+        recipe_name,
+        recipe_quiet,
+        run,
+    )?;
 
     // Combine dependency output with recipe output
     if !dependency_output.stdout.is_empty() {
@@ -118,6 +429,14 @@ pub fn execute_recipe(
     }
 
     recipe_result.duration_ms += dependency_output.duration_ms;
+    dependency_output
+        .transcript
+        .append(&mut recipe_result.transcript);
+    recipe_result.transcript = dependency_output.transcript;
+    dependency_output
+        .failures
+        .append(&mut recipe_result.failures);
+    recipe_result.failures = dependency_output.failures;
     if dependency_output.exit_code != 0 {
         recipe_result.exit_code = dependency_output.exit_code;
     }
@@ -125,6 +444,74 @@ pub fn execute_recipe(
     Ok(recipe_result)
 }
 
+/// Resolve the commands `execute_recipe` would run, in order, including
+/// dependencies. Arguments are validated and substituted the same way.
+pub fn plan_recipe(
+    justfile: &Justfile,
+    recipe_name: &str,
+    args: &[String],
+    working_dir: &Path,
+) -> Result<ExecutionPlan> {
+    let mut steps = Vec::new();
+    plan_recipe_inner(justfile, recipe_name, args, &mut steps)?;
+    Ok(ExecutionPlan {
+        recipe_name: recipe_name.to_string(),
+        working_dir: working_dir.display().to_string(),
+        shell: SHELL.iter().map(|arg| arg.to_string()).collect(),
+        order: steps.iter().map(|step| step.recipe_name.clone()).collect(),
+        steps,
+    })
+}
+
+/// Resolve the parameters and interpolations of `recipe_name` the way
+/// `execute_recipe` would, without running it or its dependencies.
+pub fn expand_recipe(
+    justfile: &Justfile,
+    recipe_name: &str,
+    args: &[String],
+) -> Result<ExpandedRecipe> {
+    let recipe = find_recipe(justfile, recipe_name)?;
+    let arguments = validate_arguments(recipe, args)?;
+    let body = substitute_parameters(&recipe.body, &arguments, &justfile.variables)?;
+    Ok(ExpandedRecipe { arguments, body })
+}
+
+fn plan_recipe_inner(
+    justfile: &Justfile,
+    recipe_name: &str,
+    args: &[String],
+    steps: &mut Vec<PlannedRecipe>,
+) -> Result<()> {
+    let recipe = find_recipe(justfile, recipe_name)?;
+    let param_values = validate_arguments(recipe, args)?;
+
+    for dep in &recipe.dependencies {
+        plan_recipe_inner(justfile, dep, &[], steps).map_err(|e| {
+            ExecutionError::DependencyFailed {
+                recipe_name: recipe_name.to_string(),
+                dependency: dep.clone(),
+                source: Box::new(e),
+            }
+        })?;
+    }
+
+    let substituted_body = substitute_parameters(&recipe.body, &param_values, &justfile.variables)?;
+    let commands = substituted_body
+        .lines()
+        .filter_map(command_line)
+        .map(|(command, quiet, ignore_errors)| PlannedCommand {
+            command: command.to_string(),
+            quiet,
+            ignore_errors,
+        })
+        .collect();
+    steps.push(PlannedRecipe {
+        recipe_name: recipe_name.to_string(),
+        commands,
+    });
+    Ok(())
+}
+
 fn find_recipe<'a>(justfile: &'a Justfile, recipe_name: &str) -> Result<&'a Recipe> {
     justfile
         .recipes
@@ -202,6 +589,9 @@ fn substitute_parameters(
         result = result.replace(&pattern_without_spaces, clean_value);
     }
 
+    // Secrets come from the environment, filled in by the server
+    let result = secrets::replace_secret_references(&result);
+
     // Check for any remaining unsubstituted variables
     if result.contains("{{") && result.contains("}}") {
         return Err(ExecutionError::SubstitutionFailed {
@@ -212,45 +602,58 @@ fn substitute_parameters(
     Ok(result)
 }
 
-fn execute_commands(body: &str, working_dir: &Path, recipe_name: &str) -> Result<ExecutionResult> {
+fn execute_commands(
+    body: &str,
+    working_dir: &Path,
+    recipe_name: &str,
+    recipe_quiet: bool,
+    run: &Run,
+) -> Result<ExecutionResult> {
+    let options = run.options;
     let start_time = Instant::now();
     let mut combined_stdout = String::new();
     let mut combined_stderr = String::new();
+    let mut transcript = Vec::new();
+    let mut failures = Vec::new();
     let mut final_exit_code = 0;
 
-    for line in body.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
-        }
+    for (command_line, quiet, ignore_errors) in body.lines().filter_map(command_line) {
+        ensure!(!run.cancel.is_cancelled(), CancelledSnafu { recipe_name });
 
-        // Remove leading tabs/spaces from command
-        let command_line = if let Some(stripped) = line.strip_prefix('\t') {
-            stripped
-        } else if let Some(stripped) = line.strip_prefix("    ") {
-            stripped
-        } else {
-            line
-        };
-
-        // Handle special prefixes
-        let (quiet, command_line) = if let Some(stripped) = command_line.strip_prefix('@') {
-            (true, stripped)
-        } else {
-            (false, command_line)
-        };
+        // Like just, '@' inverts the echo behavior of a quiet recipe
+        if options.echo && quiet == recipe_quiet {
+            if !combined_stderr.is_empty() {
+                combined_stderr.push('\n');
+            }
+            combined_stderr.push_str(command_line);
+            let entry = TranscriptEntry {
+                elapsed_us: run.started.elapsed().as_micros() as u64,
+                stream: OutputStream::Command,
+                line: command_line.to_string(),
+            };
+            (run.on_output)(&entry);
+            if options.transcript {
+                transcript.push(entry);
+            }
+        }
 
         // Execute the command
-        let mut cmd = Command::new("sh");
-        cmd.arg("-c")
+        let mut cmd = Command::new(SHELL[0]);
+        cmd.args(&SHELL[1..])
             .arg(command_line)
-            .current_dir(working_dir)
+            .current_dir(working_dir);
+        if !options.inherit_env.is_unrestricted() {
+            let inherited = std::env::vars().filter(|(name, _)| options.inherit_env.inherits(name));
+            cmd.env_clear().envs(inherited);
+        }
+        cmd.envs(&options.env)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
-        let output = cmd.output().with_context(|_| ExecutionFailedSnafu {
+        let output = run_command(&mut cmd, run).with_context(|_| ExecutionFailedSnafu {
             recipe_name: recipe_name.to_string(),
         })?;
+        ensure!(!run.cancel.is_cancelled(), CancelledSnafu { recipe_name });
 
         // Collect output
         let stdout = String::from_utf8_lossy(&output.stdout);
@@ -270,12 +673,26 @@ fn execute_commands(body: &str, working_dir: &Path, recipe_name: &str) -> Result
             combined_stderr.push_str(&stderr);
         }
 
+        transcript.extend(output.transcript);
+
         // Update exit code (keep the last non-zero exit code, or stop on first failure)
-        let exit_code = output.status.code().unwrap_or(-1);
+        let exit_code = output.exit_code;
         if exit_code != 0 {
+            let ignored = ignore_errors && options.exit_strategy == ExitStrategy::Just;
+            failures.push(CommandFailure {
+                recipe_name: recipe_name.to_string(),
+                command: command_line.to_string(),
+                exit_code,
+                ignored,
+            });
+            if ignored {
+                continue;
+            }
             final_exit_code = exit_code;
-            // Stop executing remaining commands on failure
-            break;
+            if options.exit_strategy != ExitStrategy::KeepGoing {
+                // Stop executing remaining commands on failure
+                break;
+            }
         }
     }
 
@@ -286,6 +703,129 @@ fn execute_commands(body: &str, working_dir: &Path, recipe_name: &str) -> Result
         stderr: combined_stderr,
         exit_code: final_exit_code,
         duration_ms: duration.as_millis() as u64,
+        transcript,
+        failures,
+    })
+}
+
+/// A body line as a command with its `@` (quiet) and `-` (ignore errors)
+/// flags; `None` for blank lines and comments.
+fn command_line(line: &str) -> Option<(&str, bool, bool)> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+
+    // Remove leading tabs/spaces from command
+    let mut command_line = if let Some(stripped) = line.strip_prefix('\t') {
+        stripped
+    } else if let Some(stripped) = line.strip_prefix("    ") {
+        stripped
+    } else {
+        line
+    };
+
+    // Handle special prefixes ('@' and '-' may appear in either order)
+    let mut quiet = false;
+    let mut ignore_errors = false;
+    loop {
+        if let Some(stripped) = command_line.strip_prefix('@') {
+            quiet = true;
+            command_line = stripped;
+        } else if let Some(stripped) = command_line.strip_prefix('-') {
+            ignore_errors = true;
+            command_line = stripped;
+        } else {
+            break;
+        }
+    }
+    Some((command_line, quiet, ignore_errors))
+}
+
+struct CommandOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    exit_code: i32,
+    transcript: Vec<TranscriptEntry>,
+}
+
+/// Spawn a command and drain stdout/stderr concurrently, preserving the
+/// order in which lines arrive so they can be interleaved in a transcript.
+fn run_command(cmd: &mut Command, run: &Run) -> std::io::Result<CommandOutput> {
+    // A process group of its own, so cancelling kills the whole pipeline
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+
+    let mut child = cmd.spawn()?;
+    run.cancel.set_process_group(Some(child.id()));
+
+    let (tx, rx) = mpsc::channel();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|pipe| spawn_line_reader(pipe, OutputStream::Stdout, tx.clone())),
+        child
+            .stderr
+            .take()
+            .map(|pipe| spawn_line_reader(pipe, OutputStream::Stderr, tx.clone())),
+    ];
+    drop(tx);
+
+    let mut output = CommandOutput {
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        exit_code: 0,
+        transcript: Vec::new(),
+    };
+
+    // Timestamps are taken on receipt so the transcript is monotonic
+    for (stream, chunk) in rx {
+        let entry = TranscriptEntry {
+            elapsed_us: run.started.elapsed().as_micros() as u64,
+            stream,
+            line: String::from_utf8_lossy(&chunk)
+                .trim_end_matches(['\n', '\r'])
+                .to_string(),
+        };
+        (run.on_output)(&entry);
+        if run.options.transcript {
+            output.transcript.push(entry);
+        }
+        match stream {
+            OutputStream::Stdout => output.stdout.extend_from_slice(&chunk),
+            OutputStream::Stderr | OutputStream::Command => output.stderr.extend_from_slice(&chunk),
+        }
+    }
+
+    for reader in readers.into_iter().flatten() {
+        reader.join().ok();
+    }
+
+    let status = child.wait();
+    run.cancel.set_process_group(None);
+    output.exit_code = status?.code().unwrap_or(-1);
+    Ok(output)
+}
+
+fn spawn_line_reader(
+    pipe: impl Read + Send + 'static,
+    stream: OutputStream,
+    tx: mpsc::Sender<(OutputStream, Vec<u8>)>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        loop {
+            let mut chunk = Vec::new();
+            match reader.read_until(b'\n', &mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if tx.send((stream, chunk)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
     })
 }
 
@@ -307,6 +847,7 @@ mod tests {
             documentation: None,
             body: body.to_string(),
             dependencies: deps.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
         }
     }
 
@@ -316,6 +857,7 @@ mod tests {
         let justfile = Justfile {
             recipes: vec![recipe],
             variables: HashMap::new(),
+            ..Default::default()
         };
 
         assert!(find_recipe(&justfile, "build").is_ok());
@@ -397,4 +939,198 @@ mod tests {
                 .contains("Unresolved parameter")
         );
     }
+
+    #[test]
+    fn test_plan_recipe() {
+        let justfile = crate::parser::parse_justfile_str(
+            "version := \"1.0\"\n\nbuild:\n    cargo build\n\n# comment-only lines are skipped\nrelease target=\"x86\": build\n    # not a command\n    @echo {{ version }} {{ target }}\n    -rm -f out\n",
+        )
+        .unwrap();
+
+        let plan = plan_recipe(
+            &justfile,
+            "release",
+            &["arm".to_string()],
+            Path::new("/work"),
+        )
+        .unwrap();
+        assert_eq!(plan.order, vec!["build", "release"]);
+        assert_eq!(plan.working_dir, "/work");
+        assert_eq!(plan.shell, vec!["sh", "-c"]);
+        assert_eq!(
+            plan.steps[1].commands,
+            vec![
+                PlannedCommand {
+                    command: "echo 1.0 arm".to_string(),
+                    quiet: true,
+                    ignore_errors: false,
+                },
+                PlannedCommand {
+                    command: "rm -f out".to_string(),
+                    quiet: false,
+                    ignore_errors: true,
+                },
+            ]
+        );
+
+        assert!(matches!(
+            plan_recipe(
+                &justfile,
+                "release",
+                &["a".into(), "b".into()],
+                Path::new(".")
+            ),
+            Err(ExecutionError::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_expand_recipe() {
+        let justfile = crate::parser::parse_justfile_str(
+            "image := \"app\"\n\npush tag registry=\"ghcr.io\":\n    # push {{ image }}\n    docker push {{ registry }}/{{ image }}:{{tag}}\n",
+        )
+        .unwrap();
+
+        let expanded = expand_recipe(&justfile, "push", &["v1".to_string()]).unwrap();
+        assert_eq!(
+            expanded.body,
+            "    # push app\n    docker push ghcr.io/app:v1"
+        );
+        assert_eq!(expanded.arguments["registry"], "ghcr.io");
+        assert_eq!(expanded.arguments["tag"], "v1");
+
+        assert!(matches!(
+            expand_recipe(&justfile, "push", &[]),
+            Err(ExecutionError::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_execute_recipe_with_env() {
+        let justfile =
+            crate::parser::parse_justfile_str("greet:\n    echo \"$GREETING $USER_NAME\"\n")
+                .unwrap();
+        let options = ExecutionOptions {
+            env: BTreeMap::from([
+                ("GREETING".to_string(), "hello".to_string()),
+                ("USER_NAME".to_string(), "ada".to_string()),
+            ]),
+            ..Default::default()
+        };
+
+        let result =
+            execute_recipe_with_options(&justfile, "greet", &[], Path::new("."), &options).unwrap();
+        assert_eq!(result.stdout.trim(), "hello ada");
+    }
+
+    #[test]
+    fn test_execute_recipe_with_env_policy() {
+        let justfile =
+            crate::parser::parse_justfile_str("greet:\n    echo \"${HOME:-none} $GREETING\"\n")
+                .unwrap();
+        let options = ExecutionOptions {
+            env: BTreeMap::from([("GREETING".to_string(), "hello".to_string())]),
+            inherit_env: EnvPolicy {
+                deny: vec!["HOME".to_string(), "GREETING".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Denied variables are not inherited, but explicit ones still pass
+        let result =
+            execute_recipe_with_options(&justfile, "greet", &[], Path::new("."), &options).unwrap();
+        assert_eq!(result.stdout.trim(), "none hello");
+    }
+
+    #[test]
+    fn test_execute_recipe_with_mcp_environment() {
+        let justfile =
+            crate::parser::parse_justfile_str("greet:\n    echo \"$GREETING $USER_NAME\"\n")
+                .unwrap();
+        let mut environment = McpEnvironment::new();
+        environment.set_server_config(
+            "config".to_string(),
+            HashMap::from([
+                ("GREETING".to_string(), "hello".to_string()),
+                ("USER_NAME".to_string(), "ada".to_string()),
+            ]),
+        );
+        environment.set_custom(HashMap::from([(
+            "USER_NAME".to_string(),
+            "grace".to_string(),
+        )]));
+        let options = ExecutionOptions::default().with_environment(&environment);
+
+        let result =
+            execute_recipe_with_options(&justfile, "greet", &[], Path::new("."), &options).unwrap();
+        assert_eq!(result.stdout.trim(), "hello grace");
+    }
+
+    #[test]
+    fn test_execute_recipe_with_output() {
+        let justfile = crate::parser::parse_justfile_str(
+            "setup:\n    echo ready\n\nbuild: setup\n    echo built\n    echo warn >&2\n",
+        )
+        .unwrap();
+
+        let lines = std::cell::RefCell::new(Vec::new());
+        let result = execute_recipe_with_output(
+            &justfile,
+            "build",
+            &[],
+            Path::new("."),
+            &ExecutionOptions::default(),
+            &|entry| lines.borrow_mut().push((entry.stream, entry.line.clone())),
+        )
+        .unwrap();
+
+        assert_eq!(result.exit_code, 0);
+        // Lines are reported even though no transcript was requested
+        assert!(result.transcript.is_empty());
+        assert_eq!(
+            lines.into_inner(),
+            vec![
+                (OutputStream::Stdout, "ready".to_string()),
+                (OutputStream::Stdout, "built".to_string()),
+                (OutputStream::Stderr, "warn".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_kills_running_command() {
+        let justfile = crate::parser::parse_justfile_str(
+            "slow:\n    sleep 5 | cat\n\nall: slow\n    echo never\n",
+        )
+        .unwrap();
+
+        let cancel = CancelToken::new();
+        let canceller = {
+            let cancel = cancel.clone();
+            thread::spawn(move || {
+                thread::sleep(std::time::Duration::from_millis(200));
+                cancel.cancel();
+            })
+        };
+
+        let started = Instant::now();
+        let result = execute_recipe_cancellable(
+            &justfile,
+            "all",
+            &[],
+            Path::new("."),
+            &ExecutionOptions::default(),
+            &|_| {},
+            &cancel,
+        );
+        canceller.join().unwrap();
+
+        // The dependency was cancelled, not failed, and the pipeline is gone
+        assert!(matches!(
+            result,
+            Err(ExecutionError::Cancelled { recipe_name }) if recipe_name == "slow"
+        ));
+        assert!(started.elapsed() < std::time::Duration::from_secs(4));
+    }
 }
@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+
+use crate::parser::{LineEnd, Result, parse_justfile_str};
+
+/// Indentation of recipe bodies in formatted justfiles.
+const INDENT: &str = "    ";
+
+/// Canonical form of a justfile:
+///
+/// - recipe bodies indented with four spaces, keeping relative indentation
+/// - single spaces between parameters and dependencies in recipe headers
+/// - one space on each side of `:=` in assignments, settings and aliases
+/// - no trailing whitespace, at most one blank line in a row outside recipe
+///   bodies, a blank line before each recipe, and a single final newline
+///
+/// Comments, attributes and recipe bodies are otherwise kept as written, and
+/// so are statements spanning several lines, through a `"""`, `'''` or
+/// ```` ``` ```` string or a trailing `\`.
+///
+/// This works line by line on the spans of `parse_justfile_str`, not on a
+/// syntax tree, so it is not `just --fmt`: expressions, such as `if` and
+/// function calls, are left as written but for the spacing around `:=`, and
+/// justfiles the parser rejects, such as ones with `import` or `mod`, are
+/// an error rather than formatted.
+pub fn format_justfile_str(source: &str) -> Result<String> {
+    let justfile = parse_justfile_str(source)?;
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut headers = HashSet::new();
+    let mut preambles = HashSet::new();
+    // Body line index -> indentation common to the recipe's body
+    let mut bodies = vec![None; lines.len()];
+    for recipe in &justfile.recipes {
+        let header = recipe.span.start_line - 1;
+        headers.insert(header);
+        preambles.insert(preamble_start(&lines, header));
+
+        let body = header + 1..recipe.span.end_line;
+        let common = lines[body.clone()]
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| indentation(line))
+            .min()
+            .unwrap_or(0);
+        for index in body {
+            bodies[index] = Some(common);
+        }
+    }
+
+    let verbatim = continued_statements(&lines, &bodies);
+
+    let mut formatted: Vec<String> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim.contains(&index) {
+            formatted.push(line.to_string());
+            continue;
+        }
+        if let Some(common) = bodies[index] {
+            let line = line.trim_end();
+            formatted.push(if line.is_empty() {
+                String::new()
+            } else {
+                format!("{INDENT}{}", &line[common..])
+            });
+            continue;
+        }
+
+        let line = line.trim();
+        let previous_blank = formatted.last().is_none_or(|last| last.is_empty());
+        if line.is_empty() {
+            if !previous_blank {
+                formatted.push(String::new());
+            }
+            continue;
+        }
+        if preambles.contains(&index) && !previous_blank {
+            formatted.push(String::new());
+        }
+        formatted.push(if headers.contains(&index) {
+            format_header(line)
+        } else if line.starts_with('#') || line.starts_with('[') {
+            line.to_string()
+        } else {
+            format_assignment(line)
+        });
+    }
+
+    while formatted.last().is_some_and(|line| line.is_empty()) {
+        formatted.pop();
+    }
+    let mut output = formatted.join("\n");
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Bytes of leading spaces and tabs. Other whitespace, such as a
+/// non-breaking space, counts as part of the line, so cutting the
+/// indentation never splits a character.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Lines outside recipe bodies that belong to a statement spanning several
+/// lines: one ending in `\`, or with a `"""`, `'''` or ```` ``` ````
+/// string left open at the end of the line. Whitespace in those is part of
+/// the string or the continued expression.
+fn continued_statements(lines: &[&str], bodies: &[Option<usize>]) -> HashSet<usize> {
+    let mut verbatim = HashSet::new();
+    // First line of the statement the previous line left unfinished
+    let mut start = None;
+    let mut end = LineEnd::Complete;
+    for (index, line) in lines.iter().enumerate() {
+        if start.is_none() && bodies[index].is_some() {
+            continue;
+        }
+        let first = start.unwrap_or(index);
+        end = end.after(line);
+        if start.is_some() || end != LineEnd::Complete {
+            verbatim.extend(first..=index);
+        }
+        start = (end != LineEnd::Complete).then_some(first);
+    }
+    verbatim
+}
+
+/// First line of the comments and attributes directly above a header.
+fn preamble_start(lines: &[&str], header: usize) -> usize {
+    let mut start = header;
+    while start > 0 {
+        let line = lines[start - 1].trim();
+        if !(line.starts_with('#') || line.starts_with('[')) {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+/// `name  a   b="x" :dep` -> `name a b="x": dep`. A trailing comment is
+/// kept as written.
+fn format_header(header: &str) -> String {
+    let (header, comment) = match find_unquoted(header, '#') {
+        Some(hash) => (header[..hash].trim_end(), Some(&header[hash..])),
+        None => (header, None),
+    };
+    let formatted = format_header_words(header);
+    match comment {
+        Some(comment) => format!("{formatted} {comment}"),
+        None => formatted,
+    }
+}
+
+fn format_header_words(header: &str) -> String {
+    let (prefix, dependencies) = match find_unquoted(header, ':') {
+        Some(colon) => (&header[..colon], &header[colon + 1..]),
+        None => (header, ""),
+    };
+    let dependencies = words(dependencies);
+    if dependencies.is_empty() {
+        format!("{}:", words(prefix).join(" "))
+    } else {
+        format!("{}: {}", words(prefix).join(" "), dependencies.join(" "))
+    }
+}
+
+/// `name:=value` -> `name := value`, for variables, `export`, `set` and
+/// `alias`. Lines without `:=` are returned unchanged.
+fn format_assignment(line: &str) -> String {
+    match find_unquoted(line, ':').filter(|&colon| line[colon + 1..].starts_with('=')) {
+        Some(colon) => format!(
+            "{} := {}",
+            words(&line[..colon]).join(" "),
+            line[colon + 2..].trim()
+        ),
+        None => line.to_string(),
+    }
+}
+
+/// Byte offset of the first `target` outside quotes and backticks.
+fn find_unquoted(text: &str, target: char) -> Option<usize> {
+    let mut quote = None;
+    for (index, ch) in text.char_indices() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => {}
+            None if matches!(ch, '"' | '\'' | '`') => quote = Some(ch),
+            None if ch == target => return Some(index),
+            None => {}
+        }
+    }
+    None
+}
+
+/// Whitespace-separated words; whitespace inside quotes does not split.
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for ch in text.chars() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => {}
+            None if matches!(ch, '"' | '\'' | '`') => quote = Some(ch),
+            None if ch.is_whitespace() => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+            None => {}
+        }
+        current.push(ch);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_justfile() {
+        let source = "\n\nset shell:=[\"bash\", \"-c\"]\nversion   :=  \"1.0\"\nalias b:=build\n# Build it\n[linux]\nbuild  target=\"debug\"   mode=\"a b\" :   setup\n  cargo build   \n  if true; then\n    echo nested\n  fi\n\n\nsetup:\n\tmkdir -p out\n\n\n\n";
+        assert_eq!(
+            format_justfile_str(source).unwrap(),
+            "set shell := [\"bash\", \"-c\"]\nversion := \"1.0\"\nalias b := build\n\n# Build it\n[linux]\nbuild target=\"debug\" mode=\"a b\": setup\n    cargo build\n    if true; then\n      echo nested\n    fi\n\nsetup:\n    mkdir -p out\n"
+        );
+    }
+
+    #[test]
+    fn test_formatting_is_idempotent() {
+        let formatted = "version := \"1.0\"\n\n# Build it\nbuild:\n    cargo build\n\n    cargo doc\n\ntest: build\n    cargo test\n";
+        assert_eq!(format_justfile_str(formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_keeps_multiline_strings() {
+        let source = "message   :=  \"\"\"\n  indented:  text  \n\n\n  name:=value\n\"\"\"\nscript := '''\n    #!/bin/sh\n'''\nbuild:\n  echo {{message}}\n";
+        let formatted = format_justfile_str(source).unwrap();
+        assert_eq!(
+            formatted,
+            "message   :=  \"\"\"\n  indented:  text  \n\n\n  name:=value\n\"\"\"\nscript := '''\n    #!/bin/sh\n'''\n\nbuild:\n    echo {{message}}\n"
+        );
+        assert_eq!(format_justfile_str(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_keeps_line_continuations() {
+        let source = "flags   :=  \"--release\" + \\\n    \" --locked\"\nversion:=\"1.0\"  # not \\\n\nbuild:\n  cargo build {{flags}}\n";
+        let formatted = format_justfile_str(source).unwrap();
+        assert_eq!(
+            formatted,
+            "flags   :=  \"--release\" + \\\n    \" --locked\"\nversion := \"1.0\"  # not \\\n\nbuild:\n    cargo build {{flags}}\n"
+        );
+        assert_eq!(format_justfile_str(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_keeps_quoted_colons() {
+        assert_eq!(
+            format_header("serve   url=\"http://localhost\"  :"),
+            "serve url=\"http://localhost\":"
+        );
+        assert_eq!(format_assignment("url:=\"a:=b\""), "url := \"a:=b\"");
+        assert_eq!(
+            format_header("build:   dep    #  see  docs"),
+            "build: dep #  see  docs"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_non_ascii_indentation() {
+        let formatted = format_justfile_str("build:\n  echo a\n \u{a0}echo b\n").unwrap();
+        assert_eq!(formatted, "build:\n     echo a\n    \u{a0}echo b\n");
+        assert_eq!(format_justfile_str(&formatted).unwrap(), formatted);
+    }
+}
@@ -0,0 +1,158 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::Justfile;
+
+/// Output format of the `dependency_graph` tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    /// Nodes and edges as JSON
+    #[default]
+    Json,
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+/// Recipe dependency DAG. An edge `from -> to` means `from` depends on `to`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub documentation: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+}
+
+impl DependencyGraph {
+    /// Build the graph in recipe order. Dependencies on unknown recipes are
+    /// kept as edges so they still show up when rendered.
+    pub fn new(justfile: &Justfile) -> Self {
+        let nodes = justfile
+            .recipes
+            .iter()
+            .map(|recipe| GraphNode {
+                name: recipe.name.clone(),
+                documentation: recipe.documentation.clone(),
+            })
+            .collect();
+        let edges = justfile
+            .recipes
+            .iter()
+            .flat_map(|recipe| {
+                recipe.dependencies.iter().map(|dependency| GraphEdge {
+                    from: recipe.name.clone(),
+                    to: dependency.clone(),
+                })
+            })
+            .collect();
+        DependencyGraph { nodes, edges }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph justfile {\n    rankdir=LR;\n");
+        for node in &self.nodes {
+            dot.push_str(&format!("    {};\n", dot_id(&node.name)));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!(
+                "    {} -> {};\n",
+                dot_id(&edge.from),
+                dot_id(&edge.to)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Mermaid flowchart. Nodes get generated ids so recipe names such as
+    /// `end` cannot collide with Mermaid keywords.
+    pub fn to_mermaid(&self) -> String {
+        let mut ids: HashMap<&str, String> = HashMap::new();
+        let mut mermaid = String::from("flowchart LR\n");
+        let names = self
+            .nodes
+            .iter()
+            .map(|node| node.name.as_str())
+            .chain(self.edges.iter().map(|edge| edge.to.as_str()));
+        for name in names {
+            if !ids.contains_key(name) {
+                let id = format!("n{}", ids.len());
+                mermaid.push_str(&format!("    {id}[\"{}\"]\n", name.replace('"', "#quot;")));
+                ids.insert(name, id);
+            }
+        }
+        for edge in &self.edges {
+            mermaid.push_str(&format!(
+                "    {} --> {}\n",
+                ids[edge.from.as_str()],
+                ids[edge.to.as_str()]
+            ));
+        }
+        mermaid
+    }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    const JUSTFILE: &str = "build:\n    cargo build\n\ntest: build\n    cargo test\n\nrelease: build test\n    cargo publish\n";
+
+    #[test]
+    fn test_graph_edges_follow_dependencies() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let graph = DependencyGraph::new(&justfile);
+
+        let names: Vec<&str> = graph.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["build", "test", "release"]);
+        let edges: Vec<(&str, &str)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![("test", "build"), ("release", "build"), ("release", "test")]
+        );
+    }
+
+    #[test]
+    fn test_dot_output() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let dot = DependencyGraph::new(&justfile).to_dot();
+
+        assert!(dot.starts_with("digraph justfile {"));
+        assert!(dot.contains("    \"test\" -> \"build\";\n"));
+        assert!(dot.contains("    \"release\" -> \"test\";\n"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn test_mermaid_output() {
+        let justfile = parse_justfile_str("end: start\n    echo end\n").unwrap();
+        let mermaid = DependencyGraph::new(&justfile).to_mermaid();
+
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n    n0[\"end\"]\n    n1[\"start\"]\n    n0 --> n1\n"
+        );
+    }
+}
@@ -0,0 +1,440 @@
+use rmcp::schemars::{self, JsonSchema};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::audit::cut_string;
+
+/// Executions remembered by default; older records are dropped.
+pub const DEFAULT_CAPACITY: usize = 1000;
+
+/// Records returned by `query` when no limit is given.
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// Bytes of stdout and of stderr kept per execution.
+pub const OUTPUT_LIMIT: usize = 2048;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS executions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recipe_name TEXT NOT NULL,
+    justfile TEXT NOT NULL,
+    args TEXT NOT NULL,
+    started_at_ms INTEGER NOT NULL,
+    finished_at_ms INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    exit_code INTEGER,
+    status TEXT NOT NULL,
+    cached INTEGER NOT NULL,
+    error TEXT,
+    stdout TEXT NOT NULL,
+    stderr TEXT NOT NULL,
+    session_id INTEGER
+);
+CREATE INDEX IF NOT EXISTS executions_by_recipe ON executions (recipe_name);
+";
+
+const COLUMNS: &str = "recipe_name, justfile, args, started_at_ms, finished_at_ms, duration_ms, \
+                       exit_code, status, cached, error, stdout, stderr, session_id";
+
+#[derive(Debug, Snafu)]
+pub enum HistoryError {
+    #[snafu(display("Failed to open history database {}: {}", path.display(), source))]
+    Open {
+        path: PathBuf,
+        source: rusqlite::Error,
+    },
+
+    #[snafu(display("Failed to query the execution history: {}", source))]
+    Query { source: rusqlite::Error },
+}
+
+pub type Result<T> = std::result::Result<T, HistoryError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    /// Exited with code 0
+    Succeeded,
+    /// Exited with a non-zero code
+    Failed,
+    /// Stopped with `cancel_execution`
+    Cancelled,
+    /// Could not run, e.g. a substitution error
+    Error,
+}
+
+impl ExecutionStatus {
+    pub const ALL: [Self; 4] = [Self::Succeeded, Self::Failed, Self::Cancelled, Self::Error];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == name)
+    }
+}
+
+/// One recipe execution, from `run_recipe`, `start_recipe` or `just_<recipe>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionRecord {
+    pub recipe_name: String,
+    pub justfile: String,
+    pub args: Vec<String>,
+    /// Milliseconds since the Unix epoch
+    pub started_at_ms: u64,
+    /// Milliseconds since the Unix epoch
+    pub finished_at_ms: u64,
+    pub duration_ms: u64,
+    /// Absent when the recipe did not run to completion
+    pub exit_code: Option<i32>,
+    pub status: ExecutionStatus,
+    /// The result was reused from the cache instead of running the recipe
+    pub cached: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The first `OUTPUT_LIMIT` bytes of stdout, secrets masked
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stdout: String,
+    /// The first `OUTPUT_LIMIT` bytes of stderr, secrets masked
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+    /// Session of the client that ran it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<u64>,
+}
+
+impl ExecutionRecord {
+    /// Set the output to keep, cut to `OUTPUT_LIMIT` bytes each.
+    pub fn with_output(mut self, mut stdout: String, mut stderr: String) -> Self {
+        cut_string(&mut stdout, OUTPUT_LIMIT);
+        cut_string(&mut stderr, OUTPUT_LIMIT);
+        self.stdout = stdout;
+        self.stderr = stderr;
+        self
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let args: String = row.get("args")?;
+        let status: String = row.get("status")?;
+        let millis = |column: &str| -> rusqlite::Result<u64> {
+            row.get::<_, i64>(column).map(|value| value as u64)
+        };
+        Ok(Self {
+            recipe_name: row.get("recipe_name")?,
+            justfile: row.get("justfile")?,
+            args: serde_json::from_str(&args).unwrap_or_default(),
+            started_at_ms: millis("started_at_ms")?,
+            finished_at_ms: millis("finished_at_ms")?,
+            duration_ms: millis("duration_ms")?,
+            exit_code: row.get("exit_code")?,
+            status: ExecutionStatus::parse(&status).unwrap_or(ExecutionStatus::Error),
+            cached: row.get("cached")?,
+            error: row.get("error")?,
+            stdout: row.get("stdout")?,
+            stderr: row.get("stderr")?,
+            session_id: row.get::<_, Option<i64>>("session_id")?.map(|id| id as u64),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    pub recipe_name: Option<String>,
+    pub status: Option<ExecutionStatus>,
+    /// Most records to return, newest first (default `DEFAULT_LIMIT`)
+    pub limit: Option<usize>,
+}
+
+/// Bounded log of past executions in an SQLite database, kept in memory or,
+/// when opened on a file, on disk so it survives restarts.
+#[derive(Debug)]
+pub struct ExecutionHistory {
+    db: Mutex<Connection>,
+    capacity: usize,
+}
+
+impl Default for ExecutionHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ExecutionHistory {
+    pub fn new(capacity: usize) -> Self {
+        let db = Connection::open_in_memory()
+            .and_then(|db| db.execute_batch(SCHEMA).map(|()| db))
+            .expect("failed to create the in-memory history database");
+        Self {
+            db: Mutex::new(db),
+            capacity,
+        }
+    }
+
+    /// A history that survives restarts, keeping the newest `capacity`
+    /// records in the database at `path`.
+    pub fn open(path: &Path, capacity: usize) -> Result<Self> {
+        let db = Connection::open(path).context(OpenSnafu { path })?;
+        db.execute_batch(SCHEMA).context(OpenSnafu { path })?;
+        Ok(Self {
+            db: Mutex::new(db),
+            capacity,
+        })
+    }
+
+    pub fn record(&self, record: ExecutionRecord) {
+        if let Err(error) = self.insert(&record).and_then(|()| self.prune()) {
+            tracing::warn!(%error, "failed to write the execution history");
+        }
+    }
+
+    fn insert(&self, record: &ExecutionRecord) -> rusqlite::Result<()> {
+        let args = serde_json::to_string(&record.args).unwrap_or_default();
+        self.db.lock().unwrap().execute(
+            &format!(
+                "INSERT INTO executions ({COLUMNS}) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"
+            ),
+            params![
+                record.recipe_name,
+                record.justfile,
+                args,
+                record.started_at_ms as i64,
+                record.finished_at_ms as i64,
+                record.duration_ms as i64,
+                record.exit_code,
+                record.status.as_str(),
+                record.cached,
+                record.error,
+                record.stdout,
+                record.stderr,
+                record.session_id.map(|id| id as i64),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Drop all but the newest `capacity` records.
+    fn prune(&self) -> rusqlite::Result<()> {
+        let db = self.db.lock().unwrap();
+        let oldest_kept: Option<i64> = db
+            .query_row(
+                "SELECT id FROM executions ORDER BY id DESC LIMIT 1 OFFSET ?1",
+                [self.capacity.saturating_sub(1) as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = oldest_kept {
+            db.execute("DELETE FROM executions WHERE id < ?1", [id])?;
+        }
+        Ok(())
+    }
+
+    /// Matching records, newest first.
+    pub fn query(&self, filter: &HistoryFilter) -> Result<Vec<ExecutionRecord>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIMIT);
+        let db = self.db.lock().unwrap();
+        let mut statement = db
+            .prepare(&format!(
+                "SELECT {COLUMNS} FROM executions \
+                 WHERE (?1 IS NULL OR recipe_name = ?1) AND (?2 IS NULL OR status = ?2) \
+                 ORDER BY id DESC LIMIT ?3"
+            ))
+            .context(QuerySnafu)?;
+        let rows = statement
+            .query_map(
+                params![
+                    filter.recipe_name,
+                    filter.status.map(ExecutionStatus::as_str),
+                    i64::try_from(limit).unwrap_or(i64::MAX),
+                ],
+                ExecutionRecord::from_row,
+            )
+            .context(QuerySnafu)?;
+        rows.collect::<rusqlite::Result<_>>().context(QuerySnafu)
+    }
+}
+
+/// Records as a table for the terminal, one per line.
+pub fn history_table(records: &[ExecutionRecord]) -> String {
+    let rows: Vec<[String; 5]> = records
+        .iter()
+        .map(|record| {
+            let started = UNIX_EPOCH + Duration::from_millis(record.started_at_ms);
+            let mut command = record.recipe_name.clone();
+            for arg in &record.args {
+                command.push(' ');
+                command.push_str(arg);
+            }
+            [
+                crate::server_log::rfc3339(started),
+                record.status.as_str().to_string(),
+                record
+                    .exit_code
+                    .map_or_else(|| "-".to_string(), |code| code.to_string()),
+                format!("{}ms", record.duration_ms),
+                command,
+            ]
+        })
+        .collect();
+    let header = ["STARTED", "STATUS", "EXIT", "DURATION", "RECIPE"].map(str::to_string);
+    let width = |column: usize| {
+        rows.iter()
+            .chain([&header])
+            .map(|row| row[column].len())
+            .max()
+            .unwrap_or(0)
+    };
+    let widths = [width(0), width(1), width(2), width(3)];
+
+    let mut table = String::new();
+    for [started, status, exit, duration, command] in [header].iter().chain(&rows) {
+        let line = format!(
+            "{started:w0$}  {status:w1$}  {exit:>w2$}  {duration:>w3$}  {command}",
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// Milliseconds since the Unix epoch.
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(recipe_name: &str, status: ExecutionStatus) -> ExecutionRecord {
+        ExecutionRecord {
+            recipe_name: recipe_name.to_string(),
+            justfile: "justfile".to_string(),
+            args: Vec::new(),
+            started_at_ms: 0,
+            finished_at_ms: 0,
+            duration_ms: 0,
+            exit_code: Some(0),
+            status,
+            cached: false,
+            error: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_query_filters_newest_first() {
+        let history = ExecutionHistory::default();
+        history.record(record("deploy", ExecutionStatus::Failed));
+        history.record(record("build", ExecutionStatus::Succeeded));
+        history.record(record("deploy", ExecutionStatus::Succeeded));
+
+        let deploys = history
+            .query(&HistoryFilter {
+                recipe_name: Some("deploy".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            deploys
+                .iter()
+                .map(|record| record.status)
+                .collect::<Vec<_>>(),
+            vec![ExecutionStatus::Succeeded, ExecutionStatus::Failed]
+        );
+
+        let failed = history
+            .query(&HistoryFilter {
+                status: Some(ExecutionStatus::Failed),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(failed, vec![record("deploy", ExecutionStatus::Failed)]);
+
+        let latest = history
+            .query(&HistoryFilter {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(latest, vec![record("deploy", ExecutionStatus::Succeeded)]);
+    }
+
+    #[test]
+    fn test_oldest_records_are_dropped() {
+        let history = ExecutionHistory::new(2);
+        for name in ["a", "b", "c"] {
+            history.record(record(name, ExecutionStatus::Succeeded));
+        }
+        let names: Vec<String> = history
+            .query(&HistoryFilter::default())
+            .unwrap()
+            .into_iter()
+            .map(|record| record.recipe_name)
+            .collect();
+        assert_eq!(names, vec!["c", "b"]);
+    }
+
+    #[test]
+    fn test_history_database_survives_restarts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("history.db");
+        let history = ExecutionHistory::open(&path, 2).unwrap();
+        for name in ["a", "b", "c"] {
+            history.record(record(name, ExecutionStatus::Succeeded));
+        }
+        let failed = ExecutionRecord {
+            args: vec!["--release".to_string()],
+            exit_code: Some(101),
+            session_id: Some(3),
+            ..record("d", ExecutionStatus::Failed)
+        }
+        .with_output(
+            "x".repeat(OUTPUT_LIMIT * 2),
+            "error: linker failed".to_string(),
+        );
+        history.record(failed.clone());
+        drop(history);
+
+        let history = ExecutionHistory::open(&path, 2).unwrap();
+        let records = history.query(&HistoryFilter::default()).unwrap();
+        assert_eq!(
+            records,
+            vec![failed, record("c", ExecutionStatus::Succeeded)]
+        );
+        assert!(records[0].stdout.len() < OUTPUT_LIMIT + 64);
+        assert!(records[0].stdout.ends_with("bytes truncated]"));
+    }
+
+    #[test]
+    fn test_history_table() {
+        let record = ExecutionRecord {
+            args: vec!["prod".to_string()],
+            started_at_ms: 1_751_362_200_123,
+            duration_ms: 1500,
+            ..record("deploy", ExecutionStatus::Succeeded)
+        };
+        assert_eq!(
+            history_table(&[record]),
+            "STARTED                   STATUS     EXIT  DURATION  RECIPE\n\
+             2025-07-01T09:30:00.123Z  succeeded     0    1500ms  deploy prod\n"
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use axum::Router;
+use axum::extract::{Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use hyper::server::conn::http1;
+use hyper_util::rt::{TokioIo, TokioTimer};
+use hyper_util::service::TowerToHyperService;
+use rmcp::ServiceExt;
+use rmcp::transport::sse_server::{SseServer, SseServerConfig};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+use snafu::prelude::*;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::Semaphore;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+use crate::auth;
+use crate::mcp_server::JustMcpServer;
+use crate::tls::HANDSHAKE_TIMEOUT;
+use crate::transport::{BindSnafu, NetworkOptions, Result, accept_failed};
+
+/// Longest request head read before answering 431.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Most connections served at once. Later clients wait to be accepted.
+const MAX_CONNECTIONS: usize = 256;
+
+/// Content type of the Prometheus text exposition format.
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Where the streamable HTTP transport takes JSON-RPC messages.
+pub const MCP_PATH: &str = "/mcp";
+
+/// Where SSE clients open their event stream.
+pub const SSE_PATH: &str = "/sse";
+
+/// Where SSE clients post their JSON-RPC messages.
+pub const SSE_MESSAGE_PATH: &str = "/message";
+
+/// The MCP transport served over HTTP besides the status endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpEndpoint {
+    /// Streamable HTTP: messages are posted to `/mcp`, and responses and
+    /// server requests come back as JSON or as an event stream
+    StreamableHttp,
+    /// The older HTTP+SSE transport: events from `GET /sse`, client
+    /// messages posted to `/message`
+    Sse,
+}
+
+/// The HTTP status endpoints, for orchestrators such as Kubernetes and for
+/// Prometheus, and the MCP transport at `endpoint`, if any:
+///
+/// - `/healthz` answers 200 while the process is running.
+/// - `/readyz` answers 200 once the justfiles parse, else 503 with the
+///   problems.
+/// - `/metrics` returns the server's metrics in the Prometheus text format.
+///
+/// Each MCP client gets its own session, as on the Unix socket. With an
+/// `auth_token`, `/metrics` and the MCP routes answer 401 unless the
+/// request carries `Authorization: Bearer <auth_token>`; the probes stay
+/// open.
+pub fn router(
+    server: JustMcpServer,
+    endpoint: Option<McpEndpoint>,
+    auth_token: Option<&str>,
+) -> Router {
+    let status = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(server.clone());
+    let mut protected = Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(server.clone());
+    protected = match endpoint {
+        None => protected,
+        Some(McpEndpoint::StreamableHttp) => protected.merge(streamable_http_routes(server)),
+        Some(McpEndpoint::Sse) => protected.merge(sse_routes(server)),
+    };
+    if let Some(token) = auth_token {
+        protected = protected.route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_auth_token,
+        ));
+    }
+    status.merge(protected)
+}
+
+fn streamable_http_routes(server: JustMcpServer) -> Router {
+    let service = StreamableHttpService::new(
+        move || {
+            let session = server.new_session();
+            session.keep_session_open("http");
+            Ok(session)
+        },
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+    Router::new().route_service(MCP_PATH, service)
+}
+
+/// The SSE routes, with a task running a session for each event stream
+/// opened until the process exits.
+fn sse_routes(server: JustMcpServer) -> Router {
+    let (mut sse, routes) = SseServer::new(SseServerConfig {
+        // Only used when rmcp binds the listener itself
+        bind: SocketAddr::from(([127, 0, 0, 1], 0)),
+        sse_path: SSE_PATH.to_string(),
+        post_path: SSE_MESSAGE_PATH.to_string(),
+        ct: CancellationToken::new(),
+        sse_keep_alive: None,
+    });
+    tokio::spawn(async move {
+        while let Some(transport) = sse.next_transport().await {
+            let session = server.new_session();
+            let guard = session.open_session("sse");
+            let span = tracing::info_span!("session", id = guard.id(), client = "sse");
+            tokio::spawn(
+                async move {
+                    tracing::info!("client connected");
+                    match session.serve(transport).await {
+                        Ok(running) => {
+                            let _ = running.waiting().await;
+                            tracing::info!("client disconnected");
+                        }
+                        Err(error) => tracing::error!(%error, "session failed to start"),
+                    }
+                    drop(guard);
+                }
+                .instrument(span),
+            );
+        }
+    });
+    routes
+}
+
+/// Serve the status endpoints alone on `address`, over HTTPS with
+/// `options.tls`.
+pub async fn serve_http(
+    server: JustMcpServer,
+    address: SocketAddr,
+    options: NetworkOptions,
+) -> Result<()> {
+    serve(
+        router(server, None, options.auth_token.as_deref()),
+        address,
+        options.tls,
+    )
+    .await
+}
+
+/// Serve `router` over HTTP/1.1 on `address`. A client has
+/// `HANDSHAKE_TIMEOUT` to finish the TLS handshake, when `tls` is set, and
+/// then to send each request head. At most `MAX_CONNECTIONS` are served at
+/// once.
+pub async fn serve(router: Router, address: SocketAddr, tls: Option<TlsAcceptor>) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .context(BindSnafu {
+            address: address.to_string(),
+        })?;
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let permit = connections
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the connection semaphore is never closed");
+        let (stream, client) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                accept_failed(error).await;
+                continue;
+            }
+        };
+        let router = router.clone();
+        let tls = tls.clone();
+        tokio::spawn(async move {
+            let Some(tls) = tls else {
+                serve_connection(stream, router, client).await;
+                return drop(permit);
+            };
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.accept(stream)).await {
+                Ok(Ok(stream)) => serve_connection(stream, router, client).await,
+                Ok(Err(error)) => tracing::debug!(%client, %error, "TLS handshake failed"),
+                Err(_) => tracing::debug!(%client, "TLS handshake timed out"),
+            }
+            drop(permit);
+        });
+    }
+}
+
+async fn serve_connection<S>(stream: S, router: Router, client: SocketAddr)
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let mut builder = http1::Builder::new();
+    builder
+        .timer(TokioTimer::new())
+        .header_read_timeout(HANDSHAKE_TIMEOUT)
+        .max_header_size(MAX_REQUEST_HEAD);
+    let connection = builder
+        .serve_connection(TokioIo::new(stream), TowerToHyperService::new(router))
+        .with_upgrades();
+    if let Err(error) = connection.await {
+        tracing::debug!(%client, %error, "HTTP connection failed");
+    }
+}
+
+/// Refuse requests without `Authorization: Bearer <token>` with 401.
+async fn require_auth_token(
+    State(token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if auth::is_authorized(&token, authorization) {
+        return next.run(request).await;
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        "unauthorized\n",
+    )
+        .into_response()
+}
+
+async fn healthz() -> &'static str {
+    "ok\n"
+}
+
+async fn readyz(State(server): State<JustMcpServer>) -> Response {
+    let problems = server.readiness_problems();
+    if problems.is_empty() {
+        "ready\n".into_response()
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("{}\n", problems.join("\n")),
+        )
+            .into_response()
+    }
+}
+
+async fn metrics(State(server): State<JustMcpServer>) -> Response {
+    (
+        [(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)],
+        server.metrics().render(),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    /// The status and body of `method` on `uri`, with `authorization` as
+    /// the `Authorization` header.
+    async fn request(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        authorization: Option<&str>,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        let response = router
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_healthz_and_readyz() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let router = router(JustMcpServer::new(dir.path()), None, None);
+        let (status, _) = request(&router, "GET", "/healthz", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // No justfile yet
+        let (status, body) = request(&router, "GET", "/readyz", None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("not found"), "{body}");
+
+        std::fs::write(dir.path().join("justfile"), "build:\n    cargo build\n").unwrap();
+        let (status, _) = request(&router, "GET", "/readyz?verbose", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        std::fs::write(
+            dir.path().join("justfile"),
+            "build: generate\n    cargo build\n",
+        )
+        .unwrap();
+        let (status, body) = request(&router, "GET", "/readyz", None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains("unknown recipe 'generate'"), "{body}");
+        let (status, body) = request(&router, "HEAD", "/readyz", None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let server = JustMcpServer::new(".");
+        server.metrics().record_cache_lookup(true);
+        let router = router(server, None, Some("secret"));
+        let (status, _) = request(&router, "GET", "/metrics", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = request(&router, "GET", "/metrics", Some("Bearer guess")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = request(&router, "GET", "/healthz", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = request(&router, "GET", "/metrics", Some("Bearer secret")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("just_mcp_cache_lookups_total{result=\"hit\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_routes_need_the_token() {
+        for (endpoint, method, path) in [
+            (McpEndpoint::StreamableHttp, "POST", MCP_PATH),
+            (McpEndpoint::Sse, "GET", SSE_PATH),
+            (McpEndpoint::Sse, "POST", SSE_MESSAGE_PATH),
+        ] {
+            let router = router(JustMcpServer::new("."), Some(endpoint), Some("secret"));
+            let (status, _) = request(&router, method, path, None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{method} {path}");
+            let (status, _) = request(&router, method, path, Some("Bearer guess")).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{method} {path}");
+            let (status, _) = request(&router, "GET", "/healthz", None).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_routes() {
+        let router = router(JustMcpServer::new("."), None, None);
+        let (status, _) = request(&router, "GET", "/mcp", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = request(&router, "POST", "/healthz", None).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
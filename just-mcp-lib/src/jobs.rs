@@ -0,0 +1,466 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::executor::{CancelToken, TranscriptEntry};
+
+/// Finished jobs kept for `status`/`result` calls; older ones are dropped.
+pub const MAX_FINISHED_JOBS: usize = 100;
+
+const ID_PREFIX: &str = "exec-";
+
+#[derive(Debug, Snafu)]
+pub enum JobError {
+    #[snafu(display("Unknown execution id '{}'", execution_id))]
+    UnknownExecution { execution_id: String },
+
+    #[snafu(display("Execution '{}' is still running", execution_id))]
+    StillRunning { execution_id: String },
+
+    #[snafu(display("Execution '{}' is not running", execution_id))]
+    NotRunning { execution_id: String },
+
+    #[snafu(display("Execution '{}' was cancelled", execution_id))]
+    Cancelled { execution_id: String },
+
+    #[snafu(display("Execution '{}' failed: {}", execution_id, message))]
+    Failed {
+        execution_id: String,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, JobError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    /// The recipe ran; its exit code is in the result
+    Finished,
+    /// The recipe could not be run, e.g. a substitution error
+    Failed,
+    /// Stopped by `cancel`
+    Cancelled,
+}
+
+/// Progress of a background execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct JobStatus {
+    pub execution_id: String,
+    pub recipe_name: String,
+    pub state: JobState,
+    pub elapsed_ms: u64,
+    /// Output lines received so far
+    pub output_lines: usize,
+    /// Output lines after the first `since`, in the order they were received
+    pub output: Vec<TranscriptEntry>,
+}
+
+/// Part of an execution's log to return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogRange {
+    /// The last N lines
+    LastLines(usize),
+    /// `length` bytes (default: to the end) starting at byte `offset`
+    Bytes {
+        offset: usize,
+        length: Option<usize>,
+    },
+}
+
+/// A slice of an execution's log: its output lines, one per line, in the
+/// order they were received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LogTail {
+    pub execution_id: String,
+    pub state: JobState,
+    pub total_lines: usize,
+    pub total_bytes: usize,
+    /// Byte offset of `text` in the log; pass `offset + text length` as the
+    /// next offset to follow the log
+    pub offset: usize,
+    pub text: String,
+}
+
+#[derive(Debug)]
+struct Job<T> {
+    recipe_name: String,
+    started: Instant,
+    finished: Option<Instant>,
+    output: Vec<TranscriptEntry>,
+    result: Option<std::result::Result<T, String>>,
+    cancel: CancelToken,
+}
+
+#[derive(Debug)]
+struct Jobs<T> {
+    next_id: u64,
+    jobs: BTreeMap<u64, Job<T>>,
+}
+
+impl<T> Jobs<T> {
+    fn get(&self, execution_id: &str) -> Result<&Job<T>> {
+        parse_id(execution_id)
+            .and_then(|id| self.jobs.get(&id))
+            .context(UnknownExecutionSnafu { execution_id })
+    }
+}
+
+impl<T> Job<T> {
+    fn state(&self) -> JobState {
+        match &self.result {
+            _ if self.cancel.is_cancelled() => JobState::Cancelled,
+            None => JobState::Running,
+            Some(Ok(_)) => JobState::Finished,
+            Some(Err(_)) => JobState::Failed,
+        }
+    }
+}
+
+/// Background executions, each identified by an execution id such as
+/// `exec-1`. The runner reports output and the outcome through the
+/// `JobHandle` returned by `start`; callers poll `status` and `result`.
+#[derive(Debug)]
+pub struct JobManager<T> {
+    jobs: Arc<Mutex<Jobs<T>>>,
+}
+
+impl<T> Clone for JobManager<T> {
+    fn clone(&self) -> Self {
+        Self {
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+impl<T> Default for JobManager<T> {
+    fn default() -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(Jobs {
+                next_id: 1,
+                jobs: BTreeMap::new(),
+            })),
+        }
+    }
+}
+
+/// Reports progress of one job.
+#[derive(Debug)]
+pub struct JobHandle<T> {
+    id: u64,
+    jobs: Arc<Mutex<Jobs<T>>>,
+}
+
+impl<T> Clone for JobHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+impl<T: Clone> JobManager<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a running job for `recipe_name`.
+    pub fn start(&self, recipe_name: &str) -> JobHandle<T> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let id = jobs.next_id;
+        jobs.next_id += 1;
+        jobs.jobs.insert(
+            id,
+            Job {
+                recipe_name: recipe_name.to_string(),
+                started: Instant::now(),
+                finished: None,
+                output: Vec::new(),
+                result: None,
+                cancel: CancelToken::new(),
+            },
+        );
+        JobHandle {
+            id,
+            jobs: self.jobs.clone(),
+        }
+    }
+
+    /// Current state of a job, with the output lines after the first `since`.
+    pub fn status(&self, execution_id: &str, since: usize) -> Result<JobStatus> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.get(execution_id)?;
+        let elapsed = job.finished.unwrap_or_else(Instant::now) - job.started;
+        Ok(JobStatus {
+            execution_id: execution_id.to_string(),
+            recipe_name: job.recipe_name.clone(),
+            state: job.state(),
+            elapsed_ms: elapsed.as_millis() as u64,
+            output_lines: job.output.len(),
+            output: job.output.iter().skip(since).cloned().collect(),
+        })
+    }
+
+    /// Outcome of a finished job.
+    pub fn result(&self, execution_id: &str) -> Result<T> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.get(execution_id)?;
+        match &job.result {
+            _ if job.cancel.is_cancelled() => CancelledSnafu { execution_id }.fail(),
+            None => StillRunningSnafu { execution_id }.fail(),
+            Some(Ok(result)) => Ok(result.clone()),
+            Some(Err(message)) => FailedSnafu {
+                execution_id,
+                message: message.clone(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Cancel a running job, killing its current command. The job is
+    /// reported as `Cancelled` from then on.
+    pub fn cancel(&self, execution_id: &str) -> Result<JobStatus> {
+        {
+            let jobs = self.jobs.lock().unwrap();
+            let job = jobs.get(execution_id)?;
+            ensure!(
+                job.result.is_none() && !job.cancel.is_cancelled(),
+                NotRunningSnafu { execution_id }
+            );
+            job.cancel.cancel();
+        }
+        self.status(execution_id, 0)
+    }
+
+    /// Part of a job's log, whether it is running or done.
+    pub fn tail(&self, execution_id: &str, range: LogRange) -> Result<LogTail> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs.get(execution_id)?;
+        let log: String = job
+            .output
+            .iter()
+            .map(|entry| format!("{}\n", entry.line))
+            .collect();
+
+        let (offset, end) = match range {
+            LogRange::LastLines(lines) => {
+                let skipped = job.output.len().saturating_sub(lines);
+                let offset = job.output[..skipped]
+                    .iter()
+                    .map(|entry| entry.line.len() + 1)
+                    .sum();
+                (offset, log.len())
+            }
+            LogRange::Bytes { offset, length } => {
+                let offset = offset.min(log.len());
+                let end = length.map_or(log.len(), |length| {
+                    offset.saturating_add(length).min(log.len())
+                });
+                (offset, end)
+            }
+        };
+
+        Ok(LogTail {
+            execution_id: execution_id.to_string(),
+            state: job.state(),
+            total_lines: job.output.len(),
+            total_bytes: log.len(),
+            offset,
+            // A range may split a multi-byte character
+            text: String::from_utf8_lossy(&log.as_bytes()[offset..end]).into_owned(),
+        })
+    }
+}
+
+impl<T> JobHandle<T> {
+    pub fn execution_id(&self) -> String {
+        format!("{ID_PREFIX}{}", self.id)
+    }
+
+    /// Token the runner checks so `JobManager::cancel` can stop it.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.jobs.lock().unwrap().jobs[&self.id].cancel.clone()
+    }
+
+    pub fn push_output(&self, entry: &TranscriptEntry) {
+        if let Some(job) = self.jobs.lock().unwrap().jobs.get_mut(&self.id) {
+            job.output.push(entry.clone());
+        }
+    }
+
+    /// Record the outcome and drop the oldest finished jobs beyond
+    /// `MAX_FINISHED_JOBS`.
+    pub fn finish(&self, result: std::result::Result<T, String>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.jobs.get_mut(&self.id) {
+            job.finished = Some(Instant::now());
+            job.result = Some(result);
+        }
+
+        let finished: Vec<u64> = jobs
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.result.is_some())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished
+            .iter()
+            .take(finished.len().saturating_sub(MAX_FINISHED_JOBS))
+        {
+            jobs.jobs.remove(id);
+        }
+    }
+}
+
+fn parse_id(execution_id: &str) -> Option<u64> {
+    execution_id.strip_prefix(ID_PREFIX)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::OutputStream;
+
+    fn line(text: &str) -> TranscriptEntry {
+        TranscriptEntry {
+            elapsed_us: 0,
+            stream: OutputStream::Stdout,
+            line: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let jobs = JobManager::<i32>::new();
+        let job = jobs.start("build");
+        let id = job.execution_id();
+        assert_eq!(id, "exec-1");
+
+        job.push_output(&line("compiling"));
+        job.push_output(&line("linking"));
+        let status = jobs.status(&id, 1).unwrap();
+        assert_eq!(status.state, JobState::Running);
+        assert_eq!(status.output_lines, 2);
+        assert_eq!(status.output, vec![line("linking")]);
+        assert!(matches!(
+            jobs.result(&id),
+            Err(JobError::StillRunning { .. })
+        ));
+
+        job.finish(Ok(0));
+        assert_eq!(jobs.status(&id, 0).unwrap().state, JobState::Finished);
+        assert_eq!(jobs.result(&id).unwrap(), 0);
+
+        let failed = jobs.start("deploy");
+        failed.finish(Err("Recipe 'deploy' not found".to_string()));
+        assert_eq!(
+            jobs.status(&failed.execution_id(), 0).unwrap().state,
+            JobState::Failed
+        );
+        assert!(matches!(
+            jobs.result(&failed.execution_id()),
+            Err(JobError::Failed { .. })
+        ));
+        assert!(matches!(
+            jobs.status("exec-99", 0),
+            Err(JobError::UnknownExecution { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cancel_job() {
+        let jobs = JobManager::<i32>::new();
+        let job = jobs.start("deploy");
+        let cancel = job.cancel_token();
+
+        let status = jobs.cancel(&job.execution_id()).unwrap();
+        assert_eq!(status.state, JobState::Cancelled);
+        assert!(cancel.is_cancelled());
+        assert!(matches!(
+            jobs.cancel(&job.execution_id()),
+            Err(JobError::NotRunning { .. })
+        ));
+
+        job.finish(Err("Execution of recipe 'deploy' was cancelled".to_string()));
+        assert!(matches!(
+            jobs.result(&job.execution_id()),
+            Err(JobError::Cancelled { .. })
+        ));
+
+        let finished = jobs.start("build");
+        finished.finish(Ok(0));
+        assert!(matches!(
+            jobs.cancel(&finished.execution_id()),
+            Err(JobError::NotRunning { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tail_log() {
+        let jobs = JobManager::<i32>::new();
+        let job = jobs.start("build");
+        let id = job.execution_id();
+        for text in ["one", "two", "three"] {
+            job.push_output(&line(text));
+        }
+
+        let tail = jobs.tail(&id, LogRange::LastLines(2)).unwrap();
+        assert_eq!(tail.text, "two\nthree\n");
+        assert_eq!(tail.offset, 4);
+        assert_eq!((tail.total_lines, tail.total_bytes), (3, 14));
+        assert_eq!(tail.state, JobState::Running);
+
+        let tail = jobs
+            .tail(
+                &id,
+                LogRange::Bytes {
+                    offset: 4,
+                    length: Some(3),
+                },
+            )
+            .unwrap();
+        assert_eq!(tail.text, "two");
+
+        // Following the log picks up only new output
+        job.push_output(&line("four"));
+        let tail = jobs
+            .tail(
+                &id,
+                LogRange::Bytes {
+                    offset: 14,
+                    length: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(tail.text, "four\n");
+
+        let past_end = jobs
+            .tail(
+                &id,
+                LogRange::Bytes {
+                    offset: 100,
+                    length: None,
+                },
+            )
+            .unwrap();
+        assert_eq!((past_end.offset, past_end.text.as_str()), (19, ""));
+    }
+
+    #[test]
+    fn test_oldest_finished_jobs_are_dropped() {
+        let jobs = JobManager::<()>::new();
+        let running = jobs.start("watch");
+        for _ in 0..=MAX_FINISHED_JOBS {
+            jobs.start("build").finish(Ok(()));
+        }
+        // exec-2 was the oldest finished job
+        assert!(jobs.status("exec-2", 0).is_err());
+        assert!(jobs.status("exec-3", 0).is_ok());
+        assert!(jobs.status(&running.execution_id(), 0).is_ok());
+    }
+}
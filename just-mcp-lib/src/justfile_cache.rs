@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::Justfile;
+use crate::parser::{ParserError, Result, parse_justfile_str};
+use crate::watcher::{self, Stamp};
+
+/// Files modified this recently when they were read are hashed on every
+/// lookup: a second edit within the filesystem's timestamp resolution can
+/// leave both the size and the modification time unchanged.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// A justfile's source and what it parses to.
+#[derive(Debug)]
+pub struct CachedJustfile {
+    pub source: String,
+    pub justfile: Justfile,
+}
+
+#[derive(Debug)]
+struct Entry {
+    stamp: Stamp,
+    hash: u64,
+    /// Modified within `RACY_WINDOW` of being read
+    racy: bool,
+    parsed: Arc<CachedJustfile>,
+}
+
+/// Parsed justfiles by path, so tool calls do not read and parse the
+/// justfile again while it is unchanged. An entry is reused as long as the
+/// file's size and modification time are; when they change, the file is
+/// read again but only parsed if its content hash differs too.
+#[derive(Debug, Default)]
+pub struct JustfileCache {
+    entries: HashMap<PathBuf, Entry>,
+}
+
+impl JustfileCache {
+    /// The justfile at `path`, from `cache` when it is still current. Files
+    /// that fail to parse are not cached.
+    pub fn load(cache: &RwLock<Self>, path: &Path) -> Result<Arc<CachedJustfile>> {
+        let stamp = watcher::stamp(path);
+        if let Some(entry) = cache.read().unwrap().entries.get(path)
+            && entry.stamp == stamp
+            && !entry.racy
+        {
+            return Ok(entry.parsed.clone());
+        }
+
+        let source = std::fs::read_to_string(path).map_err(|source| ParserError::FileRead {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let hash = hash_source(&source);
+        let unchanged = cache
+            .read()
+            .unwrap()
+            .entries
+            .get(path)
+            .filter(|entry| entry.hash == hash)
+            .map(|entry| entry.parsed.clone());
+        let parsed = match unchanged {
+            Some(parsed) => parsed,
+            None => {
+                let justfile = parse_justfile_str(&source)?;
+                Arc::new(CachedJustfile { source, justfile })
+            }
+        };
+
+        let entry = Entry {
+            racy: is_racy(&stamp),
+            stamp,
+            hash,
+            parsed: parsed.clone(),
+        };
+        cache
+            .write()
+            .unwrap()
+            .entries
+            .insert(path.to_path_buf(), entry);
+        Ok(parsed)
+    }
+
+    /// Forget `path`; true if it was cached.
+    pub fn invalidate(&mut self, path: &Path) -> bool {
+        self.entries.remove(path).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn hash_source(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn is_racy(stamp: &Stamp) -> bool {
+    match stamp {
+        Some((Some(modified), _)) => !SystemTime::now()
+            .duration_since(*modified)
+            .is_ok_and(|age| age >= RACY_WINDOW),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_reuses_unchanged_justfiles() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("justfile");
+        fs::write(&path, "build:\n    cargo build\n").unwrap();
+        let old = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let cache = RwLock::new(JustfileCache::default());
+        let first = JustfileCache::load(&cache, &path).unwrap();
+        let second = JustfileCache::load(&cache, &path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Touched but identical content is not parsed again
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now()).unwrap();
+        let touched = JustfileCache::load(&cache, &path).unwrap();
+        assert!(Arc::ptr_eq(&first, &touched));
+
+        // Same size and a recent modification time: the hash catches it
+        fs::write(&path, "check:\n    cargo check\n").unwrap();
+        let edited = JustfileCache::load(&cache, &path).unwrap();
+        assert_eq!(edited.justfile.recipes[0].name, "check");
+
+        fs::write(&path, "broken recipe\n").unwrap();
+        assert!(JustfileCache::load(&cache, &path).is_err());
+        assert!(cache.write().unwrap().invalidate(&path));
+        assert!(cache.read().unwrap().is_empty());
+    }
+}
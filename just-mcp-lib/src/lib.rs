@@ -1,27 +1,156 @@
+pub mod artifacts;
+pub mod audit;
+pub mod auth;
+pub mod completion;
+pub mod config;
+pub mod diff;
+pub mod discovery;
+pub mod docgen;
+pub mod doctor;
+pub mod editor;
 pub mod environment;
 pub mod executor;
+pub mod formatter;
+pub mod graph;
+pub mod history;
+pub mod http;
+pub mod jobs;
+pub mod justfile_cache;
+pub mod limits;
+pub mod lints;
+pub mod locks;
+pub mod logging;
+pub mod makefile;
 pub mod mcp_server;
+pub mod memo;
+pub mod metrics;
 pub mod parser;
+pub mod policy;
+pub mod prompts;
+pub mod redact;
 pub mod registry;
+pub mod roots;
+pub mod scaffold;
+pub mod search;
+pub mod secrets;
+pub mod server_log;
+pub mod sessions;
+pub mod settings;
+pub mod shutdown;
+pub mod telemetry;
+pub mod timeouts;
+pub mod tls;
+pub mod transport;
 pub mod validator;
+pub mod watcher;
 
 pub use registry::JustfileRegistry;
 
-use std::collections::HashMap;
+use rmcp::schemars::{self, JsonSchema};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Justfile {
     pub recipes: Vec<Recipe>,
     pub variables: HashMap<String, String>,
+    /// `set NAME := VALUE` statements; a bare `set NAME` is stored as `"true"`
+    pub settings: HashMap<String, String>,
+    /// `alias NAME := RECIPE` statements, keyed by alias name
+    pub aliases: HashMap<String, String>,
+    /// Variables declared with `export`
+    pub exported: HashSet<String>,
+    /// Line on which each variable is assigned
+    pub variable_spans: HashMap<String, Span>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Justfile {
+    /// True when a boolean setting such as `set quiet` is enabled.
+    pub fn setting_enabled(&self, name: &str) -> bool {
+        self.settings
+            .get(name)
+            .is_some_and(|value| value.trim() == "true")
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Recipe {
     pub name: String,
     pub parameters: Vec<Parameter>,
     pub documentation: Option<String>,
     pub body: String,
     pub dependencies: Vec<String>,
+    /// Declared as `@name:` — lines are not echoed unless prefixed with `@`
+    pub quiet: bool,
+    /// Lines from the recipe header through its last body line
+    pub span: Span,
+    /// Attributes such as `[linux]` or `[group('ci')]`, one entry each
+    pub attributes: Vec<String>,
+    /// Types declared in doc comments with `# @param NAME: TYPE`
+    pub parameter_types: HashMap<String, ParameterType>,
+}
+
+impl Recipe {
+    /// Body lines paired with their 1-based line number in the justfile.
+    pub fn body_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+        let first_line = self.span.start_line + 1;
+        self.body
+            .lines()
+            .enumerate()
+            .map(move |(index, line)| (first_line + index, line))
+    }
+
+    /// True when the recipe is restricted to particular platforms, e.g. `[linux]`.
+    pub fn has_platform_attribute(&self) -> bool {
+        const PLATFORMS: &[&str] = &["linux", "macos", "windows", "unix", "openbsd"];
+        self.attributes
+            .iter()
+            .any(|attribute| PLATFORMS.contains(&attribute.as_str()))
+    }
+
+    /// True for `[private]` recipes and names starting with `_`, which are
+    /// not exposed as tools or documented.
+    pub fn is_private(&self) -> bool {
+        self.name.starts_with('_') || self.attributes.iter().any(|a| a == "private")
+    }
+
+    /// Groups from `[group('NAME')]` attributes.
+    pub fn groups(&self) -> Vec<&str> {
+        self.attributes
+            .iter()
+            .filter_map(|attribute| {
+                attribute
+                    .strip_prefix("group(")?
+                    .strip_suffix(')')
+                    .map(|name| name.trim().trim_matches(['\'', '"']))
+            })
+            .collect()
+    }
+
+    /// True when just asks before running the recipe: `[confirm]` or `[confirm("prompt")]`.
+    pub fn requires_confirmation(&self) -> bool {
+        self.attributes.iter().any(|attribute| {
+            attribute == "confirm"
+                || attribute.starts_with("confirm(")
+                || attribute.starts_with("confirm:")
+        })
+    }
+}
+
+/// A 1-based, inclusive line range in the justfile source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Span {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Span {
+    pub fn line(line: usize) -> Self {
+        Span {
+            start_line: line,
+            end_line: line,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -29,3 +158,87 @@ pub struct Parameter {
     pub name: String,
     pub default_value: Option<String>,
 }
+
+/// Declared type of a recipe parameter, e.g. `int` or `enum(dev,staging,prod)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParameterType {
+    String,
+    Int,
+    Float,
+    Bool,
+    Enum(Vec<String>),
+}
+
+impl ParameterType {
+    /// Parse a type annotation; returns `None` for unrecognized types.
+    pub fn parse(annotation: &str) -> Option<Self> {
+        let annotation = annotation.trim();
+        if let Some(values) = annotation
+            .strip_prefix("enum(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let values: Vec<String> = values
+                .split(',')
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect();
+            return (!values.is_empty()).then_some(ParameterType::Enum(values));
+        }
+        match annotation {
+            "string" | "str" => Some(ParameterType::String),
+            "int" | "integer" => Some(ParameterType::Int),
+            "float" | "number" => Some(ParameterType::Float),
+            "bool" | "boolean" => Some(ParameterType::Bool),
+            _ => None,
+        }
+    }
+
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            ParameterType::String => true,
+            ParameterType::Int => value.parse::<i64>().is_ok(),
+            ParameterType::Float => value.parse::<f64>().is_ok(),
+            ParameterType::Bool => matches!(value, "true" | "false"),
+            ParameterType::Enum(values) => values.iter().any(|v| v == value),
+        }
+    }
+
+    /// JSON schema describing values of this type.
+    pub fn json_schema(&self) -> serde_json::Value {
+        match self {
+            ParameterType::String => serde_json::json!({ "type": "string" }),
+            ParameterType::Int => serde_json::json!({ "type": "integer" }),
+            ParameterType::Float => serde_json::json!({ "type": "number" }),
+            ParameterType::Bool => serde_json::json!({ "type": "boolean" }),
+            ParameterType::Enum(values) => serde_json::json!({ "type": "string", "enum": values }),
+        }
+    }
+
+    /// `value` as JSON of this type, e.g. `8080` rather than `"8080"` for an
+    /// `int`. Values the type does not accept stay strings.
+    pub fn json_value(&self, value: &str) -> serde_json::Value {
+        let typed = match self {
+            ParameterType::Int => value.parse::<i64>().ok().map(serde_json::Value::from),
+            ParameterType::Float => value
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number),
+            ParameterType::Bool => value.parse::<bool>().ok().map(serde_json::Value::Bool),
+            ParameterType::String | ParameterType::Enum(_) => None,
+        };
+        typed.unwrap_or_else(|| serde_json::Value::String(value.to_string()))
+    }
+}
+
+impl std::fmt::Display for ParameterType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParameterType::String => write!(f, "string"),
+            ParameterType::Int => write!(f, "int"),
+            ParameterType::Float => write!(f, "float"),
+            ParameterType::Bool => write!(f, "bool"),
+            ParameterType::Enum(values) => write!(f, "enum({})", values.join(",")),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Deserialize;
+use snafu::prelude::*;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Window `tool_calls_per_minute` is counted over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// What one client session may do, from the config's `[limits]` table.
+/// Each limit is off when absent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SessionLimits {
+    /// Recipe executions the session may have running at once
+    pub max_concurrent_executions: Option<usize>,
+    /// Tool calls the session may make in any 60 seconds
+    pub tool_calls_per_minute: Option<usize>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum LimitError {
+    #[snafu(display(
+        "Rate limit exceeded: at most {} tool calls per minute; retry in {} ms",
+        limit,
+        retry_after_ms
+    ))]
+    TooManyToolCalls { limit: usize, retry_after_ms: u64 },
+
+    #[snafu(display(
+        "Concurrency limit exceeded: at most {} recipe executions may run at once",
+        limit
+    ))]
+    TooManyExecutions { limit: usize },
+}
+
+pub type Result<T> = std::result::Result<T, LimitError>;
+
+/// Enforces `SessionLimits` for one session.
+#[derive(Debug)]
+pub struct SessionLimiter {
+    limits: SessionLimits,
+    /// When each tool call in the current window was made, oldest first
+    calls: Mutex<VecDeque<Instant>>,
+    running: Arc<AtomicUsize>,
+}
+
+impl SessionLimiter {
+    pub fn new(limits: SessionLimits) -> Self {
+        Self {
+            limits,
+            calls: Mutex::new(VecDeque::new()),
+            running: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn limits(&self) -> &SessionLimits {
+        &self.limits
+    }
+
+    /// Count a tool call, failing when the session has made its quota of
+    /// calls in the last minute. Refused calls do not count.
+    pub fn check_tool_call(&self) -> Result<()> {
+        self.check_tool_call_at(Instant::now())
+    }
+
+    fn check_tool_call_at(&self, now: Instant) -> Result<()> {
+        let Some(limit) = self.limits.tool_calls_per_minute else {
+            return Ok(());
+        };
+        let mut calls = self.calls.lock().unwrap();
+        while calls
+            .front()
+            .is_some_and(|call| now.duration_since(*call) >= RATE_WINDOW)
+        {
+            calls.pop_front();
+        }
+        if calls.len() >= limit {
+            let oldest = calls.front().copied().unwrap_or(now);
+            let retry_after = RATE_WINDOW.saturating_sub(now.duration_since(oldest));
+            return TooManyToolCallsSnafu {
+                limit,
+                retry_after_ms: retry_after.as_millis() as u64,
+            }
+            .fail();
+        }
+        calls.push_back(now);
+        Ok(())
+    }
+
+    /// Reserve a slot for a recipe execution, released when the permit is
+    /// dropped.
+    pub fn start_execution(&self) -> Result<ExecutionPermit> {
+        let limit = self.limits.max_concurrent_executions.unwrap_or(usize::MAX);
+        self.running
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |running| {
+                (running < limit).then_some(running + 1)
+            })
+            .map_err(|_| LimitError::TooManyExecutions { limit })?;
+        Ok(ExecutionPermit(self.running.clone()))
+    }
+}
+
+/// A running execution counted against `max_concurrent_executions`.
+#[derive(Debug)]
+pub struct ExecutionPermit(Arc<AtomicUsize>);
+
+impl Drop for ExecutionPermit {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_calls_per_minute() {
+        let limiter = SessionLimiter::new(SessionLimits {
+            tool_calls_per_minute: Some(2),
+            ..Default::default()
+        });
+        let start = Instant::now();
+        limiter.check_tool_call_at(start).unwrap();
+        limiter
+            .check_tool_call_at(start + Duration::from_secs(10))
+            .unwrap();
+
+        let error = limiter
+            .check_tool_call_at(start + Duration::from_secs(20))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            LimitError::TooManyToolCalls {
+                limit: 2,
+                retry_after_ms: 40_000
+            }
+        ));
+
+        // The first call has left the window
+        limiter
+            .check_tool_call_at(start + Duration::from_secs(60))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_executions() {
+        let limiter = SessionLimiter::new(SessionLimits {
+            max_concurrent_executions: Some(1),
+            ..Default::default()
+        });
+        let permit = limiter.start_execution().unwrap();
+        assert!(matches!(
+            limiter.start_execution(),
+            Err(LimitError::TooManyExecutions { limit: 1 })
+        ));
+        drop(permit);
+        limiter.start_execution().unwrap();
+    }
+
+    #[test]
+    fn test_no_limits() {
+        let limiter = SessionLimiter::new(SessionLimits::default());
+        let _permits: Vec<_> = (0..100)
+            .map(|_| limiter.start_execution().unwrap())
+            .collect();
+        for _ in 0..100 {
+            limiter.check_tool_call().unwrap();
+        }
+    }
+}
@@ -0,0 +1,655 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::parser::ParserError;
+use crate::validator::{self, ValidationErrorKind};
+use crate::{Justfile, Span};
+
+/// How a lint rule is treated, as configured in the `[lints]` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Do not report the rule
+    Allow,
+    /// Report as a warning
+    Warn,
+    /// Report as an error; the justfile is not valid
+    Deny,
+}
+
+/// Severity of a reported diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintRule {
+    pub id: &'static str,
+    pub default_level: LintLevel,
+    pub description: &'static str,
+}
+
+/// Every rule the lint engine knows about.
+pub const RULES: &[LintRule] = &[
+    LintRule {
+        id: "syntax-error",
+        default_level: LintLevel::Deny,
+        description: "The justfile could not be parsed",
+    },
+    LintRule {
+        id: "unknown-dependency",
+        default_level: LintLevel::Deny,
+        description: "A recipe depends on a recipe that does not exist",
+    },
+    LintRule {
+        id: "self-dependency",
+        default_level: LintLevel::Deny,
+        description: "A recipe lists itself as a dependency",
+    },
+    LintRule {
+        id: "circular-dependency",
+        default_level: LintLevel::Deny,
+        description: "Recipe dependencies form a cycle",
+    },
+    LintRule {
+        id: "undefined-variable",
+        default_level: LintLevel::Deny,
+        description: "A {{ }} reference is neither a parameter nor a variable",
+    },
+    LintRule {
+        id: "duplicate-recipe",
+        default_level: LintLevel::Deny,
+        description: "A recipe is defined more than once",
+    },
+    LintRule {
+        id: "duplicate-parameter",
+        default_level: LintLevel::Deny,
+        description: "A recipe declares the same parameter twice",
+    },
+    LintRule {
+        id: "missing-documentation",
+        default_level: LintLevel::Warn,
+        description: "A recipe has no doc comment",
+    },
+    LintRule {
+        id: "empty-recipe",
+        default_level: LintLevel::Warn,
+        description: "A recipe has neither a body nor dependencies",
+    },
+    LintRule {
+        id: "dangerous-command",
+        default_level: LintLevel::Warn,
+        description: "A recipe runs a destructive command (rm -rf /, curl | sh, sudo, force-push)",
+    },
+    LintRule {
+        id: "unused-variable",
+        default_level: LintLevel::Warn,
+        description: "A variable is never referenced or exported",
+    },
+    LintRule {
+        id: "unused-recipe",
+        default_level: LintLevel::Allow,
+        description: "A recipe is never used as a dependency or alias target",
+    },
+    LintRule {
+        id: "shadowed-variable",
+        default_level: LintLevel::Warn,
+        description: "A recipe parameter has the same name as a top-level variable",
+    },
+    LintRule {
+        id: "bashism",
+        default_level: LintLevel::Warn,
+        description: "Bash-only syntax in a recipe run by sh or on Windows",
+    },
+];
+
+pub fn find_rule(id: &str) -> Option<&'static LintRule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+/// Per-rule level overrides, e.g.
+///
+/// ```toml
+/// [lints]
+/// missing-documentation = "allow"
+/// empty-recipe = "deny"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct LintConfig {
+    pub levels: BTreeMap<String, LintLevel>,
+}
+
+impl LintConfig {
+    pub fn level(&self, rule: &LintRule) -> LintLevel {
+        self.levels
+            .get(rule.id)
+            .copied()
+            .unwrap_or(rule.default_level)
+    }
+
+    /// Configured rule IDs that do not match any known rule.
+    pub fn unknown_rules(&self) -> Vec<&str> {
+        self.levels
+            .keys()
+            .map(String::as_str)
+            .filter(|id| find_rule(id).is_none())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// A second location involved in the finding, e.g. a shadowed definition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_span: Option<Span>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LintReport {
+    pub diagnostics: Vec<Diagnostic>,
+    pub error_count: usize,
+    pub warning_count: usize,
+}
+
+impl LintReport {
+    /// Report for a justfile that failed to parse.
+    pub fn syntax_error(error: &ParserError, config: &LintConfig) -> Self {
+        let span = match error {
+            ParserError::ParseError { line, .. } => Some(Span::line(*line)),
+            _ => None,
+        };
+        let mut report = LintReport::default();
+        report.push(
+            config,
+            "syntax-error",
+            Finding {
+                message: error.to_string(),
+                recipe: None,
+                span,
+                related_span: None,
+            },
+        );
+        report
+    }
+
+    /// True when no diagnostic has error severity.
+    pub fn is_valid(&self) -> bool {
+        self.error_count == 0
+    }
+
+    fn push(&mut self, config: &LintConfig, rule_id: &'static str, finding: Finding) {
+        let rule = find_rule(rule_id).expect("lint rule is registered");
+        let severity = match config.level(rule) {
+            LintLevel::Allow => return,
+            LintLevel::Warn => {
+                self.warning_count += 1;
+                Severity::Warning
+            }
+            LintLevel::Deny => {
+                self.error_count += 1;
+                Severity::Error
+            }
+        };
+        self.diagnostics.push(Diagnostic {
+            rule: rule.id,
+            severity,
+            message: finding.message,
+            recipe: finding.recipe,
+            span: finding.span,
+            related_span: finding.related_span,
+        });
+    }
+}
+
+struct Finding {
+    message: String,
+    recipe: Option<String>,
+    span: Option<Span>,
+    related_span: Option<Span>,
+}
+
+fn rule_id(kind: ValidationErrorKind) -> Option<&'static str> {
+    match kind {
+        ValidationErrorKind::UnknownDependency => Some("unknown-dependency"),
+        ValidationErrorKind::SelfDependency => Some("self-dependency"),
+        ValidationErrorKind::CircularDependency => Some("circular-dependency"),
+        ValidationErrorKind::UndefinedVariable => Some("undefined-variable"),
+        ValidationErrorKind::DuplicateRecipe => Some("duplicate-recipe"),
+        ValidationErrorKind::DuplicateParameter => Some("duplicate-parameter"),
+        // Argument errors only arise when validating a call, never a justfile
+        ValidationErrorKind::MissingArgument
+        | ValidationErrorKind::TooManyArguments
+        | ValidationErrorKind::InvalidType
+        | ValidationErrorKind::UnknownParameter
+        | ValidationErrorKind::ConflictingArgument => None,
+    }
+}
+
+/// Run the semantic validator and the style checks, applying the configured
+/// level of each rule.
+pub fn lint_justfile(justfile: &Justfile, config: &LintConfig) -> LintReport {
+    let mut report = LintReport::default();
+
+    for error in validator::validate_justfile(justfile).errors {
+        let Some(rule_id) = rule_id(error.kind) else {
+            continue;
+        };
+        report.push(
+            config,
+            rule_id,
+            Finding {
+                message: error.message,
+                recipe: error.recipe,
+                span: error.span,
+                related_span: None,
+            },
+        );
+    }
+
+    for name in validator::unused_variables(justfile) {
+        report.push(
+            config,
+            "unused-variable",
+            Finding {
+                message: format!("Variable '{name}' is never used"),
+                recipe: None,
+                span: justfile.variable_spans.get(name).copied(),
+                related_span: None,
+            },
+        );
+    }
+
+    for recipe in validator::unused_recipes(justfile) {
+        report.push(
+            config,
+            "unused-recipe",
+            Finding {
+                message: format!(
+                    "Recipe '{}' is not referenced by any recipe or alias",
+                    recipe.name
+                ),
+                recipe: Some(recipe.name.clone()),
+                span: Some(recipe.span),
+                related_span: None,
+            },
+        );
+    }
+
+    for (recipe, param) in validator::shadowed_parameters(justfile) {
+        let variable_span = justfile.variable_spans.get(&param.name).copied();
+        report.push(
+            config,
+            "shadowed-variable",
+            Finding {
+                message: format!(
+                    "Parameter '{}' of recipe '{}' (line {}) shadows variable '{}' (line {}); the parameter value is used",
+                    param.name,
+                    recipe.name,
+                    recipe.span.start_line,
+                    param.name,
+                    variable_span.map_or(0, |span| span.start_line)
+                ),
+                recipe: Some(recipe.name.clone()),
+                span: Some(Span::line(recipe.span.start_line)),
+                related_span: variable_span,
+            },
+        );
+    }
+
+    let shell = configured_shell(justfile);
+    let posix_shell = matches!(shell.as_str(), "sh" | "dash");
+    let windows = justfile.settings.contains_key("windows-shell")
+        || justfile.setting_enabled("windows-powershell");
+
+    for recipe in &justfile.recipes {
+        // Shebang recipes choose their own interpreter
+        let shebang = recipe.body.trim_start().starts_with("#!");
+        let portable = posix_shell || windows || recipe.attributes.iter().any(|a| a == "windows");
+        if portable && !shebang {
+            for (line_number, line) in recipe.body_lines() {
+                if let Some(bashism) = bashism(line) {
+                    let target = if posix_shell {
+                        shell.as_str()
+                    } else {
+                        "Windows"
+                    };
+                    report.push(
+                        config,
+                        "bashism",
+                        Finding {
+                            message: format!(
+                                "Recipe '{}' uses {} which is not portable to {}: {}",
+                                recipe.name,
+                                bashism,
+                                target,
+                                line.trim()
+                            ),
+                            recipe: Some(recipe.name.clone()),
+                            span: Some(Span::line(line_number)),
+                            related_span: None,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    for recipe in &justfile.recipes {
+        if recipe.documentation.is_none() {
+            report.push(
+                config,
+                "missing-documentation",
+                Finding {
+                    message: format!("Recipe '{}' has no documentation comment", recipe.name),
+                    recipe: Some(recipe.name.clone()),
+                    span: Some(recipe.span),
+                    related_span: None,
+                },
+            );
+        }
+        if recipe.body.trim().is_empty() && recipe.dependencies.is_empty() {
+            report.push(
+                config,
+                "empty-recipe",
+                Finding {
+                    message: format!("Recipe '{}' does nothing", recipe.name),
+                    recipe: Some(recipe.name.clone()),
+                    span: Some(recipe.span),
+                    related_span: None,
+                },
+            );
+        }
+
+        for (line_number, line) in recipe.body_lines() {
+            if let Some(danger) = dangerous_command(line) {
+                report.push(
+                    config,
+                    "dangerous-command",
+                    Finding {
+                        message: format!("Recipe '{}' {}: {}", recipe.name, danger, line.trim()),
+                        recipe: Some(recipe.name.clone()),
+                        span: Some(Span::line(line_number)),
+                        related_span: None,
+                    },
+                );
+            }
+        }
+    }
+
+    report
+}
+
+/// The program from `set shell := ["bash", "-c"]`; just defaults to `sh`.
+fn configured_shell(justfile: &Justfile) -> String {
+    justfile
+        .settings
+        .get("shell")
+        .and_then(|value| value.split(['"', '\'']).nth(1))
+        .map(|program| program.rsplit('/').next().unwrap_or(program).to_string())
+        .unwrap_or_else(|| "sh".to_string())
+}
+
+/// Describe the bash-only construct a command line uses, if any.
+fn bashism(line: &str) -> Option<&'static str> {
+    let line = line.trim().trim_start_matches(['@', '-']);
+    let first_word = line.split_whitespace().next().unwrap_or("");
+
+    if line.contains("[[ ") {
+        Some("`[[ ]]` tests")
+    } else if first_word == "source" {
+        Some("`source`")
+    } else if first_word == "function" {
+        Some("the `function` keyword")
+    } else if first_word == "declare" || line.contains("=(") || line.contains("[@]") {
+        Some("arrays")
+    } else if line.contains("<(") {
+        Some("process substitution")
+    } else if line.contains("&>") {
+        Some("`&>` redirection")
+    } else {
+        None
+    }
+}
+
+/// Describe the destructive pattern a command line matches, if any.
+pub fn dangerous_command(line: &str) -> Option<&'static str> {
+    let line = line.trim().trim_start_matches(['@', '-']);
+    let segments = || line.split(['|', ';', '&']).map(str::split_whitespace);
+
+    for mut words in segments() {
+        match words.next() {
+            Some("sudo") => return Some("runs a command with sudo"),
+            Some("rm") => {
+                let args: Vec<&str> = words.collect();
+                let recursive = args.iter().any(|arg| {
+                    *arg == "--recursive"
+                        || (arg.starts_with('-')
+                            && !arg.starts_with("--")
+                            && arg.contains(['r', 'R']))
+                });
+                let absolute = args
+                    .iter()
+                    .any(|arg| arg.starts_with(['/', '~']) || arg.starts_with("$HOME"));
+                if recursive && absolute {
+                    return Some("recursively deletes an absolute path");
+                }
+            }
+            Some("git") => {
+                let args: Vec<&str> = words.collect();
+                let force = args.iter().any(|arg| {
+                    *arg == "-f" || *arg == "--force" || arg.starts_with("--force-with-lease")
+                });
+                if args.first() == Some(&"push") && force {
+                    return Some("force-pushes");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // A download piped straight into a shell
+    let mut stages = line.split('|').map(str::trim);
+    if let Some(first) = stages.next()
+        && (first.starts_with("curl") || first.starts_with("wget"))
+        && stages.any(|stage| {
+            let shell = stage.split_whitespace().next();
+            matches!(shell, Some("sh" | "bash" | "zsh"))
+        })
+    {
+        return Some("pipes a download into a shell");
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    const CONTENT: &str = r#"
+# Build the project
+build: missing
+    cargo build
+
+noop:
+"#;
+
+    #[test]
+    fn test_default_levels() {
+        let justfile = parse_justfile_str(CONTENT).unwrap();
+        let report = lint_justfile(&justfile, &LintConfig::default());
+
+        assert!(!report.is_valid());
+        assert_eq!(report.error_count, 1);
+        assert_eq!(report.warning_count, 2);
+
+        let rules: Vec<_> = report.diagnostics.iter().map(|d| d.rule).collect();
+        assert_eq!(
+            rules,
+            vec![
+                "unknown-dependency",
+                "missing-documentation",
+                "empty-recipe"
+            ]
+        );
+        assert_eq!(report.diagnostics[0].severity, Severity::Error);
+        assert_eq!(report.diagnostics[1].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_configured_levels_override_defaults() {
+        let justfile = parse_justfile_str(CONTENT).unwrap();
+        let mut config = LintConfig::default();
+        config
+            .levels
+            .insert("unknown-dependency".to_string(), LintLevel::Warn);
+        config
+            .levels
+            .insert("missing-documentation".to_string(), LintLevel::Allow);
+        config
+            .levels
+            .insert("empty-recipe".to_string(), LintLevel::Deny);
+
+        let report = lint_justfile(&justfile, &config);
+        assert!(!report.is_valid());
+        assert_eq!(report.error_count, 1);
+        assert_eq!(report.warning_count, 1);
+        assert_eq!(report.diagnostics.len(), 2);
+        assert_eq!(report.diagnostics[1].rule, "empty-recipe");
+        assert_eq!(report.diagnostics[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_unknown_rules_are_listed() {
+        let mut config = LintConfig::default();
+        config
+            .levels
+            .insert("missing-documentation".to_string(), LintLevel::Allow);
+        config
+            .levels
+            .insert("no-such-rule".to_string(), LintLevel::Deny);
+
+        assert_eq!(config.unknown_rules(), vec!["no-such-rule"]);
+    }
+
+    #[test]
+    fn test_dangerous_commands() {
+        assert_eq!(
+            dangerous_command("    rm -rf /var/lib/app"),
+            Some("recursively deletes an absolute path")
+        );
+        assert_eq!(
+            dangerous_command("\t-rm -r ~/cache"),
+            Some("recursively deletes an absolute path")
+        );
+        assert_eq!(
+            dangerous_command("    curl -fsSL https://example.com/install.sh | sh"),
+            Some("pipes a download into a shell")
+        );
+        assert_eq!(
+            dangerous_command("    cargo build && sudo make install"),
+            Some("runs a command with sudo")
+        );
+        assert_eq!(
+            dangerous_command("    git push --force origin main"),
+            Some("force-pushes")
+        );
+
+        assert_eq!(dangerous_command("    rm -rf target"), None);
+        assert_eq!(dangerous_command("    rm /tmp/file.txt"), None);
+        assert_eq!(
+            dangerous_command("    curl -o out.json https://example.com"),
+            None
+        );
+        assert_eq!(dangerous_command("    git push origin main"), None);
+    }
+
+    #[test]
+    fn test_dangerous_command_reports_line() {
+        let content = "# Clean up\nclean:\n    cargo clean\n\n    rm -rf /opt/app\n";
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let report = lint_justfile(&justfile, &LintConfig::default());
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].rule, "dangerous-command");
+        assert_eq!(report.diagnostics[0].severity, Severity::Warning);
+        assert_eq!(report.diagnostics[0].span, Some(Span::line(5)));
+    }
+
+    #[test]
+    fn test_shadowed_variable_includes_both_locations() {
+        let content = "env := \"dev\"\n\n# Deploy\ndeploy env:\n    echo {{ env }}\n";
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let report = lint_justfile(&justfile, &LintConfig::default());
+        assert_eq!(report.diagnostics.len(), 1);
+
+        let diagnostic = &report.diagnostics[0];
+        assert_eq!(diagnostic.rule, "shadowed-variable");
+        assert_eq!(diagnostic.span, Some(Span::line(4)));
+        assert_eq!(diagnostic.related_span, Some(Span::line(1)));
+        assert!(diagnostic.message.contains("(line 4)"));
+        assert!(diagnostic.message.contains("(line 1)"));
+    }
+
+    #[test]
+    fn test_bashisms() {
+        assert_eq!(
+            bashism("    [[ -f Cargo.toml ]] && cargo build"),
+            Some("`[[ ]]` tests")
+        );
+        assert_eq!(bashism("    source .env"), Some("`source`"));
+        assert_eq!(bashism("    files=(a b c)"), Some("arrays"));
+        assert_eq!(
+            bashism("    diff <(ls a) <(ls b)"),
+            Some("process substitution")
+        );
+        assert_eq!(bashism("    [ -f Cargo.toml ] && cargo build"), None);
+        assert_eq!(bashism("    . ./.env"), None);
+    }
+
+    #[test]
+    fn test_bashism_depends_on_shell() {
+        let body = "# Load env\nload:\n    source .env\n";
+        let default_shell = parse_justfile_str(body).unwrap();
+        let report = lint_justfile(&default_shell, &LintConfig::default());
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].rule, "bashism");
+        assert_eq!(report.diagnostics[0].span, Some(Span::line(3)));
+
+        let bash = parse_justfile_str(&format!("set shell := [\"bash\", \"-c\"]\n{body}")).unwrap();
+        let report = lint_justfile(&bash, &LintConfig::default());
+        assert!(report.diagnostics.is_empty());
+
+        let windows = parse_justfile_str(&format!(
+            "set shell := [\"bash\", \"-c\"]\nset windows-shell := [\"pwsh\", \"-c\"]\n{body}"
+        ))
+        .unwrap();
+        let report = lint_justfile(&windows, &LintConfig::default());
+        assert_eq!(report.diagnostics.len(), 1);
+        assert!(report.diagnostics[0].message.contains("Windows"));
+    }
+
+    #[test]
+    fn test_syntax_error_report() {
+        let error = parse_justfile_str("build:\n    cargo build\n%%%\n").unwrap_err();
+
+        let report = LintReport::syntax_error(&error, &LintConfig::default());
+        assert!(!report.is_valid());
+        assert_eq!(report.diagnostics[0].rule, "syntax-error");
+        assert_eq!(report.diagnostics[0].span, Some(Span::line(3)));
+    }
+}
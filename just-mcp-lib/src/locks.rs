@@ -0,0 +1,133 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+/// What to do when another execution of the same recipe is in progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LockMode {
+    /// Queue behind the running execution
+    #[default]
+    Wait,
+    /// Return an error immediately
+    FailFast,
+}
+
+#[derive(Debug, Snafu)]
+pub enum LockError {
+    #[snafu(display("Recipe '{}' is already running", recipe_name))]
+    RecipeBusy { recipe_name: String },
+}
+
+pub type Result<T> = std::result::Result<T, LockError>;
+
+type LockKey = (PathBuf, String);
+
+/// Mutual exclusion per recipe, keyed by justfile path and recipe name.
+///
+/// Holding the returned guard serializes executions of that recipe; other
+/// recipes (and the same recipe name in other justfiles) are unaffected.
+#[derive(Debug, Default)]
+pub struct RecipeLocks {
+    locks: Mutex<HashMap<LockKey, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl RecipeLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn acquire(
+        &self,
+        justfile: &Path,
+        recipe_name: &str,
+        mode: LockMode,
+    ) -> Result<OwnedMutexGuard<()>> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry((justfile.to_path_buf(), recipe_name.to_string()))
+            .or_default()
+            .clone();
+
+        match mode {
+            LockMode::Wait => Ok(lock.lock_owned().await),
+            LockMode::FailFast => lock.try_lock_owned().map_err(|_| LockError::RecipeBusy {
+                recipe_name: recipe_name.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_fail_fast_rejects_busy_recipe() {
+        let locks = RecipeLocks::new();
+        let path = Path::new("justfile");
+
+        let _guard = locks
+            .acquire(path, "db-migrate", LockMode::Wait)
+            .await
+            .unwrap();
+        let result = locks.acquire(path, "db-migrate", LockMode::FailFast).await;
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("already running"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_acquires_after_release() {
+        let locks = Arc::new(RecipeLocks::new());
+        let path = Path::new("justfile");
+
+        let guard = locks
+            .acquire(path, "db-migrate", LockMode::Wait)
+            .await
+            .unwrap();
+
+        let waiter = {
+            let locks = locks.clone();
+            tokio::spawn(async move {
+                locks
+                    .acquire(Path::new("justfile"), "db-migrate", LockMode::Wait)
+                    .await
+                    .is_ok()
+            })
+        };
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(guard);
+        assert!(waiter.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_distinct_recipes_do_not_contend() {
+        let locks = RecipeLocks::new();
+        let path = Path::new("justfile");
+
+        let _build = locks.acquire(path, "build", LockMode::Wait).await.unwrap();
+        assert!(
+            locks
+                .acquire(path, "test", LockMode::FailFast)
+                .await
+                .is_ok()
+        );
+        assert!(
+            locks
+                .acquire(Path::new("other/justfile"), "build", LockMode::FailFast)
+                .await
+                .is_ok()
+        );
+    }
+}
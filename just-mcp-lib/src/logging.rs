@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer};
+use tokio::sync::mpsc;
+
+/// Name reported as the `logger` of every message.
+pub const LOGGER_NAME: &str = "just-mcp";
+
+/// Sends `notifications/message` entries to the connected client, dropping
+/// messages below the level set with `logging/setLevel` (default `info`).
+///
+/// Messages are queued and delivered in order by a single task, so logging
+/// never blocks and can be done from synchronous code.
+#[derive(Clone)]
+pub struct ClientLogger {
+    sender: Arc<OnceLock<mpsc::UnboundedSender<LoggingMessageNotificationParam>>>,
+    level: Arc<Mutex<LoggingLevel>>,
+}
+
+impl Default for ClientLogger {
+    fn default() -> Self {
+        Self {
+            sender: Arc::new(OnceLock::new()),
+            level: Arc::new(Mutex::new(LoggingLevel::Info)),
+        }
+    }
+}
+
+impl ClientLogger {
+    /// Start delivering messages to `peer`. Only the first call has an effect.
+    pub fn attach(&self, peer: Peer<RoleServer>) {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        if self.sender.set(sender).is_err() {
+            return;
+        }
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if peer.notify_logging_message(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    pub fn set_level(&self, level: LoggingLevel) {
+        *self.level.lock().unwrap() = level;
+    }
+
+    pub fn is_enabled(&self, level: LoggingLevel) -> bool {
+        severity(level) >= severity(*self.level.lock().unwrap())
+    }
+
+    /// Queue a message; a no-op before a client is attached. The message
+    /// also goes to the server's own log whatever the client's level.
+    pub fn log(&self, level: LoggingLevel, data: serde_json::Value) {
+        trace_message(level, &data);
+        if !self.is_enabled(level) {
+            return;
+        }
+        if let Some(sender) = self.sender.get() {
+            let _ = sender.send(LoggingMessageNotificationParam {
+                level,
+                logger: Some(LOGGER_NAME.to_string()),
+                data,
+            });
+        }
+    }
+}
+
+fn trace_message(level: LoggingLevel, data: &serde_json::Value) {
+    let event = data.get("event").and_then(|event| event.as_str()).unwrap_or("message");
+    match level {
+        LoggingLevel::Debug => tracing::debug!(target: LOGGER_NAME, %data, "{event}"),
+        LoggingLevel::Info | LoggingLevel::Notice => {
+            tracing::info!(target: LOGGER_NAME, %data, "{event}")
+        }
+        LoggingLevel::Warning => tracing::warn!(target: LOGGER_NAME, %data, "{event}"),
+        _ => tracing::error!(target: LOGGER_NAME, %data, "{event}"),
+    }
+}
+
+fn severity(level: LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_level_is_info() {
+        let logger = ClientLogger::default();
+        assert!(!logger.is_enabled(LoggingLevel::Debug));
+        assert!(logger.is_enabled(LoggingLevel::Info));
+        assert!(logger.is_enabled(LoggingLevel::Error));
+    }
+
+    #[test]
+    fn test_set_level_filters_messages() {
+        let logger = ClientLogger::default();
+        logger.set_level(LoggingLevel::Warning);
+        assert!(!logger.is_enabled(LoggingLevel::Notice));
+        assert!(logger.is_enabled(LoggingLevel::Warning));
+
+        // Clones share the level
+        let clone = logger.clone();
+        clone.set_level(LoggingLevel::Debug);
+        assert!(logger.is_enabled(LoggingLevel::Debug));
+    }
+
+    #[test]
+    fn test_log_without_client_is_a_no_op() {
+        let logger = ClientLogger::default();
+        logger.log(LoggingLevel::Error, serde_json::json!({ "event": "test" }));
+    }
+}
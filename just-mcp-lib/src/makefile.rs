@@ -0,0 +1,630 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::formatter::format_justfile_str;
+
+/// Makefile names in the order make looks for them.
+pub const MAKEFILE_NAMES: &[&str] = &["GNUmakefile", "makefile", "Makefile"];
+
+/// Justfile draft converted from a Makefile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MakefileConversion {
+    pub justfile: String,
+    /// Makefile constructs that were dropped or only partly converted
+    pub untranslated: Vec<Untranslated>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Untranslated {
+    /// 1-based line in the Makefile
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+enum Item {
+    Blank,
+    Comment(String),
+    Variable(Variable),
+    Rule(Rule),
+}
+
+struct Variable {
+    name: String,
+    value: Value,
+    export: bool,
+}
+
+enum Value {
+    /// `=`, `:=`, `::=` and `+=`
+    Text(String),
+    /// `?=`: the environment wins
+    Default(String),
+    /// `!=`: output of a shell command
+    Shell(String),
+}
+
+struct Rule {
+    line: usize,
+    target: String,
+    prerequisites: Vec<String>,
+    commands: Vec<String>,
+}
+
+/// What tab-indented lines belong to.
+enum RuleState {
+    None,
+    /// Index of the rules the lines are added to; several for `a b: c`
+    Rules(Vec<usize>),
+    /// A rule that is not translated, such as a pattern rule
+    Skipped,
+}
+
+/// Convert a Makefile into a justfile draft: variables become assignments,
+/// targets become recipes, and prerequisites that are targets become
+/// dependencies. Pattern rules, conditionals, `include`, `define` and
+/// make functions other than `$(shell ...)` are reported as untranslated.
+pub fn convert_makefile(source: &str) -> MakefileConversion {
+    let mut untranslated = Vec::new();
+    let items = parse(source, &mut untranslated);
+
+    let variables: HashSet<&str> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Variable(variable) => Some(variable.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let targets: HashSet<&str> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Rule(rule) => Some(rule.target.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut draft = String::new();
+    for item in &items {
+        match item {
+            Item::Blank => draft.push('\n'),
+            Item::Comment(comment) => draft.push_str(&format!("#{comment}\n")),
+            Item::Variable(variable) => {
+                let expression = match &variable.value {
+                    Value::Text(text) => expression(text, &variables),
+                    Value::Default(text) => format!(
+                        "env_var_or_default(\"{}\", {})",
+                        variable.name,
+                        expression(text, &variables)
+                    ),
+                    Value::Shell(command) => format!("`{command}`"),
+                };
+                let export = if variable.export { "export " } else { "" };
+                draft.push_str(&format!("{export}{} := {expression}\n", variable.name));
+            }
+            Item::Rule(rule) => {
+                let name = recipe_name(&rule.target);
+                if name != rule.target {
+                    untranslated.push(Untranslated {
+                        line: rule.line,
+                        text: rule.target.clone(),
+                        reason: format!(
+                            "File target renamed to recipe `{name}`; recipes always run, whatever the file's timestamp"
+                        ),
+                    });
+                }
+                let (dependencies, files): (Vec<&String>, Vec<&String>) = rule
+                    .prerequisites
+                    .iter()
+                    .partition(|prerequisite| targets.contains(prerequisite.as_str()));
+                if !files.is_empty() {
+                    let files: Vec<&str> = files.iter().map(|file| file.as_str()).collect();
+                    untranslated.push(Untranslated {
+                        line: rule.line,
+                        text: files.join(" "),
+                        reason: format!(
+                            "Prerequisites of `{}` that are not targets are not tracked",
+                            rule.target
+                        ),
+                    });
+                }
+
+                draft.push_str(&name);
+                draft.push(':');
+                for dependency in dependencies {
+                    draft.push(' ');
+                    draft.push_str(&recipe_name(dependency));
+                }
+                draft.push('\n');
+                for command in &rule.commands {
+                    let command = convert_command(command, rule, &variables, &mut untranslated);
+                    draft.push_str(&format!("    {command}\n"));
+                }
+            }
+        }
+    }
+
+    let justfile = match format_justfile_str(&draft) {
+        Ok(formatted) => formatted,
+        Err(error) => {
+            untranslated.push(Untranslated {
+                line: 0,
+                text: String::new(),
+                reason: format!("The draft does not parse as a justfile: {error}"),
+            });
+            draft
+        }
+    };
+    untranslated.sort_by_key(|entry| entry.line);
+    MakefileConversion {
+        justfile,
+        untranslated,
+    }
+}
+
+fn parse(source: &str, untranslated: &mut Vec<Untranslated>) -> Vec<Item> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut items: Vec<Item> = Vec::new();
+    let mut state = RuleState::None;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = index + 1;
+        let raw = lines[index];
+        index += 1;
+
+        if let Some(command) = raw.strip_prefix('\t') {
+            match &state {
+                RuleState::Rules(rules) => {
+                    for &rule in rules {
+                        if let Item::Rule(rule) = &mut items[rule] {
+                            rule.commands.push(command.to_string());
+                        }
+                    }
+                }
+                RuleState::Skipped => {}
+                RuleState::None => untranslated.push(Untranslated {
+                    line,
+                    text: raw.trim().to_string(),
+                    reason: "Command outside of a rule".to_string(),
+                }),
+            }
+            continue;
+        }
+
+        // Join continuation lines
+        let mut logical = raw.to_string();
+        while logical.ends_with('\\') && index < lines.len() {
+            logical.pop();
+            logical.push(' ');
+            logical.push_str(lines[index].trim_start());
+            index += 1;
+        }
+        let text = logical.trim();
+
+        // Blank lines and comments do not end a rule's commands
+        if text.is_empty() {
+            items.push(Item::Blank);
+            continue;
+        }
+        if let Some(comment) = text.strip_prefix('#') {
+            items.push(Item::Comment(comment.to_string()));
+            continue;
+        }
+        state = RuleState::None;
+
+        let mut skip = |reason: &str| {
+            untranslated.push(Untranslated {
+                line,
+                text: text.to_string(),
+                reason: reason.to_string(),
+            })
+        };
+        let directive = text.split_whitespace().next().unwrap_or_default();
+        match directive {
+            "ifeq" | "ifneq" | "ifdef" | "ifndef" | "else" | "endif" => {
+                skip("Conditional skipped; the lines it guards are kept unconditionally");
+                continue;
+            }
+            "include" | "-include" | "sinclude" => {
+                skip("Included makefiles are not imported");
+                continue;
+            }
+            "define" => {
+                skip("Multi-line variables are not translated");
+                while index < lines.len() && lines[index].trim() != "endef" {
+                    index += 1;
+                }
+                index += 1;
+                continue;
+            }
+            "override" | "unexport" | "vpath" | ".RECIPEPREFIX" => {
+                skip("Directive has no justfile equivalent");
+                continue;
+            }
+            _ => {}
+        }
+
+        let colon = text.find(':');
+        let equals = text.find('=');
+        let is_assignment = match (colon, equals) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            // `:=` and `::=`, or `=` before any `:`
+            (Some(colon), Some(equals)) => {
+                equals < colon || text[colon..equals].chars().all(|ch| ch == ':')
+            }
+        };
+
+        if is_assignment {
+            match parse_assignment(text) {
+                Some(variable) => add_variable(&mut items, variable),
+                None => skip("Variable name is not a valid justfile identifier"),
+            }
+            continue;
+        }
+
+        let Some(colon) = colon else {
+            skip("Unrecognized line");
+            continue;
+        };
+        let target_list = &text[..colon];
+        let mut rest = text[colon + 1..].trim_start_matches(':');
+        let mut inline_command = None;
+        if let Some((prerequisites, command)) = rest.split_once(';') {
+            rest = prerequisites;
+            inline_command = Some(command.trim().to_string());
+        }
+        let targets: Vec<&str> = target_list.split_whitespace().collect();
+
+        if targets.iter().all(|target| *target == ".PHONY") {
+            // Every recipe is phony in just
+            continue;
+        }
+        if targets.iter().any(|target| target.starts_with('.')) {
+            skip("Special targets are not translated");
+            state = RuleState::Skipped;
+            continue;
+        }
+        if targets.iter().any(|target| target.contains('%')) {
+            skip("Pattern rules are not translated");
+            state = RuleState::Skipped;
+            continue;
+        }
+        if rest.contains('=') {
+            skip("Target-specific variables are not translated");
+            state = RuleState::Skipped;
+            continue;
+        }
+
+        let prerequisites: Vec<String> = rest
+            .split_whitespace()
+            .filter(|prerequisite| *prerequisite != "|")
+            .map(str::to_string)
+            .collect();
+        let mut rules = Vec::new();
+        for target in targets {
+            // Later rules for the same target add prerequisites and commands
+            let existing = items
+                .iter()
+                .position(|item| matches!(item, Item::Rule(rule) if rule.target == target));
+            let rule = match existing {
+                Some(existing) => existing,
+                None => {
+                    items.push(Item::Rule(Rule {
+                        line,
+                        target: target.to_string(),
+                        prerequisites: Vec::new(),
+                        commands: Vec::new(),
+                    }));
+                    items.len() - 1
+                }
+            };
+            if let Item::Rule(rule) = &mut items[rule] {
+                for prerequisite in &prerequisites {
+                    if !rule.prerequisites.contains(prerequisite) {
+                        rule.prerequisites.push(prerequisite.clone());
+                    }
+                }
+                rule.commands.extend(inline_command.clone());
+            }
+            rules.push(rule);
+        }
+        state = RuleState::Rules(rules);
+    }
+    items
+}
+
+fn parse_assignment(text: &str) -> Option<Variable> {
+    let (export, text) = match text.strip_prefix("export ") {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, text),
+    };
+    let equals = text.find('=')?;
+    let value = text[equals + 1..].trim().to_string();
+    let lhs = &text[..equals];
+    let (name, value) = if let Some(name) = lhs.strip_suffix('?') {
+        (name, Value::Default(value))
+    } else if let Some(name) = lhs.strip_suffix('!') {
+        (name, Value::Shell(value))
+    } else if let Some(name) = lhs.strip_suffix('+') {
+        // Appending is resolved in `add_variable`
+        (name, Value::Text(format!("+{value}")))
+    } else {
+        (lhs.trim_end_matches(':'), Value::Text(value))
+    };
+    let name = name.trim();
+    is_identifier(name).then(|| Variable {
+        name: name.to_string(),
+        value,
+        export,
+    })
+}
+
+/// Add a variable, applying `+=` to an earlier definition.
+fn add_variable(items: &mut Vec<Item>, mut variable: Variable) {
+    if let Value::Text(text) = &variable.value
+        && let Some(appended) = text.strip_prefix('+')
+    {
+        let existing = items.iter_mut().find_map(|item| match item {
+            Item::Variable(existing) if existing.name == variable.name => Some(existing),
+            _ => None,
+        });
+        match existing {
+            Some(Variable {
+                value: Value::Text(text) | Value::Default(text),
+                ..
+            }) => {
+                text.push(' ');
+                text.push_str(appended);
+                return;
+            }
+            _ => variable.value = Value::Text(appended.to_string()),
+        }
+    }
+    items.push(Item::Variable(variable));
+}
+
+/// A piece of make text: literal text or a `$` reference.
+enum Part {
+    Text(String),
+    Variable(String),
+    /// `$(shell ...)`
+    Shell(String),
+    /// `$@`, `$<`, ... or a function such as `$(wildcard ...)`
+    Other(String),
+}
+
+fn split_references(text: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, ch)) = chars.next() {
+        if ch != '$' {
+            literal.push(ch);
+            continue;
+        }
+        let Some(&(next_index, next)) = chars.peek() else {
+            literal.push('$');
+            break;
+        };
+        let reference = match next {
+            '$' => {
+                chars.next();
+                literal.push('$');
+                continue;
+            }
+            '(' | '{' => {
+                let close = if next == '(' { ')' } else { '}' };
+                // Find the matching bracket, allowing nested references
+                let mut depth = 0;
+                let mut end = None;
+                for (position, ch) in text[next_index..].char_indices() {
+                    if ch == next {
+                        depth += 1;
+                    } else if ch == close {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(next_index + position);
+                            break;
+                        }
+                    }
+                }
+                let Some(end) = end else {
+                    literal.push_str(&text[index..]);
+                    break;
+                };
+                while chars.peek().is_some_and(|&(position, _)| position <= end) {
+                    chars.next();
+                }
+                let inner = &text[next_index + 1..end];
+                if let Some(command) = inner.strip_prefix("shell ") {
+                    Part::Shell(command.trim().to_string())
+                } else if is_identifier(inner) {
+                    Part::Variable(inner.to_string())
+                } else {
+                    Part::Other(text[index..=end].to_string())
+                }
+            }
+            _ => {
+                chars.next();
+                if next.is_alphabetic() {
+                    Part::Variable(next.to_string())
+                } else {
+                    Part::Other(format!("${next}"))
+                }
+            }
+        };
+        if !literal.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut literal)));
+        }
+        parts.push(reference);
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Text(literal));
+    }
+    parts
+}
+
+/// Justfile expression for a variable value, e.g. `$(CC) -O2` -> `CC + " -O2"`.
+fn expression(text: &str, variables: &HashSet<&str>) -> String {
+    let parts: Vec<String> = split_references(text)
+        .into_iter()
+        .map(|part| match part {
+            Part::Variable(name) if variables.contains(name.as_str()) => name,
+            Part::Variable(name) => format!("env_var_or_default(\"{name}\", \"\")"),
+            Part::Shell(command) => format!("`{command}`"),
+            Part::Text(text) | Part::Other(text) => quote(&text),
+        })
+        .collect();
+    if parts.is_empty() {
+        "\"\"".to_string()
+    } else {
+        parts.join(" + ")
+    }
+}
+
+/// Recipe line with make references rewritten: variables become `{{ NAME }}`
+/// interpolations, unknown variables shell variables, `$(shell ...)` a
+/// command substitution, and `$@`, `$<` and `$^` the rule's target and
+/// prerequisites.
+fn convert_command(
+    command: &str,
+    rule: &Rule,
+    variables: &HashSet<&str>,
+    untranslated: &mut Vec<Untranslated>,
+) -> String {
+    let mut converted = String::new();
+    for part in split_references(command) {
+        match part {
+            Part::Text(text) => converted.push_str(&text.replace('$', "$$")),
+            Part::Variable(name) if name == "MAKE" => converted.push_str("just"),
+            Part::Variable(name) if variables.contains(name.as_str()) => {
+                converted.push_str(&format!("{{{{ {name} }}}}"))
+            }
+            Part::Variable(name) => converted.push_str(&format!("${{{name}}}")),
+            Part::Shell(command) => converted.push_str(&format!("$({command})")),
+            Part::Other(reference) => match reference.as_str() {
+                "$@" => converted.push_str(&rule.target),
+                "$<" => converted.push_str(rule.prerequisites.first().map_or("", String::as_str)),
+                "$^" | "$+" => converted.push_str(&rule.prerequisites.join(" ")),
+                _ => {
+                    untranslated.push(Untranslated {
+                        line: rule.line,
+                        text: reference.clone(),
+                        reason: format!(
+                            "Make reference in `{}` left as is; it is not a shell expansion",
+                            rule.target
+                        ),
+                    });
+                    converted.push_str(&reference);
+                }
+            },
+        }
+    }
+    // `$$` was only needed to escape make; shells take a single `$`
+    converted.replace("$$", "$")
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+/// Recipe name for a make target; file targets such as `build/app` become
+/// `build-app`.
+fn recipe_name(target: &str) -> String {
+    let name: String = target
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('-');
+    if name.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_') {
+        name.to_string()
+    } else {
+        format!("target-{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    const MAKEFILE: &str = "CC := gcc\nCFLAGS = -O2 -Wall\nCFLAGS += -g\nPREFIX ?= /usr/local\nVERSION != git describe\nexport RUST_LOG = debug\n\n.PHONY: all clean\n\n# Build everything\nall: app\n\napp: main.o\n\t$(CC) $(CFLAGS) -o $@ $^\n\n%.o: %.c\n\t$(CC) -c $<\n\nclean:\n\t@rm -f app *.o\n\t-echo \"$$HOME\" $(HOME)\n\ninstall: app\n\tinstall -m 755 app $(PREFIX)/bin\n\tcd docs && $(MAKE) html\n\nifeq ($(OS),Windows_NT)\nEXE = .exe\nendif\n\ninclude extra.mk\n";
+
+    #[test]
+    fn test_convert_makefile() {
+        let conversion = convert_makefile(MAKEFILE);
+        let justfile = &conversion.justfile;
+
+        assert!(justfile.contains("CC := \"gcc\"\n"));
+        assert!(justfile.contains("CFLAGS := \"-O2 -Wall -g\"\n"));
+        assert!(justfile.contains("PREFIX := env_var_or_default(\"PREFIX\", \"/usr/local\")\n"));
+        assert!(justfile.contains("VERSION := `git describe`\n"));
+        assert!(justfile.contains("export RUST_LOG := \"debug\"\n"));
+        assert!(justfile.contains("# Build everything\nall: app\n"));
+        assert!(justfile.contains("app:\n    {{ CC }} {{ CFLAGS }} -o app main.o\n"));
+        assert!(justfile.contains("clean:\n    @rm -f app *.o\n    -echo \"$HOME\" ${HOME}\n"));
+        assert!(justfile.contains(
+            "install: app\n    install -m 755 app {{ PREFIX }}/bin\n    cd docs && just html\n"
+        ));
+        assert!(!justfile.contains("PHONY"));
+        assert!(!justfile.contains("%.o"));
+
+        let parsed = parse_justfile_str(justfile).unwrap();
+        assert_eq!(parsed.recipes[0].name, "all");
+        assert_eq!(
+            parsed.recipes[0].documentation.as_deref(),
+            Some("Build everything")
+        );
+
+        let reasons: Vec<(usize, &str)> = conversion
+            .untranslated
+            .iter()
+            .map(|entry| (entry.line, entry.text.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (13, "main.o"),
+                (16, "%.o: %.c"),
+                (27, "ifeq ($(OS),Windows_NT)"),
+                (29, "endif"),
+                (31, "include extra.mk"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_targets_are_renamed() {
+        let conversion = convert_makefile("build/app: src/main.c\n\tcc -o $@ $<\n");
+        assert_eq!(
+            conversion.justfile,
+            "build-app:\n    cc -o build/app src/main.c\n"
+        );
+        assert_eq!(conversion.untranslated.len(), 2);
+    }
+
+    #[test]
+    fn test_split_references() {
+        let variables = HashSet::from(["CC"]);
+        assert_eq!(
+            expression("$(CC) -I${INCLUDE} $(wildcard *.c)", &variables),
+            "CC + \" -I\" + env_var_or_default(\"INCLUDE\", \"\") + \" \" + \"$(wildcard *.c)\""
+        );
+        assert_eq!(expression("", &variables), "\"\"");
+    }
+}
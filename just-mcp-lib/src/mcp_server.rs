@@ -1,21 +1,81 @@
 use rmcp::schemars::{self, JsonSchema};
 use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::Instrument;
 
 use rmcp::{
-    handler::server::{ServerHandler, router::tool::ToolRouter, tool::Parameters},
+    RoleServer, ServiceError,
+    handler::server::{
+        ServerHandler, router::tool::ToolRouter, tool::ToolCallContext,
+        tool::cached_schema_for_type, wrapper::Parameters,
+    },
     model::{
-        CallToolResult, Content, ErrorCode, ErrorData as McpError, Implementation, ProtocolVersion,
-        ServerCapabilities, ServerInfo,
+        AnnotateAble, CallToolRequestParam, CallToolResult, CompleteRequestParam, CompleteResult,
+        CompletionInfo, Content, CreateElicitationRequestParam, CreateMessageRequestParam,
+        ElicitationAction, ErrorCode, ErrorData as McpError, GetPromptRequestParam,
+        GetPromptResult, Implementation, JsonObject, ListPromptsResult, ListResourcesResult,
+        ListToolsResult, LoggingLevel, PaginatedRequestParam, Prompt, PromptArgument,
+        PromptMessage, PromptMessageRole, ProtocolVersion, RawContent, RawResource,
+        ReadResourceRequestParam, ReadResourceResult, Reference, Resource, ResourceContents,
+        ResourceUpdatedNotificationParam, ServerCapabilities, ServerInfo, SetLevelRequestParam,
+        SubscribeRequestParam, Tool, ToolAnnotations, UnsubscribeRequestParam,
     },
-    tool, tool_handler, tool_router,
+    service::{NotificationContext, Peer, RequestContext},
+    tool, tool_router,
 };
 
-use crate::executor::{ExecutionError, execute_recipe};
+use crate::artifacts::{self, Artifact, ArtifactError};
+use crate::audit::AuditLog;
+use crate::completion;
+use crate::config::EnvSources;
+use crate::diff::unified_diff;
+use crate::discovery::{DiscoveryOptions, WorkspaceIndex};
+use crate::docgen::{self, DocFormat};
+use crate::editor::{self, EditError, ParameterDefinition, RecipeDefinition, RecipeUpdate};
+use crate::environment::{self, EnvironmentReport, McpEnvironment};
+use crate::executor::{
+    CancelToken, CommandFailure, ExecutionError, ExecutionOptions, ExecutionPlan, ExitStrategy,
+    TranscriptEntry, execute_recipe_cancellable, expand_recipe, plan_recipe,
+};
+use crate::formatter::format_justfile_str;
+use crate::graph::{DependencyGraph, GraphFormat};
+use crate::history::{
+    ExecutionHistory, ExecutionRecord, ExecutionStatus, HistoryError, HistoryFilter, unix_millis,
+};
+use crate::jobs::{JobError, JobManager, JobStatus, LogRange, LogTail};
+use crate::justfile_cache::{CachedJustfile, JustfileCache};
+use crate::limits::{LimitError, SessionLimiter, SessionLimits};
+use crate::lints::{
+    Diagnostic, LintConfig, LintReport, Severity, dangerous_command, lint_justfile,
+};
+use crate::locks::{LockError, LockMode, RecipeLocks};
+use crate::logging::ClientLogger;
+use crate::makefile::{self, Untranslated, convert_makefile};
+use crate::memo::{CacheKey, ExecutionCache};
+use crate::metrics::Metrics;
 use crate::parser::{ParserError, parse_justfile_str};
+use crate::policy::{EnvPolicy, RecipePolicy};
+use crate::prompts;
+use crate::redact::{self, Redactor};
 use crate::registry::JustfileRegistry;
+use crate::roots::{self, JUSTFILE_NAMES, ProjectRoot};
+use crate::scaffold::{self, ProjectType, scaffold_justfile};
+use crate::search::{RecipeMatch, SearchError, SearchOptions, search_recipes};
+use crate::secrets::{self, KeyringProvider, SecretError, SecretProvider};
+use crate::sessions::{SessionGuard, SessionInfo, SessionRegistry};
+use crate::settings;
+use crate::shutdown::{DrainReport, Shutdown};
+use crate::timeouts::ToolTimeouts;
+use crate::validator;
+use crate::watcher::{
+    Changes, DEFAULT_DEBOUNCE, DEFAULT_WATCH_INTERVAL, RecipeSnapshots, WatchTargets, Watcher,
+};
 use crate::{Justfile, Recipe};
 
 #[derive(Debug, Snafu)]
@@ -38,139 +98,632 @@ pub enum McpServerError {
     #[snafu(display("Justfile not registered: {} — register it via b00t justfile datum or --allow flag", path))]
     JustfileNotRegistered { path: String },
 
-    #[snafu(display("Recipe '{}' not found", recipe_name))]
-    RecipeNotFound { recipe_name: String },
+    #[snafu(display(
+        "Recipe '{}' not found{}",
+        recipe_name,
+        did_you_mean(recipe_name, suggestions)
+    ))]
+    RecipeNotFound {
+        recipe_name: String,
+        suggestions: Vec<String>,
+    },
+
+    #[snafu(display("{}", source))]
+    LockFailed { source: LockError },
+
+    #[snafu(display("Execution task failed: {}", source))]
+    TaskFailed { source: tokio::task::JoinError },
+
+    #[snafu(display("{}", source))]
+    EditFailed { source: EditError },
+
+    #[snafu(display("{}", source))]
+    SearchFailed { source: SearchError },
+
+    #[snafu(display("{}", source))]
+    JobFailed { source: JobError },
+
+    #[snafu(display("{}", source))]
+    ArtifactFailed { source: ArtifactError },
+
+    #[snafu(display(
+        "Invalid environment variable '{}': names must be non-empty without '=' or NUL, and values without NUL",
+        name
+    ))]
+    InvalidEnvironmentVariable { name: String },
+
+    #[snafu(display("The client does not support sampling, which generate_recipe needs"))]
+    SamplingUnsupported,
+
+    #[snafu(display("Sampling request failed: {}", message))]
+    SamplingFailed { message: String },
+
+    #[snafu(display("Invalid cursor '{}': pass the next_cursor of a previous page", cursor))]
+    InvalidCursor { cursor: String },
+
+    #[snafu(display(
+        "'{}' is not a directory inside the server's working directory or the client's roots",
+        path
+    ))]
+    WorkingDirectoryNotAllowed { path: String },
+
+    #[snafu(display(
+        "'{}' is outside the working directory, the project roots and the client's roots",
+        path
+    ))]
+    PathNotAllowed { path: String },
+
+    #[snafu(display(
+        "The user declined to provide the parameters of recipe '{}'",
+        recipe_name
+    ))]
+    ParametersDeclined { recipe_name: String },
+
+    #[snafu(display("Elicitation request failed: {}", message))]
+    ElicitationFailed { message: String },
+
+    #[snafu(display(
+        "Recipe '{}' is blocked by the server's recipe policy{}",
+        recipe_name,
+        blocked_dependency(recipe_name, blocked)
+    ))]
+    RecipeBlocked {
+        recipe_name: String,
+        blocked: String,
+    },
+
+    #[snafu(display(
+        "Tool '{}' is disabled while a recipe policy is set; edits could get around it",
+        tool
+    ))]
+    EditingDisabled { tool: String },
+
+    #[snafu(display("Makefile not found at path: {}", path))]
+    MakefileNotFound { path: String },
+
+    #[snafu(display("A justfile already exists at {}", path))]
+    JustfileExists { path: String },
+
+    #[snafu(display("{}", source))]
+    LimitExceeded { source: LimitError },
+
+    #[snafu(display("Server is shutting down and not accepting new requests"))]
+    ShuttingDown,
+
+    #[snafu(display(
+        "Tool '{}' timed out after {} s; its running recipes were cancelled",
+        tool,
+        seconds
+    ))]
+    ToolTimedOut { tool: String, seconds: u64 },
+
+    #[snafu(display(
+        "Tool result of {} bytes does not fit MCP_MAX_MESSAGE_SIZE ({} bytes)",
+        size,
+        max
+    ))]
+    MessageTooLarge { size: usize, max: usize },
+
+    #[snafu(display("Unknown profile '{}' (known: {})", name, known))]
+    UnknownProfile { name: String, known: String },
+
+    #[snafu(display("{}", source))]
+    SecretFailed { source: SecretError },
+
+    #[snafu(display("{}", source))]
+    HistoryFailed { source: HistoryError },
+}
+
+fn did_you_mean(recipe_name: &str, suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        return String::new();
+    }
+    let names: Vec<String> = suggestions.iter().map(|name| format!("`{name}`")).collect();
+    format!(" — did you mean `{recipe_name}` → {}?", names.join(" or "))
+}
+
+fn blocked_dependency(recipe_name: &str, blocked: &str) -> String {
+    if recipe_name == blocked {
+        String::new()
+    } else {
+        format!(": it depends on '{blocked}'")
+    }
 }
 
 // Bridge snafu errors to MCP errors
 impl From<McpServerError> for McpError {
     fn from(err: McpServerError) -> Self {
+        let data = match &err {
+            McpServerError::RecipeNotFound { suggestions, .. } => {
+                Some(serde_json::json!({ "suggestions": suggestions }))
+            }
+            McpServerError::LimitExceeded { source } => Some(match source {
+                LimitError::TooManyToolCalls {
+                    limit,
+                    retry_after_ms,
+                } => serde_json::json!({
+                    "limit": "tool_calls_per_minute",
+                    "max": limit,
+                    "retry_after_ms": retry_after_ms,
+                }),
+                LimitError::TooManyExecutions { limit } => serde_json::json!({
+                    "limit": "max_concurrent_executions",
+                    "max": limit,
+                }),
+            }),
+            McpServerError::ToolTimedOut { tool, seconds } => {
+                Some(serde_json::json!({ "tool": tool, "timeout_seconds": seconds }))
+            }
+            _ => None,
+        };
         McpError {
             code: ErrorCode(-1),
             message: err.to_string().into(),
-            data: None,
+            data,
         }
     }
 }
 
+/// Recipe arguments as a JSON array, also accepting the legacy form of a
+/// string holding one, e.g. `"[\"Claude\"]"`.
+fn deserialize_args<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Args {
+        List(Vec<String>),
+        Legacy(String),
+    }
+
+    match Option::<Args>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Args::List(args)) => Ok(Some(args)),
+        Some(Args::Legacy(args)) => serde_json::from_str(&args)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
 // Parameter structs for tools
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ListRecipesParams {
+    /// Justfile path, or a justfile id from list_justfiles
     pub justfile_path: Option<String>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Recipes per page; all recipes are returned when neither this nor `cursor` is given
+    pub page_size: Option<usize>,
+    /// Return only recipe names, in `names`
+    pub names_only: Option<bool>,
+    /// Order of the recipes (default: justfile order)
+    pub sort: Option<RecipeSort>,
 }
 
-#[derive(Debug, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeSort {
+    /// Order of the justfile
+    #[default]
+    Source,
+    Name,
+    /// By `[group]`, ungrouped recipes last, then by name
+    Group,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct ExecuteRecipeParams {
     pub recipe_name: String,
-    pub args: Option<String>,
+    /// Positional arguments, e.g. `["Claude"]`
+    #[serde(default, deserialize_with = "deserialize_args")]
+    pub args: Option<Vec<String>>,
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    /// Reuse a previous successful result for identical inputs (opt-in)
+    pub cache: Option<bool>,
+    /// Input files whose contents are part of the cache key (relative to the working directory)
+    pub inputs: Option<Vec<String>>,
+    /// Behavior when the same recipe is already running: wait (default) or fail_fast
+    pub lock: Option<LockMode>,
+    /// Include a merged, timestamped stdout/stderr transcript in the result
+    pub transcript: Option<bool>,
+    /// Echo each command before running it, as just does (respects `@` and `set quiet`)
+    pub echo: Option<bool>,
+    /// What to do when a command fails: fail_fast (default), keep_going, or just (honor `-` prefixes)
+    pub exit_strategy: Option<ExitStrategy>,
+    /// Files the recipe produces, as paths or glob patterns (`*`, `?`, `**`) relative to the
+    /// working directory; matching files are published as resources and linked in the result
+    pub outputs: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SequenceStep {
+    #[serde(alias = "recipe")]
+    pub recipe_name: String,
+    /// Positional arguments, as for run_recipe
+    #[serde(default, deserialize_with = "deserialize_args")]
+    pub args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RunSequenceParams {
+    /// Recipes to run, in order
+    pub steps: Vec<SequenceStep>,
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    /// Stop at the first failing step (default true); the remaining steps are skipped
+    pub stop_on_failure: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DryRunRecipeParams {
+    pub recipe_name: String,
+    /// Positional arguments, as for run_recipe
+    #[serde(default, deserialize_with = "deserialize_args")]
+    pub args: Option<Vec<String>>,
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExplainRecipeParams {
+    pub recipe_name: String,
+    /// Positional arguments, as for run_recipe
+    #[serde(default, deserialize_with = "deserialize_args")]
+    pub args: Option<Vec<String>>,
+    /// Justfile path, or a justfile id from list_justfiles
     pub justfile_path: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetRecipeInfoParams {
     pub recipe_name: String,
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchRecipesParams {
+    /// Text to find in recipe names, documentation, and bodies
+    pub query: String,
+    /// Treat the query as a regular expression
+    pub regex: Option<bool>,
+    /// Match case exactly (default: case-insensitive)
+    pub case_sensitive: Option<bool>,
+    /// Justfile path, or a justfile id from list_justfiles
     pub justfile_path: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ValidateJustfileParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DependencyGraphParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    /// Output format: json (default), dot, or mermaid
+    pub format: Option<GraphFormat>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateRecipeParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    pub name: String,
+    pub parameters: Option<Vec<ParameterDefinition>>,
+    /// Doc comment written above the recipe
+    pub documentation: Option<String>,
+    /// Recipes (or aliases) to run first
+    pub dependencies: Option<Vec<String>>,
+    /// Commands, one per line; indentation is normalized to the justfile's
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GenerateRecipeParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    /// What the recipe should do, e.g. "runs clippy on every crate"
+    pub goal: String,
+    /// Name for the new recipe; by default the model picks one
+    pub name: Option<String>,
+    /// Append the recipe to the justfile (default false: only return it)
+    pub write: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UpdateRecipeParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    pub recipe_name: String,
+    /// Replaces all parameters and their types
+    pub parameters: Option<Vec<ParameterDefinition>>,
+    /// New doc comment; an empty string removes it
+    pub documentation: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+    pub body: Option<String>,
+    /// Return the diff without writing the file
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeleteRecipeParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    pub recipe_name: String,
+    /// Delete even if other recipes depend on it, dropping those dependencies
+    pub force: Option<bool>,
+    /// Return the diff without writing the file
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RenameRecipeParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    pub recipe_name: String,
+    pub new_name: String,
+    /// Return the diff without writing the file
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetVariableParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    pub name: String,
+    /// New value; quoted as a string unless already quoted or `raw` is set
+    pub value: String,
+    /// Write `value` as a just expression, e.g. `env_var('HOME')`
+    pub raw: Option<bool>,
+    /// Add (true) or remove (false) the `export` keyword; kept as is by default
+    pub export: Option<bool>,
+    /// Return the diff without writing the file
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FormatJustfileParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    /// Only report whether the file is formatted and the diff; never write
+    pub check_only: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DocumentJustfileParams {
+    /// Justfile path, or a justfile id from list_justfiles
+    pub justfile_path: Option<String>,
+    /// Also write the documentation next to the justfile, as JUSTFILE.md or JUSTFILE.html
+    pub write: Option<bool>,
+    /// Include `[private]` and `_`-prefixed recipes
+    pub include_private: Option<bool>,
+    /// markdown (default) or html, a standalone page
+    pub format: Option<DocFormat>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ImportMakefileParams {
+    /// Makefile path; defaults to the Makefile in the working directory
+    pub makefile_path: Option<String>,
+    /// Write the draft as `justfile` next to the Makefile; an existing justfile is never overwritten
+    pub write: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct InitJustfileParams {
+    /// Project directory, relative to the working directory (default: the working directory)
+    pub directory: Option<String>,
+    /// Project type to scaffold for instead of the detected one
+    pub project_type: Option<ProjectType>,
+    /// Replace an existing justfile
+    pub force: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetWorkingDirectoryParams {
+    /// Directory to use for later calls, absolute or relative to the current one; omit to go back to the default
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExecutionStatusParams {
+    /// Id returned by start_recipe
+    pub execution_id: String,
+    /// Number of output lines already seen; only later lines are returned
+    pub since: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExecutionResultParams {
+    /// Id returned by start_recipe
+    pub execution_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TailExecutionLogParams {
+    /// Id returned by start_recipe
+    pub execution_id: String,
+    /// Return the last N lines (default 50); ignored when `offset` is given
+    pub lines: Option<usize>,
+    /// Return the log from this byte offset, e.g. the previous `offset` plus the length of its `text`
+    pub offset: Option<usize>,
+    /// Bytes to return from `offset` (default: to the end)
+    pub length: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExecutionHistoryParams {
+    /// Only executions of this recipe
+    pub recipe_name: Option<String>,
+    /// Only executions with this status: succeeded, failed, cancelled, or error
+    pub status: Option<ExecutionStatus>,
+    /// Most executions to return, newest first (default 20)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetEnvironmentParams {
+    /// Justfile path, or a justfile id from list_justfiles
     pub justfile_path: Option<String>,
+    /// Variables to look up, e.g. ["FOO", "DATABASE_URL"]
+    pub names: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetSessionEnvironmentParams {
+    /// Variables to set for later recipe runs; `null` removes one
+    pub variables: BTreeMap<String, Option<String>>,
+    /// Remove all session variables before applying `variables`
+    pub clear: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetProfileParams {
+    /// Profile to apply to later recipe runs, e.g. "staging"; omit for none
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListJustfilesParams {
+    /// Directory levels below the working directory to search (default 3)
+    pub max_depth: Option<usize>,
+    /// Directory names to skip, in addition to hidden ones, target and node_modules
+    pub ignore: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ListWorkspaceRecipesParams {
+    /// Directory levels below the working directory to search (default 3)
+    pub max_depth: Option<usize>,
+    /// Directory names to skip, in addition to hidden ones, target and node_modules
+    pub ignore: Option<Vec<String>>,
 }
 
 // Response structs
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RecipeInfo {
     pub name: String,
     pub parameters: Vec<ParameterInfo>,
     pub documentation: Option<String>,
     pub dependencies: Vec<String>,
+    /// Groups from `[group]` attributes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    /// The server's recipe policy refuses to run it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocked: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ParameterInfo {
-    pub name: String,
-    pub default_value: Option<String>,
-    pub required: bool,
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RecipeExplanation {
+    pub recipe_name: String,
+    pub documentation: Option<String>,
+    /// Value of every parameter: the given argument or its default
+    pub arguments: HashMap<String, String>,
+    /// Body with parameters and variables substituted
+    pub body: String,
+    /// Recipes that run first, in order
+    pub dependencies: Vec<String>,
+    /// `set` statements in effect
+    pub settings: HashMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct JustfileInfo {
-    pub path: String,
-    pub recipes: Vec<RecipeInfo>,
-    pub variables: HashMap<String, String>,
+/// Variables added to every recipe run in this session, secrets masked.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SessionEnvironment {
+    pub variables: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ExecutionOutput {
-    pub recipe_name: String,
-    pub stdout: String,
-    pub stderr: String,
-    pub exit_code: i32,
-    pub duration_ms: u64,
-    pub success: bool,
+/// The profile applied to recipe runs in this session, secrets masked.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ActiveProfile {
+    pub active: Option<String>,
+    /// Every profile in the config
+    pub profiles: Vec<String>,
+    /// Variables of the active profile
+    pub variables: BTreeMap<String, String>,
 }
 
-#[derive(Clone)]
-pub struct JustMcpServer {
-    working_dir: std::path::PathBuf,
-    tool_router: ToolRouter<Self>,
-    registry: JustfileRegistry,
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JustfileList {
+    pub justfiles: Vec<JustfileEntry>,
 }
 
-impl JustMcpServer {
-    /// Create with permissive registry — any justfile in `working_dir` is accessible.
-    /// Use `with_registry` to enable the sandbox gate.
-    pub fn new(working_dir: impl AsRef<Path>) -> Self {
-        Self {
-            working_dir: working_dir.as_ref().to_path_buf(),
-            tool_router: Self::tool_router(),
-            registry: JustfileRegistry::permissive(),
-        }
-    }
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JustfileEntry {
+    /// Pass as `justfile_path` to target this justfile
+    pub id: String,
+    pub path: String,
+    /// Absent when the justfile does not parse
+    pub recipe_count: Option<usize>,
+}
 
-    /// Create with a strict registry — only registered justfiles are in scope.
-    pub fn with_registry(working_dir: impl AsRef<Path>, registry: JustfileRegistry) -> Self {
-        Self {
-            working_dir: working_dir.as_ref().to_path_buf(),
-            tool_router: Self::tool_router(),
-            registry,
-        }
-    }
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceRecipes {
+    pub recipes: Vec<WorkspaceRecipe>,
+}
 
-    fn load_justfile(
-        &self,
-        justfile_path: Option<&str>,
-    ) -> Result<(Justfile, std::path::PathBuf), McpServerError> {
-        let justfile_path = if let Some(path) = justfile_path {
-            self.working_dir.join(path)
-        } else {
-            // Default justfile locations
-            let candidates = ["justfile", "Justfile", ".justfile"];
-            candidates
-                .iter()
-                .map(|name| self.working_dir.join(name))
-                .find(|path| path.exists())
-                .ok_or_else(|| McpServerError::JustfileNotFound {
-                    path: self.working_dir.display().to_string(),
-                })?
-        };
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceRecipe {
+    pub name: String,
+    /// Id of the justfile defining the recipe, as in list_justfiles
+    pub justfile_id: String,
+    pub path: String,
+    pub documentation: Option<String>,
+    pub parameters: Vec<String>,
+    /// Groups from `[group]` attributes
+    pub groups: Vec<String>,
+}
 
-        // Registry gate — absent from scope is not an error message, it's silence.
-        // The error message here is only surfaced in strict mode (non-empty registry).
-        if !self.registry.is_in_scope(&justfile_path) {
-            return Err(McpServerError::JustfileNotRegistered {
-                path: justfile_path.display().to_string(),
-            });
-        }
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SearchResults {
+    pub query: String,
+    pub matches: Vec<RecipeMatch>,
+}
 
-        let content = std::fs::read_to_string(&justfile_path).context(IoSnafu)?;
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ParameterInfo {
+    pub name: String,
+    pub default_value: Option<String>,
+    pub required: bool,
+    /// Declared with `# @param NAME: TYPE`, e.g. `int` or `enum(dev,prod)`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none", default)]
+    pub param_type: Option<String>,
+}
 
-        let justfile = parse_justfile_str(&content).context(ParseFailedSnafu)?;
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RecipeSignature {
+    pub recipe_name: String,
+    /// Call form followed by documentation and parameter details, e.g.
+    /// `deploy(env, port=8080)`
+    pub signature: String,
+    pub documentation: Option<String>,
+    pub parameters: Vec<ParameterInfo>,
+}
 
-        Ok((justfile, justfile_path))
-    }
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JustfileInfo {
+    pub path: String,
+    /// Recipes of this page; empty with `names_only`
+    pub recipes: Vec<RecipeInfo>,
+    /// Recipe names of this page, with `names_only`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub names: Option<Vec<String>>,
+    pub variables: HashMap<String, String>,
+    /// Recipes in the justfile, across all pages
+    pub total_recipes: usize,
+    /// Pass as `cursor` to get the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
 
-    fn recipe_to_info(recipe: &Recipe) -> RecipeInfo {
+impl From<&Recipe> for RecipeInfo {
+    fn from(recipe: &Recipe) -> Self {
         RecipeInfo {
             name: recipe.name.clone(),
             parameters: recipe
@@ -180,135 +733,3702 @@ impl JustMcpServer {
                     name: p.name.clone(),
                     default_value: p.default_value.clone(),
                     required: p.default_value.is_none(),
+                    param_type: recipe.parameter_types.get(&p.name).map(|t| t.to_string()),
                 })
                 .collect(),
             documentation: recipe.documentation.clone(),
             dependencies: recipe.dependencies.clone(),
+            groups: recipe.groups().into_iter().map(str::to_string).collect(),
+            blocked: false,
         }
     }
 }
 
-#[tool_router]
-impl JustMcpServer {
-    #[tool(description = "List all available recipes in the justfile")]
-    async fn list_recipes(
-        &self,
-        Parameters(params): Parameters<ListRecipesParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let (justfile, path) = self.load_justfile(params.justfile_path.as_deref())?;
-
-        let info = JustfileInfo {
+impl JustfileInfo {
+    /// Every recipe and variable of a justfile, on a single page.
+    pub fn from_justfile(path: &Path, justfile: &Justfile) -> Self {
+        JustfileInfo {
             path: path.display().to_string(),
-            recipes: justfile.recipes.iter().map(Self::recipe_to_info).collect(),
-            variables: justfile.variables,
-        };
-
-        let content = serde_json::to_string_pretty(&info).context(SerializationSnafu)?;
-
-        Ok(CallToolResult::success(vec![Content::text(content)]))
+            recipes: justfile.recipes.iter().map(RecipeInfo::from).collect(),
+            names: None,
+            variables: justfile.variables.clone(),
+            total_recipes: justfile.recipes.len(),
+            next_cursor: None,
+        }
     }
+}
 
-    #[tool(description = "Execute a specific recipe with optional arguments")]
-    async fn run_recipe(
-        &self,
-        Parameters(params): Parameters<ExecuteRecipeParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+/// Output of the `validate_justfile` tool.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ValidationReport {
+    pub path: String,
+    pub is_valid: bool,
+    pub message: String,
+    pub summary: ValidationSummary,
+    /// Diagnostics grouped by recipe, in justfile order
+    pub recipes: Vec<RecipeDiagnostics>,
+    /// Diagnostics not tied to a recipe, e.g. syntax errors and unused variables
+    pub global: Vec<Diagnostic>,
+}
 
-        // Parse arguments from JSON if provided
-        let parsed_args: Vec<String> = if let Some(args_str) = params.args {
-            serde_json::from_str(&args_str).context(SerializationSnafu)?
-        } else {
-            Vec::new()
-        };
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ValidationSummary {
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub recipe_count: usize,
+    pub variable_count: usize,
+}
 
-        // Execute the recipe
-        let result = execute_recipe(
-            &justfile,
-            &params.recipe_name,
-            &parsed_args,
-            &self.working_dir,
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RecipeDiagnostics {
+    pub recipe: String,
+    pub errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// Parse and lint justfile source. Syntax errors are reported like any
+    /// other diagnostic.
+    pub fn check(path: &Path, content: &str, config: &LintConfig) -> Self {
+        match parse_justfile_str(content) {
+            Ok(justfile) => {
+                let report = lint_justfile(&justfile, config);
+                ValidationReport::new(path, Some(&justfile), report)
+            }
+            Err(error) => {
+                let report = LintReport::syntax_error(&error, config);
+                ValidationReport::new(path, None, report)
+            }
+        }
+    }
+
+    /// Compiler-style text: a `path:line: severity[rule]: message` line per
+    /// diagnostic, then the summary message.
+    pub fn to_text(&self) -> String {
+        let diagnostics = self.global.iter().chain(
+            self.recipes
+                .iter()
+                .flat_map(|entry| entry.errors.iter().chain(&entry.warnings)),
+        );
+        let mut text = String::new();
+        for diagnostic in diagnostics {
+            let location = match &diagnostic.span {
+                Some(span) => format!("{}:{}", self.path, span.start_line),
+                None => self.path.clone(),
+            };
+            let severity = match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            text.push_str(&format!(
+                "{location}: {severity}[{}]: {}\n",
+                diagnostic.rule, diagnostic.message
+            ));
+        }
+        text.push_str(&format!(
+            "{}: {} ({} errors, {} warnings)\n",
+            self.path, self.message, self.summary.error_count, self.summary.warning_count
+        ));
+        text
+    }
+
+    fn new(path: &Path, justfile: Option<&Justfile>, report: LintReport) -> Self {
+        let message = match justfile {
+            Some(justfile) if report.is_valid() => format!(
+                "Justfile parsed successfully with {} recipes",
+                justfile.recipes.len()
+            ),
+            Some(_) => format!("Justfile has {} errors", report.error_count),
+            None => "Justfile could not be parsed".to_string(),
+        };
+
+        let mut recipes: Vec<RecipeDiagnostics> = justfile
+            .map(|justfile| {
+                justfile
+                    .recipes
+                    .iter()
+                    .map(|recipe| RecipeDiagnostics {
+                        recipe: recipe.name.clone(),
+                        errors: Vec::new(),
+                        warnings: Vec::new(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut global = Vec::new();
+
+        for diagnostic in report.diagnostics {
+            let entry = diagnostic
+                .recipe
+                .as_ref()
+                .and_then(|name| recipes.iter_mut().find(|entry| &entry.recipe == name));
+            match (entry, diagnostic.severity) {
+                (Some(entry), Severity::Error) => entry.errors.push(diagnostic),
+                (Some(entry), Severity::Warning) => entry.warnings.push(diagnostic),
+                (None, _) => global.push(diagnostic),
+            }
+        }
+        recipes.retain(|entry| !entry.errors.is_empty() || !entry.warnings.is_empty());
+
+        ValidationReport {
+            path: path.display().to_string(),
+            is_valid: report.error_count == 0,
+            message,
+            summary: ValidationSummary {
+                error_count: report.error_count,
+                warning_count: report.warning_count,
+                recipe_count: justfile.map_or(0, |justfile| justfile.recipes.len()),
+                variable_count: justfile.map_or(0, |justfile| justfile.variables.len()),
+            },
+            recipes,
+            global,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionOutput {
+    pub recipe_name: String,
+    /// The justfile the recipe was run from
+    pub justfile: String,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub cached: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<Vec<TranscriptEntry>>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub failures: Vec<CommandFailure>,
+    /// Files matching `outputs`, published as resources
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub artifacts: Vec<Artifact>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Succeeded,
+    /// Ran and exited with a non-zero code
+    Failed,
+    /// Could not run, e.g. a substitution error
+    Error,
+    /// Not run because an earlier step failed
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SequenceStepResult {
+    pub recipe_name: String,
+    pub status: StepStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<ExecutionOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SequenceOutput {
+    /// Every step succeeded
+    pub success: bool,
+    pub duration_ms: u64,
+    pub steps: Vec<SequenceStepResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionHistoryList {
+    /// Newest first
+    pub executions: Vec<ExecutionRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SessionList {
+    /// Id of the session making the call
+    pub current: Option<u64>,
+    /// Connected sessions, oldest first
+    pub sessions: Vec<SessionInfo>,
+    /// Sessions opened since the server started, including closed ones
+    pub served: u64,
+    pub uptime_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionStarted {
+    /// Pass to get_execution_status and get_execution_result
+    pub execution_id: String,
+    pub recipe_name: String,
+}
+
+/// Result of a tool that modifies a justfile.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct EditOutput {
+    pub path: String,
+    /// Unified diff of the change
+    pub diff: String,
+    /// False for dry runs
+    pub written: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GeneratedRecipe {
+    pub recipe_name: String,
+    /// The recipe as it is (or would be) added to the justfile
+    pub recipe: String,
+    /// Model that wrote it, as reported by the client
+    pub model: String,
+    pub edit: EditOutput,
+}
+
+impl EditOutput {
+    fn new(path: &Path, old: &str, new: &str, written: bool) -> Self {
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        EditOutput {
+            path: path.display().to_string(),
+            diff: unified_diff(old, new, &name),
+            written,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FormatOutput {
+    pub path: String,
+    /// True when the file was already in canonical form
+    pub formatted: bool,
+    /// Unified diff of the formatting changes
+    pub diff: String,
+    pub written: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Documentation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// Where the documentation was written, when `write` was set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub written_to: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ImportedMakefile {
+    /// Justfile draft
+    pub justfile: String,
+    /// Makefile constructs that were dropped or only partly converted
+    pub untranslated: Vec<Untranslated>,
+    /// Where the draft was written, when `write` was set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub written_to: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ScaffoldedJustfile {
+    pub path: String,
+    /// Project type the recipes were generated for; absent when none was detected
+    pub project_type: Option<ProjectType>,
+    /// Project types whose marker files were found, e.g. `Cargo.toml`
+    pub detected: Vec<ProjectType>,
+    pub justfile: String,
+    /// An existing justfile was replaced
+    pub overwritten: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WorkingDirectory {
+    pub path: String,
+    /// The justfile in the directory, if any
+    pub justfile: Option<String>,
+    /// Directories a working directory must be inside of
+    pub allowed_roots: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct JustMcpServer {
+    working_dir: std::path::PathBuf,
+    tool_router: ToolRouter<Self>,
+    registry: JustfileRegistry,
+    execution_cache: Arc<Mutex<ExecutionCache>>,
+    /// Parsed justfiles, shared by every session
+    justfile_cache: Arc<RwLock<JustfileCache>>,
+    recipe_locks: Arc<RecipeLocks>,
+    lint_config: Arc<LintConfig>,
+    /// `justfile://` URIs the client asked to be notified about
+    subscriptions: Arc<Mutex<HashSet<String>>>,
+    watch_interval: Option<Duration>,
+    logger: ClientLogger,
+    /// Directories of the client's workspace roots
+    client_roots: Arc<Mutex<Vec<PathBuf>>>,
+    /// Justfiles discovered in the client's workspace roots
+    root_justfiles: Arc<Mutex<Vec<PathBuf>>>,
+    /// Justfiles and recipes below each directory list_justfiles searched
+    workspace_indexes: Arc<Mutex<BTreeMap<PathBuf, WorkspaceIndex>>>,
+    /// Directory chosen with set_working_directory
+    session_dir: Arc<Mutex<Option<PathBuf>>>,
+    /// Ask the user for missing required parameters of run_recipe
+    elicit_parameters: bool,
+    /// Recipes clients may run
+    recipe_policy: Arc<RecipePolicy>,
+    /// Which of the server's variables recipes inherit
+    env_policy: Arc<EnvPolicy>,
+    /// Recipes started with start_recipe
+    jobs: JobManager<ExecutionOutput>,
+    history: Arc<ExecutionHistory>,
+    metrics: Arc<Metrics>,
+    /// Files published by runs with `outputs`, by resource URI
+    artifacts: Arc<Mutex<BTreeMap<String, PathBuf>>>,
+    /// The connected client, set once initialized
+    peer: Arc<OnceLock<Peer<RoleServer>>>,
+    /// Variables from set_session_environment, added to every recipe run
+    session_env: Arc<Mutex<McpEnvironment>>,
+    /// Variables from the config file, added to every recipe run in every session
+    config_env: Arc<Mutex<BTreeMap<String, String>>>,
+    /// Variables of each config profile, by name
+    profiles: Arc<Mutex<BTreeMap<String, BTreeMap<String, String>>>>,
+    /// Where `config_env` and `profiles` come from, read again when one of
+    /// their env files changes
+    env_sources: Option<Arc<EnvSources>>,
+    /// Profile new sessions start with, from `--profile` or the config
+    default_profile: Option<String>,
+    /// Profile chosen with set_profile, applied on top of `config_env`
+    session_profile: Arc<Mutex<Option<String>>>,
+    /// Load `.env`, `.env.local` and `.env.<profile>` from the working
+    /// directory for every recipe run
+    dotenv_stack: bool,
+    /// Justfile given with `--justfile`, used instead of looking for one in
+    /// the working directory
+    justfile: Option<PathBuf>,
+    /// Highest directory searched for a justfile above the working directory
+    search_boundary: Option<PathBuf>,
+    /// Projects served side by side, from repeated `--directory` flags
+    project_roots: Arc<Vec<ProjectRoot>>,
+    /// Tool call rate and concurrent executions of this session
+    limiter: Arc<SessionLimiter>,
+    /// Executions in flight across every session, drained on shutdown
+    shutdown: Arc<Shutdown>,
+    /// Clients connected to this server
+    sessions: Arc<SessionRegistry>,
+    /// This session's id in `sessions`, once opened
+    session_id: Arc<OnceLock<u64>>,
+    /// Keeps this session listed while the session is alive, for
+    /// transports that create and drop sessions themselves
+    session_guard: Arc<OnceLock<SessionGuard>>,
+    /// Where every JSON-RPC message is recorded, if anywhere
+    audit_log: Option<Arc<AuditLog>>,
+    /// How long each tool call may take
+    tool_timeouts: Arc<ToolTimeouts>,
+    /// Largest tool result in bytes, from `MCP_MAX_MESSAGE_SIZE`
+    max_message_size: Option<usize>,
+    /// Names of the variables whose values are masked in everything sent
+    /// to clients
+    secret_patterns: Arc<Vec<String>>,
+    /// Where `{{ secret("NAME") }}` and `secret_env` are looked up
+    secret_provider: Arc<dyn SecretProvider>,
+    /// Secrets every recipe run gets as environment variables
+    secret_env: Arc<Vec<String>>,
+    /// Secrets fetched so far, masked in everything sent to clients
+    secret_values: Arc<Mutex<BTreeMap<String, String>>>,
+    /// Bumped whenever something a redactor masks may have changed
+    redactor_generation: Arc<AtomicU64>,
+    /// This session's redactor and the generation it was built at
+    redactor: Arc<Mutex<Option<(u64, Redactor)>>>,
+}
+
+impl JustMcpServer {
+    /// Create with permissive registry — any justfile in `working_dir` is accessible.
+    /// Use `with_registry` to enable the sandbox gate.
+    pub fn new(working_dir: impl AsRef<Path>) -> Self {
+        Self {
+            working_dir: working_dir.as_ref().to_path_buf(),
+            tool_router: Self::tool_router(),
+            registry: JustfileRegistry::permissive(),
+            execution_cache: Arc::new(Mutex::new(ExecutionCache::default())),
+            justfile_cache: Arc::new(RwLock::new(JustfileCache::default())),
+            recipe_locks: Arc::new(RecipeLocks::new()),
+            lint_config: Arc::new(LintConfig::default()),
+            subscriptions: Arc::new(Mutex::new(HashSet::new())),
+            watch_interval: Some(DEFAULT_WATCH_INTERVAL),
+            logger: ClientLogger::default(),
+            client_roots: Arc::new(Mutex::new(Vec::new())),
+            root_justfiles: Arc::new(Mutex::new(Vec::new())),
+            workspace_indexes: Arc::new(Mutex::new(BTreeMap::new())),
+            session_dir: Arc::new(Mutex::new(None)),
+            elicit_parameters: false,
+            recipe_policy: Arc::new(RecipePolicy::default()),
+            env_policy: Arc::new(EnvPolicy::default()),
+            jobs: JobManager::new(),
+            history: Arc::new(ExecutionHistory::default()),
+            metrics: Arc::new(Metrics::default()),
+            artifacts: Arc::new(Mutex::new(BTreeMap::new())),
+            peer: Arc::new(OnceLock::new()),
+            session_env: Arc::new(Mutex::new(McpEnvironment::new())),
+            config_env: Arc::new(Mutex::new(BTreeMap::new())),
+            profiles: Arc::new(Mutex::new(BTreeMap::new())),
+            env_sources: None,
+            default_profile: None,
+            session_profile: Arc::new(Mutex::new(None)),
+            dotenv_stack: false,
+            justfile: None,
+            search_boundary: None,
+            project_roots: Arc::new(Vec::new()),
+            limiter: Arc::new(SessionLimiter::new(SessionLimits::default())),
+            shutdown: Arc::new(Shutdown::default()),
+            sessions: Arc::new(SessionRegistry::default()),
+            session_id: Arc::new(OnceLock::new()),
+            session_guard: Arc::new(OnceLock::new()),
+            audit_log: None,
+            tool_timeouts: Arc::new(ToolTimeouts::default()),
+            max_message_size: None,
+            secret_patterns: Arc::new(redact::default_secret_patterns()),
+            secret_provider: Arc::new(KeyringProvider::default()),
+            secret_env: Arc::new(Vec::new()),
+            secret_values: Arc::new(Mutex::new(BTreeMap::new())),
+            redactor_generation: Arc::new(AtomicU64::new(0)),
+            redactor: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Create with a strict registry — only registered justfiles are in scope.
+    pub fn with_registry(working_dir: impl AsRef<Path>, registry: JustfileRegistry) -> Self {
+        Self {
+            working_dir: working_dir.as_ref().to_path_buf(),
+            tool_router: Self::tool_router(),
+            registry,
+            execution_cache: Arc::new(Mutex::new(ExecutionCache::default())),
+            justfile_cache: Arc::new(RwLock::new(JustfileCache::default())),
+            recipe_locks: Arc::new(RecipeLocks::new()),
+            lint_config: Arc::new(LintConfig::default()),
+            subscriptions: Arc::new(Mutex::new(HashSet::new())),
+            watch_interval: Some(DEFAULT_WATCH_INTERVAL),
+            logger: ClientLogger::default(),
+            client_roots: Arc::new(Mutex::new(Vec::new())),
+            root_justfiles: Arc::new(Mutex::new(Vec::new())),
+            workspace_indexes: Arc::new(Mutex::new(BTreeMap::new())),
+            session_dir: Arc::new(Mutex::new(None)),
+            elicit_parameters: false,
+            recipe_policy: Arc::new(RecipePolicy::default()),
+            env_policy: Arc::new(EnvPolicy::default()),
+            jobs: JobManager::new(),
+            history: Arc::new(ExecutionHistory::default()),
+            metrics: Arc::new(Metrics::default()),
+            artifacts: Arc::new(Mutex::new(BTreeMap::new())),
+            peer: Arc::new(OnceLock::new()),
+            session_env: Arc::new(Mutex::new(McpEnvironment::new())),
+            config_env: Arc::new(Mutex::new(BTreeMap::new())),
+            profiles: Arc::new(Mutex::new(BTreeMap::new())),
+            env_sources: None,
+            default_profile: None,
+            session_profile: Arc::new(Mutex::new(None)),
+            dotenv_stack: false,
+            justfile: None,
+            search_boundary: None,
+            project_roots: Arc::new(Vec::new()),
+            limiter: Arc::new(SessionLimiter::new(SessionLimits::default())),
+            shutdown: Arc::new(Shutdown::default()),
+            sessions: Arc::new(SessionRegistry::default()),
+            session_id: Arc::new(OnceLock::new()),
+            session_guard: Arc::new(OnceLock::new()),
+            audit_log: None,
+            tool_timeouts: Arc::new(ToolTimeouts::default()),
+            max_message_size: None,
+            secret_patterns: Arc::new(redact::default_secret_patterns()),
+            secret_provider: Arc::new(KeyringProvider::default()),
+            secret_env: Arc::new(Vec::new()),
+            secret_values: Arc::new(Mutex::new(BTreeMap::new())),
+            redactor_generation: Arc::new(AtomicU64::new(0)),
+            redactor: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Set how long memoized results stay valid for `run_recipe` calls with `cache: true`.
+    pub fn with_cache_ttl(self, ttl: Duration) -> Self {
+        Self {
+            execution_cache: Arc::new(Mutex::new(ExecutionCache::new(ttl))),
+            ..self
+        }
+    }
+
+    /// Set the lint rule levels applied by `validate_justfile`.
+    pub fn with_lint_config(self, lint_config: LintConfig) -> Self {
+        Self {
+            lint_config: Arc::new(lint_config),
+            ..self
+        }
+    }
+
+    /// Set how often justfiles are checked for changes; `None` disables watching.
+    pub fn with_watch_interval(self, watch_interval: Option<Duration>) -> Self {
+        Self {
+            watch_interval,
+            ..self
+        }
+    }
+
+    /// A server for another client connection: the justfiles, registry,
+    /// cache, locks, jobs, history, metrics and shutdown state are shared, while the client's roots,
+    /// subscriptions, log level, session settings and limit counters start out empty.
+    pub fn new_session(&self) -> Self {
+        Self {
+            subscriptions: Arc::new(Mutex::new(HashSet::new())),
+            logger: ClientLogger::default(),
+            client_roots: Arc::new(Mutex::new(Vec::new())),
+            root_justfiles: Arc::new(Mutex::new(Vec::new())),
+            peer: Arc::new(OnceLock::new()),
+            session_env: Arc::new(Mutex::new(McpEnvironment::new())),
+            session_profile: Arc::new(Mutex::new(self.default_profile.clone())),
+            session_dir: Arc::new(Mutex::new(None)),
+            limiter: Arc::new(SessionLimiter::new(self.limiter.limits().clone())),
+            session_id: Arc::new(OnceLock::new()),
+            session_guard: Arc::new(OnceLock::new()),
+            redactor: Arc::new(Mutex::new(None)),
+            ..self.clone()
+        }
+    }
+
+    /// Ask the user, through MCP elicitation, for required parameters that
+    /// `run_recipe` was called without, instead of failing.
+    pub fn with_parameter_elicitation(self, elicit_parameters: bool) -> Self {
+        Self {
+            elicit_parameters,
+            ..self
+        }
+    }
+
+    /// Use this justfile, which may have any name such as `build.just`,
+    /// instead of looking for `justfile` in the working directory. Recipes
+    /// run in its directory. A session's set_working_directory still
+    /// switches away from it.
+    pub fn with_justfile(self, justfile: Option<PathBuf>) -> Self {
+        Self { justfile, ..self }
+    }
+
+    /// Stop looking for a justfile above the working directory at
+    /// `boundary` rather than the filesystem root.
+    pub fn with_search_boundary(self, search_boundary: Option<PathBuf>) -> Self {
+        Self {
+            search_boundary,
+            ..self
+        }
+    }
+
+    /// Serve several projects at once. Each root's recipes become
+    /// `just_<root>__<recipe>` tools, its justfile is published as a
+    /// resource, and `<root>` or `<root>/<dir>` works as a `justfile_path`.
+    pub fn with_project_roots(self, project_roots: Vec<ProjectRoot>) -> Self {
+        Self {
+            project_roots: Arc::new(project_roots),
+            ..self
+        }
+    }
+
+    /// Variables every recipe run gets, e.g. from the config file's `[env]`;
+    /// session variables take precedence.
+    pub fn with_environment(self, variables: BTreeMap<String, String>) -> Self {
+        Self {
+            config_env: Arc::new(Mutex::new(variables)),
+            ..self
+        }
+    }
+
+    /// Read the config's variables and profiles from `sources` again when
+    /// one of their env files changes, while the justfile watcher runs.
+    pub fn with_env_sources(self, sources: EnvSources) -> Self {
+        Self {
+            env_sources: Some(Arc::new(sources)),
+            ..self
+        }
+    }
+
+    /// Limit which of the server's own environment variables recipes
+    /// inherit, e.g. to keep `OPENAI_API_KEY` from them.
+    pub fn with_env_policy(self, env_policy: EnvPolicy) -> Self {
+        Self {
+            env_policy: Arc::new(env_policy),
+            ..self
+        }
+    }
+
+    /// Look secrets up in `provider` instead of the OS keyring.
+    pub fn with_secret_provider(self, provider: impl SecretProvider + 'static) -> Self {
+        Self {
+            secret_provider: Arc::new(provider),
+            ..self
+        }
+    }
+
+    /// Secrets given to every recipe run as environment variables of the
+    /// same name, on top of those its body asks for with `secret()`.
+    pub fn with_secret_env(self, names: Vec<String>) -> Self {
+        Self {
+            secret_env: Arc::new(names),
+            ..self
+        }
+    }
+
+    /// Give recipes the variables of the working directory's `.env`,
+    /// `.env.local`, `.env.<profile>` and `.env.<profile>.local` files.
+    pub fn with_dotenv_stack(self, dotenv_stack: bool) -> Self {
+        Self {
+            dotenv_stack,
+            ..self
+        }
+    }
+
+    /// Named sets of variables, from the config's `[profiles]`, that
+    /// set_profile switches between; `active` is applied until then.
+    pub fn with_profiles(
+        self,
+        profiles: BTreeMap<String, BTreeMap<String, String>>,
+        active: Option<String>,
+    ) -> Self {
+        Self {
+            profiles: Arc::new(Mutex::new(profiles)),
+            session_profile: Arc::new(Mutex::new(active.clone())),
+            default_profile: active,
+            ..self
+        }
+    }
+
+    /// Restrict which recipes clients may run and see.
+    pub fn with_recipe_policy(self, recipe_policy: RecipePolicy) -> Self {
+        Self {
+            recipe_policy: Arc::new(recipe_policy),
+            ..self
+        }
+    }
+
+    /// Limit the tool calls per minute and concurrent recipe executions of
+    /// each session; calls over a limit fail with a structured error.
+    pub fn with_session_limits(self, limits: SessionLimits) -> Self {
+        Self {
+            limiter: Arc::new(SessionLimiter::new(limits)),
+            ..self
+        }
+    }
+
+    /// Fail tool calls that take longer than their timeout, cancelling the
+    /// recipes they run. This is separate from any timeout of the recipes
+    /// themselves: background jobs outlive the `start_recipe` call.
+    pub fn with_tool_timeouts(self, tool_timeouts: ToolTimeouts) -> Self {
+        Self {
+            tool_timeouts: Arc::new(tool_timeouts),
+            ..self
+        }
+    }
+
+    /// Cut the longest strings of tool results, such as recipe output, so
+    /// a result serializes to at most `max_message_size` bytes.
+    pub fn with_max_message_size(self, max_message_size: Option<usize>) -> Self {
+        Self {
+            max_message_size,
+            ..self
+        }
+    }
+
+    /// Mask the values of variables whose names match `patterns`, such as
+    /// `*_TOKEN`, in tool results, errors and log messages.
+    pub fn with_secret_patterns(self, patterns: Vec<String>) -> Self {
+        Self {
+            secret_patterns: Arc::new(patterns),
+            ..self
+        }
+    }
+
+    /// Keep execution history in `history`, e.g. one opened on a file.
+    pub fn with_history(self, history: ExecutionHistory) -> Self {
+        Self {
+            history: Arc::new(history),
+            ..self
+        }
+    }
+
+    /// Record every JSON-RPC message of every session in `audit_log`.
+    pub fn with_audit_log(self, audit_log: Option<AuditLog>) -> Self {
+        Self {
+            audit_log: audit_log.map(Arc::new),
+            ..self
+        }
+    }
+
+    pub fn audit_log(&self) -> Option<Arc<AuditLog>> {
+        self.audit_log.clone()
+    }
+
+    /// Tool call, execution and cache counters of every session.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// List this session under `client` in `list_sessions` until the guard
+    /// is dropped, normally when the client disconnects.
+    pub fn open_session(&self, client: impl Into<String>) -> SessionGuard {
+        let guard = self.sessions.open(client);
+        let _ = self.session_id.set(guard.id());
+        guard
+    }
+
+    /// List this session under `client` in `list_sessions` until the last
+    /// handle to it is dropped, for transports such as streamable HTTP
+    /// that end sessions themselves.
+    pub fn keep_session_open(&self, client: impl Into<String>) {
+        let _ = self.session_guard.set(self.open_session(client));
+    }
+
+    /// Stop accepting tool calls, wait up to `grace` for recipe executions
+    /// in flight in any session to finish, then cancel the rest, killing
+    /// their process groups.
+    pub async fn shutdown(&self, grace: Duration) -> DrainReport {
+        let in_flight = self.shutdown.in_flight();
+        tracing::info!(
+            in_flight,
+            grace_ms = grace.as_millis() as u64,
+            "draining executions"
+        );
+        let report = self.shutdown.drain(grace).await;
+        if report.cancelled > 0 {
+            tracing::warn!(
+                cancelled = report.cancelled,
+                "cancelled executions still running after the grace period"
+            );
+        }
+        if report.abandoned > 0 {
+            tracing::error!(
+                abandoned = report.abandoned,
+                "executions did not stop after being cancelled"
+            );
+        }
+        report
+    }
+
+    /// Why the server cannot serve its recipes yet: the default justfile is
+    /// missing, or a published justfile cannot be read, does not parse, or
+    /// fails validation, e.g. with an unknown dependency. Empty when it is
+    /// ready.
+    pub fn readiness_problems(&self) -> Vec<String> {
+        if self.shutdown.is_draining() {
+            return vec![McpServerError::ShuttingDown.to_string()];
+        }
+        let mut problems = Vec::new();
+        if self.registry.is_permissive()
+            && let Err(error) = self.resolve_justfile_path(None)
+        {
+            problems.push(error.to_string());
+        }
+        for path in self.resource_paths() {
+            let parsed =
+                JustfileCache::load(&self.justfile_cache, &path).map_err(|error| match error {
+                    ParserError::FileRead { source, .. } => source.to_string(),
+                    error => error.to_string(),
+                });
+            match parsed {
+                Ok(parsed) => problems.extend(
+                    validator::validate_justfile(&parsed.justfile)
+                        .errors
+                        .into_iter()
+                        .map(|error| format!("{}: {}", path.display(), error.message)),
+                ),
+                Err(error) => problems.push(format!("{}: {error}", path.display())),
+            }
+        }
+        problems
+    }
+
+    /// Watch the published justfiles, their imports and the env files, and
+    /// notify the client when recipes or files change. Reloads the
+    /// environment when one of its env files changes. The targets are
+    /// updated every `interval`. Stops once the client goes away.
+    fn spawn_watcher(&self, peer: Peer<RoleServer>, interval: Duration) {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let (mut watcher, mut events) = match Watcher::new(DEFAULT_DEBOUNCE, interval) {
+                Ok(started) => started,
+                Err(error) => {
+                    tracing::warn!(%error, "not watching justfiles");
+                    return;
+                }
+            };
+            let mut snapshots = RecipeSnapshots::new(&server.resource_paths());
+            let mut environment = server.recipe_environment();
+            loop {
+                // Justfiles, their imports and the env files can change
+                // with the client's roots and the working directory
+                if let Err(error) = watcher.watch(&server.watch_targets()) {
+                    tracing::warn!(%error, "failed to watch files");
+                }
+                let event = match tokio::time::timeout(interval, events.recv()).await {
+                    Ok(Some(event)) => event,
+                    Ok(None) => break,
+                    Err(_) => continue,
+                };
+                server.invalidate_redactor();
+                if !event.env_files.is_empty() {
+                    environment = server.reload_environment(&event.env_files, &environment);
+                }
+                server.refresh_workspace_indexes();
+                let changes = snapshots.update(&server.resource_paths(), &event);
+                if changes.is_empty() {
+                    continue;
+                }
+                if server.notify_changes(&peer, changes).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Bring the workspace indexes up to date, parsing only the justfiles
+    /// that were added or modified, and log what changed.
+    fn refresh_workspace_indexes(&self) {
+        let mut indexes = self.workspace_indexes.lock().unwrap();
+        for (root, index) in indexes.iter_mut() {
+            let changes = index.refresh();
+            if changes.is_empty() {
+                continue;
+            }
+            let paths = |paths: &[PathBuf]| -> Vec<String> {
+                paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect()
+            };
+            self.log(
+                LoggingLevel::Debug,
+                serde_json::json!({
+                    "event": "workspace_reindexed",
+                    "root": root.display().to_string(),
+                    "added": paths(&changes.added),
+                    "removed": paths(&changes.removed),
+                    "modified": paths(&changes.modified),
+                }),
+            );
+        }
+    }
+
+    /// Call `f` with the up-to-date index of the justfiles below `base`.
+    /// The index is kept between calls and rebuilt when `options` differ
+    /// from those it was built with.
+    fn with_workspace_index<T>(
+        &self,
+        base: &Path,
+        options: &DiscoveryOptions,
+        f: impl FnOnce(&WorkspaceIndex) -> T,
+    ) -> T {
+        let mut indexes = self.workspace_indexes.lock().unwrap();
+        match indexes.get_mut(base) {
+            Some(index) if index.options() == options => {
+                index.refresh();
+            }
+            _ => {
+                let index = WorkspaceIndex::build(base, options.clone());
+                indexes.insert(base.to_path_buf(), index);
+            }
+        }
+        f(&indexes[base])
+    }
+
+    /// Directories list_justfiles and list_workspace_recipes search: each
+    /// project root, whose name starts its ids, or the working directory.
+    fn workspace_bases(&self) -> Vec<(PathBuf, Option<String>)> {
+        if self.project_roots.is_empty() {
+            return vec![(self.working_dir(), None)];
+        }
+        self.project_roots
+            .iter()
+            .map(|root| (root.dir.clone(), Some(root.name.clone())))
+            .collect()
+    }
+
+    /// The files the watcher reports changes to: the published justfiles
+    /// and, so a justfile created later is seen, every name one could have
+    /// in the working directory and the project roots.
+    fn watch_targets(&self) -> WatchTargets {
+        let mut justfiles = self.resource_paths();
+        if self.registry.is_permissive() && self.explicit_justfile().is_none() {
+            let dirs = std::iter::once(self.working_dir())
+                .chain(self.project_roots.iter().map(|root| root.dir.clone()));
+            for dir in dirs {
+                justfiles.extend(JUSTFILE_NAMES.iter().map(|name| dir.join(name)));
+            }
+        }
+        WatchTargets {
+            justfiles,
+            env_files: self.env_file_paths(),
+        }
+    }
+
+    /// The env files recipe runs get variables from: the config's, every
+    /// profile's, and the stacked dotenv files of the working directory.
+    fn env_file_paths(&self) -> Vec<PathBuf> {
+        let mut paths = self
+            .env_sources
+            .as_ref()
+            .map(|sources| sources.files())
+            .unwrap_or_default();
+        if self.dotenv_stack {
+            let profile = self.session_profile.lock().unwrap().clone();
+            paths.extend(environment::dotenv_stack(
+                &self.working_dir(),
+                profile.as_deref(),
+            ));
+        }
+        paths
+    }
+
+    /// Read the config's variables again after `paths` changed and tell the
+    /// client what changed from `previous`, secrets masked. Later recipe
+    /// runs get the new values; on error the old ones are kept.
+    fn reload_environment(&self, paths: &[PathBuf], previous: &McpEnvironment) -> McpEnvironment {
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        if let Some(sources) = &self.env_sources {
+            match sources.load() {
+                Ok((config_env, profiles)) => {
+                    *self.config_env.lock().unwrap() = config_env;
+                    *self.profiles.lock().unwrap() = profiles;
+                    self.invalidate_redactor();
+                }
+                Err(error) => {
+                    self.log(
+                        LoggingLevel::Warning,
+                        serde_json::json!({
+                            "event": "env_reload_failed",
+                            "paths": paths,
+                            "error": error.to_string(),
+                        }),
+                    );
+                    return previous.clone();
+                }
+            }
+        }
+        let environment = self.recipe_environment();
+        self.log(
+            LoggingLevel::Info,
+            serde_json::json!({
+                "event": "env_reloaded",
+                "paths": paths,
+                "changes": environment.diff(previous).masked(),
+            }),
+        );
+        environment
+    }
+
+    /// Ask the client for its roots and discover the justfiles inside them.
+    async fn refresh_roots(&self, peer: &Peer<RoleServer>) {
+        let roots = match peer.list_roots().await {
+            Ok(result) => result.roots,
+            Err(error) => {
+                self.log(
+                    LoggingLevel::Warning,
+                    serde_json::json!({ "event": "roots_failed", "error": error.to_string() }),
+                );
+                return;
+            }
+        };
+        let root_dirs: Vec<PathBuf> = roots
+            .iter()
+            .filter_map(|root| roots::root_path(&root.uri))
+            .collect();
+        let justfiles: Vec<PathBuf> = root_dirs
+            .iter()
+            .flat_map(|root| roots::discover_justfiles(root, roots::MAX_DEPTH, &[]))
+            .filter(|path| self.registry.is_in_scope(path))
+            .collect();
+        self.log(
+            LoggingLevel::Info,
+            serde_json::json!({
+                "event": "roots_changed",
+                "roots": roots.iter().map(|root| &root.uri).collect::<Vec<_>>(),
+                "justfiles": justfiles
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>(),
+            }),
+        );
+        *self.client_roots.lock().unwrap() = root_dirs;
+        *self.root_justfiles.lock().unwrap() = justfiles;
+        self.invalidate_redactor();
+    }
+
+    async fn notify_changes(
+        &self,
+        peer: &Peer<RoleServer>,
+        changes: Changes,
+    ) -> Result<(), ServiceError> {
+        self.log(
+            LoggingLevel::Info,
+            serde_json::json!({
+                "event": "justfile_changed",
+                "paths": changes
+                    .modified
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>(),
+            }),
+        );
+        {
+            let mut justfile_cache = self.justfile_cache.write().unwrap();
+            for path in &changes.modified {
+                justfile_cache.invalidate(path);
+            }
+        }
+        // Results of the old recipes must not be reused
+        let invalidated: usize = {
+            let mut cache = self.execution_cache.lock().unwrap();
+            changes
+                .modified
+                .iter()
+                .map(|path| cache.invalidate_justfile(path))
+                .sum()
+        };
+        tracing::info!(
+            modified = changes.modified.len(),
+            recipes_changed = changes.recipes_changed,
+            invalidated,
+            "justfiles reloaded"
+        );
+        if changes.recipes_changed {
+            peer.notify_tool_list_changed().await?;
+        }
+        if changes.files_changed {
+            peer.notify_resource_list_changed().await?;
+        }
+        let subscriptions = self.subscriptions.lock().unwrap().clone();
+        for path in &changes.modified {
+            let uri = justfile_uri(path);
+            if subscriptions.contains(&uri) {
+                peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a recipe as `run_recipe` does, passing output lines to `on_output`
+    /// as they arrive and stopping when `cancel` is cancelled.
+    async fn execute_recipe(
+        &self,
+        params: ExecuteRecipeParams,
+        on_output: impl Fn(&TranscriptEntry) + Send + 'static,
+        cancel: CancelToken,
+    ) -> Result<ExecutionOutput, McpServerError> {
+        let (justfile, justfile_path) = self.load_justfile(params.justfile_path.as_deref())?;
+        Self::find_recipe(&justfile, &params.recipe_name)?;
+        self.check_recipe_policy(&justfile, &params.recipe_name)?;
+        for pattern in params.outputs.iter().flatten() {
+            artifacts::validate_pattern(pattern).context(ArtifactFailedSnafu)?;
+        }
+
+        let parsed_args = params.args.unwrap_or_default();
+
+        // Read the env files once; the run's output is masked with what it got
+        let environment = self.recipe_environment();
+        self.refresh_redactor(&environment);
+        let mut options = ExecutionOptions {
+            transcript: params.transcript.unwrap_or(false),
+            echo: params.echo.unwrap_or(false),
+            exit_strategy: params.exit_strategy.unwrap_or_default(),
+            inherit_env: (*self.env_policy).clone(),
+            ..Default::default()
+        }
+        .with_environment(&environment);
+        options.env.extend(
+            self.recipe_secrets(&justfile, &params.recipe_name, &options.env)
+                .await?,
+        );
+
+        let cache_key = params.cache.unwrap_or(false).then(|| {
+            let inputs: Vec<std::path::PathBuf> = params
+                .inputs
+                .iter()
+                .flatten()
+                .map(|input| self.working_dir().join(input))
+                .collect();
+            CacheKey::new(
+                &justfile_path,
+                &justfile,
+                &params.recipe_name,
+                &parsed_args,
+                &inputs,
+                &options,
+            )
+        });
+
+        let cached_result = cache_key
+            .as_ref()
+            .and_then(|key| self.execution_cache.lock().unwrap().get(key));
+        let cached = cached_result.is_some();
+        if cache_key.is_some() {
+            self.metrics.record_cache_lookup(cached);
+        }
+
+        // Execute the recipe unless a memoized result is available
+        let (result, started_at) = match cached_result {
+            Some(result) => (result, SystemTime::now()),
+            None => {
+                let _in_flight = self
+                    .shutdown
+                    .track(cancel.clone())
+                    .context(ShuttingDownSnafu)?;
+                // Serialize concurrent runs of the same recipe. The lock
+                // comes first, so runs waiting for it hold no execution slot
+                let _guard = self
+                    .recipe_locks
+                    .acquire(
+                        &justfile_path,
+                        &params.recipe_name,
+                        params.lock.unwrap_or_default(),
+                    )
+                    .await
+                    .context(LockFailedSnafu)?;
+                let _permit = self.limiter.start_execution().context(LimitExceededSnafu)?;
+
+                self.log(
+                    LoggingLevel::Info,
+                    serde_json::json!({
+                        "event": "recipe_started",
+                        "recipe": params.recipe_name,
+                        "args": parsed_args,
+                    }),
+                );
+
+                let started_at = SystemTime::now();
+                let recipe_name = params.recipe_name.clone();
+                let args = parsed_args.clone();
+                let working_dir = self.recipe_dir(&justfile_path);
+                let exec_options = options.clone();
+                // A tool call that times out drops this future; the recipe
+                // must not keep running behind it
+                let cancel_on_drop = cancel.cancel_on_drop();
+                // The execution span belongs to this tool call's
+                let span = tracing::Span::current();
+                let result = tokio::task::spawn_blocking(move || {
+                    let _span = span.enter();
+                    execute_recipe_cancellable(
+                        &justfile,
+                        &recipe_name,
+                        &args,
+                        &working_dir,
+                        &exec_options,
+                        &on_output,
+                        &cancel,
+                    )
+                })
+                .await;
+                cancel_on_drop.disarm();
+                let result = result
+                    .context(TaskFailedSnafu)?
+                    .inspect_err(|error| {
+                        self.log(
+                            LoggingLevel::Error,
+                            serde_json::json!({
+                                "event": "recipe_failed",
+                                "recipe": params.recipe_name,
+                                "error": error.to_string(),
+                            }),
+                        );
+                        let finished_at = SystemTime::now();
+                        self.record_execution(ExecutionRecord {
+                            recipe_name: params.recipe_name.clone(),
+                            justfile: justfile_path.display().to_string(),
+                            args: parsed_args.clone(),
+                            started_at_ms: unix_millis(started_at),
+                            finished_at_ms: unix_millis(finished_at),
+                            duration_ms: unix_millis(finished_at) - unix_millis(started_at),
+                            exit_code: None,
+                            status: match error {
+                                ExecutionError::Cancelled { .. } => ExecutionStatus::Cancelled,
+                                _ => ExecutionStatus::Error,
+                            },
+                            cached: false,
+                            error: Some(error.to_string()),
+                            stdout: String::new(),
+                            stderr: String::new(),
+                            session_id: self.session_id.get().copied(),
+                        });
+                    })
+                    .context(ExecutionFailedSnafu)?;
+                if let Some(key) = cache_key {
+                    self.execution_cache.lock().unwrap().insert(key, &result);
+                }
+                (result, started_at)
+            }
+        };
+
+        let output = ExecutionOutput {
+            recipe_name: params.recipe_name,
+            justfile: justfile_path.display().to_string(),
+            stdout: result.stdout,
+            stderr: result.stderr,
+            exit_code: result.exit_code,
+            duration_ms: result.duration_ms,
+            success: result.exit_code == 0,
+            cached,
+            transcript: options.transcript.then_some(result.transcript),
+            failures: result.failures,
+            artifacts: self.publish_artifacts(&params.outputs.unwrap_or_default())?,
+        };
+
+        self.log(
+            if output.success {
+                LoggingLevel::Info
+            } else {
+                LoggingLevel::Warning
+            },
+            serde_json::json!({
+                "event": "recipe_finished",
+                "recipe": output.recipe_name,
+                "exit_code": output.exit_code,
+                "duration_ms": output.duration_ms,
+                "cached": output.cached,
+            }),
+        );
+
+        let redactor = self.redactor();
+        let record = ExecutionRecord {
+            recipe_name: output.recipe_name.clone(),
+            justfile: justfile_path.display().to_string(),
+            args: parsed_args,
+            started_at_ms: unix_millis(started_at),
+            finished_at_ms: unix_millis(started_at) + output.duration_ms,
+            duration_ms: output.duration_ms,
+            exit_code: Some(output.exit_code),
+            status: if output.success {
+                ExecutionStatus::Succeeded
+            } else {
+                ExecutionStatus::Failed
+            },
+            cached: output.cached,
+            error: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            session_id: self.session_id.get().copied(),
+        }
+        .with_output(
+            redactor.redact(&output.stdout).into_owned(),
+            redactor.redact(&output.stderr).into_owned(),
+        );
+        self.record_execution(record);
+
+        Ok(output)
+    }
+
+    /// Add a finished execution to the history and the metrics, with the
+    /// secrets in its arguments and error masked.
+    fn record_execution(&self, mut record: ExecutionRecord) {
+        let redactor = self.redactor();
+        for arg in &mut record.args {
+            *arg = redactor.redact(arg).into_owned();
+        }
+        record.error = record
+            .error
+            .map(|error| redactor.redact(&error).into_owned());
+        self.metrics.record_execution(&record);
+        self.history.record(record);
+    }
+
+    /// Publish the files matching `patterns` as resources, telling the
+    /// client when new ones appear.
+    fn publish_artifacts(&self, patterns: &[String]) -> Result<Vec<Artifact>, McpServerError> {
+        let working_dir = self.working_dir();
+        let paths =
+            artifacts::find_artifacts(&working_dir, patterns).context(ArtifactFailedSnafu)?;
+
+        let mut published = Vec::new();
+        let mut added = false;
+        for path in paths {
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let artifact = Self::artifact(&working_dir, &path, metadata.len());
+            added |= self
+                .artifacts
+                .lock()
+                .unwrap()
+                .insert(artifact.uri.clone(), path)
+                .is_none();
+            published.push(artifact);
+        }
+
+        if let (true, Some(peer)) = (added, self.peer.get()) {
+            let peer = peer.clone();
+            tokio::spawn(async move {
+                let _ = peer.notify_resource_list_changed().await;
+            });
+        }
+        Ok(published)
+    }
+
+    fn artifact(working_dir: &Path, path: &Path, size: u64) -> Artifact {
+        // The first few KiB decide whether an unknown extension is text
+        let mut head = Vec::new();
+        if let Ok(file) = std::fs::File::open(path) {
+            let _ = file.take(4096).read_to_end(&mut head);
+        }
+        Artifact {
+            uri: artifacts::file_uri(path),
+            path: path
+                .strip_prefix(working_dir)
+                .unwrap_or(path)
+                .display()
+                .to_string(),
+            size,
+            mime_type: artifacts::mime_type(path, artifacts::looks_like_text(&head)).to_string(),
+        }
+    }
+
+    fn artifact_resource(artifact: &Artifact) -> Resource {
+        let mut resource = RawResource::new(artifact.uri.clone(), artifact.path.clone());
+        resource.description = Some("File produced by a recipe run".to_string());
+        resource.mime_type = Some(artifact.mime_type.clone());
+        resource.size = u32::try_from(artifact.size).ok();
+        resource.no_annotation()
+    }
+
+    /// Contents of a published artifact: text, or base64 for binary files.
+    fn read_artifact_resource(uri: &str, path: &Path) -> Result<ReadResourceResult, McpError> {
+        let bytes = std::fs::read(path).context(IoSnafu)?;
+        let is_text = artifacts::looks_like_text(&bytes);
+        let mime_type = artifacts::mime_type(path, is_text);
+        let contents = if is_text {
+            text_contents(uri, String::from_utf8_lossy(&bytes).into_owned(), mime_type)
+        } else {
+            blob_contents(uri, artifacts::base64_encode(&bytes), mime_type)
+        };
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    fn load_justfile(
+        &self,
+        justfile_path: Option<&str>,
+    ) -> Result<(Justfile, std::path::PathBuf), McpServerError> {
+        let justfile_path = self.resolve_justfile_path(justfile_path)?;
+        let justfile = self.parse_justfile_at(&justfile_path)?.justfile.clone();
+        self.log(
+            LoggingLevel::Debug,
+            serde_json::json!({
+                "event": "justfile_loaded",
+                "path": justfile_path.display().to_string(),
+                "recipes": justfile.recipes.len(),
+            }),
+        );
+
+        Ok((justfile, justfile_path))
+    }
+
+    /// The justfile at `path` and its source, read and parsed again only
+    /// when the file changed since the last call.
+    fn parse_justfile_at(&self, path: &Path) -> Result<Arc<CachedJustfile>, McpServerError> {
+        JustfileCache::load(&self.justfile_cache, path).map_err(|error| match error {
+            ParserError::FileRead { source, .. } => McpServerError::IoError { source },
+            source => {
+                self.log(
+                    LoggingLevel::Error,
+                    serde_json::json!({
+                        "event": "parse_error",
+                        "path": path.display().to_string(),
+                        "error": source.to_string(),
+                    }),
+                );
+                McpServerError::ParseFailed { source }
+            }
+        })
+    }
+
+    /// Ask the user for the required parameters after `args` and append
+    /// them, filling skipped optional parameters with their defaults. Without
+    /// a client that supports elicitation `args` is left as is, and just
+    /// reports the missing arguments.
+    async fn elicit_missing_args(
+        &self,
+        recipe: &Recipe,
+        args: &mut Vec<String>,
+    ) -> Result<(), McpServerError> {
+        let missing = &recipe.parameters[args.len().min(recipe.parameters.len())..];
+        if missing.iter().all(|param| param.default_value.is_some()) {
+            return Ok(());
+        }
+        let Some(peer) = self.peer.get().filter(|peer| {
+            peer.peer_info()
+                .is_some_and(|info| info.capabilities.elicitation.is_some())
+        }) else {
+            return Ok(());
+        };
+
+        // The recipe's input schema, limited to the parameters not given
+        let mut schema = recipe_input_schema(recipe);
+        let is_missing = |name: &str| missing.iter().any(|param| param.name == name);
+        if let Some(serde_json::Value::Object(properties)) = schema.get_mut("properties") {
+            properties.retain(|name, _| is_missing(name));
+        }
+        if let Some(serde_json::Value::Array(required)) = schema.get_mut("required") {
+            required.retain(|name| name.as_str().is_some_and(is_missing));
+        }
+        let names: Vec<&str> = missing.iter().map(|param| param.name.as_str()).collect();
+        let mut message = format!("Recipe `{}` needs: {}", recipe.name, names.join(", "));
+        if let Some(documentation) = &recipe.documentation {
+            message.push_str(&format!("\n\n{documentation}"));
+        }
+        let request = CreateElicitationRequestParam {
+            message,
+            requested_schema: schema,
+        };
+
+        let result = peer.create_elicitation(request).await.map_err(|error| {
+            McpServerError::ElicitationFailed {
+                message: error.to_string(),
+            }
+        })?;
+        let content = match result.action {
+            ElicitationAction::Accept => result.content.unwrap_or_default(),
+            _ => {
+                return Err(McpServerError::ParametersDeclined {
+                    recipe_name: recipe.name.clone(),
+                });
+            }
+        };
+
+        let values: Vec<Option<String>> = missing
+            .iter()
+            .map(|param| match content.get(&param.name) {
+                None | Some(serde_json::Value::Null) => None,
+                Some(serde_json::Value::String(value)) => Some(value.clone()),
+                Some(value) => Some(value.to_string()),
+            })
+            .collect();
+        // Positional arguments stop after the last value given
+        let count = values
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |last| last + 1);
+        for (param, value) in missing.iter().zip(values).take(count) {
+            match value.or_else(|| param.default_value.clone()) {
+                Some(value) => args.push(value),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Masks the secrets of the server's environment, the config and this
+    /// session. Built once and kept until the env files, the session's
+    /// settings or the fetched secrets change.
+    pub fn redactor(&self) -> Redactor {
+        let generation = self.redactor_generation.load(Ordering::Acquire);
+        if let Some((built, redactor)) = &*self.redactor.lock().unwrap()
+            && *built == generation
+        {
+            return redactor.clone();
+        }
+        self.refresh_redactor(&self.recipe_environment())
+    }
+
+    /// Build the redactor again from `environment` and keep it.
+    fn refresh_redactor(&self, environment: &McpEnvironment) -> Redactor {
+        let generation = self.redactor_generation.load(Ordering::Acquire);
+        let redactor = Redactor::new(&self.secret_patterns)
+            .with_variables(std::env::vars())
+            .with_variables(environment.variables())
+            .with_secrets(self.secret_values.lock().unwrap().values());
+        *self.redactor.lock().unwrap() = Some((generation, redactor.clone()));
+        redactor
+    }
+
+    /// Have every session build its redactor again on next use.
+    fn invalidate_redactor(&self) {
+        self.redactor_generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// The secrets a run of `recipe_name` needs that `env` and the server's
+    /// own environment do not already set. Each is fetched from the
+    /// provider once per server and remembered for masking.
+    async fn recipe_secrets(
+        &self,
+        justfile: &Justfile,
+        recipe_name: &str,
+        env: &BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, McpServerError> {
+        let mut names = secrets::recipe_secrets(justfile, recipe_name);
+        names.extend(self.secret_env.iter().cloned());
+        names.retain(|name| {
+            let inherited = self.env_policy.inherits(name) && std::env::var_os(name).is_some();
+            !env.contains_key(name) && !inherited
+        });
+
+        let missing: Vec<String> = {
+            let values = self.secret_values.lock().unwrap();
+            names
+                .iter()
+                .filter(|name| !values.contains_key(*name))
+                .cloned()
+                .collect()
+        };
+        if !missing.is_empty() {
+            // Keyring lookups block, e.g. on D-Bus or an unlock prompt
+            let provider = self.secret_provider.clone();
+            let fetched =
+                tokio::task::spawn_blocking(move || secrets::resolve(provider.as_ref(), missing))
+                    .await
+                    .context(TaskFailedSnafu)?
+                    .context(SecretFailedSnafu)?;
+            self.secret_values.lock().unwrap().extend(fetched);
+            self.invalidate_redactor();
+        }
+        let values = self.secret_values.lock().unwrap();
+        Ok(names
+            .into_iter()
+            .filter_map(|name| values.get(&name).map(|value| (name, value.clone())))
+            .collect())
+    }
+
+    /// Send a log message to the client, secrets masked.
+    fn log(&self, level: LoggingLevel, mut data: serde_json::Value) {
+        self.redactor().redact_json(&mut data);
+        self.logger.log(level, data);
+    }
+
+    /// What recipes run with on top of the server's own environment: the
+    /// stacked dotenv files, overridden by the config's variables,
+    /// overridden by the active profile's, overridden by this session's.
+    fn recipe_environment(&self) -> McpEnvironment {
+        let mut env = McpEnvironment::new();
+        let profile = self.session_profile.lock().unwrap().clone();
+        if self.dotenv_stack
+            && let Err(error) = env.load_dotenv_stack(&self.working_dir(), profile.as_deref())
+        {
+            tracing::warn!(%error, "skipping the dotenv files");
+        }
+        let config_env = self.config_env.lock().unwrap().clone();
+        if !config_env.is_empty() {
+            let config = config_env.into_iter();
+            env.set_server_config("config".to_string(), config.collect());
+        }
+        let profiles = self.profiles.lock().unwrap().clone();
+        if let Some(name) = profile
+            && let Some(variables) = profiles.get(&name)
+        {
+            let profile = variables.iter();
+            let profile = profile.map(|(name, value)| (name.clone(), value.clone()));
+            env.set_server_config(format!("profile:{name}"), profile.collect());
+        }
+        let session = self.session_env.lock().unwrap().variables().clone();
+        if !session.is_empty() {
+            env.set_custom(session);
+        }
+        env
+    }
+
+    /// The directory chosen with set_working_directory, else the directory
+    /// of the `--justfile`, else of the first justfile found in the client's
+    /// roots, or the configured working directory when the client has no
+    /// roots.
+    fn working_dir(&self) -> PathBuf {
+        if let Some(dir) = self.session_dir.lock().unwrap().clone() {
+            return dir;
+        }
+        if let Some(dir) = self.justfile.as_deref().and_then(Path::parent) {
+            return dir.to_path_buf();
+        }
+        self.root_justfiles
+            .lock()
+            .unwrap()
+            .first()
+            .and_then(|path| path.parent())
+            .map_or_else(|| self.working_dir.clone(), Path::to_path_buf)
+    }
+
+    /// Where the recipes of `justfile_path` run: the working directory, or
+    /// the justfile's own directory when it was found above it, as just does.
+    fn recipe_dir(&self, justfile_path: &Path) -> PathBuf {
+        let working_dir = self.working_dir();
+        let absolute = std::path::absolute(&working_dir).unwrap_or_else(|_| working_dir.clone());
+        match justfile_path.parent() {
+            Some(dir) if dir != absolute && absolute.starts_with(dir) => dir.to_path_buf(),
+            _ => working_dir,
+        }
+    }
+
+    /// The `--justfile`, unless this session switched directories.
+    fn explicit_justfile(&self) -> Option<PathBuf> {
+        if self.session_dir.lock().unwrap().is_some() {
+            return None;
+        }
+        self.justfile.clone()
+    }
+
+    /// Directories clients may point tools at, canonicalized: the server's
+    /// working directory, the project roots and the client's roots.
+    fn allowed_roots(&self) -> Vec<PathBuf> {
+        let mut roots = vec![self.working_dir.clone()];
+        roots.extend(
+            self.explicit_justfile()
+                .as_deref()
+                .and_then(Path::parent)
+                .map(Path::to_path_buf),
+        );
+        roots.extend(self.project_roots.iter().map(|root| root.dir.clone()));
+        roots.extend(self.client_roots.lock().unwrap().iter().cloned());
+        roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .collect()
+    }
+
+    /// `path` canonicalized, so `..` and symlinks cannot leave the allowed
+    /// roots. A justfile registered in strict mode is allowed wherever it is.
+    fn allowed_path(&self, path: &Path) -> Result<PathBuf, McpServerError> {
+        let allowed_roots = self.allowed_roots();
+        path.canonicalize()
+            .ok()
+            .filter(|canonical| {
+                allowed_roots.iter().any(|root| canonical.starts_with(root))
+                    || (!self.registry.is_permissive() && self.registry.is_in_scope(canonical))
+            })
+            .context(PathNotAllowedSnafu {
+                path: path.display().to_string(),
+            })
+    }
+
+    /// Locate the justfile and check it against the registry.
+    fn resolve_justfile_path(
+        &self,
+        justfile_path: Option<&str>,
+    ) -> Result<std::path::PathBuf, McpServerError> {
+        let working_dir = self.working_dir();
+        let justfile_path = if let Some(path) = justfile_path {
+            // A directory, such as an id from list_justfiles, means its justfile
+            let path = self
+                .project_roots
+                .iter()
+                .find_map(|root| root.resolve(path))
+                .unwrap_or_else(|| working_dir.join(path));
+            let path = self.allowed_path(&path)?;
+            if path.is_dir() {
+                roots::find_justfile(&path).ok_or_else(|| McpServerError::JustfileNotFound {
+                    path: path.display().to_string(),
+                })?
+            } else {
+                path
+            }
+        } else if let Some(path) = self.explicit_justfile() {
+            path
+        } else {
+            // The working directory's justfile, else the nearest parent's
+            roots::find_justfile_upward(&working_dir, self.search_boundary.as_deref()).ok_or_else(
+                || McpServerError::JustfileNotFound {
+                    path: working_dir.display().to_string(),
+                },
+            )?
+        };
+
+        // Registry gate — absent from scope is not an error message, it's silence.
+        // The error message here is only surfaced in strict mode (non-empty registry).
+        if !self.registry.is_in_scope(&justfile_path) {
+            return Err(McpServerError::JustfileNotRegistered {
+                path: justfile_path.display().to_string(),
+            });
+        }
+
+        Ok(justfile_path)
+    }
+
+    /// Justfiles published as resources: every registered justfile in
+    /// strict mode, otherwise the default justfile plus those of the project
+    /// roots and any found in the client's roots.
+    fn resource_paths(&self) -> Vec<PathBuf> {
+        if self.registry.is_permissive() {
+            let mut paths: Vec<PathBuf> = self.resolve_justfile_path(None).into_iter().collect();
+            let project_justfiles = self
+                .project_roots
+                .iter()
+                .filter_map(|root| roots::find_justfile(&root.dir));
+            let root_justfiles = self.root_justfiles.lock().unwrap().clone();
+            for path in project_justfiles.chain(root_justfiles) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+            return paths;
+        }
+        let mut paths: Vec<PathBuf> = self.registry.registered_paths().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    fn justfile_resource(&self, path: &Path) -> Resource {
+        let project_name = self.project_roots.iter().find_map(|root| {
+            let relative = path.strip_prefix(&root.dir).ok()?;
+            Some(format!("{}/{}", root.name, relative.display()))
+        });
+        let name = project_name.unwrap_or_else(|| {
+            path.strip_prefix(self.working_dir())
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        });
+        let mut resource = RawResource::new(justfile_uri(path), name);
+        resource.description = Some("Justfile source and its parsed recipes".to_string());
+        resource.mime_type = Some(JUSTFILE_MIME_TYPE.to_string());
+        resource.no_annotation()
+    }
+
+    /// Contents of a `justfile://` resource: the raw text followed by the
+    /// parsed structure as JSON.
+    fn read_justfile_resource(&self, uri: &str) -> Result<ReadResourceResult, McpError> {
+        let not_found = || McpError::resource_not_found(format!("Unknown resource: {uri}"), None);
+        let path = uri
+            .strip_prefix(JUSTFILE_URI_SCHEME)
+            .ok_or_else(not_found)?;
+        let path = self
+            .resolve_justfile_path(Some(path))
+            .map_err(|_| not_found())?;
+
+        let parsed = self.parse_justfile_at(&path)?;
+        let info = JustfileInfo::from_justfile(&path, &parsed.justfile);
+        let json = serde_json::to_string_pretty(&info).context(SerializationSnafu)?;
+
+        Ok(ReadResourceResult {
+            contents: vec![
+                text_contents(uri, parsed.source.clone(), JUSTFILE_MIME_TYPE),
+                text_contents(uri, json, "application/json"),
+            ],
+        })
+    }
+
+    /// Render a built-in prompt against the parsed justfile.
+    fn render_prompt(
+        &self,
+        name: &str,
+        arguments: Option<JsonObject>,
+    ) -> Result<GetPromptResult, McpError> {
+        let template = prompts::find_prompt(name)
+            .ok_or_else(|| McpError::invalid_params(format!("Unknown prompt: {name}"), None))?;
+        let arguments = arguments.unwrap_or_default();
+        let argument = |key: &str| arguments.get(key).and_then(|value| value.as_str());
+        let required = |key: &str| {
+            argument(key).ok_or_else(|| {
+                McpError::invalid_params(format!("Missing required argument: {key}"), None)
+            })
+        };
+
+        let path = self.resolve_justfile_path(argument("justfile_path"))?;
+        let parsed = self.parse_justfile_at(&path)?;
+        let (source, justfile) = (&parsed.source, &parsed.justfile);
+
+        let text = match template.name {
+            prompts::WRITE_RECIPE => {
+                prompts::write_recipe_prompt(source, justfile, required("goal")?)
+            }
+            _ => {
+                let recipe = Self::find_recipe(justfile, required("name")?)?;
+                prompts::explain_recipe_prompt(source, justfile, recipe)
+            }
+        };
+
+        Ok(GetPromptResult {
+            description: Some(template.description.to_string()),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    /// Completion values for an argument of a tool or prompt. Paths are
+    /// completed for `justfile_path`; everything else comes from the
+    /// default justfile.
+    fn completion_values(&self, reference: &str, argument: &str, value: &str) -> Vec<String> {
+        if argument == "justfile_path" {
+            let working_dir = self.working_dir();
+            return self
+                .resource_paths()
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&working_dir)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string()
+                })
+                .filter(|path| path.starts_with(value))
+                .collect();
+        }
+        match self.load_justfile(None) {
+            Ok((justfile, _)) => completion::complete(&justfile, reference, argument, value),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn find_recipe<'a>(
+        justfile: &'a Justfile,
+        recipe_name: &str,
+    ) -> Result<&'a Recipe, McpServerError> {
+        justfile
+            .recipes
+            .iter()
+            .find(|r| r.name == recipe_name)
+            .ok_or_else(|| McpServerError::RecipeNotFound {
+                recipe_name: recipe_name.to_string(),
+                suggestions: validator::suggest_recipe_names(justfile, recipe_name),
+            })
+    }
+
+    /// Refuse a recipe the recipe policy blocks, directly or through a dependency.
+    fn check_recipe_policy(
+        &self,
+        justfile: &Justfile,
+        recipe_name: &str,
+    ) -> Result<(), McpServerError> {
+        match self.recipe_policy.blocked_recipe(justfile, recipe_name) {
+            Some(blocked) => Err(McpServerError::RecipeBlocked {
+                recipe_name: recipe_name.to_string(),
+                blocked,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The justfile as listings show it: without blocked recipes when the
+    /// policy hides them.
+    fn visible_recipes(&self, mut justfile: Justfile) -> Justfile {
+        if self.recipe_policy.hide_blocked {
+            let blocked: HashSet<String> = justfile
+                .recipes
+                .iter()
+                .filter(|recipe| {
+                    self.recipe_policy
+                        .blocked_recipe(&justfile, &recipe.name)
+                        .is_some()
+                })
+                .map(|recipe| recipe.name.clone())
+                .collect();
+            justfile
+                .recipes
+                .retain(|recipe| !blocked.contains(&recipe.name));
+        }
+        justfile
+    }
+
+    /// Recipe info, marked blocked when the recipe policy refuses it.
+    fn recipe_info(&self, justfile: &Justfile, recipe: &Recipe) -> RecipeInfo {
+        RecipeInfo {
+            blocked: self
+                .recipe_policy
+                .blocked_recipe(justfile, &recipe.name)
+                .is_some(),
+            ..RecipeInfo::from(recipe)
+        }
+    }
+
+    /// Every tool `tools/list` returns: the built-in tools with their output
+    /// schemas, then one tool per runnable recipe.
+    pub fn tools(&self) -> Vec<Tool> {
+        let mut tools = self.tool_router.list_all();
+        tools.retain(|tool| self.tool_enabled(&tool.name));
+        for tool in &mut tools {
+            tool.output_schema = output_schema(&tool.name);
+        }
+        tools.extend(self.recipe_tools());
+        tools
+    }
+
+    fn tool_enabled(&self, name: &str) -> bool {
+        self.recipe_policy.is_unrestricted() || !EDIT_TOOLS.contains(&name)
+    }
+
+    /// One `just_<recipe>` tool per public recipe of the default justfile,
+    /// or `just_<root>__<recipe>` per public recipe of each project root.
+    fn recipe_tools(&self) -> Vec<Tool> {
+        if self.project_roots.is_empty() {
+            return self.justfile_recipe_tools(None, RECIPE_TOOL_PREFIX);
+        }
+        self.project_roots
+            .iter()
+            .flat_map(|root| {
+                let prefix = format!("{RECIPE_TOOL_PREFIX}{}{ROOT_TOOL_SEPARATOR}", root.name);
+                self.justfile_recipe_tools(Some(&root.name), &prefix)
+            })
+            .collect()
+    }
+
+    fn justfile_recipe_tools(&self, justfile_path: Option<&str>, prefix: &str) -> Vec<Tool> {
+        let Ok((justfile, _)) = self.load_justfile(justfile_path) else {
+            return Vec::new();
+        };
+        justfile
+            .recipes
+            .iter()
+            .filter(|recipe| {
+                !recipe.is_private()
+                    && self
+                        .recipe_policy
+                        .blocked_recipe(&justfile, &recipe.name)
+                        .is_none()
+            })
+            .map(|recipe| {
+                let description = recipe
+                    .documentation
+                    .clone()
+                    .unwrap_or_else(|| format!("Run the '{}' recipe", recipe.name));
+                let mut tool = Tool::new(
+                    format!("{prefix}{}", recipe.name),
+                    description,
+                    recipe_input_schema(recipe),
+                );
+                tool.output_schema = Some(cached_schema_for_type::<ExecutionOutput>());
+                tool.annotations = Some(recipe_annotations(recipe));
+                tool
+            })
+            .collect()
+    }
+
+    /// Route a tool call to its recipe tool or built-in tool.
+    async fn dispatch_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        match request.name.strip_prefix(RECIPE_TOOL_PREFIX) {
+            Some(recipe_name) => self.call_recipe_tool(recipe_name, request.arguments).await,
+            None if !self.tool_enabled(&request.name) => Err(McpServerError::EditingDisabled {
+                tool: request.name.to_string(),
+            }
+            .into()),
+            None => {
+                let tcc = ToolCallContext::new(self, request, context);
+                self.tool_router.call(tcc).await
+            }
+        }
+    }
+
+    /// Run a `just_<recipe>` tool: bind named arguments to the recipe's
+    /// parameters, then execute through `run_recipe`.
+    async fn call_recipe_tool(
+        &self,
+        tool_name: &str,
+        arguments: Option<JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        // `<root>__<recipe>` when serving several projects
+        let (justfile_path, recipe_name) = tool_name
+            .split_once(ROOT_TOOL_SEPARATOR)
+            .filter(|(root, _)| {
+                self.project_roots
+                    .iter()
+                    .any(|project| project.name == *root)
+            })
+            .map_or((None, tool_name), |(root, recipe)| {
+                (Some(root.to_string()), recipe)
+            });
+        let (justfile, _) = self.load_justfile(justfile_path.as_deref())?;
+        // Private recipes have no tool, so they fail like any unlisted one
+        let recipe = justfile
+            .recipes
+            .iter()
+            .find(|recipe| recipe.name == recipe_name && !recipe.is_private())
+            .ok_or_else(|| McpError::invalid_params("tool not found", None))?;
+
+        let kwargs: HashMap<String, String> = arguments
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(value) => value,
+                    other => other.to_string(),
+                };
+                (name, value)
+            })
+            .collect();
+
+        let validation = validator::validate_named_arguments(recipe, &[], &kwargs);
+        if !validation.is_valid {
+            let messages: Vec<String> = validation.errors.into_iter().map(|e| e.message).collect();
+            return Err(McpError::invalid_params(messages.join("; "), None));
+        }
+
+        // Positional arguments up to the last parameter given; defaults fill gaps
+        let last_given = recipe
+            .parameters
+            .iter()
+            .rposition(|param| kwargs.contains_key(&param.name));
+        let args: Vec<String> = recipe.parameters[..last_given.map_or(0, |i| i + 1)]
+            .iter()
+            .map(|param| {
+                kwargs
+                    .get(&param.name)
+                    .or(param.default_value.as_ref())
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let params = ExecuteRecipeParams {
+            recipe_name: recipe.name.clone(),
+            args: Some(args),
+            justfile_path,
+            ..Default::default()
+        };
+        self.run_recipe(Parameters(params)).await
+    }
+
+    /// Write an edited justfile unless this is a dry run.
+    fn write_edit(
+        path: &Path,
+        source: &str,
+        edited: &str,
+        dry_run: Option<bool>,
+    ) -> Result<EditOutput, McpServerError> {
+        let write = !dry_run.unwrap_or(false);
+        if write {
+            std::fs::write(path, edited).context(IoSnafu)?;
+        }
+        Ok(EditOutput::new(path, source, edited, write))
+    }
+}
+
+#[tool_router]
+impl JustMcpServer {
+    #[tool(
+        description = "List all available recipes in the justfile; page with cursor and page_size, sort by name or group, or set names_only for just the names",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn list_recipes(
+        &self,
+        Parameters(params): Parameters<ListRecipesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (justfile, path) = self.load_justfile(params.justfile_path.as_deref())?;
+        let justfile = self.visible_recipes(justfile);
+
+        let mut recipes: Vec<&Recipe> = justfile.recipes.iter().collect();
+        match params.sort.unwrap_or_default() {
+            RecipeSort::Source => {}
+            RecipeSort::Name => recipes.sort_by(|a, b| a.name.cmp(&b.name)),
+            RecipeSort::Group => recipes.sort_by_key(|recipe| {
+                // Ungrouped recipes last
+                let group = recipe.groups().first().map(|group| group.to_string());
+                (group.is_none(), group, recipe.name.clone())
+            }),
+        }
+
+        let total_recipes = recipes.len();
+        let mut next_cursor = None;
+        if params.cursor.is_some() || params.page_size.is_some() {
+            let start = match params.cursor.as_deref() {
+                Some(cursor) => cursor
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|start| *start <= total_recipes)
+                    .ok_or_else(|| McpServerError::InvalidCursor {
+                        cursor: cursor.to_string(),
+                    })?,
+                None => 0,
+            };
+            let page_size = params.page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+            let end = (start + page_size).min(total_recipes);
+            if end < total_recipes {
+                next_cursor = Some(end.to_string());
+            }
+            recipes = recipes[start..end].to_vec();
+        }
+
+        let (recipes, names) = if params.names_only.unwrap_or(false) {
+            let names = recipes.iter().map(|recipe| recipe.name.clone()).collect();
+            (Vec::new(), Some(names))
+        } else {
+            let recipes = recipes
+                .into_iter()
+                .map(|recipe| self.recipe_info(&justfile, recipe))
+                .collect();
+            (recipes, None)
+        };
+        let info = JustfileInfo {
+            path: path.display().to_string(),
+            recipes,
+            names,
+            variables: justfile.variables,
+            total_recipes,
+            next_cursor,
+        };
+
+        Ok(structured_result(&info, true)?)
+    }
+
+    #[tool(
+        description = "List the justfiles in the working directory tree, skipping files ignored by .gitignore; pass an id as justfile_path to target one",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn list_justfiles(
+        &self,
+        Parameters(params): Parameters<ListJustfilesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let options = DiscoveryOptions {
+            max_depth: params.max_depth.unwrap_or(roots::MAX_DEPTH),
+            ignore: params.ignore.unwrap_or_default(),
+        };
+        let mut justfiles = Vec::new();
+        for (base, name) in self.workspace_bases() {
+            self.with_workspace_index(&base, &options, |index| {
+                for (path, recipes) in index.justfiles() {
+                    if self.registry.is_in_scope(path) {
+                        justfiles.push(JustfileEntry {
+                            id: workspace_id(&base, name.as_deref(), path),
+                            path: path.display().to_string(),
+                            recipe_count: recipes.map(<[Recipe]>::len),
+                        });
+                    }
+                }
+            });
+        }
+
+        Ok(structured_result(&JustfileList { justfiles }, true)?)
+    }
+
+    #[tool(
+        description = "List the public recipes of every justfile in the working directory tree, each with the id of its justfile; files ignored by .gitignore are skipped",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn list_workspace_recipes(
+        &self,
+        Parameters(params): Parameters<ListWorkspaceRecipesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let options = DiscoveryOptions {
+            max_depth: params.max_depth.unwrap_or(roots::MAX_DEPTH),
+            ignore: params.ignore.unwrap_or_default(),
+        };
+        let mut recipes = Vec::new();
+        for (base, name) in self.workspace_bases() {
+            self.with_workspace_index(&base, &options, |index| {
+                for (path, recipe) in index.recipes() {
+                    let hidden =
+                        self.recipe_policy.hide_blocked && !self.recipe_policy.allows(&recipe.name);
+                    if recipe.is_private() || hidden || !self.registry.is_in_scope(path) {
+                        continue;
+                    }
+                    recipes.push(WorkspaceRecipe {
+                        name: recipe.name.clone(),
+                        justfile_id: workspace_id(&base, name.as_deref(), path),
+                        path: path.display().to_string(),
+                        documentation: recipe.documentation.clone(),
+                        parameters: recipe
+                            .parameters
+                            .iter()
+                            .map(|parameter| parameter.name.clone())
+                            .collect(),
+                        groups: recipe.groups().into_iter().map(String::from).collect(),
+                    });
+                }
+            });
+        }
+
+        Ok(structured_result(&WorkspaceRecipes { recipes }, true)?)
+    }
+
+    #[tool(
+        description = "Execute a specific recipe with optional arguments",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            open_world_hint = true
+        )
+    )]
+    async fn run_recipe(
+        &self,
+        Parameters(mut params): Parameters<ExecuteRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.elicit_parameters {
+            let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+            let recipe = Self::find_recipe(&justfile, &params.recipe_name)?;
+            let mut args = params.args.take().unwrap_or_default();
+            self.elicit_missing_args(recipe, &mut args).await?;
+            params.args = Some(args);
+        }
+        let output = self
+            .execute_recipe(params, |_| {}, CancelToken::new())
+            .await?;
+        execution_result(&output)
+    }
+
+    #[tool(
+        description = "Run several recipes in order as one operation, each with its own args, and return per-step results; stops at the first failure unless stop_on_failure is false",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            open_world_hint = true
+        )
+    )]
+    async fn run_sequence(
+        &self,
+        Parameters(params): Parameters<RunSequenceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Report unknown recipes before running any step
+        let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+        for step in &params.steps {
+            Self::find_recipe(&justfile, &step.recipe_name)?;
+            self.check_recipe_policy(&justfile, &step.recipe_name)?;
+        }
+
+        let stop_on_failure = params.stop_on_failure.unwrap_or(true);
+        let started = Instant::now();
+        let mut stopped = false;
+        let mut steps = Vec::with_capacity(params.steps.len());
+        for step in params.steps {
+            let mut result = SequenceStepResult {
+                recipe_name: step.recipe_name.clone(),
+                status: StepStatus::Skipped,
+                output: None,
+                error: None,
+            };
+            if !stopped {
+                let params = ExecuteRecipeParams {
+                    recipe_name: step.recipe_name,
+                    args: step.args,
+                    justfile_path: params.justfile_path.clone(),
+                    ..Default::default()
+                };
+                match self
+                    .execute_recipe(params, |_| {}, CancelToken::new())
+                    .await
+                {
+                    Ok(output) => {
+                        result.status = if output.success {
+                            StepStatus::Succeeded
+                        } else {
+                            StepStatus::Failed
+                        };
+                        result.output = Some(output);
+                    }
+                    Err(error) => {
+                        result.status = StepStatus::Error;
+                        result.error = Some(error.to_string());
+                    }
+                }
+                stopped = stop_on_failure && result.status != StepStatus::Succeeded;
+            }
+            steps.push(result);
+        }
+
+        let sequence = SequenceOutput {
+            success: steps
+                .iter()
+                .all(|step| step.status == StepStatus::Succeeded),
+            duration_ms: started.elapsed().as_millis() as u64,
+            steps,
+        };
+        Ok(structured_result(&sequence, sequence.success)?)
+    }
+
+    #[tool(
+        description = "Start a recipe in the background and return an execution id immediately; poll it with get_execution_status and get_execution_result",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            open_world_hint = true
+        )
+    )]
+    async fn start_recipe(
+        &self,
+        Parameters(params): Parameters<ExecuteRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Report a bad justfile or recipe name now rather than as a failed job
+        let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+        Self::find_recipe(&justfile, &params.recipe_name)?;
+        self.check_recipe_policy(&justfile, &params.recipe_name)?;
+
+        let job = self.jobs.start(&params.recipe_name);
+        let started = ExecutionStarted {
+            execution_id: job.execution_id(),
+            recipe_name: params.recipe_name.clone(),
+        };
+
+        let server = self.clone();
+        tokio::spawn(async move {
+            let output_job = job.clone();
+            let result = server
+                .execute_recipe(
+                    params,
+                    move |entry| output_job.push_output(entry),
+                    job.cancel_token(),
+                )
+                .await;
+            job.finish(result.map_err(|error| error.to_string()));
+        });
+
+        Ok(structured_result(&started, true)?)
+    }
+
+    #[tool(
+        description = "Get the state, elapsed time and output so far of a recipe started with start_recipe",
+        annotations(read_only_hint = true, open_world_hint = false)
+    )]
+    async fn get_execution_status(
+        &self,
+        Parameters(params): Parameters<ExecutionStatusParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let status = self
+            .jobs
+            .status(&params.execution_id, params.since.unwrap_or(0))
+            .context(JobFailedSnafu)?;
+        Ok(structured_result(&status, true)?)
+    }
+
+    #[tool(
+        description = "Get the last lines, or a byte range, of the output of a recipe started with start_recipe, while it runs or after it finishes",
+        annotations(read_only_hint = true, open_world_hint = false)
+    )]
+    async fn tail_execution_log(
+        &self,
+        Parameters(params): Parameters<TailExecutionLogParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let range = match params.offset {
+            Some(offset) => LogRange::Bytes {
+                offset,
+                length: params.length,
+            },
+            None => LogRange::LastLines(params.lines.unwrap_or(DEFAULT_TAIL_LINES)),
+        };
+        let tail = self
+            .jobs
+            .tail(&params.execution_id, range)
+            .context(JobFailedSnafu)?;
+        Ok(structured_result(&tail, true)?)
+    }
+
+    #[tool(
+        description = "Cancel a recipe started with start_recipe, killing its running command and process group",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn cancel_execution(
+        &self,
+        Parameters(params): Parameters<ExecutionResultParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let status = self
+            .jobs
+            .cancel(&params.execution_id)
+            .context(JobFailedSnafu)?;
+        self.log(
+            LoggingLevel::Info,
+            serde_json::json!({
+                "event": "recipe_cancelled",
+                "recipe": status.recipe_name,
+                "execution_id": status.execution_id,
+            }),
+        );
+        Ok(structured_result(&status, true)?)
+    }
+
+    #[tool(
+        description = "Get the result of a recipe started with start_recipe once it has finished",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn get_execution_result(
+        &self,
+        Parameters(params): Parameters<ExecutionResultParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let output = self
+            .jobs
+            .result(&params.execution_id)
+            .context(JobFailedSnafu)?;
+        execution_result(&output)
+    }
+
+    #[tool(
+        description = "List past recipe executions, newest first, with arguments, timestamps, exit code and duration; filter by recipe and status",
+        annotations(read_only_hint = true, open_world_hint = false)
+    )]
+    async fn execution_history(
+        &self,
+        Parameters(params): Parameters<ExecutionHistoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let executions = self
+            .history
+            .query(&HistoryFilter {
+                recipe_name: params.recipe_name,
+                status: params.status,
+                limit: params.limit,
+            })
+            .context(HistoryFailedSnafu)?;
+        Ok(structured_result(
+            &ExecutionHistoryList { executions },
+            true,
+        )?)
+    }
+
+    #[tool(
+        description = "List the clients connected to this server, with when each connected and how many tool calls it made",
+        annotations(read_only_hint = true, open_world_hint = false)
+    )]
+    async fn list_sessions(&self) -> Result<CallToolResult, McpError> {
+        let sessions = SessionList {
+            current: self.session_id.get().copied(),
+            sessions: self.sessions.list(),
+            served: self.sessions.served(),
+            uptime_ms: self.sessions.uptime_ms(),
+        };
+        Ok(structured_result(&sessions, true)?)
+    }
+
+    #[tool(
+        description = "Show the environment recipes run with: sources, MCP_* variables, requested variables and the justfile's .env file; secret values are masked",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn get_environment(
+        &self,
+        Parameters(params): Parameters<GetEnvironmentParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Without a justfile the rest of the report is still useful
+        let dotenv = match self.load_justfile(params.justfile_path.as_deref()) {
+            Ok((justfile, path)) => {
+                let dir = path.parent().unwrap_or(Path::new("."));
+                Some(environment::dotenv_status(&justfile, dir))
+            }
+            Err(_) if params.justfile_path.is_none() => None,
+            Err(error) => return Err(error.into()),
+        };
+        let mut process = McpEnvironment::with_process_env();
+        let names: Vec<String> = process.variables().keys().cloned().collect();
+        for name in names {
+            if !self.env_policy.inherits(&name) {
+                process.remove(&name);
+            }
+        }
+        let mut env = process.clone();
+        env.merge(self.recipe_environment());
+        let mut report = env.report(&params.names.unwrap_or_default(), dotenv);
+        report.changes = env.diff(&process).masked();
+        Ok(structured_result(&report, true)?)
+    }
+
+    #[tool(
+        description = "Set environment variables for every later recipe run in this session, without changing the server's own environment; a null value removes one",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn set_session_environment(
+        &self,
+        Parameters(params): Parameters<SetSessionEnvironmentParams>,
+    ) -> Result<CallToolResult, McpError> {
+        for (name, value) in &params.variables {
+            let valid = environment::is_valid_name(name)
+                && value.as_ref().is_none_or(|value| !value.contains('\0'));
+            ensure!(valid, InvalidEnvironmentVariableSnafu { name });
+        }
+
+        let mut session = self.session_env.lock().unwrap();
+        if params.clear.unwrap_or(false) {
+            *session = McpEnvironment::new();
+        }
+        for (name, value) in params.variables {
+            match value {
+                Some(value) => session.set(name, value),
+                None => {
+                    session.remove(&name);
+                }
+            }
+        }
+        let variables = session
+            .variables()
+            .iter()
+            .map(|(name, value)| (name.clone(), environment::masked(name, value)))
+            .collect();
+        drop(session);
+        self.invalidate_redactor();
+
+        Ok(structured_result(&SessionEnvironment { variables }, true)?)
+    }
+
+    #[tool(
+        description = "Switch the config profile, such as dev, staging or prod, whose env files and variables later recipe runs in this session get; omit name for none",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn set_profile(
+        &self,
+        Parameters(params): Parameters<SetProfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let profiles = self.profiles.lock().unwrap().clone();
+        if let Some(name) = &params.name {
+            ensure!(
+                profiles.contains_key(name),
+                UnknownProfileSnafu {
+                    name,
+                    known: profiles.keys().cloned().collect::<Vec<_>>().join(", "),
+                }
+            );
+        }
+        *self.session_profile.lock().unwrap() = params.name.clone();
+        self.invalidate_redactor();
+
+        let variables = params
+            .name
+            .as_ref()
+            .and_then(|name| profiles.get(name))
+            .map(|variables| {
+                variables
+                    .iter()
+                    .map(|(name, value)| (name.clone(), environment::masked(name, value)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let profile = ActiveProfile {
+            active: params.name,
+            profiles: profiles.keys().cloned().collect(),
+            variables,
+        };
+        Ok(structured_result(&profile, true)?)
+    }
+
+    #[tool(
+        description = "Switch this session to another project directory inside the server's working directory or the client's roots; later calls use its justfile. Omit path to go back to the default",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
         )
-        .context(ExecutionFailedSnafu)?;
+    )]
+    async fn set_working_directory(
+        &self,
+        Parameters(params): Parameters<SetWorkingDirectoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let allowed_roots = self.allowed_roots();
+        let dir = match params.path.as_deref() {
+            Some(path) => {
+                let dir = self.working_dir().join(path).canonicalize().ok();
+                let dir = dir
+                    .filter(|dir| {
+                        dir.is_dir() && allowed_roots.iter().any(|root| dir.starts_with(root))
+                    })
+                    .context(WorkingDirectoryNotAllowedSnafu { path })?;
+                Some(dir)
+            }
+            None => None,
+        };
+        *self.session_dir.lock().unwrap() = dir;
+        self.invalidate_redactor();
 
-        let output = ExecutionOutput {
-            recipe_name: params.recipe_name,
-            stdout: result.stdout,
-            stderr: result.stderr,
-            exit_code: result.exit_code,
-            duration_ms: result.duration_ms,
-            success: result.exit_code == 0,
+        let dir = self.working_dir();
+        let output = WorkingDirectory {
+            path: dir.display().to_string(),
+            justfile: self
+                .resolve_justfile_path(None)
+                .ok()
+                .map(|path| path.display().to_string()),
+            allowed_roots: allowed_roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect(),
         };
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Preview a recipe without running it: the substituted commands, dependency order, working directory and shell",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn dry_run_recipe(
+        &self,
+        Parameters(params): Parameters<DryRunRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (justfile, path) = self.load_justfile(params.justfile_path.as_deref())?;
+        Self::find_recipe(&justfile, &params.recipe_name)?;
 
-        let content = serde_json::to_string_pretty(&output).context(SerializationSnafu)?;
+        let args = params.args.unwrap_or_default();
+        let dir = self.recipe_dir(&path);
+        let plan = plan_recipe(&justfile, &params.recipe_name, &args, &dir)
+            .context(ExecutionFailedSnafu)?;
 
-        if output.success {
-            Ok(CallToolResult::success(vec![Content::text(content)]))
-        } else {
-            Ok(CallToolResult::error(vec![Content::text(content)]))
-        }
+        Ok(structured_result(&plan, true)?)
+    }
+
+    #[tool(
+        description = "Explain what a recipe will do: its body with interpolations resolved for the given arguments, the dependencies that run first, and the settings in effect",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn explain_recipe(
+        &self,
+        Parameters(params): Parameters<ExplainRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (justfile, path) = self.load_justfile(params.justfile_path.as_deref())?;
+        let recipe = Self::find_recipe(&justfile, &params.recipe_name)?;
+
+        let args = params.args.unwrap_or_default();
+        let expanded =
+            expand_recipe(&justfile, &recipe.name, &args).context(ExecutionFailedSnafu)?;
+        let plan = plan_recipe(&justfile, &recipe.name, &args, &self.recipe_dir(&path))
+            .context(ExecutionFailedSnafu)?;
+
+        let mut dependencies = plan.order;
+        dependencies.pop();
+        let explanation = RecipeExplanation {
+            recipe_name: recipe.name.clone(),
+            documentation: recipe.documentation.clone(),
+            arguments: expanded.arguments,
+            body: expanded.body,
+            dependencies,
+            settings: justfile.settings.clone(),
+        };
+        Ok(structured_result(&explanation, true)?)
     }
 
-    #[tool(description = "Get detailed information about a specific recipe")]
+    #[tool(
+        description = "Get detailed information about a specific recipe",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
     async fn get_recipe_info(
         &self,
         Parameters(params): Parameters<GetRecipeInfoParams>,
     ) -> Result<CallToolResult, McpError> {
         let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+        let justfile = self.visible_recipes(justfile);
 
-        let recipe = justfile
-            .recipes
-            .iter()
-            .find(|r| r.name == params.recipe_name)
-            .ok_or_else(|| McpServerError::RecipeNotFound {
-                recipe_name: params.recipe_name.clone(),
-            })?;
+        let recipe = Self::find_recipe(&justfile, &params.recipe_name)?;
 
-        let info = Self::recipe_to_info(recipe);
-        let content = serde_json::to_string_pretty(&info).context(SerializationSnafu)?;
+        let info = self.recipe_info(&justfile, recipe);
+        Ok(structured_result(&info, true)?)
+    }
 
-        Ok(CallToolResult::success(vec![Content::text(content)]))
+    #[tool(
+        description = "Get a recipe's signature for calling it: required and optional parameters, defaults, types and documentation, plus a formatted summary",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn get_recipe_signature(
+        &self,
+        Parameters(params): Parameters<GetRecipeInfoParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+        let recipe = Self::find_recipe(&justfile, &params.recipe_name)?;
+
+        let help = validator::get_signature_help(recipe);
+        let signature = RecipeSignature {
+            signature: validator::format_signature_help(&help),
+            recipe_name: help.recipe_name,
+            documentation: help.documentation,
+            parameters: help
+                .parameters
+                .into_iter()
+                .map(|param| ParameterInfo {
+                    name: param.name,
+                    default_value: param.default_value,
+                    required: param.required,
+                    param_type: param.param_type.map(|param_type| param_type.to_string()),
+                })
+                .collect(),
+        };
+        Ok(structured_result(&signature, true)?)
+    }
+
+    #[tool(
+        description = "Search recipe names, documentation, and bodies; returns matching recipes and lines",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn search_recipes(
+        &self,
+        Parameters(params): Parameters<SearchRecipesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+        let justfile = self.visible_recipes(justfile);
+        let options = SearchOptions {
+            regex: params.regex.unwrap_or(false),
+            case_sensitive: params.case_sensitive.unwrap_or(false),
+        };
+        let matches =
+            search_recipes(&justfile, &params.query, options).context(SearchFailedSnafu)?;
+
+        let results = SearchResults {
+            query: params.query,
+            matches,
+        };
+        Ok(structured_result(&results, true)?)
     }
 
-    #[tool(description = "Validate the justfile for syntax and semantic errors")]
+    #[tool(
+        description = "Validate the justfile for syntax and semantic errors",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
     async fn validate_justfile(
         &self,
         Parameters(params): Parameters<ValidateJustfileParams>,
     ) -> Result<CallToolResult, McpError> {
+        let path = self.resolve_justfile_path(params.justfile_path.as_deref())?;
+        let content = std::fs::read_to_string(&path).context(IoSnafu)?;
+
+        let result = ValidationReport::check(&path, &content, &self.lint_config);
+        Ok(structured_result(&result, true)?)
+    }
+
+    #[tool(
+        description = "Get the recipe dependency graph as JSON, Graphviz DOT, or a Mermaid flowchart",
+        annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false)
+    )]
+    async fn dependency_graph(
+        &self,
+        Parameters(params): Parameters<DependencyGraphParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (justfile, _) = self.load_justfile(params.justfile_path.as_deref())?;
+        let graph = DependencyGraph::new(&justfile);
+
+        let content = match params.format.unwrap_or_default() {
+            GraphFormat::Json => {
+                serde_json::to_string_pretty(&graph).context(SerializationSnafu)?
+            }
+            GraphFormat::Dot => graph.to_dot(),
+            GraphFormat::Mermaid => graph.to_mermaid(),
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(content)]))
+    }
+
+    #[tool(
+        description = "Append a new recipe to the justfile and return the diff; the result must parse",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn create_recipe(
+        &self,
+        Parameters(params): Parameters<CreateRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = self.resolve_justfile_path(params.justfile_path.as_deref())?;
+        let source = std::fs::read_to_string(&path).context(IoSnafu)?;
+        let recipe = RecipeDefinition {
+            name: params.name,
+            parameters: params.parameters.unwrap_or_default(),
+            documentation: params.documentation,
+            dependencies: params.dependencies.unwrap_or_default(),
+            body: params.body,
+        };
+        let edited = editor::create_recipe(&source, &recipe).context(EditFailedSnafu)?;
+        std::fs::write(&path, &edited).context(IoSnafu)?;
+
+        let output = EditOutput::new(&path, &source, &edited, true);
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Ask the client's model (via sampling) to write a recipe for a goal, using the justfile as context; the result is checked to parse and only written with write: true",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            open_world_hint = true
+        )
+    )]
+    async fn generate_recipe(
+        &self,
+        Parameters(params): Parameters<GenerateRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let peer = self
+            .peer
+            .get()
+            .filter(|peer| {
+                peer.peer_info()
+                    .is_some_and(|info| info.capabilities.sampling.is_some())
+            })
+            .context(SamplingUnsupportedSnafu)?;
         let (justfile, path) = self.load_justfile(params.justfile_path.as_deref())?;
+        let source = std::fs::read_to_string(&path).context(IoSnafu)?;
+
+        let mut prompt = prompts::write_recipe_prompt(&source, &justfile, &params.goal);
+        if let Some(name) = &params.name {
+            prompt.push_str(&format!("\nName the recipe `{name}`.\n"));
+        }
+        let request: CreateMessageRequestParam = serde_json::from_value(serde_json::json!({
+            "messages": [{ "role": "user", "content": { "type": "text", "text": prompt } }],
+            "systemPrompt": "You write recipes for justfiles, the command runner `just`. Reply with only the recipe in a ```just code block.",
+            "maxTokens": 1024,
+        }))
+        .context(SerializationSnafu)?;
+        let sampling_failed = |message: String| McpServerError::SamplingFailed { message };
+        let reply = peer
+            .create_message(request)
+            .await
+            .map_err(|error| sampling_failed(error.to_string()))?;
+        let text = match &reply.message.content.raw {
+            RawContent::Text(text) => text.text.clone(),
+            _ => return Err(sampling_failed("the client replied without text".to_string()).into()),
+        };
+
+        // Rebuild the recipe through the editor so it is checked and
+        // indented like the rest of the justfile
+        let generated = parse_justfile_str(prompts::recipe_from_reply(&text))
+            .ok()
+            .and_then(|generated| generated.recipes.into_iter().next())
+            .ok_or_else(|| {
+                sampling_failed(format!("the reply does not contain a recipe:\n{text}"))
+            })?;
+        let recipe = RecipeDefinition {
+            name: params.name.unwrap_or(generated.name),
+            parameters: generated
+                .parameters
+                .iter()
+                .map(|param| ParameterDefinition {
+                    name: param.name.clone(),
+                    default: param.default_value.clone(),
+                    parameter_type: generated
+                        .parameter_types
+                        .get(&param.name)
+                        .map(|param_type| param_type.to_string()),
+                })
+                .collect(),
+            documentation: generated.documentation,
+            dependencies: generated.dependencies,
+            body: generated.body,
+        };
+        let edited = editor::create_recipe(&source, &recipe).context(EditFailedSnafu)?;
+
+        let dry_run = !params.write.unwrap_or(false);
+        let edit = Self::write_edit(&path, &source, &edited, Some(dry_run))?;
+        let generated = GeneratedRecipe {
+            recipe_name: recipe.name,
+            recipe: edited[source.trim_end().len()..].trim().to_string(),
+            model: reply.model,
+            edit,
+        };
+        Ok(structured_result(&generated, true)?)
+    }
+
+    #[tool(
+        description = "Replace a recipe's parameters, documentation, dependencies, or body in place; dry_run returns the diff without writing",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn update_recipe(
+        &self,
+        Parameters(params): Parameters<UpdateRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = self.resolve_justfile_path(params.justfile_path.as_deref())?;
+        let source = std::fs::read_to_string(&path).context(IoSnafu)?;
+        let update = RecipeUpdate {
+            parameters: params.parameters,
+            documentation: params.documentation,
+            dependencies: params.dependencies,
+            body: params.body,
+        };
+        let edited = editor::update_recipe(&source, &params.recipe_name, &update)
+            .context(EditFailedSnafu)?;
+
+        let output = Self::write_edit(&path, &source, &edited, params.dry_run)?;
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Delete a recipe and its aliases; refuses when other recipes depend on it unless force is set",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn delete_recipe(
+        &self,
+        Parameters(params): Parameters<DeleteRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = self.resolve_justfile_path(params.justfile_path.as_deref())?;
+        let source = std::fs::read_to_string(&path).context(IoSnafu)?;
+        let force = params.force.unwrap_or(false);
+        let edited =
+            editor::delete_recipe(&source, &params.recipe_name, force).context(EditFailedSnafu)?;
+
+        let output = Self::write_edit(&path, &source, &edited, params.dry_run)?;
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Rename a recipe, updating dependencies on it and aliases that point to it",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            open_world_hint = false
+        )
+    )]
+    async fn rename_recipe(
+        &self,
+        Parameters(params): Parameters<RenameRecipeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = self.resolve_justfile_path(params.justfile_path.as_deref())?;
+        let source = std::fs::read_to_string(&path).context(IoSnafu)?;
+        let edited = editor::rename_recipe(&source, &params.recipe_name, &params.new_name)
+            .context(EditFailedSnafu)?;
+
+        let output = Self::write_edit(&path, &source, &edited, params.dry_run)?;
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Set a top-level variable such as `version := \"1.2.0\"`, adding it if missing; the result must parse",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn set_variable(
+        &self,
+        Parameters(params): Parameters<SetVariableParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = self.resolve_justfile_path(params.justfile_path.as_deref())?;
+        let source = std::fs::read_to_string(&path).context(IoSnafu)?;
+        let value = if params.raw.unwrap_or(false) {
+            params.value
+        } else {
+            editor::quote_value(&params.value)
+        };
+        let edited = editor::set_variable(&source, &params.name, &value, params.export)
+            .context(EditFailedSnafu)?;
 
-        // For now, just validate that it parsed correctly
-        // TODO: Add more comprehensive validation using validate_arguments for each recipe
-        let is_valid = true;
-        let message = format!(
-            "Justfile parsed successfully with {} recipes",
-            justfile.recipes.len()
+        let output = Self::write_edit(&path, &source, &edited, params.dry_run)?;
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Format the justfile: canonical indentation, spacing around `:=`, and single-spaced recipe headers; check_only reports what would change",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn format_justfile(
+        &self,
+        Parameters(params): Parameters<FormatJustfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = self.resolve_justfile_path(params.justfile_path.as_deref())?;
+        let source = std::fs::read_to_string(&path).context(IoSnafu)?;
+        let formatted = format_justfile_str(&source).context(ParseFailedSnafu)?;
+
+        // Files already in canonical form are never rewritten
+        let unchanged = formatted == source;
+        let dry_run = params.check_only.unwrap_or(false) || unchanged;
+        let edit = Self::write_edit(&path, &source, &formatted, Some(dry_run))?;
+        let output = FormatOutput {
+            path: edit.path,
+            formatted: unchanged,
+            diff: edit.diff,
+            written: edit.written,
+        };
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Generate Markdown or HTML documentation for the justfile: recipes grouped by [group], usage, parameters, dependencies with a Mermaid diagram, and variables; write saves it to JUSTFILE.md or JUSTFILE.html",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn document_justfile(
+        &self,
+        Parameters(params): Parameters<DocumentJustfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (justfile, path) = self.load_justfile(params.justfile_path.as_deref())?;
+        let format = params.format.unwrap_or_default();
+        let docs = docgen::generate(
+            &justfile,
+            &docgen::default_title(&path),
+            params.include_private.unwrap_or(false),
+            format,
         );
 
-        let result = serde_json::json!({
-            "path": path.display().to_string(),
-            "is_valid": is_valid,
-            "message": message,
-            "recipe_count": justfile.recipes.len(),
-            "variable_count": justfile.variables.len(),
-        });
+        let written_to = if params.write.unwrap_or(false) {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let docs_path = dir.join(format.file_name());
+            std::fs::write(&docs_path, &docs).context(IoSnafu)?;
+            Some(docs_path.display().to_string())
+        } else {
+            None
+        };
+        let output = match format {
+            DocFormat::Markdown => Documentation {
+                markdown: Some(docs),
+                html: None,
+                written_to,
+            },
+            DocFormat::Html => Documentation {
+                markdown: None,
+                html: Some(docs),
+                written_to,
+            },
+        };
+        Ok(structured_result(&output, true)?)
+    }
+
+    #[tool(
+        description = "Convert a Makefile into a justfile draft: variables become assignments, targets recipes and prerequisites dependencies; lists the constructs it could not translate. write saves the draft as justfile next to the Makefile",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn import_makefile(
+        &self,
+        Parameters(params): Parameters<ImportMakefileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let working_dir = self.working_dir();
+        let makefile_path = match params.makefile_path.as_deref() {
+            Some(path) => self.allowed_path(&working_dir.join(path))?,
+            None => makefile::MAKEFILE_NAMES
+                .iter()
+                .map(|name| working_dir.join(name))
+                .find(|path| path.is_file())
+                .ok_or_else(|| McpServerError::MakefileNotFound {
+                    path: working_dir.display().to_string(),
+                })?,
+        };
+        if !makefile_path.is_file() {
+            return Err(McpServerError::MakefileNotFound {
+                path: makefile_path.display().to_string(),
+            }
+            .into());
+        }
+        let source = std::fs::read_to_string(&makefile_path).context(IoSnafu)?;
+        let conversion = convert_makefile(&source);
 
-        let content = serde_json::to_string_pretty(&result).context(SerializationSnafu)?;
+        let written_to = if params.write.unwrap_or(false) {
+            let dir = makefile_path.parent().unwrap_or(Path::new("."));
+            if let Some(existing) = roots::find_justfile(dir) {
+                return Err(McpServerError::JustfileExists {
+                    path: existing.display().to_string(),
+                }
+                .into());
+            }
+            let justfile_path = dir.join("justfile");
+            if !self.registry.is_in_scope(&justfile_path) {
+                return Err(McpServerError::JustfileNotRegistered {
+                    path: justfile_path.display().to_string(),
+                }
+                .into());
+            }
+            std::fs::write(&justfile_path, &conversion.justfile).context(IoSnafu)?;
+            Some(justfile_path.display().to_string())
+        } else {
+            None
+        };
+        Ok(structured_result(
+            &ImportedMakefile {
+                justfile: conversion.justfile,
+                untranslated: conversion.untranslated,
+                written_to,
+            },
+            true,
+        )?)
+    }
 
-        Ok(CallToolResult::success(vec![Content::text(content)]))
+    #[tool(
+        description = "Create a starter justfile with build, test, lint and run recipes for the project type detected from Cargo.toml, package.json, pyproject.toml or go.mod; refuses to replace an existing justfile unless force is set",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            idempotent_hint = true,
+            open_world_hint = false
+        )
+    )]
+    async fn init_justfile(
+        &self,
+        Parameters(params): Parameters<InitJustfileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let working_dir = self.working_dir();
+        let dir = match params.directory.as_deref() {
+            Some(directory) => self.allowed_path(&working_dir.join(directory))?,
+            None => working_dir,
+        };
+        let existing = roots::find_justfile(&dir);
+        if let Some(existing) = &existing
+            && !params.force.unwrap_or(false)
+        {
+            return Err(McpServerError::JustfileExists {
+                path: existing.display().to_string(),
+            }
+            .into());
+        }
+        let path = existing.clone().unwrap_or_else(|| dir.join("justfile"));
+        if !self.registry.is_in_scope(&path) {
+            return Err(McpServerError::JustfileNotRegistered {
+                path: path.display().to_string(),
+            }
+            .into());
+        }
+
+        let detected = scaffold::detect_project_types(&dir);
+        let project_type = params.project_type.or(detected.first().copied());
+        let justfile = scaffold_justfile(project_type, &dir);
+        std::fs::write(&path, &justfile).context(IoSnafu)?;
+        Ok(structured_result(
+            &ScaffoldedJustfile {
+                path: path.display().to_string(),
+                project_type,
+                detected,
+                justfile,
+                overwritten: existing.is_some(),
+            },
+            true,
+        )?)
+    }
+}
+
+/// Id of a justfile found below `base`; ids of a project root's justfiles
+/// start with its `name`.
+fn workspace_id(base: &Path, name: Option<&str>, justfile: &Path) -> String {
+    match (roots::justfile_id(base, justfile).as_str(), name) {
+        (".", Some(name)) => name.to_string(),
+        (id, Some(name)) if !id.starts_with('/') => format!("{name}/{id}"),
+        (id, _) => id.to_string(),
+    }
+}
+
+/// Schema of the `structuredContent` returned by a built-in tool. Tools
+/// whose output is not JSON, such as `dependency_graph`, have none.
+fn output_schema(tool_name: &str) -> Option<Arc<JsonObject>> {
+    match tool_name {
+        "list_recipes" => Some(cached_schema_for_type::<JustfileInfo>()),
+        "list_justfiles" => Some(cached_schema_for_type::<JustfileList>()),
+        "list_workspace_recipes" => Some(cached_schema_for_type::<WorkspaceRecipes>()),
+        "start_recipe" => Some(cached_schema_for_type::<ExecutionStarted>()),
+        "get_execution_status" => Some(cached_schema_for_type::<JobStatus>()),
+        "get_execution_result" => Some(cached_schema_for_type::<ExecutionOutput>()),
+        "cancel_execution" => Some(cached_schema_for_type::<JobStatus>()),
+        "tail_execution_log" => Some(cached_schema_for_type::<LogTail>()),
+        "execution_history" => Some(cached_schema_for_type::<ExecutionHistoryList>()),
+        "list_sessions" => Some(cached_schema_for_type::<SessionList>()),
+        "get_environment" => Some(cached_schema_for_type::<EnvironmentReport>()),
+        "set_session_environment" => Some(cached_schema_for_type::<SessionEnvironment>()),
+        "set_profile" => Some(cached_schema_for_type::<ActiveProfile>()),
+        "run_recipe" => Some(cached_schema_for_type::<ExecutionOutput>()),
+        "run_sequence" => Some(cached_schema_for_type::<SequenceOutput>()),
+        "dry_run_recipe" => Some(cached_schema_for_type::<ExecutionPlan>()),
+        "explain_recipe" => Some(cached_schema_for_type::<RecipeExplanation>()),
+        "get_recipe_info" => Some(cached_schema_for_type::<RecipeInfo>()),
+        "get_recipe_signature" => Some(cached_schema_for_type::<RecipeSignature>()),
+        "search_recipes" => Some(cached_schema_for_type::<SearchResults>()),
+        "validate_justfile" => Some(cached_schema_for_type::<ValidationReport>()),
+        "create_recipe" | "update_recipe" | "delete_recipe" | "rename_recipe" | "set_variable" => {
+            Some(cached_schema_for_type::<EditOutput>())
+        }
+        "format_justfile" => Some(cached_schema_for_type::<FormatOutput>()),
+        "generate_recipe" => Some(cached_schema_for_type::<GeneratedRecipe>()),
+        "document_justfile" => Some(cached_schema_for_type::<Documentation>()),
+        "import_makefile" => Some(cached_schema_for_type::<ImportedMakefile>()),
+        "init_justfile" => Some(cached_schema_for_type::<ScaffoldedJustfile>()),
+        "set_working_directory" => Some(cached_schema_for_type::<WorkingDirectory>()),
+        _ => None,
+    }
+}
+
+/// Tool result carrying `value` as `structuredContent`, with the same JSON
+/// pretty-printed in a text block for clients without structured output.
+fn structured_result<T: Serialize>(
+    value: &T,
+    success: bool,
+) -> Result<CallToolResult, McpServerError> {
+    let json = serde_json::to_value(value).context(SerializationSnafu)?;
+    let text = serde_json::to_string_pretty(&json).context(SerializationSnafu)?;
+    let mut result = if success {
+        CallToolResult::structured(json)
+    } else {
+        CallToolResult::structured_error(json)
+    };
+    result.content = vec![Content::text(text)];
+    Ok(result)
+}
+
+/// Mask secrets in a tool result or error before it leaves the server.
+fn redact_result(
+    redactor: &Redactor,
+    result: Result<CallToolResult, McpError>,
+) -> Result<CallToolResult, McpError> {
+    match result {
+        Ok(mut result) => {
+            for content in &mut result.content {
+                if let RawContent::Text(content) = &mut content.raw {
+                    content.text = redactor.redact(&content.text).into_owned();
+                }
+            }
+            if let Some(structured) = &mut result.structured_content {
+                redactor.redact_json(structured);
+            }
+            Ok(result)
+        }
+        Err(mut error) => {
+            error.message = redactor.redact(&error.message).into_owned().into();
+            if let Some(data) = &mut error.data {
+                redactor.redact_json(data);
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Mask the secrets in a tool result, then cut it to `max_message_size`.
+/// Cutting first could leave the start of a secret that no longer matches.
+fn finish_result(
+    redactor: &Redactor,
+    result: Result<CallToolResult, McpError>,
+    max_message_size: Option<usize>,
+) -> Result<CallToolResult, McpError> {
+    match (redact_result(redactor, result), max_message_size) {
+        (Ok(result), Some(max)) => fit_result(result, max).map_err(McpError::from),
+        (result, _) => result,
+    }
+}
+
+/// Cut the longest strings of `result` until it serializes to at most
+/// `max_bytes`, keeping its text content in step with its structured
+/// content.
+fn fit_result(
+    mut result: CallToolResult,
+    max_bytes: usize,
+) -> Result<CallToolResult, McpServerError> {
+    let size = |result: &CallToolResult| serde_json::to_string(result).map_or(0, |json| json.len());
+    let original = size(&result);
+    if original <= max_bytes {
+        return Ok(result);
+    }
+    let too_large = MessageTooLargeSnafu {
+        size: original,
+        max: max_bytes,
+    };
+    let Some(mut structured) = result.structured_content.take() else {
+        let mut value = serde_json::to_value(&result).context(SerializationSnafu)?;
+        ensure!(settings::fit_to_size(&mut value, max_bytes), too_large);
+        return serde_json::from_value(value).context(SerializationSnafu);
+    };
+    // The text content repeats the structured content, pretty-printed
+    let mut budget = max_bytes / 2;
+    loop {
+        ensure!(settings::fit_to_size(&mut structured, budget), too_large);
+        let text = serde_json::to_string_pretty(&structured).context(SerializationSnafu)?;
+        for content in &mut result.content {
+            if let RawContent::Text(content) = &mut content.raw {
+                content.text = text.clone();
+            }
+        }
+        result.structured_content = Some(structured.clone());
+        let fitted = size(&result);
+        if fitted <= max_bytes {
+            return Ok(result);
+        }
+        budget = budget.saturating_sub(fitted - max_bytes);
+    }
+}
+
+/// Result of a recipe run, with a resource link for each artifact.
+fn execution_result(output: &ExecutionOutput) -> Result<CallToolResult, McpError> {
+    let mut result = structured_result(output, output.success)?;
+    for artifact in &output.artifacts {
+        let mut link = RawResource::new(artifact.uri.clone(), artifact.path.clone());
+        link.mime_type = Some(artifact.mime_type.clone());
+        link.size = u32::try_from(artifact.size).ok();
+        result.content.push(Content::resource_link(link));
+    }
+    Ok(result)
+}
+
+/// URI scheme of justfile resources, e.g. `justfile:///project/justfile`.
+pub const JUSTFILE_URI_SCHEME: &str = "justfile://";
+
+const JUSTFILE_MIME_TYPE: &str = "text/x-justfile";
+
+pub fn justfile_uri(path: &Path) -> String {
+    format!("{JUSTFILE_URI_SCHEME}{}", path.display())
+}
+
+fn text_contents(uri: &str, text: String, mime: &str) -> ResourceContents {
+    let mut contents = ResourceContents::text(text, uri);
+    if let ResourceContents::TextResourceContents { mime_type, .. } = &mut contents {
+        *mime_type = Some(mime.to_string());
+    }
+    contents
+}
+
+fn blob_contents(uri: &str, blob: String, mime: &str) -> ResourceContents {
+    // Built from JSON since the variant's fields differ between rmcp versions
+    serde_json::from_value(serde_json::json!({
+        "uri": uri,
+        "mimeType": mime,
+        "blob": blob,
+    }))
+    .expect("blob resource contents are valid")
+}
+
+/// Recipes per `list_recipes` page when only `cursor` is given.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Lines returned by `tail_execution_log` when neither `lines` nor `offset` is given.
+const DEFAULT_TAIL_LINES: usize = 50;
+
+/// Prefix of the dynamically registered per-recipe tools, e.g. `just_deploy`.
+pub const RECIPE_TOOL_PREFIX: &str = "just_";
+
+/// Separates the project root from the recipe in `just_<root>__<recipe>`.
+pub const ROOT_TOOL_SEPARATOR: &str = "__";
+
+/// Tools that change recipes or variables. They are turned off while a
+/// recipe policy is set, since an edit could get around it: renaming a
+/// denied recipe, or giving an allowed one the body of a denied one.
+pub const EDIT_TOOLS: &[&str] = &[
+    "create_recipe",
+    "generate_recipe",
+    "update_recipe",
+    "rename_recipe",
+    "delete_recipe",
+    "set_variable",
+    "import_makefile",
+];
+
+/// Hints for a `just_<recipe>` tool. A recipe is destructive when it asks
+/// for confirmation (`[confirm]`) or a command matches a dangerous pattern
+/// such as `sudo` or `rm -rf` on an absolute path.
+pub fn recipe_annotations(recipe: &Recipe) -> ToolAnnotations {
+    let destructive = recipe.requires_confirmation()
+        || recipe
+            .body_lines()
+            .any(|(_, line)| dangerous_command(line).is_some());
+    let mut annotations = ToolAnnotations::new();
+    annotations.read_only_hint = Some(false);
+    annotations.destructive_hint = Some(destructive);
+    annotations.idempotent_hint = Some(false);
+    annotations.open_world_hint = Some(true);
+    annotations
+}
+
+/// JSON schema for a recipe's parameters: declared types, defaults, and
+/// `required` for parameters without a default.
+pub fn recipe_input_schema(recipe: &Recipe) -> JsonObject {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    for param in &recipe.parameters {
+        let param_type = recipe.parameter_types.get(&param.name);
+        let mut schema = param_type
+            .map(|param_type| param_type.json_schema())
+            .unwrap_or_else(|| serde_json::json!({ "type": "string" }));
+        match &param.default_value {
+            Some(default) => {
+                schema["default"] = param_type.map_or_else(
+                    || serde_json::Value::String(default.clone()),
+                    |param_type| param_type.json_value(default),
+                );
+            }
+            None => required.push(serde_json::Value::String(param.name.clone())),
+        }
+        properties.insert(param.name.clone(), schema);
     }
+
+    let mut schema = JsonObject::new();
+    schema.insert("type".to_string(), "object".into());
+    schema.insert("properties".to_string(), properties.into());
+    schema.insert("required".to_string(), required.into());
+    schema
 }
 
-#[tool_handler]
 impl ServerHandler for JustMcpServer {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = %request.name,
+            otel.status_message = tracing::field::Empty
+        );
+        async move {
+            let tool = request.name.to_string();
+            let started = Instant::now();
+            if let Some(id) = self.session_id.get() {
+                self.sessions.record_tool_call(*id);
+            }
+            let result = match self.limiter.check_tool_call() {
+                _ if self.shutdown.is_draining() => Err(McpServerError::ShuttingDown.into()),
+                Err(source) => Err(McpServerError::LimitExceeded { source }.into()),
+                Ok(()) => match self.tool_timeouts.get(&tool) {
+                    // Dropping the call on timeout cancels its recipes
+                    Some(timeout) => {
+                        tokio::time::timeout(timeout, self.dispatch_tool(request, context))
+                            .await
+                            .unwrap_or_else(|_| {
+                                Err(McpServerError::ToolTimedOut {
+                                    tool: tool.clone(),
+                                    seconds: timeout.as_secs(),
+                                }
+                                .into())
+                            })
+                    }
+                    None => self.dispatch_tool(request, context).await,
+                },
+            };
+            let result = finish_result(&self.redactor(), result, self.max_message_size);
+            let elapsed = started.elapsed();
+            let success = result
+                .as_ref()
+                .is_ok_and(|result| result.is_error != Some(true));
+            self.metrics.record_tool_call(&tool, success, elapsed);
+            let elapsed_ms = elapsed.as_millis() as u64;
+            match &result {
+                Ok(result) => {
+                    tracing::debug!(elapsed_ms, is_error = result.is_error, "tool call finished")
+                }
+                Err(error) => {
+                    // Marks the exported span failed
+                    tracing::Span::current().record("otel.status_message", &*error.message);
+                    tracing::warn!(elapsed_ms, error = %error.message, "tool call failed")
+                }
+            }
+            result
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tools()))
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut resources: Vec<Resource> = self
+            .resource_paths()
+            .iter()
+            .map(|path| self.justfile_resource(path))
+            .collect();
+        let working_dir = self.working_dir();
+        for path in self.artifacts.lock().unwrap().values() {
+            if let Ok(metadata) = std::fs::metadata(path) {
+                let artifact = Self::artifact(&working_dir, path, metadata.len());
+                resources.push(Self::artifact_resource(&artifact));
+            }
+        }
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let _span = tracing::info_span!("read_resource", uri = %request.uri).entered();
+        let artifact = self.artifacts.lock().unwrap().get(&request.uri).cloned();
+        match artifact {
+            Some(path) => Self::read_artifact_resource(&request.uri, &path),
+            None => self.read_justfile_resource(&request.uri),
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let known = self
+            .resource_paths()
+            .iter()
+            .any(|path| justfile_uri(path) == request.uri);
+        if !known {
+            return Err(McpError::resource_not_found(
+                format!("Unknown resource: {}", request.uri),
+                None,
+            ));
+        }
+        self.subscriptions.lock().unwrap().insert(request.uri);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.subscriptions.lock().unwrap().remove(&request.uri);
+        Ok(())
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.logger.set_level(request.level);
+        Ok(())
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.logger.attach(context.peer.clone());
+        let _ = self.peer.set(context.peer.clone());
+        let has_roots = context
+            .peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        if has_roots {
+            // Requests to the client must not block the notification handler
+            let server = self.clone();
+            let peer = context.peer.clone();
+            tokio::spawn(async move {
+                server.refresh_roots(&peer).await;
+                let _ = peer.notify_tool_list_changed().await;
+                let _ = peer.notify_resource_list_changed().await;
+            });
+        }
+        if let Some(interval) = self.watch_interval {
+            self.spawn_watcher(context.peer, interval);
+        }
+    }
+
+    async fn on_roots_list_changed(&self, context: NotificationContext<RoleServer>) {
+        let server = self.clone();
+        tokio::spawn(async move {
+            server.refresh_roots(&context.peer).await;
+            let _ = context.peer.notify_tool_list_changed().await;
+            let _ = context.peer.notify_resource_list_changed().await;
+        });
+    }
+
+    async fn complete(
+        &self,
+        request: CompleteRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        let reference = match &request.r#ref {
+            Reference::Prompt(prompt) => prompt.name.as_str(),
+            Reference::Resource(resource) => resource.uri.as_str(),
+        };
+        let mut values =
+            self.completion_values(reference, &request.argument.name, &request.argument.value);
+        let total = values.len();
+        values.truncate(completion::MAX_COMPLETIONS);
+
+        Ok(CompleteResult {
+            completion: CompletionInfo {
+                values,
+                total: Some(total as u32),
+                has_more: Some(total > completion::MAX_COMPLETIONS),
+            },
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        let prompts = prompts::PROMPTS
+            .iter()
+            .map(|template| {
+                let arguments = template
+                    .arguments
+                    .iter()
+                    .map(|&(name, description, required)| PromptArgument {
+                        name: name.to_string(),
+                        title: None,
+                        description: Some(description.to_string()),
+                        required: Some(required),
+                    })
+                    .collect();
+                Prompt::new(template.name, Some(template.description), Some(arguments))
+            })
+            .collect();
+        Ok(ListPromptsResult::with_all_items(prompts))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let _span = tracing::info_span!("get_prompt", prompt = %request.name).entered();
+        self.render_prompt(&request.name, request.arguments)
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
             server_info: Implementation::from_build_env(),
-            instructions: Some("MCP server for Justfile integration. Provides tools to list, execute, inspect, and validate Justfile recipes.".into()),
+            instructions: Some("MCP server for Justfile integration. Provides tools to list, execute, inspect, and validate Justfile recipes, publishes each justfile as a justfile:// resource, and offers prompts for writing and explaining recipes.".into()),
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
+                .enable_tool_list_changed()
+                .enable_resources()
+                .enable_resources_list_changed()
+                .enable_resources_subscribe()
+                .enable_prompts()
+                .enable_logging()
+                .enable_completions()
                 .build(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_result_masks_secrets_before_cutting() {
+        let secret = "s3cret-deploy-key-0123456789";
+        let redactor = Redactor::new(&[]).with_secrets([secret]);
+        let text = format!("{}{secret}{}", "x".repeat(100), "y".repeat(100));
+        // Wherever the limit falls, no part of the secret is left
+        for max in 100..400 {
+            let result = Ok(CallToolResult::success(vec![Content::text(text.clone())]));
+            if let Ok(result) = finish_result(&redactor, result, Some(max)) {
+                let json = serde_json::to_string(&result).unwrap();
+                assert!(json.len() <= max);
+                assert!(!json.contains(&secret[..6]), "{json}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_redactor_is_kept_until_invalidated() {
+        let dir = tempfile::tempdir().unwrap();
+        let env_file = dir.path().join(".env");
+        std::fs::write(&env_file, "DEPLOY_TOKEN=tok-first-1234\n").unwrap();
+        let server = JustMcpServer::new(dir.path()).with_dotenv_stack(true);
+        assert_eq!(server.redactor().redact("tok-first-1234"), "****");
+
+        // Not read again until the watcher reports the change
+        std::fs::write(&env_file, "DEPLOY_TOKEN=tok-second-5678\n").unwrap();
+        assert_eq!(
+            server.redactor().redact("tok-second-5678"),
+            "tok-second-5678"
+        );
+        server.invalidate_redactor();
+        assert_eq!(server.redactor().redact("tok-second-5678"), "****");
+
+        // Other sessions see the invalidation too
+        let session = server.new_session();
+        session.redactor();
+        std::fs::write(&env_file, "DEPLOY_TOKEN=tok-third-9012\n").unwrap();
+        server.invalidate_redactor();
+        assert_eq!(session.redactor().redact("tok-third-9012"), "****");
+    }
+}
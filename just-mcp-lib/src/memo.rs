@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::Justfile;
+use crate::executor::{ExecutionOptions, ExecutionResult};
+
+/// Identity of a recipe execution for memoization purposes.
+///
+/// Two executions share a key only when the recipe, its arguments, the
+/// justfile's variables, settings and recipe definitions (parameters and
+/// their defaults included), the execution options, and the contents of
+/// every declared input file are identical.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub justfile: PathBuf,
+    pub recipe_name: String,
+    pub args: Vec<String>,
+    pub variables: Vec<(String, String)>,
+    /// `set` lines such as `shell`, `dotenv-load` or `positional-arguments`
+    pub settings: Vec<(String, String)>,
+    pub definition_hash: u64,
+    pub options: ExecutionOptions,
+    /// Content hash per input file; `None` when the file could not be read.
+    pub input_hashes: Vec<(PathBuf, Option<u64>)>,
+}
+
+impl CacheKey {
+    pub fn new(
+        justfile_path: &Path,
+        justfile: &Justfile,
+        recipe_name: &str,
+        args: &[String],
+        inputs: &[PathBuf],
+        options: &ExecutionOptions,
+    ) -> Self {
+        let mut variables: Vec<(String, String)> = justfile
+            .variables
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        variables.sort();
+        let mut settings: Vec<(String, String)> = justfile
+            .settings
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        settings.sort();
+
+        // Any edit to a recipe definition must invalidate cached results
+        let mut hasher = DefaultHasher::new();
+        for recipe in &justfile.recipes {
+            recipe.name.hash(&mut hasher);
+            recipe.body.hash(&mut hasher);
+            recipe.dependencies.hash(&mut hasher);
+            recipe.quiet.hash(&mut hasher);
+            recipe.attributes.hash(&mut hasher);
+            for param in &recipe.parameters {
+                param.name.hash(&mut hasher);
+                param.default_value.hash(&mut hasher);
+            }
+        }
+        let mut exported: Vec<&String> = justfile.exported.iter().collect();
+        exported.sort();
+        exported.hash(&mut hasher);
+
+        let input_hashes = inputs
+            .iter()
+            .map(|path| (path.clone(), hash_file(path)))
+            .collect();
+
+        CacheKey {
+            justfile: justfile_path.to_path_buf(),
+            recipe_name: recipe_name.to_string(),
+            args: args.to_vec(),
+            variables,
+            settings,
+            definition_hash: hasher.finish(),
+            options: options.clone(),
+            input_hashes,
+        }
+    }
+}
+
+fn hash_file(path: &Path) -> Option<u64> {
+    let bytes = std::fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    stored_at: Instant,
+    result: ExecutionResult,
+}
+
+/// In-memory cache of successful recipe executions with a fixed TTL.
+#[derive(Debug, Clone)]
+pub struct ExecutionCache {
+    ttl: Duration,
+    entries: HashMap<CacheKey, CacheEntry>,
+}
+
+impl ExecutionCache {
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Look up a cached result, evicting it if the TTL has elapsed.
+    pub fn get(&mut self, key: &CacheKey) -> Option<ExecutionResult> {
+        let expired = match self.entries.get(key) {
+            Some(entry) if entry.stored_at.elapsed() < self.ttl => {
+                return Some(entry.result.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            self.entries.remove(key);
+        }
+        None
+    }
+
+    /// Store a result. Failed executions (non-zero exit code) are never cached.
+    pub fn insert(&mut self, key: CacheKey, result: &ExecutionResult) {
+        if result.exit_code != 0 {
+            return;
+        }
+        self.entries.insert(
+            key,
+            CacheEntry {
+                stored_at: Instant::now(),
+                result: result.clone(),
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drop every result of recipes from `justfile`, e.g. after it was
+    /// edited. Returns how many were dropped.
+    pub fn invalidate_justfile(&mut self, justfile: &Path) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|key, _| key.justfile != justfile);
+        before - self.entries.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for ExecutionCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+    use tempfile::tempdir;
+
+    fn sample_result(exit_code: i32) -> ExecutionResult {
+        ExecutionResult {
+            stdout: "built".to_string(),
+            stderr: String::new(),
+            exit_code,
+            duration_ms: 5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_for_identical_inputs() {
+        let options = ExecutionOptions::default();
+        let justfile = parse_justfile_str("build:\n    cargo build\n").unwrap();
+        let path = Path::new("justfile");
+        let mut cache = ExecutionCache::default();
+
+        let key = CacheKey::new(path, &justfile, "build", &[], &[], &options);
+        cache.insert(key.clone(), &sample_result(0));
+
+        let same = CacheKey::new(path, &justfile, "build", &[], &[], &options);
+        assert_eq!(cache.get(&same), Some(sample_result(0)));
+
+        let other_args = CacheKey::new(path, &justfile, "build", &["x".to_string()], &[], &options);
+        assert_eq!(cache.get(&other_args), None);
+    }
+
+    #[test]
+    fn test_failed_results_are_not_cached() {
+        let options = ExecutionOptions::default();
+        let justfile = parse_justfile_str("build:\n    false\n").unwrap();
+        let mut cache = ExecutionCache::default();
+
+        let key = CacheKey::new(
+            Path::new("justfile"),
+            &justfile,
+            "build",
+            &[],
+            &[],
+            &options,
+        );
+        cache.insert(key.clone(), &sample_result(1));
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&key), None);
+    }
+
+    #[test]
+    fn test_expired_entries_are_evicted() {
+        let options = ExecutionOptions::default();
+        let justfile = parse_justfile_str("build:\n    cargo build\n").unwrap();
+        let mut cache = ExecutionCache::new(Duration::ZERO);
+
+        let key = CacheKey::new(
+            Path::new("justfile"),
+            &justfile,
+            "build",
+            &[],
+            &[],
+            &options,
+        );
+        cache.insert(key.clone(), &sample_result(0));
+        assert_eq!(cache.get(&key), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_invalidate_justfile() {
+        let options = ExecutionOptions::default();
+        let justfile = parse_justfile_str("build:\n    cargo build\n").unwrap();
+        let mut cache = ExecutionCache::default();
+        for path in ["api/justfile", "web/justfile"] {
+            let key = CacheKey::new(Path::new(path), &justfile, "build", &[], &[], &options);
+            cache.insert(key, &sample_result(0));
+        }
+
+        assert_eq!(cache.invalidate_justfile(Path::new("api/justfile")), 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.invalidate_justfile(Path::new("api/justfile")), 0);
+    }
+
+    #[test]
+    fn test_input_file_change_invalidates_key() {
+        let options = ExecutionOptions::default();
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("src.txt");
+        std::fs::write(&input, "v1").unwrap();
+
+        let justfile = parse_justfile_str("build:\n    cat src.txt\n").unwrap();
+        let path = Path::new("justfile");
+        let before = CacheKey::new(
+            path,
+            &justfile,
+            "build",
+            &[],
+            std::slice::from_ref(&input),
+            &options,
+        );
+
+        std::fs::write(&input, "v2").unwrap();
+        let after = CacheKey::new(path, &justfile, "build", &[], &[input], &options);
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_parameter_defaults_and_settings_change_key() {
+        let options = ExecutionOptions::default();
+        let path = Path::new("justfile");
+        let key = |source: &str| {
+            let justfile = parse_justfile_str(source).unwrap();
+            CacheKey::new(path, &justfile, "serve", &[], &[], &options)
+        };
+
+        let base = key("serve port=\"8080\":\n    ./serve {{ port }}\n");
+        assert_eq!(base, key("serve port=\"8080\":\n    ./serve {{ port }}\n"));
+        assert_ne!(base, key("serve port=\"9090\":\n    ./serve {{ port }}\n"));
+        assert_ne!(base, key("serve bind=\"8080\":\n    ./serve {{ port }}\n"));
+        assert_ne!(
+            base,
+            key("set shell := [\"zsh\", \"-c\"]\n\nserve port=\"8080\":\n    ./serve {{ port }}\n")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::history::{ExecutionRecord, ExecutionStatus};
+
+/// Upper bounds, in seconds, of the duration histogram buckets.
+const DURATION_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 30.0, 60.0, 300.0,
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(index) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[index] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    /// By tool and outcome
+    tool_calls: BTreeMap<(String, &'static str), u64>,
+    tool_durations: BTreeMap<String, Histogram>,
+    /// By recipe and status
+    executions: BTreeMap<(String, &'static str), u64>,
+    /// By recipe; results reused from the cache are left out
+    execution_durations: BTreeMap<String, Histogram>,
+    /// By result: hit or miss
+    cache_lookups: BTreeMap<&'static str, u64>,
+}
+
+/// Counters and histograms shared by every session, rendered in the
+/// Prometheus text format for `/metrics`.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+}
+
+impl Metrics {
+    pub fn record_tool_call(&self, tool: &str, success: bool, duration: Duration) {
+        let outcome = if success { "success" } else { "error" };
+        let mut counters = self.counters.lock().unwrap();
+        *counters
+            .tool_calls
+            .entry((tool.to_string(), outcome))
+            .or_default() += 1;
+        counters
+            .tool_durations
+            .entry(tool.to_string())
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn record_execution(&self, record: &ExecutionRecord) {
+        let status = match record.status {
+            ExecutionStatus::Succeeded => "succeeded",
+            ExecutionStatus::Failed => "failed",
+            ExecutionStatus::Cancelled => "cancelled",
+            ExecutionStatus::Error => "error",
+        };
+        let mut counters = self.counters.lock().unwrap();
+        *counters
+            .executions
+            .entry((record.recipe_name.clone(), status))
+            .or_default() += 1;
+        if !record.cached {
+            counters
+                .execution_durations
+                .entry(record.recipe_name.clone())
+                .or_default()
+                .observe(record.duration_ms as f64 / 1000.0);
+        }
+    }
+
+    /// Count a lookup in the execution result cache.
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        *self
+            .counters
+            .lock()
+            .unwrap()
+            .cache_lookups
+            .entry(result)
+            .or_default() += 1;
+    }
+
+    /// Everything recorded so far, in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let counters = self.counters.lock().unwrap();
+        let mut text = String::new();
+        header(
+            &mut text,
+            "just_mcp_tool_calls_total",
+            "counter",
+            "MCP tool calls by tool and outcome.",
+        );
+        for ((tool, outcome), count) in &counters.tool_calls {
+            let labels = format!("tool=\"{}\",outcome=\"{outcome}\"", escape(tool));
+            let _ = writeln!(text, "just_mcp_tool_calls_total{{{labels}}} {count}");
+        }
+        header(
+            &mut text,
+            "just_mcp_tool_call_duration_seconds",
+            "histogram",
+            "Time to answer MCP tool calls.",
+        );
+        for (tool, histogram) in &counters.tool_durations {
+            let labels = format!("tool=\"{}\"", escape(tool));
+            write_histogram(
+                &mut text,
+                "just_mcp_tool_call_duration_seconds",
+                &labels,
+                histogram,
+            );
+        }
+        header(
+            &mut text,
+            "just_mcp_recipe_executions_total",
+            "counter",
+            "Recipe executions by recipe and status: succeeded, failed, cancelled or error.",
+        );
+        for ((recipe, status), count) in &counters.executions {
+            let labels = format!("recipe=\"{}\",status=\"{status}\"", escape(recipe));
+            let _ = writeln!(text, "just_mcp_recipe_executions_total{{{labels}}} {count}");
+        }
+        header(
+            &mut text,
+            "just_mcp_recipe_duration_seconds",
+            "histogram",
+            "Time recipes took to run, leaving out results reused from the cache.",
+        );
+        for (recipe, histogram) in &counters.execution_durations {
+            let labels = format!("recipe=\"{}\"", escape(recipe));
+            write_histogram(
+                &mut text,
+                "just_mcp_recipe_duration_seconds",
+                &labels,
+                histogram,
+            );
+        }
+        header(
+            &mut text,
+            "just_mcp_cache_lookups_total",
+            "counter",
+            "Lookups in the execution result cache by result: hit or miss.",
+        );
+        for (result, count) in &counters.cache_lookups {
+            let _ = writeln!(
+                text,
+                "just_mcp_cache_lookups_total{{result=\"{result}\"}} {count}"
+            );
+        }
+        text
+    }
+}
+
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {name} {help}");
+    let _ = writeln!(text, "# TYPE {name} {kind}");
+}
+
+fn write_histogram(text: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+    let mut cumulative = 0;
+    for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+        cumulative += count;
+        let _ = writeln!(
+            text,
+            "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}"
+        );
+    }
+    let _ = writeln!(
+        text,
+        "{name}_bucket{{{labels},le=\"+Inf\"}} {}",
+        histogram.count
+    );
+    let _ = writeln!(text, "{name}_sum{{{labels}}} {}", histogram.sum);
+    let _ = writeln!(text, "{name}_count{{{labels}}} {}", histogram.count);
+}
+
+/// A label value with backslashes, quotes and newlines escaped.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execution(recipe_name: &str, status: ExecutionStatus, cached: bool) -> ExecutionRecord {
+        ExecutionRecord {
+            recipe_name: recipe_name.to_string(),
+            justfile: "justfile".to_string(),
+            args: Vec::new(),
+            started_at_ms: 0,
+            finished_at_ms: 250,
+            duration_ms: 250,
+            exit_code: Some(0),
+            status,
+            cached,
+            error: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_render_counters_and_histograms() {
+        let metrics = Metrics::default();
+        metrics.record_tool_call("run_recipe", true, Duration::from_millis(20));
+        metrics.record_tool_call("run_recipe", false, Duration::from_secs(2));
+        metrics.record_execution(&execution("build", ExecutionStatus::Succeeded, false));
+        metrics.record_execution(&execution("build", ExecutionStatus::Succeeded, true));
+        metrics.record_execution(&execution("test", ExecutionStatus::Failed, false));
+        metrics.record_cache_lookup(true);
+
+        let text = metrics.render();
+        let lines: Vec<&str> = text.lines().collect();
+        for expected in [
+            "# TYPE just_mcp_tool_calls_total counter",
+            "just_mcp_tool_calls_total{tool=\"run_recipe\",outcome=\"error\"} 1",
+            "just_mcp_tool_calls_total{tool=\"run_recipe\",outcome=\"success\"} 1",
+            "just_mcp_tool_call_duration_seconds_bucket{tool=\"run_recipe\",le=\"0.025\"} 1",
+            "just_mcp_tool_call_duration_seconds_bucket{tool=\"run_recipe\",le=\"5\"} 2",
+            "just_mcp_tool_call_duration_seconds_count{tool=\"run_recipe\"} 2",
+            "just_mcp_recipe_executions_total{recipe=\"build\",status=\"succeeded\"} 2",
+            "just_mcp_recipe_executions_total{recipe=\"test\",status=\"failed\"} 1",
+            "just_mcp_recipe_duration_seconds_count{recipe=\"build\"} 1",
+            "just_mcp_recipe_duration_seconds_sum{recipe=\"build\"} 0.25",
+            "just_mcp_cache_lookups_total{result=\"hit\"} 1",
+        ] {
+            assert!(lines.contains(&expected), "missing {expected} in\n{text}");
+        }
+    }
+
+    #[test]
+    fn test_escape_label_values() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,9 +1,9 @@
 use snafu::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
-use crate::{Justfile, Parameter, Recipe};
+use crate::{Justfile, Parameter, ParameterType, Recipe, Span};
 
 #[derive(Debug, Snafu)]
 pub enum ParserError {
@@ -29,35 +29,142 @@ pub fn parse_justfile(path: &Path) -> Result<Justfile> {
 
 pub fn parse_justfile_str(content: &str) -> Result<Justfile> {
     let mut recipes = Vec::new();
-    let mut variables = HashMap::new();
+    let mut variables: HashMap<String, String> = HashMap::new();
+    let mut settings = HashMap::new();
+    let mut aliases = HashMap::new();
+    let mut exported = HashSet::new();
+    let mut variable_spans = HashMap::new();
     let mut current_recipe: Option<Recipe> = None;
     let mut current_doc: Option<String> = None;
+    let mut current_attributes: Vec<String> = Vec::new();
+    let mut current_parameter_types: HashMap<String, ParameterType> = HashMap::new();
+    // How the previous top-level statement ended, and the value it left
+    // unfinished
+    let mut line_end = LineEnd::Complete;
+    let mut continued: Option<Continued> = None;
     for (line_number, line) in content.lines().enumerate() {
         let line_number = line_number + 1;
         let trimmed = line.trim();
 
+        // Lines of a multi-line string, or after a trailing `\`, belong to
+        // the statement above, whatever they look like
+        if line_end != LineEnd::Complete {
+            line_end = line_end.after(line);
+            let value = match &continued {
+                Some(Continued::Variable(name)) => variables.get_mut(name),
+                Some(Continued::Setting(name)) => settings.get_mut(name),
+                None => None,
+            };
+            if let Some(value) = value {
+                value.push('\n');
+                value.push_str(line);
+            }
+            continue;
+        }
+
         // Skip empty lines
         if trimmed.is_empty() {
             continue;
         }
 
+        // Indented lines belong to the current recipe body; check this first so
+        // body content containing ':' or '=', or starting with '#', is never
+        // mistaken for a definition or a doc comment. Any indent counts, even
+        // a single space, as it does for just
+        if line.starts_with([' ', '\t'])
+            && let Some(ref mut recipe) = current_recipe
+        {
+            if !recipe.body.is_empty() {
+                recipe.body.push('\n');
+            }
+            // Keep skipped (blank) lines so body lines map back to source lines
+            for _ in recipe.span.end_line + 1..line_number {
+                recipe.body.push('\n');
+            }
+            recipe.body.push_str(line);
+            recipe.span.end_line = line_number;
+            continue;
+        }
+
         // Handle comments and documentation
         if let Some(stripped) = trimmed.strip_prefix('#') {
             let comment = stripped.trim();
+            // `# @param NAME: TYPE` annotates the next recipe's parameters;
+            // unrecognized types are ignored
+            if let Some(annotation) = comment.strip_prefix("@param ") {
+                if let Some((name, annotation)) = annotation.split_once(':')
+                    && let Some(parameter_type) = ParameterType::parse(annotation)
+                {
+                    current_parameter_types.insert(name.trim().to_string(), parameter_type);
+                }
+                continue;
+            }
             if !comment.is_empty() {
                 current_doc = Some(comment.to_string());
             }
             continue;
         }
 
-        // Handle variable assignments
-        if let Some((key, value)) = parse_variable_assignment(trimmed) {
-            variables.insert(key, value);
+        // Handle attributes (`[linux]`, `[private, group('ci')]`) for the next recipe
+        if let Some(attributes) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            current_attributes.extend(
+                attributes
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|attribute| !attribute.is_empty())
+                    .map(str::to_string),
+            );
+            continue;
+        }
+
+        // Handle settings (`set quiet`, `set shell := ["bash", "-c"]`)
+        if let Some(setting) = trimmed.strip_prefix("set ") {
+            let (name, value) = setting
+                .split_once(":=")
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .unwrap_or((setting.trim(), "true"));
+            settings.insert(name.to_string(), value.to_string());
+            line_end = LineEnd::Complete.after(line);
+            continued = Some(Continued::Setting(name.to_string()));
+            continue;
+        }
+
+        // Handle aliases (`alias b := build`)
+        if let Some(alias) = trimmed.strip_prefix("alias ")
+            && let Some((name, target)) = alias.split_once(":=")
+        {
+            aliases.insert(name.trim().to_string(), target.trim().to_string());
+            line_end = LineEnd::Complete.after(line);
+            continued = None;
+            continue;
+        }
+
+        // Handle variable assignments; `export NAME := VALUE` assigns NAME
+        // and records it as exported
+        let (export, assignment) = match trimmed.strip_prefix("export ") {
+            Some(assignment) => (true, assignment),
+            None => (false, trimmed),
+        };
+        if let Some((key, value)) = parse_variable_assignment(assignment) {
+            if export {
+                exported.insert(key.clone());
+            }
+            variable_spans.insert(key.clone(), Span::line(line_number));
+            variables.insert(key.clone(), value);
+            line_end = LineEnd::Complete.after(line);
+            continued = Some(Continued::Variable(key));
             continue;
         }
 
         // Handle recipe definitions
-        if let Some(recipe) = parse_recipe_line(trimmed, current_doc.take())? {
+        if let Some(mut recipe) = parse_recipe_line(trimmed, current_doc.take())? {
+            recipe.span = Span::line(line_number);
+            recipe.attributes = std::mem::take(&mut current_attributes);
+            recipe.parameter_types = std::mem::take(&mut current_parameter_types);
+
             // If we have a current recipe, save it
             if let Some(existing_recipe) = current_recipe.take() {
                 recipes.push(existing_recipe);
@@ -67,14 +174,8 @@ pub fn parse_justfile_str(content: &str) -> Result<Justfile> {
             continue;
         }
 
-        // Handle recipe body lines (indented)
-        if line.starts_with('\t') || line.starts_with("    ") {
-            if let Some(ref mut recipe) = current_recipe {
-                if !recipe.body.is_empty() {
-                    recipe.body.push('\n');
-                }
-                recipe.body.push_str(line);
-            }
+        // Indented lines before any recipe are ignored
+        if line.starts_with([' ', '\t']) {
             continue;
         }
 
@@ -92,7 +193,78 @@ pub fn parse_justfile_str(content: &str) -> Result<Justfile> {
         recipes.push(recipe);
     }
 
-    Ok(Justfile { recipes, variables })
+    Ok(Justfile {
+        recipes,
+        variables,
+        settings,
+        aliases,
+        exported,
+        variable_spans,
+    })
+}
+
+/// The variable or setting a statement spanning several lines assigns.
+enum Continued {
+    Variable(String),
+    Setting(String),
+}
+
+/// How a top-level line ends, for statements spanning several lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineEnd {
+    /// The statement ends on this line
+    Complete,
+    /// A trailing `\` continues it on the next line
+    Continued,
+    /// A `"""`, `'''` or ```` ``` ```` string is still open
+    InString(&'static str),
+}
+
+impl LineEnd {
+    /// How `line` ends, when the line before it ended as `self`.
+    pub(crate) fn after(self, line: &str) -> LineEnd {
+        let mut rest = line;
+        if let LineEnd::InString(delimiter) = self {
+            match rest.find(delimiter) {
+                Some(end) => rest = &rest[end + delimiter.len()..],
+                None => return self,
+            }
+        }
+        while let Some(start) = rest.find(['"', '\'', '`', '#']) {
+            rest = &rest[start..];
+            if rest.starts_with('#') {
+                return LineEnd::Complete;
+            }
+            if let Some(delimiter) = ["\"\"\"", "'''", "```"]
+                .into_iter()
+                .find(|delimiter| rest.starts_with(delimiter))
+            {
+                rest = &rest[delimiter.len()..];
+                match rest.find(delimiter) {
+                    Some(end) => rest = &rest[end + delimiter.len()..],
+                    None => return LineEnd::InString(delimiter),
+                }
+                continue;
+            }
+            // A string or backtick on one line; only `"` strings have escapes
+            let quote = rest.chars().next().unwrap();
+            let mut escaped = false;
+            let end = rest[1..].find(|ch| {
+                let closes = ch == quote && !escaped;
+                escaped = quote == '"' && ch == '\\' && !escaped;
+                closes
+            });
+            match end {
+                Some(end) => rest = &rest[end + 2..],
+                None => return LineEnd::Complete,
+            }
+        }
+        if rest.trim_end().ends_with('\\') {
+            LineEnd::Continued
+        } else {
+            LineEnd::Complete
+        }
+    }
 }
 
 fn parse_recipe_header(header: &str) -> Result<Vec<String>> {
@@ -133,8 +305,11 @@ fn parse_recipe_header(header: &str) -> Result<Vec<String>> {
     Ok(parts)
 }
 
+/// `NAME := VALUE`, or the older `NAME = VALUE`. `:=` is looked for first,
+/// so a value holding `=` (`url := "a?b=c"`) is not split inside. An
+/// `export ` prefix is stripped by the caller.
 fn parse_variable_assignment(line: &str) -> Option<(String, String)> {
-    if let Some((key, value)) = line.split_once('=') {
+    if let Some((key, value)) = line.split_once(":=").or_else(|| line.split_once('=')) {
         let key = key.trim();
         let value = value.trim();
 
@@ -159,7 +334,11 @@ fn parse_recipe_line(line: &str, documentation: Option<String>) -> Result<Option
             return Ok(None);
         }
 
-        let name = parts[0].to_string();
+        // A leading '@' marks the whole recipe as quiet
+        let (quiet, name) = match parts[0].strip_prefix('@') {
+            Some(stripped) => (true, stripped.to_string()),
+            None => (false, parts[0].to_string()),
+        };
         let mut parameters = Vec::new();
 
         // Parse parameters
@@ -184,6 +363,10 @@ fn parse_recipe_line(line: &str, documentation: Option<String>) -> Result<Option
             documentation,
             body: String::new(),
             dependencies,
+            quiet,
+            span: Span::default(),
+            attributes: Vec::new(),
+            parameter_types: HashMap::new(),
         }));
     }
 
@@ -323,4 +506,253 @@ write_file filename content="Hello from just-mcp!":
             Some("Hello from just-mcp!".to_string())
         );
     }
+
+    #[test]
+    fn test_parse_settings_and_colon_equals_variables() {
+        let content = r#"
+set quiet
+set shell := ["bash", "-c"]
+export version := "1.0.0"
+
+@build:
+    echo "Building: {{ version }}"
+    FOO=bar cargo build
+"#;
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert!(justfile.setting_enabled("quiet"));
+        assert_eq!(
+            justfile.settings.get("shell"),
+            Some(&r#"["bash", "-c"]"#.to_string())
+        );
+        assert_eq!(
+            justfile.variables.get("version"),
+            Some(&"\"1.0.0\"".to_string())
+        );
+        assert_eq!(justfile.variables.len(), 1);
+        assert!(justfile.exported.contains("version"));
+        assert_eq!(justfile.variable_spans.get("version"), Some(&Span::line(4)));
+
+        // Body lines containing ':' or '=' stay in the body
+        assert_eq!(justfile.recipes.len(), 1);
+        let recipe = &justfile.recipes[0];
+        assert_eq!(recipe.name, "build");
+        assert!(recipe.quiet);
+        assert!(recipe.body.contains("FOO=bar cargo build"));
+    }
+
+    #[test]
+    fn test_parse_export_prefix() {
+        let content = "export TOKEN := \"abc\"\nregion := \"eu\"\n";
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert_eq!(
+            justfile.variables.get("TOKEN"),
+            Some(&"\"abc\"".to_string())
+        );
+        assert!(!justfile.variables.contains_key("export TOKEN"));
+        assert!(justfile.exported.contains("TOKEN"));
+        assert!(!justfile.exported.contains("region"));
+    }
+
+    #[test]
+    fn test_parse_colon_equals_assignments() {
+        let content = "url := \"https://x?a=b\"\nlegacy = \"1\"\n";
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert_eq!(
+            justfile.variables.get("url"),
+            Some(&"\"https://x?a=b\"".to_string())
+        );
+        assert_eq!(justfile.variables.get("legacy"), Some(&"\"1\"".to_string()));
+        assert!(justfile.recipes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_body_before_comments() {
+        let content = "# Build it\nbuild:\n    # not documentation\n    target: debug\n\ntest:\n    cargo test\n";
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert_eq!(justfile.recipes.len(), 2);
+        assert_eq!(
+            justfile.recipes[0].body,
+            "    # not documentation\n    target: debug"
+        );
+        assert_eq!(justfile.recipes[1].documentation, None);
+    }
+
+    #[test]
+    fn test_parse_single_space_indent_is_body() {
+        let content = "build:\n echo one\n\techo two\n";
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert_eq!(justfile.recipes.len(), 1);
+        assert_eq!(justfile.recipes[0].body, " echo one\n\techo two");
+    }
+
+    #[test]
+    fn test_parse_recipe_spans() {
+        let content =
+            "# Build\nbuild:\n    cargo build\n\n    cargo doc\n\ntest: build\n    cargo test\n";
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert_eq!(
+            justfile.recipes[0].span,
+            Span {
+                start_line: 2,
+                end_line: 5
+            }
+        );
+        assert_eq!(
+            justfile.recipes[1].span,
+            Span {
+                start_line: 7,
+                end_line: 8
+            }
+        );
+
+        let lines: Vec<_> = justfile.recipes[0].body_lines().collect();
+        assert_eq!(
+            lines,
+            vec![(3, "    cargo build"), (4, ""), (5, "    cargo doc")]
+        );
+    }
+
+    #[test]
+    fn test_parse_recipe_attributes() {
+        let content = r#"
+[linux]
+build:
+    make
+
+[private, group('ci')]
+check:
+    cargo check
+"#;
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert_eq!(justfile.recipes[0].attributes, vec!["linux"]);
+        assert!(justfile.recipes[0].has_platform_attribute());
+        assert_eq!(
+            justfile.recipes[1].attributes,
+            vec!["private", "group('ci')"]
+        );
+        assert!(!justfile.recipes[1].has_platform_attribute());
+        assert!(!justfile.recipes[1].requires_confirmation());
+    }
+
+    #[test]
+    fn test_parse_confirm_attribute() {
+        let content = "[confirm]
+clean:
+    rm -rf target
+
+[confirm('Deploy?')]
+deploy:
+    ./deploy
+";
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert!(justfile.recipes[0].requires_confirmation());
+        assert!(justfile.recipes[1].requires_confirmation());
+    }
+
+    #[test]
+    fn test_parse_parameter_type_annotations() {
+        let content = r#"
+# Start the server
+# @param port: int
+# @param env: enum(dev, staging, prod)
+# @param mode: whatever
+serve port env mode="fast":
+    ./serve {{ port }} {{ env }}
+"#;
+
+        let justfile = parse_justfile_str(content).unwrap();
+        let recipe = &justfile.recipes[0];
+        assert_eq!(recipe.documentation, Some("Start the server".to_string()));
+        assert_eq!(recipe.parameter_types.len(), 2);
+        assert_eq!(
+            recipe.parameter_types.get("port"),
+            Some(&ParameterType::Int)
+        );
+        assert_eq!(
+            recipe.parameter_types.get("env"),
+            Some(&ParameterType::Enum(vec![
+                "dev".to_string(),
+                "staging".to_string(),
+                "prod".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        let content = r#"
+alias b := build
+
+build:
+    cargo build
+"#;
+
+        let justfile = parse_justfile_str(content).unwrap();
+        assert_eq!(justfile.recipes.len(), 1);
+        assert_eq!(justfile.aliases.get("b"), Some(&"build".to_string()));
+    }
+
+    #[test]
+    fn test_parse_multiline_values() {
+        let content = r#"
+build:
+    cargo build
+message := """
+  deploy: not a recipe
+
+  done
+"""
+flags := "--release" + \
+    " --locked"
+# A string with \ at the end is not continued: "\"
+test:
+    cargo test
+"#;
+
+        let justfile = parse_justfile_str(content).unwrap();
+        let names: Vec<&str> = justfile
+            .recipes
+            .iter()
+            .map(|recipe| recipe.name.as_str())
+            .collect();
+        assert_eq!(names, ["build", "test"]);
+        assert_eq!(justfile.recipes[0].body.trim(), "cargo build");
+        assert_eq!(
+            justfile.variables["message"],
+            "\"\"\"\n  deploy: not a recipe\n\n  done\n\"\"\""
+        );
+        assert_eq!(
+            justfile.variables["flags"],
+            "\"--release\" + \\\n    \" --locked\""
+        );
+    }
+
+    #[test]
+    fn test_line_end() {
+        assert_eq!(LineEnd::Complete.after("a := 'x'"), LineEnd::Complete);
+        assert_eq!(LineEnd::Complete.after("a := 'x' + \\"), LineEnd::Continued);
+        assert_eq!(LineEnd::Complete.after("a := '\\'"), LineEnd::Complete);
+        assert_eq!(LineEnd::Complete.after("a := 'x' # \\"), LineEnd::Complete);
+        assert_eq!(
+            LineEnd::Complete.after("a := \"\"\"text"),
+            LineEnd::InString("\"\"\"")
+        );
+        assert_eq!(
+            LineEnd::InString("\"\"\"").after("# not a comment"),
+            LineEnd::InString("\"\"\"")
+        );
+        assert_eq!(
+            LineEnd::InString("\"\"\"").after("end\"\"\" + \\"),
+            LineEnd::Continued
+        );
+        assert_eq!(LineEnd::Complete.after("a := '''x'''"), LineEnd::Complete);
+    }
 }
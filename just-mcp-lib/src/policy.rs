@@ -0,0 +1,156 @@
+use serde::Deserialize;
+
+use crate::Justfile;
+use crate::artifacts::component_matches;
+
+/// Which recipes the server lets clients run, from `--allow-recipes` and
+/// `--deny-recipes` or the `[recipes]` section of the config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RecipePolicy {
+    /// Patterns such as `build` or `lint-*`; when empty every recipe is allowed
+    pub allow: Vec<String>,
+    /// Patterns of recipes that never run, even when allowed
+    pub deny: Vec<String>,
+    /// Leave blocked recipes out of listings instead of marking them blocked
+    pub hide_blocked: bool,
+}
+
+/// Which of the server's own environment variables recipes inherit, from
+/// the `[inherit-env]` section of the config file. Variables the server
+/// passes on purpose, from `[env]` or a session, are not affected.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct EnvPolicy {
+    /// Patterns such as `PATH` or `CARGO_*`; when empty every variable is
+    /// inherited
+    pub allow: Vec<String>,
+    /// Patterns of variables never inherited, such as `OPENAI_API_KEY`
+    pub deny: Vec<String>,
+}
+
+impl EnvPolicy {
+    pub fn is_unrestricted(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether a recipe inherits the server's variable `name`. Patterns
+    /// match regardless of case.
+    pub fn inherits(&self, name: &str) -> bool {
+        let name = name.to_uppercase();
+        let matches = |pattern: &String| component_matches(&pattern.to_uppercase(), &name);
+        (self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+    }
+}
+
+impl RecipePolicy {
+    /// Split a comma-separated pattern list such as `build,test,lint-*`.
+    pub fn parse_patterns(list: &str) -> Vec<String> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether the recipe itself matches the allow list and not the deny list.
+    pub fn allows(&self, recipe_name: &str) -> bool {
+        let matches = |pattern: &String| component_matches(pattern, recipe_name);
+        (self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+    }
+
+    /// The first recipe that would be blocked when running `recipe_name`:
+    /// the recipe itself or one of the dependencies just runs before it.
+    pub fn blocked_recipe(&self, justfile: &Justfile, recipe_name: &str) -> Option<String> {
+        if self.is_unrestricted() {
+            return None;
+        }
+        let mut pending = vec![recipe_name.to_string()];
+        let mut seen = Vec::new();
+        while let Some(name) = pending.pop() {
+            if seen.contains(&name) {
+                continue;
+            }
+            if !self.allows(&name) {
+                return Some(name);
+            }
+            if let Some(recipe) = justfile.recipes.iter().find(|recipe| recipe.name == name) {
+                pending.extend(recipe.dependencies.iter().cloned());
+            }
+            seen.push(name);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    #[test]
+    fn test_allows() {
+        let policy = RecipePolicy {
+            allow: RecipePolicy::parse_patterns("build, test,lint-*"),
+            deny: RecipePolicy::parse_patterns("lint-slow"),
+            ..Default::default()
+        };
+        assert!(policy.allows("build"));
+        assert!(policy.allows("lint-fast"));
+        assert!(!policy.allows("lint-slow"));
+        assert!(!policy.allows("deploy"));
+
+        let policy = RecipePolicy {
+            deny: RecipePolicy::parse_patterns("deploy*,db-*"),
+            ..Default::default()
+        };
+        assert!(policy.allows("build"));
+        assert!(!policy.allows("deploy-prod"));
+        assert!(!policy.allows("db-reset"));
+    }
+
+    #[test]
+    fn test_env_policy() {
+        let policy = EnvPolicy {
+            deny: vec!["OPENAI_API_KEY".to_string(), "*_token".to_string()],
+            ..Default::default()
+        };
+        assert!(policy.inherits("PATH"));
+        assert!(!policy.inherits("OPENAI_API_KEY"));
+        assert!(!policy.inherits("GITHUB_TOKEN"));
+
+        let policy = EnvPolicy {
+            allow: vec!["PATH".to_string(), "CARGO_*".to_string()],
+            deny: vec!["CARGO_REGISTRY_TOKEN".to_string()],
+        };
+        assert!(policy.inherits("CARGO_HOME"));
+        assert!(!policy.inherits("CARGO_REGISTRY_TOKEN"));
+        assert!(!policy.inherits("HOME"));
+        assert!(EnvPolicy::default().inherits("HOME"));
+    }
+
+    #[test]
+    fn test_blocked_dependency() {
+        let justfile = parse_justfile_str(
+            "build:\n    cargo build\n\nmigrate:\n    ./migrate\n\nrelease: build migrate\n    ./release\n",
+        )
+        .unwrap();
+        let policy = RecipePolicy {
+            deny: vec!["migrate".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(policy.blocked_recipe(&justfile, "build"), None);
+        assert_eq!(
+            policy.blocked_recipe(&justfile, "release"),
+            Some("migrate".to_string())
+        );
+        assert_eq!(
+            RecipePolicy::default().blocked_recipe(&justfile, "release"),
+            None
+        );
+    }
+}
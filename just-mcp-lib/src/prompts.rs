@@ -0,0 +1,208 @@
+use crate::validator::{format_signature_help, get_signature_help};
+use crate::{Justfile, Recipe};
+
+/// A built-in prompt template and the arguments it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// `(name, description, required)` for each argument
+    pub arguments: &'static [(&'static str, &'static str, bool)],
+}
+
+pub const WRITE_RECIPE: &str = "write_recipe";
+pub const EXPLAIN_RECIPE: &str = "explain_recipe";
+
+pub const PROMPTS: &[PromptTemplate] = &[
+    PromptTemplate {
+        name: WRITE_RECIPE,
+        description: "Write a new recipe for this justfile that accomplishes a goal",
+        arguments: &[
+            ("goal", "What the recipe should do", true),
+            (
+                "justfile_path",
+                "Justfile to extend (defaults to the working directory's)",
+                false,
+            ),
+        ],
+    },
+    PromptTemplate {
+        name: EXPLAIN_RECIPE,
+        description: "Explain what a recipe does, what it depends on, and how to run it",
+        arguments: &[
+            ("name", "Recipe to explain", true),
+            (
+                "justfile_path",
+                "Justfile containing the recipe (defaults to the working directory's)",
+                false,
+            ),
+        ],
+    },
+];
+
+pub fn find_prompt(name: &str) -> Option<&'static PromptTemplate> {
+    PROMPTS.iter().find(|prompt| prompt.name == name)
+}
+
+/// Prompt asking for a new recipe, with the whole justfile as context.
+pub fn write_recipe_prompt(source: &str, justfile: &Justfile, goal: &str) -> String {
+    let mut prompt = format!("Write a recipe for the justfile below that {goal}.\n\n");
+    prompt.push_str("Guidelines:\n");
+    prompt.push_str("- Start with a `#` doc comment describing the recipe\n");
+    prompt
+        .push_str("- Declare typed parameters with `# @param NAME: TYPE` comments where useful\n");
+    prompt.push_str("- Depend on existing recipes instead of duplicating their commands\n");
+    prompt.push_str("- Reuse the justfile's variables and settings\n");
+    prompt.push_str("- Reply with only the new recipe\n");
+
+    if !justfile.recipes.is_empty() {
+        prompt.push_str("\nExisting recipes:\n");
+        for recipe in &justfile.recipes {
+            prompt.push_str(&format!("- {}", signature(recipe)));
+            if let Some(doc) = &recipe.documentation {
+                prompt.push_str(&format!(" - {doc}"));
+            }
+            prompt.push('\n');
+        }
+    }
+
+    prompt.push_str(&format!(
+        "\nJustfile:\n```just\n{}\n```\n",
+        source.trim_end()
+    ));
+    prompt
+}
+
+/// The recipe in a model's reply to `write_recipe_prompt`: the contents of
+/// the first fenced code block, or the whole reply when it has none.
+pub fn recipe_from_reply(reply: &str) -> &str {
+    let Some(start) = reply.find("```") else {
+        return reply.trim();
+    };
+    // Skip the fence line, including a language tag such as `just`
+    let code = &reply[start + 3..];
+    let code = code.find('\n').map_or("", |newline| &code[newline + 1..]);
+    match code.find("```") {
+        Some(end) => code[..end].trim_end(),
+        None => code.trim_end(),
+    }
+}
+
+/// Prompt asking for an explanation of one recipe, with its source,
+/// signature, and the recipes it depends on.
+pub fn explain_recipe_prompt(source: &str, justfile: &Justfile, recipe: &Recipe) -> String {
+    let mut prompt = format!(
+        "Explain what the `{}` recipe does, including any side effects, and show how to run it.\n\n",
+        recipe.name
+    );
+    prompt.push_str(&format!(
+        "Signature:\n{}\n",
+        format_signature_help(&get_signature_help(recipe))
+    ));
+    prompt.push_str(&format!(
+        "\nSource:\n```just\n{}\n```\n",
+        recipe_source(source, recipe)
+    ));
+
+    let dependencies: Vec<&Recipe> = recipe
+        .dependencies
+        .iter()
+        .filter_map(|name| justfile.recipes.iter().find(|r| &r.name == name))
+        .collect();
+    if !dependencies.is_empty() {
+        prompt.push_str("\nDependencies (run first):\n");
+        for dependency in dependencies {
+            prompt.push_str(&format!(
+                "```just\n{}\n```\n",
+                recipe_source(source, dependency)
+            ));
+        }
+    }
+
+    if !justfile.variables.is_empty() {
+        let mut variables: Vec<_> = justfile.variables.iter().collect();
+        variables.sort();
+        prompt.push_str("\nVariables:\n");
+        for (name, value) in variables {
+            prompt.push_str(&format!("- {name} := {value}\n"));
+        }
+    }
+
+    prompt
+}
+
+fn signature(recipe: &Recipe) -> String {
+    let mut signature = recipe.name.clone();
+    for param in &recipe.parameters {
+        signature.push(' ');
+        match &param.default_value {
+            Some(default) => signature.push_str(&format!("{}={}", param.name, default)),
+            None => signature.push_str(&param.name),
+        }
+    }
+    signature
+}
+
+/// The recipe's lines in the justfile, header through last body line.
+fn recipe_source(source: &str, recipe: &Recipe) -> String {
+    let span = recipe.span;
+    source
+        .lines()
+        .skip(span.start_line.saturating_sub(1))
+        .take((span.end_line + 1).saturating_sub(span.start_line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    const JUSTFILE: &str = "version := \"1.0\"\n\n# Build the project\nbuild:\n    cargo build\n\n# Run tests\ntest filter=\"\": build\n    cargo test {{ filter }}\n";
+
+    #[test]
+    fn test_prompt_templates() {
+        assert_eq!(find_prompt(WRITE_RECIPE).unwrap().arguments[0].0, "goal");
+        assert_eq!(find_prompt(EXPLAIN_RECIPE).unwrap().arguments[0].0, "name");
+        assert!(find_prompt("unknown").is_none());
+    }
+
+    #[test]
+    fn test_write_recipe_prompt() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let prompt = write_recipe_prompt(JUSTFILE, &justfile, "runs clippy");
+
+        assert!(prompt.starts_with("Write a recipe for the justfile below that runs clippy."));
+        assert!(prompt.contains("- build - Build the project\n"));
+        assert!(prompt.contains("- test filter= - Run tests\n"));
+        assert!(prompt.contains("```just\nversion := \"1.0\""));
+    }
+
+    #[test]
+    fn test_recipe_from_reply() {
+        let recipe = "# Lint\nlint:\n    cargo clippy";
+        assert_eq!(recipe_from_reply(recipe), recipe);
+        assert_eq!(
+            recipe_from_reply(&format!("Here it is:\n\n```just\n{recipe}\n```\n\nEnjoy.")),
+            recipe
+        );
+        assert_eq!(recipe_from_reply(&format!("```\n{recipe}")), recipe);
+    }
+
+    #[test]
+    fn test_explain_recipe_prompt() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let recipe = justfile.recipes.iter().find(|r| r.name == "test").unwrap();
+        let prompt = explain_recipe_prompt(JUSTFILE, &justfile, recipe);
+
+        assert!(prompt.starts_with("Explain what the `test` recipe does"));
+        assert!(prompt.contains(
+            "Source:\n```just\ntest filter=\"\": build\n    cargo test {{ filter }}\n```"
+        ));
+        assert!(
+            prompt.contains("Dependencies (run first):\n```just\nbuild:\n    cargo build\n```")
+        );
+        assert!(prompt.contains("- version := \"1.0\"\n"));
+    }
+}
@@ -0,0 +1,152 @@
+use serde_json::Value;
+use std::borrow::Cow;
+
+use crate::artifacts::component_matches;
+use crate::environment::{MASKED_VALUE, SECRET_NAME_PARTS};
+
+/// Secret values shorter than this are left alone; masking every `true` or
+/// `1234` in recipe output would hide more than it protects.
+pub const MIN_SECRET_LEN: usize = 6;
+
+/// Variable name patterns used without `secret-patterns`: names containing
+/// `TOKEN`, `SECRET`, `PASSWORD` and the like.
+pub fn default_secret_patterns() -> Vec<String> {
+    SECRET_NAME_PARTS
+        .iter()
+        .map(|part| format!("*{part}*"))
+        .collect()
+}
+
+/// Masks the values of secret variables wherever they appear in text
+/// leaving the server: recipe output, tool results, error messages and
+/// log lines. Secrets are variables whose names match one of the patterns,
+/// such as `*_TOKEN` or `PASSWORD*`, compared case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    patterns: Vec<String>,
+    /// Longest first, so a secret containing another is masked whole
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns
+                .iter()
+                .map(|pattern| pattern.to_uppercase())
+                .collect(),
+            secrets: Vec::new(),
+        }
+    }
+
+    pub fn is_secret_name(&self, name: &str) -> bool {
+        let name = name.to_uppercase();
+        self.patterns
+            .iter()
+            .any(|pattern| component_matches(pattern, &name))
+    }
+
+    /// Remember the values of the secret variables among `variables`.
+    pub fn with_variables<K, V>(mut self, variables: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (name, value) in variables {
+            let value = value.as_ref();
+            if value.len() >= MIN_SECRET_LEN
+                && self.is_secret_name(name.as_ref())
+                && !self.secrets.iter().any(|secret| secret == value)
+            {
+                self.secrets.push(value.to_string());
+            }
+        }
+        self.secrets
+            .sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        self
+    }
+
+    /// Remember `values` whatever their variables are called, e.g. those
+    /// read from the OS keyring.
+    pub fn with_secrets<V: AsRef<str>>(mut self, values: impl IntoIterator<Item = V>) -> Self {
+        for value in values {
+            let value = value.as_ref();
+            if value.len() >= MIN_SECRET_LEN && !self.secrets.iter().any(|secret| secret == value) {
+                self.secrets.push(value.to_string());
+            }
+        }
+        self.secrets
+            .sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        self
+    }
+
+    /// `text` with every secret value replaced by `****`.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for secret in &self.secrets {
+            if text.contains(secret.as_str()) {
+                text = Cow::Owned(text.replace(secret.as_str(), MASKED_VALUE));
+            }
+        }
+        text
+    }
+
+    /// Redact every string in `value`, in place.
+    pub fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                if let Cow::Owned(redacted) = self.redact(text) {
+                    *text = redacted;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact_json(value)),
+            Value::Object(map) => map.values_mut().for_each(|value| self.redact_json(value)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_secret_values() {
+        let patterns = vec![
+            "*_TOKEN".to_string(),
+            "*_SECRET".to_string(),
+            "PASSWORD*".to_string(),
+        ];
+        let redactor = Redactor::new(&patterns).with_variables([
+            ("GITHUB_TOKEN", "ghp_abcdef123"),
+            ("password_db", "hunter22"),
+            ("CLIENT_SECRET", "short"),
+            ("DEPLOY_KEY", "not-matched-by-patterns"),
+        ]);
+        assert!(redactor.is_secret_name("api_token"));
+        assert!(!redactor.is_secret_name("TOKEN_URL"));
+
+        let output =
+            "pushing with ghp_abcdef123 and hunter22 using short key not-matched-by-patterns";
+        assert_eq!(
+            redactor.redact(output),
+            "pushing with **** and **** using short key not-matched-by-patterns"
+        );
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+
+        let redactor = redactor.with_secrets(["not-matched-by-patterns"]);
+        assert_eq!(redactor.redact("key not-matched-by-patterns"), "key ****");
+
+        let mut value = serde_json::json!({ "stdout": "token=ghp_abcdef123", "exit_code": 0 });
+        redactor.redact_json(&mut value);
+        assert_eq!(value["stdout"], "token=****");
+    }
+
+    #[test]
+    fn test_default_patterns() {
+        let redactor = Redactor::new(&default_secret_patterns());
+        assert!(redactor.is_secret_name("aws_secret_access_key"));
+        assert!(redactor.is_secret_name("NPM_TOKEN"));
+        assert!(!redactor.is_secret_name("HOME"));
+    }
+}
@@ -10,7 +10,7 @@ use std::path::{Path, PathBuf};
 /// Two modes:
 /// - **Permissive** (empty registry): all justfiles allowed — backward compatible.
 /// - **Strict** (non-empty registry): only registered absolute paths allowed.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct JustfileRegistry {
     /// Canonicalized absolute paths of registered justfiles.
     allowed: HashSet<PathBuf>,
@@ -19,12 +19,6 @@ pub struct JustfileRegistry {
     strict: bool,
 }
 
-impl Default for JustfileRegistry {
-    fn default() -> Self {
-        Self { allowed: HashSet::new(), strict: false }
-    }
-}
-
 impl JustfileRegistry {
     /// Create a permissive registry (no restrictions).
     pub fn permissive() -> Self {
@@ -79,6 +73,11 @@ impl JustfileRegistry {
         self.allowed.len()
     }
 
+    /// True when no justfile is registered.
+    pub fn is_empty(&self) -> bool {
+        self.allowed.is_empty()
+    }
+
     /// Iterate registered paths.
     pub fn registered_paths(&self) -> impl Iterator<Item = &PathBuf> {
         self.allowed.iter()
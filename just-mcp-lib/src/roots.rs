@@ -0,0 +1,280 @@
+use std::path::{Path, PathBuf};
+
+/// File names recognized as a justfile, in lookup order.
+pub const JUSTFILE_NAMES: &[&str] = &["justfile", "Justfile", ".justfile"];
+
+/// How many directory levels below a root are searched for justfiles.
+pub const MAX_DEPTH: usize = 3;
+
+/// Directories that never contain project justfiles worth exposing.
+pub(crate) const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// The justfile in `dir`, if any.
+pub fn find_justfile(dir: &Path) -> Option<PathBuf> {
+    JUSTFILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The justfile in `dir` or, as just does, in the nearest parent directory
+/// that has one. The search stops after `boundary` when `dir` is inside it,
+/// otherwise at the filesystem root.
+pub fn find_justfile_upward(dir: &Path, boundary: Option<&Path>) -> Option<PathBuf> {
+    let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let boundary = boundary
+        .and_then(|boundary| std::path::absolute(boundary).ok())
+        .filter(|boundary| dir.starts_with(boundary));
+    for ancestor in dir.ancestors() {
+        if let Some(path) = find_justfile(ancestor) {
+            return Some(path);
+        }
+        if boundary.as_deref() == Some(ancestor) {
+            break;
+        }
+    }
+    None
+}
+
+/// Local path of a `file://` root URI; other schemes are not supported.
+pub fn root_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // Drop an optional host, e.g. file://localhost/home/user
+    let path = &path[path.find('/')?..];
+    Some(PathBuf::from(percent_decode(path)?))
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut input = text.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next()?, input.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Justfiles in `root` and up to `max_depth` levels below it, shallowest
+/// first. Hidden directories, `target`, `node_modules` and directories
+/// named in `ignore` are skipped.
+pub fn discover_justfiles(root: &Path, max_depth: usize, ignore: &[String]) -> Vec<PathBuf> {
+    let mut justfiles = Vec::new();
+    let mut level = vec![root.to_path_buf()];
+    for depth in 0..=max_depth {
+        let mut next_level = Vec::new();
+        for dir in &level {
+            justfiles.extend(find_justfile(dir));
+            if depth == max_depth {
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            let mut subdirs: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    !name.starts_with('.')
+                        && !SKIPPED_DIRS.contains(&name.as_ref())
+                        && !ignore.iter().any(|ignored| *ignored == name)
+                })
+                .map(|entry| entry.path())
+                .collect();
+            subdirs.sort();
+            next_level.extend(subdirs);
+        }
+        level = next_level;
+    }
+    justfiles
+}
+
+/// Id of a discovered justfile: its directory relative to `base`, `.` for
+/// `base` itself, or the absolute directory when outside `base`. Tools
+/// accept the id wherever they take a `justfile_path`.
+pub fn justfile_id(base: &Path, justfile: &Path) -> String {
+    let dir = justfile.parent().unwrap_or(justfile);
+    match dir.strip_prefix(base) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.display().to_string(),
+        Err(_) => dir.display().to_string(),
+    }
+}
+
+/// A project directory served next to others. Its name namespaces its
+/// recipe tools (`just_<name>__<recipe>`) and justfile ids (`<name>/...`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectRoot {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl ProjectRoot {
+    /// The path an id such as `api` or `api/services/auth` names in this
+    /// root, if the id starts with its name.
+    pub fn resolve(&self, id: &str) -> Option<PathBuf> {
+        if id == self.name {
+            return Some(self.dir.clone());
+        }
+        let rest = id.strip_prefix(&self.name)?.strip_prefix('/')?;
+        Some(self.dir.join(rest))
+    }
+}
+
+/// Name each directory after its last component, keeping only characters
+/// allowed in tool names and never two underscores in a row. Repeated names
+/// get `-2`, `-3`, and so on.
+pub fn project_roots(dirs: &[PathBuf]) -> Vec<ProjectRoot> {
+    let mut roots: Vec<ProjectRoot> = Vec::new();
+    for dir in dirs {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+        let base: String = dir
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+            .chars()
+            .map(|ch| match ch {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => ch,
+                _ => '-',
+            })
+            .collect();
+        let mut base = base.trim_matches(|ch| ch == '-' || ch == '_').to_string();
+        while base.contains("__") {
+            base = base.replace("__", "_");
+        }
+        if base.is_empty() {
+            base = "root".to_string();
+        }
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while roots.iter().any(|root| root.name == name) {
+            name = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        roots.push(ProjectRoot { name, dir });
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_root_path() {
+        assert_eq!(
+            root_path("file:///home/user/my%20project"),
+            Some(PathBuf::from("/home/user/my project"))
+        );
+        assert_eq!(
+            root_path("file://localhost/srv/app"),
+            Some(PathBuf::from("/srv/app"))
+        );
+        assert_eq!(root_path("https://example.com/repo"), None);
+        assert_eq!(root_path("file:///bad%2"), None);
+    }
+
+    #[test]
+    fn test_find_justfile_upward() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("project/crates/app")).unwrap();
+        fs::write(root.join("justfile"), "").unwrap();
+        fs::write(root.join("project/Justfile"), "").unwrap();
+
+        let app = root.join("project/crates/app");
+        assert_eq!(
+            find_justfile_upward(&app, None),
+            Some(root.join("project/Justfile"))
+        );
+        assert_eq!(
+            find_justfile_upward(&root.join("project"), None),
+            Some(root.join("project/Justfile"))
+        );
+
+        fs::remove_file(root.join("project/Justfile")).unwrap();
+        assert_eq!(
+            find_justfile_upward(&app, None),
+            Some(root.join("justfile"))
+        );
+        assert_eq!(
+            find_justfile_upward(&app, Some(&root.join("project"))),
+            None
+        );
+        // A boundary the directory is not inside is ignored
+        assert_eq!(
+            find_justfile_upward(&app, Some(&root.join("elsewhere"))),
+            Some(root.join("justfile"))
+        );
+    }
+
+    #[test]
+    fn test_discover_justfiles() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        for sub in ["api", "web/app", "node_modules/pkg", ".git", "a/b/c/d"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(root.join("justfile"), "").unwrap();
+        fs::write(root.join("api/Justfile"), "").unwrap();
+        fs::write(root.join("web/app/.justfile"), "").unwrap();
+        fs::write(root.join("node_modules/pkg/justfile"), "").unwrap();
+        fs::write(root.join(".git/justfile"), "").unwrap();
+        fs::write(root.join("a/b/c/d/justfile"), "").unwrap();
+
+        let found = discover_justfiles(root, MAX_DEPTH, &[]);
+        assert_eq!(
+            found,
+            vec![
+                root.join("justfile"),
+                root.join("api/Justfile"),
+                root.join("web/app/.justfile"),
+            ]
+        );
+
+        let found = discover_justfiles(root, MAX_DEPTH, &["web".to_string()]);
+        assert_eq!(
+            found,
+            vec![root.join("justfile"), root.join("api/Justfile")]
+        );
+        let found = discover_justfiles(root, 0, &[]);
+        assert_eq!(found, vec![root.join("justfile")]);
+    }
+
+    #[test]
+    fn test_justfile_id() {
+        let base = Path::new("/repo");
+        assert_eq!(justfile_id(base, Path::new("/repo/justfile")), ".");
+        assert_eq!(
+            justfile_id(base, Path::new("/repo/web/app/justfile")),
+            "web/app"
+        );
+        assert_eq!(justfile_id(base, Path::new("/other/justfile")), "/other");
+    }
+
+    #[test]
+    fn test_project_roots() {
+        let roots = project_roots(&[
+            PathBuf::from("/work/api"),
+            PathBuf::from("/other/api"),
+            PathBuf::from("/work/my web__app"),
+        ]);
+        let names: Vec<&str> = roots.iter().map(|root| root.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "api-2", "my-web_app"]);
+
+        assert_eq!(roots[0].resolve("api"), Some(PathBuf::from("/work/api")));
+        assert_eq!(
+            roots[0].resolve("api/services/auth"),
+            Some(PathBuf::from("/work/api/services/auth"))
+        );
+        assert_eq!(roots[0].resolve("api-2"), None);
+        assert_eq!(roots[1].resolve("api-2"), Some(PathBuf::from("/other/api")));
+    }
+}
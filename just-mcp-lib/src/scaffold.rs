@@ -0,0 +1,224 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectType {
+    /// `Cargo.toml`
+    Rust,
+    /// `package.json`
+    Node,
+    /// `pyproject.toml`
+    Python,
+    /// `go.mod`
+    Go,
+}
+
+/// Marker file of each project type, in detection order.
+const MARKERS: &[(&str, ProjectType)] = &[
+    ("Cargo.toml", ProjectType::Rust),
+    ("package.json", ProjectType::Node),
+    ("pyproject.toml", ProjectType::Python),
+    ("go.mod", ProjectType::Go),
+];
+
+/// Project types whose marker file is in `dir`, in detection order.
+pub fn detect_project_types(dir: &Path) -> Vec<ProjectType> {
+    MARKERS
+        .iter()
+        .filter(|(marker, _)| dir.join(marker).is_file())
+        .map(|(_, project_type)| *project_type)
+        .collect()
+}
+
+/// Starter recipe: documentation, header and body lines.
+struct Starter {
+    doc: &'static str,
+    header: &'static str,
+    body: Vec<String>,
+}
+
+fn starter(doc: &'static str, header: &'static str, body: &[&str]) -> Starter {
+    Starter {
+        doc,
+        header,
+        body: body.iter().map(|line| line.to_string()).collect(),
+    }
+}
+
+/// Starter justfile for a project: a `default` recipe listing the others,
+/// then build, test, lint, fmt and run recipes using the project's tools.
+/// Node projects use the package manager of their lockfile and Python
+/// projects use uv when `uv.lock` is present. Without a project type only
+/// the `default` recipe is generated.
+pub fn scaffold_justfile(project_type: Option<ProjectType>, dir: &Path) -> String {
+    let mut starters = vec![starter(
+        "List available recipes",
+        "default",
+        &["@just --list"],
+    )];
+    match project_type {
+        Some(ProjectType::Rust) => starters.extend([
+            starter("Build the project", "build", &["cargo build"]),
+            starter("Run the tests", "test", &["cargo test"]),
+            starter(
+                "Run clippy and check formatting",
+                "lint",
+                &[
+                    "cargo clippy --all-targets -- -D warnings",
+                    "cargo fmt --check",
+                ],
+            ),
+            starter("Format the code", "fmt", &["cargo fmt"]),
+            starter("Run the binary", "run *args", &["cargo run -- {{ args }}"]),
+        ]),
+        Some(ProjectType::Node) => {
+            let manager = node_package_manager(dir);
+            let command = |script: &str| format!("{manager} {script}");
+            starters.extend([
+                starter("Install dependencies", "install", &[&command("install")]),
+                starter("Build the project", "build", &[&command("run build")]),
+                starter("Run the tests", "test", &[&command("test")]),
+                starter("Lint the code", "lint", &[&command("run lint")]),
+                starter("Start the application", "run", &[&command("start")]),
+            ]);
+        }
+        Some(ProjectType::Python) if dir.join("uv.lock").is_file() => starters.extend([
+            starter("Install dependencies", "install", &["uv sync"]),
+            starter("Build the package", "build", &["uv build"]),
+            starter("Run the tests", "test *args", &["uv run pytest {{ args }}"]),
+            starter(
+                "Lint and check formatting",
+                "lint",
+                &["uv run ruff check .", "uv run ruff format --check ."],
+            ),
+            starter("Format the code", "fmt", &["uv run ruff format ."]),
+            starter(
+                "Run a module",
+                "run module *args",
+                &["uv run python -m {{ module }} {{ args }}"],
+            ),
+        ]),
+        Some(ProjectType::Python) => starters.extend([
+            starter(
+                "Install the package in editable mode",
+                "install",
+                &["python -m pip install -e ."],
+            ),
+            starter("Build the package", "build", &["python -m build"]),
+            starter(
+                "Run the tests",
+                "test *args",
+                &["python -m pytest {{ args }}"],
+            ),
+            starter(
+                "Lint and check formatting",
+                "lint",
+                &["ruff check .", "ruff format --check ."],
+            ),
+            starter("Format the code", "fmt", &["ruff format ."]),
+            starter(
+                "Run a module",
+                "run module *args",
+                &["python -m {{ module }} {{ args }}"],
+            ),
+        ]),
+        Some(ProjectType::Go) => starters.extend([
+            starter("Build the project", "build", &["go build ./..."]),
+            starter("Run the tests", "test", &["go test ./..."]),
+            starter("Vet the code", "lint", &["go vet ./..."]),
+            starter("Format the code", "fmt", &["gofmt -w ."]),
+            starter(
+                "Run the main package",
+                "run *args",
+                &["go run . {{ args }}"],
+            ),
+        ]),
+        None => {}
+    }
+
+    let recipes: Vec<String> = starters
+        .iter()
+        .map(|starter| {
+            let mut recipe = format!("# {}\n{}:\n", starter.doc, starter.header);
+            for line in &starter.body {
+                recipe.push_str(&format!("    {line}\n"));
+            }
+            recipe
+        })
+        .collect();
+    recipes.join("\n")
+}
+
+/// `pnpm`, `yarn` or `bun` when their lockfile is present, otherwise `npm`.
+fn node_package_manager(dir: &Path) -> &'static str {
+    [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+    ]
+    .iter()
+    .find(|(lockfile, _)| dir.join(lockfile).is_file())
+    .map_or("npm", |(_, manager)| manager)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::format_justfile_str;
+    use crate::parser::parse_justfile_str;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_project_types() {
+        let dir = TempDir::new().unwrap();
+        assert!(detect_project_types(dir.path()).is_empty());
+
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        assert_eq!(
+            detect_project_types(dir.path()),
+            vec![ProjectType::Rust, ProjectType::Node]
+        );
+    }
+
+    #[test]
+    fn test_scaffolds_are_formatted_justfiles() {
+        let dir = TempDir::new().unwrap();
+        for project_type in [
+            None,
+            Some(ProjectType::Rust),
+            Some(ProjectType::Node),
+            Some(ProjectType::Python),
+            Some(ProjectType::Go),
+        ] {
+            let justfile = scaffold_justfile(project_type, dir.path());
+            let parsed = parse_justfile_str(&justfile).unwrap();
+            assert_eq!(parsed.recipes[0].name, "default");
+            assert_eq!(format_justfile_str(&justfile).unwrap(), justfile);
+            if project_type.is_some() {
+                for name in ["build", "test", "lint", "run"] {
+                    assert!(
+                        parsed.recipes.iter().any(|recipe| recipe.name == name),
+                        "{project_type:?} has no {name} recipe"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tools_follow_lockfiles() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        fs::write(dir.path().join("uv.lock"), "").unwrap();
+
+        let node = scaffold_justfile(Some(ProjectType::Node), dir.path());
+        assert!(node.contains("test:\n    pnpm test\n"));
+        let python = scaffold_justfile(Some(ProjectType::Python), dir.path());
+        assert!(python.contains("    uv run pytest {{ args }}\n"));
+    }
+}
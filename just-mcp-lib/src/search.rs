@@ -0,0 +1,140 @@
+use regex::{Regex, RegexBuilder};
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+
+use crate::Justfile;
+
+#[derive(Debug, Snafu)]
+pub enum SearchError {
+    #[snafu(display("Invalid search pattern: {}", source))]
+    InvalidPattern { source: regex::Error },
+}
+
+pub type Result<T> = std::result::Result<T, SearchError>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression instead of plain text
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+/// A recipe whose name, documentation, or body matched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RecipeMatch {
+    pub recipe_name: String,
+    pub name_matched: bool,
+    pub documentation_matched: bool,
+    /// Matching body lines
+    pub lines: Vec<MatchedLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MatchedLine {
+    /// 1-based line number in the justfile
+    pub line: usize,
+    pub text: String,
+}
+
+/// Recipes matching `query`, in justfile order.
+pub fn search_recipes(
+    justfile: &Justfile,
+    query: &str,
+    options: SearchOptions,
+) -> Result<Vec<RecipeMatch>> {
+    let pattern = pattern(query, options)?;
+    let matches = justfile
+        .recipes
+        .iter()
+        .map(|recipe| RecipeMatch {
+            recipe_name: recipe.name.clone(),
+            name_matched: pattern.is_match(&recipe.name),
+            documentation_matched: recipe
+                .documentation
+                .as_deref()
+                .is_some_and(|documentation| pattern.is_match(documentation)),
+            lines: recipe
+                .body_lines()
+                .filter(|(_, text)| pattern.is_match(text))
+                .map(|(line, text)| MatchedLine {
+                    line,
+                    text: text.trim().to_string(),
+                })
+                .collect(),
+        })
+        .filter(|found| {
+            found.name_matched || found.documentation_matched || !found.lines.is_empty()
+        })
+        .collect();
+    Ok(matches)
+}
+
+fn pattern(query: &str, options: SearchOptions) -> Result<Regex> {
+    let query = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&query)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .context(InvalidPatternSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    const JUSTFILE: &str = "# Build the Docker image\nimage:\n    docker build -t app .\n\n# Push to the registry\npush: image\n    docker push app\n\ntest:\n    cargo test\n";
+
+    #[test]
+    fn test_plain_search_is_case_insensitive() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let found = search_recipes(&justfile, "DOCKER", SearchOptions::default()).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found[0].documentation_matched);
+        assert_eq!(
+            found[0].lines,
+            vec![MatchedLine {
+                line: 3,
+                text: "docker build -t app .".to_string(),
+            }]
+        );
+        assert!(!found[1].documentation_matched);
+        assert_eq!(found[1].lines[0].line, 7);
+
+        let options = SearchOptions {
+            case_sensitive: true,
+            ..SearchOptions::default()
+        };
+        assert!(
+            search_recipes(&justfile, "DOCKER", options)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_regex_search() {
+        let justfile = parse_justfile_str(JUSTFILE).unwrap();
+        let options = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let found = search_recipes(&justfile, "^(push|test)$", options).unwrap();
+        let names: Vec<&str> = found.iter().map(|m| m.recipe_name.as_str()).collect();
+        assert_eq!(names, vec!["push", "test"]);
+        assert!(found.iter().all(|m| m.name_matched));
+
+        // Regex metacharacters are literal in plain searches
+        let found = search_recipes(&justfile, ".", SearchOptions::default()).unwrap();
+        assert_eq!(found.len(), 1);
+
+        assert!(matches!(
+            search_recipes(&justfile, "(", options),
+            Err(SearchError::InvalidPattern { .. })
+        ));
+    }
+}
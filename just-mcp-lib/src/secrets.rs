@@ -0,0 +1,238 @@
+use regex::Regex;
+use serde::Deserialize;
+use snafu::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::sync::LazyLock;
+
+use crate::Justfile;
+
+/// Keyring service secrets are looked up under without `keyring-service`.
+pub const DEFAULT_KEYRING_SERVICE: &str = "just-mcp";
+
+/// `{{ secret("NAME") }}` in a recipe body.
+static SECRET_CALL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\{\{\s*secret\(\s*["']([A-Za-z_][A-Za-z0-9_]*)["']\s*\)\s*\}\}"#).unwrap()
+});
+
+#[derive(Debug, Snafu)]
+pub enum SecretError {
+    #[snafu(display(
+        "Secret '{}' is neither in the environment nor in the {}",
+        name,
+        provider
+    ))]
+    SecretNotFound { name: String, provider: String },
+
+    #[snafu(display("Failed to read secret '{}' from the {}: {}", name, provider, message))]
+    SecretUnavailable {
+        name: String,
+        provider: String,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, SecretError>;
+
+/// Where the secrets recipes ask for come from, so they never have to be
+/// written into the justfile or a dotenv file.
+pub trait SecretProvider: Debug + Send + Sync {
+    /// Named in errors, e.g. `keyring service 'just-mcp'`
+    fn describe(&self) -> String;
+
+    /// The secret `name`, or `None` when there is no such secret.
+    fn get(&self, name: &str) -> Result<Option<String>>;
+}
+
+/// Secrets from the OS keyring: the macOS Keychain, the Windows Credential
+/// Manager or the Secret Service on Linux, stored under one service name.
+/// Built without the `keyring` feature, every lookup fails.
+#[derive(Debug, Clone)]
+pub struct KeyringProvider {
+    service: String,
+}
+
+impl KeyringProvider {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+}
+
+impl Default for KeyringProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_KEYRING_SERVICE)
+    }
+}
+
+impl SecretProvider for KeyringProvider {
+    fn describe(&self) -> String {
+        format!("keyring service '{}'", self.service)
+    }
+
+    #[cfg(feature = "keyring")]
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        let unavailable = |error: keyring::Error| SecretError::SecretUnavailable {
+            name: name.to_string(),
+            provider: self.describe(),
+            message: error.to_string(),
+        };
+        let entry = keyring::Entry::new(&self.service, name).map_err(unavailable)?;
+        match entry.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(error) => Err(unavailable(error)),
+        }
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        SecretUnavailableSnafu {
+            name,
+            provider: self.describe(),
+            message: "just-mcp was built without the `keyring` feature",
+        }
+        .fail()
+    }
+}
+
+/// Secrets held in memory, for tests and programs embedding the server.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+    secrets: BTreeMap<String, String>,
+}
+
+impl MemoryProvider {
+    pub fn new(secrets: BTreeMap<String, String>) -> Self {
+        Self { secrets }
+    }
+}
+
+impl SecretProvider for MemoryProvider {
+    fn describe(&self) -> String {
+        "in-memory secrets".to_string()
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.secrets.get(name).cloned())
+    }
+}
+
+/// The `[secrets]` section of the config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SecretsConfig {
+    /// Keyring service secrets are stored under; `just-mcp` when absent
+    pub keyring_service: Option<String>,
+    /// Secrets every recipe run gets as environment variables
+    pub env: Vec<String>,
+}
+
+impl SecretsConfig {
+    pub fn provider(&self) -> KeyringProvider {
+        KeyringProvider::new(
+            self.keyring_service
+                .as_deref()
+                .unwrap_or(DEFAULT_KEYRING_SERVICE),
+        )
+    }
+}
+
+/// Names of the `{{ secret("NAME") }}` calls in `text`.
+pub fn secret_references(text: &str) -> BTreeSet<String> {
+    SECRET_CALL
+        .captures_iter(text)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// Replace each `{{ secret("NAME") }}` with `${NAME}`. The value reaches
+/// commands through their environment, so it never shows up in command
+/// lines, echoed commands or dry runs.
+pub fn replace_secret_references(text: &str) -> String {
+    SECRET_CALL.replace_all(text, "$${$1}").into_owned()
+}
+
+/// Secrets referenced by `recipe_name` and the recipes it depends on.
+pub fn recipe_secrets(justfile: &Justfile, recipe_name: &str) -> BTreeSet<String> {
+    let mut secrets = BTreeSet::new();
+    let mut pending = vec![recipe_name.to_string()];
+    let mut seen = BTreeSet::new();
+    while let Some(name) = pending.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        if let Some(recipe) = justfile.recipes.iter().find(|recipe| recipe.name == name) {
+            secrets.extend(secret_references(&recipe.body));
+            pending.extend(recipe.dependencies.iter().cloned());
+        }
+    }
+    secrets
+}
+
+/// Fetch every secret in `names` from `provider`.
+pub fn resolve(
+    provider: &dyn SecretProvider,
+    names: impl IntoIterator<Item = String>,
+) -> Result<BTreeMap<String, String>> {
+    names
+        .into_iter()
+        .map(|name| {
+            let secret = provider.get(&name)?.context(SecretNotFoundSnafu {
+                name: name.clone(),
+                provider: provider.describe(),
+            })?;
+            Ok((name, secret))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_justfile_str;
+
+    #[test]
+    fn test_secret_references() {
+        let body = "curl -H \"Authorization: {{ secret(\"API_TOKEN\") }}\" {{url}}\nscp -i {{secret('DEPLOY_KEY')}} x y";
+        assert_eq!(
+            secret_references(body).into_iter().collect::<Vec<_>>(),
+            vec!["API_TOKEN", "DEPLOY_KEY"]
+        );
+        assert_eq!(
+            replace_secret_references(body),
+            "curl -H \"Authorization: ${API_TOKEN}\" {{url}}\nscp -i ${DEPLOY_KEY} x y"
+        );
+    }
+
+    #[test]
+    fn test_recipe_secrets_follow_dependencies() {
+        let justfile = parse_justfile_str(
+            "login:\n    docker login -p {{ secret(\"REGISTRY_TOKEN\") }}\n\npush: login\n    docker push app\n\nother:\n    echo {{ secret(\"UNUSED\") }}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            recipe_secrets(&justfile, "push")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["REGISTRY_TOKEN"]
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let provider = MemoryProvider::new(BTreeMap::from([(
+            "DEPLOY_KEY".to_string(),
+            "k3y-material".to_string(),
+        )]));
+        let secrets = resolve(&provider, ["DEPLOY_KEY".to_string()]).unwrap();
+        assert_eq!(secrets["DEPLOY_KEY"], "k3y-material");
+
+        let error = resolve(&provider, ["MISSING".to_string()]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Secret 'MISSING' is neither in the environment nor in the in-memory secrets"
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use std::borrow::Cow;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use opentelemetry::trace::Status;
+use opentelemetry::{Context, KeyValue, Value};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SdkTracer, Span, SpanData, SpanProcessor};
+use tracing::level_filters::LevelFilter;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::filter::FromEnvError;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+use crate::redact::Redactor;
+
+/// Secrets masked in every log line, once the config is loaded.
+static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// Environment variable with the log level when `--log-level` is not given.
+pub const LOG_LEVEL_VAR: &str = "MCP_LOG_LEVEL";
+
+/// How server log lines are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `timestamp LEVEL span{field=value}: target: message field=value`
+    #[default]
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format '{name}', expected pretty or json")),
+        }
+    }
+}
+
+/// Which events are logged: those at `level` and above, else as
+/// `MCP_LOG_LEVEL` says, else info and above.
+pub fn log_filter(level: Option<Level>) -> Result<EnvFilter, FromEnvError> {
+    match level {
+        Some(level) => Ok(EnvFilter::new(level.as_str())),
+        None => EnvFilter::builder()
+            .with_default_directive(LevelFilter::INFO.into())
+            .with_env_var(LOG_LEVEL_VAR)
+            .from_env(),
+    }
+}
+
+/// The server's own log: each event with the spans it happened in, such as
+/// the MCP request and the recipe execution, as `filter` lets through. With
+/// a tracer, info spans are also exported to OpenTelemetry whatever the
+/// filter, with the events logged in them.
+pub fn subscriber(
+    filter: EnvFilter,
+    format: LogFormat,
+    writer: Box<dyn Write + Send>,
+    tracer: Option<SdkTracer>,
+) -> impl Subscriber + Send + Sync {
+    let writer = RedactingWriter(Mutex::new(writer));
+    let lines = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(writer);
+    let lines: Box<dyn Layer<Registry> + Send + Sync> = match format {
+        LogFormat::Pretty => lines.boxed(),
+        LogFormat::Json => lines
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .boxed(),
+    };
+    let spans = tracer.map(|tracer| {
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(LevelFilter::INFO)
+    });
+    tracing_subscriber::registry()
+        .with(lines.with_filter(filter))
+        .with(spans)
+}
+
+/// Install `subscriber` for the whole process; false when one is already
+/// installed.
+pub fn init(
+    filter: EnvFilter,
+    format: LogFormat,
+    writer: Box<dyn Write + Send>,
+    tracer: Option<SdkTracer>,
+) -> bool {
+    tracing::subscriber::set_global_default(subscriber(filter, format, writer, tracer)).is_ok()
+}
+
+/// Mask the secrets `redactor` knows in log lines and exported spans from
+/// now on; only the first call has an effect.
+pub fn set_redactor(redactor: Redactor) {
+    let _ = REDACTOR.set(redactor);
+}
+
+/// `text` with the known secrets masked.
+fn redact(text: &str) -> Cow<'_, str> {
+    match REDACTOR.get() {
+        Some(redactor) => redactor.redact(text),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Writes each formatted line to the log, secrets masked.
+struct RedactingWriter(Mutex<Box<dyn Write + Send>>);
+
+impl<'a> MakeWriter<'a> for RedactingWriter {
+    type Writer = RedactedLine<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactedLine {
+            line: Vec::new(),
+            writer: &self.0,
+        }
+    }
+}
+
+/// One log line, collected so a secret is masked even when it is written
+/// in pieces, and written out when dropped.
+struct RedactedLine<'a> {
+    line: Vec<u8>,
+    writer: &'a Mutex<Box<dyn Write + Send>>,
+}
+
+impl Write for RedactedLine<'_> {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.line.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RedactedLine<'_> {
+    fn drop(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+        let line = String::from_utf8_lossy(&self.line);
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer.write_all(redact(&line).as_bytes());
+        let _ = writer.flush();
+    }
+}
+
+/// Span processor masking secrets in the attributes, events and status of
+/// finished spans before `P` exports them.
+#[derive(Debug)]
+pub struct RedactingProcessor<P>(pub P);
+
+impl<P: SpanProcessor> SpanProcessor for RedactingProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.0.on_start(span, cx);
+    }
+
+    fn on_end(&self, mut span: SpanData) {
+        redact_attributes(&mut span.attributes);
+        for event in span.events.events.iter_mut() {
+            if let Cow::Owned(name) = redact(&event.name) {
+                event.name = name.into();
+            }
+            redact_attributes(&mut event.attributes);
+        }
+        if let Status::Error { description } = &span.status
+            && let Cow::Owned(description) = redact(description)
+        {
+            span.status = Status::error(description);
+        }
+        self.0.on_end(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.0.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.0.shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.0.set_resource(resource);
+    }
+}
+
+fn redact_attributes(attributes: &mut [KeyValue]) {
+    for attribute in attributes {
+        if let Value::String(value) = &attribute.value
+            && let Cow::Owned(masked) = redact(value.as_str())
+        {
+            attribute.value = masked.into();
+        }
+    }
+}
+
+/// UTC time such as `2025-07-01T09:30:00.123Z`.
+pub(crate) fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (hour, minute, second) = (seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}.{:03}Z",
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Writer collecting output for assertions.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture(format: LogFormat, log: impl FnOnce()) -> Vec<String> {
+        let buffer = Buffer::default();
+        let subscriber = subscriber(
+            log_filter(Some(Level::INFO)).unwrap(),
+            format,
+            Box::new(buffer.clone()),
+            None,
+        );
+        tracing::subscriber::with_default(subscriber, log);
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        output.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_json_lines_name_their_spans() {
+        let lines = capture(LogFormat::Json, || {
+            let request = tracing::info_span!("tool_call", tool = "run_recipe");
+            let _request = request.enter();
+            let execution = tracing::info_span!("execution", recipe = "build");
+            let _execution = execution.enter();
+            tracing::info!(exit_code = 0, "recipe finished");
+            tracing::debug!("below the level");
+        });
+        assert_eq!(lines.len(), 1);
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "recipe finished");
+        assert_eq!(line["fields"]["exit_code"], 0);
+        assert_eq!(
+            line["spans"],
+            serde_json::json!([
+                { "name": "tool_call", "tool": "run_recipe" },
+                { "name": "execution", "recipe": "build" },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_pretty_lines() {
+        let lines = capture(LogFormat::Pretty, || {
+            tracing::warn!(target: "just_mcp", path = "justfile", "parse failed");
+            let _span = tracing::info_span!("session", client = "127.0.0.1:5000").entered();
+            tracing::info!(target: "just_mcp", "connected");
+        });
+        assert!(lines[0].ends_with("  WARN just_mcp: parse failed path=\"justfile\""));
+        assert!(
+            lines[1].ends_with(" INFO session{client=\"127.0.0.1:5000\"}: just_mcp: connected")
+        );
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let filter = log_filter(Some(Level::WARN)).unwrap();
+        assert_eq!(filter.max_level_hint(), Some(LevelFilter::WARN));
+    }
+
+    #[test]
+    fn test_spans_are_exported() {
+        use opentelemetry::trace::TracerProvider as _;
+        use opentelemetry_sdk::trace::{
+            InMemorySpanExporter, SdkTracerProvider, SimpleSpanProcessor,
+        };
+
+        set_redactor(
+            Redactor::new(&crate::redact::default_secret_patterns())
+                .with_variables([("DEPLOY_TOKEN", "hunter2-secret")]),
+        );
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(RedactingProcessor(SimpleSpanProcessor::new(
+                exporter.clone(),
+            )))
+            .build();
+        let subscriber = subscriber(
+            log_filter(Some(Level::WARN)).unwrap(),
+            LogFormat::Json,
+            Box::new(Buffer::default()),
+            Some(provider.tracer("test")),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let request = tracing::info_span!("tool_call", tool = "run_recipe");
+            let _request = request.enter();
+            let execution = tracing::info_span!(
+                "execution",
+                recipe = "build",
+                token = "hunter2-secret",
+                exit_code = tracing::field::Empty,
+                otel.status_message = tracing::field::Empty
+            );
+            let _execution = execution.enter();
+            execution.record("exit_code", 2);
+            execution.record("otel.status_message", "exit code 2");
+            tracing::warn!("recipe failed");
+            tracing::debug!("not exported");
+        });
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let names: Vec<&str> = spans.iter().map(|span| span.name.as_ref()).collect();
+        assert_eq!(names, ["execution", "tool_call"]);
+        let (execution, request) = (&spans[0], &spans[1]);
+        assert_eq!(execution.parent_span_id, request.span_context.span_id());
+        assert_eq!(
+            execution.span_context.trace_id(),
+            request.span_context.trace_id()
+        );
+        assert!(
+            execution
+                .attributes
+                .contains(&KeyValue::new("recipe", "build"))
+        );
+        assert!(
+            execution
+                .attributes
+                .contains(&KeyValue::new("token", "****"))
+        );
+        assert!(
+            execution
+                .attributes
+                .contains(&KeyValue::new("exit_code", 2_i64))
+        );
+        assert_eq!(execution.status, Status::error("exit code 2"));
+        let events: Vec<&str> = execution
+            .events
+            .iter()
+            .map(|event| event.name.as_ref())
+            .collect();
+        assert_eq!(events, ["recipe failed"]);
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_751_362_200_123);
+        assert_eq!(rfc3339(time), "2025-07-01T09:30:00.123Z");
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    }
+}
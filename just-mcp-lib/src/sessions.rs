@@ -0,0 +1,140 @@
+use rmcp::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::history::unix_millis;
+
+/// A connected client, as listed by `list_sessions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionInfo {
+    pub id: u64,
+    /// Peer address, `stdio`, or the socket path for Unix socket clients
+    pub client: String,
+    /// Milliseconds since the Unix epoch
+    pub connected_at_ms: u64,
+    pub tool_calls: u64,
+}
+
+/// The client sessions of a long-running server.
+#[derive(Debug)]
+pub struct SessionRegistry {
+    state: Mutex<State>,
+    started_at: SystemTime,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: u64,
+    sessions: BTreeMap<u64, SessionInfo>,
+}
+
+impl Default for SessionRegistry {
+    fn default() -> Self {
+        Self {
+            state: Mutex::default(),
+            started_at: SystemTime::now(),
+        }
+    }
+}
+
+impl SessionRegistry {
+    /// Record a client connecting; it is listed until the guard is dropped.
+    pub fn open(self: &Arc<Self>, client: impl Into<String>) -> SessionGuard {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.sessions.insert(
+            id,
+            SessionInfo {
+                id,
+                client: client.into(),
+                connected_at_ms: unix_millis(SystemTime::now()),
+                tool_calls: 0,
+            },
+        );
+        SessionGuard {
+            registry: self.clone(),
+            id,
+        }
+    }
+
+    pub fn record_tool_call(&self, id: u64) {
+        if let Some(session) = self.state.lock().unwrap().sessions.get_mut(&id) {
+            session.tool_calls += 1;
+        }
+    }
+
+    /// Connected sessions, oldest first.
+    pub fn list(&self) -> Vec<SessionInfo> {
+        self.state
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Sessions opened since the server started, including closed ones.
+    pub fn served(&self) -> u64 {
+        self.state.lock().unwrap().next_id
+    }
+
+    pub fn uptime_ms(&self) -> u64 {
+        self.started_at
+            .elapsed()
+            .map_or(0, |elapsed| elapsed.as_millis() as u64)
+    }
+}
+
+/// A session listed in a `SessionRegistry`, removed on drop.
+#[derive(Debug)]
+pub struct SessionGuard {
+    registry: Arc<SessionRegistry>,
+    id: u64,
+}
+
+impl SessionGuard {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.registry
+            .state
+            .lock()
+            .unwrap()
+            .sessions
+            .remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_are_listed_while_open() {
+        let registry = Arc::new(SessionRegistry::default());
+        let editor = registry.open("/run/just-mcp.sock");
+        let agent = registry.open("127.0.0.1:50312");
+        registry.record_tool_call(agent.id());
+        registry.record_tool_call(agent.id());
+
+        let sessions = registry.list();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, editor.id());
+        assert_eq!(sessions[1].client, "127.0.0.1:50312");
+        assert_eq!(sessions[1].tool_calls, 2);
+
+        drop(editor);
+        let sessions = registry.list();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, agent.id());
+        assert_eq!(registry.served(), 2);
+    }
+}
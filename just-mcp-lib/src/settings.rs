@@ -0,0 +1,180 @@
+use serde_json::Value;
+use snafu::prelude::*;
+use std::path::PathBuf;
+
+use crate::audit::cut_string;
+
+pub const MAX_MESSAGE_SIZE_VAR: &str = "MCP_MAX_MESSAGE_SIZE";
+pub const TEMP_DIR_VAR: &str = "MCP_TEMP_DIR";
+pub const DATA_DIR_VAR: &str = "MCP_DATA_DIR";
+
+/// SQLite database in `MCP_DATA_DIR` the execution history is kept in.
+pub const HISTORY_FILE: &str = "history.db";
+
+/// Strings are not cut below this many bytes to make a result fit.
+const MIN_STRING: usize = 64;
+
+/// Room left for the `… [N bytes truncated]` note when cutting a string.
+const CUT_NOTE: usize = 32;
+
+#[derive(Debug, Snafu)]
+pub enum SettingsError {
+    #[snafu(display("Invalid {}: '{}' is not a number of bytes", var, value))]
+    InvalidSize { var: &'static str, value: String },
+
+    #[snafu(display("Failed to create {} {}: {}", var, path.display(), source))]
+    CreateDir {
+        var: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, SettingsError>;
+
+/// Server settings from the `MCP_*` environment variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct McpSettings {
+    /// `MCP_MAX_MESSAGE_SIZE`: largest tool result, in bytes of JSON
+    pub max_message_size: Option<usize>,
+    /// `MCP_TEMP_DIR`: given to recipes as `TMPDIR`, for the scripts and
+    /// scratch files they write
+    pub temp_dir: Option<PathBuf>,
+    /// `MCP_DATA_DIR`: where the execution history is kept across restarts
+    pub data_dir: Option<PathBuf>,
+}
+
+impl McpSettings {
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Settings from `vars`; empty values count as unset.
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut settings = Self::default();
+        for (var, value) in vars {
+            if value.is_empty() {
+                continue;
+            }
+            match var.as_str() {
+                MAX_MESSAGE_SIZE_VAR => {
+                    let size = value.trim().parse().ok().context(InvalidSizeSnafu {
+                        var: MAX_MESSAGE_SIZE_VAR,
+                        value: value.clone(),
+                    })?;
+                    settings.max_message_size = Some(size);
+                }
+                TEMP_DIR_VAR => settings.temp_dir = Some(PathBuf::from(value)),
+                DATA_DIR_VAR => settings.data_dir = Some(PathBuf::from(value)),
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Create the temp and data directories if they are missing.
+    pub fn create_dirs(&self) -> Result<()> {
+        for (var, dir) in [
+            (TEMP_DIR_VAR, &self.temp_dir),
+            (DATA_DIR_VAR, &self.data_dir),
+        ] {
+            if let Some(path) = dir {
+                std::fs::create_dir_all(path).context(CreateDirSnafu { var, path })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The execution history database, when `MCP_DATA_DIR` is set.
+    pub fn history_path(&self) -> Option<PathBuf> {
+        self.data_dir.as_ref().map(|dir| dir.join(HISTORY_FILE))
+    }
+}
+
+/// Cut the longest strings in `value` until it serializes to at most
+/// `max_bytes`. False when it cannot fit, e.g. when it holds many short
+/// strings.
+pub fn fit_to_size(value: &mut Value, max_bytes: usize) -> bool {
+    loop {
+        let size = value.to_string().len();
+        if size <= max_bytes {
+            return true;
+        }
+        let mut strings = Vec::new();
+        collect_strings(value, &mut strings);
+        let Some(longest) = strings.into_iter().max_by_key(|text| text.len()) else {
+            return false;
+        };
+        let before = longest.len();
+        let keep = before
+            .saturating_sub(size - max_bytes + CUT_NOTE)
+            .max(MIN_STRING);
+        cut_string(longest, keep);
+        if longest.len() >= before {
+            return false;
+        }
+    }
+}
+
+fn collect_strings<'a>(value: &'a mut Value, strings: &mut Vec<&'a mut String>) {
+    match value {
+        Value::String(text) => strings.push(text),
+        Value::Array(values) => {
+            for value in values {
+                collect_strings(value, strings);
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values_mut() {
+                collect_strings(value, strings);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_vars() {
+        let settings = McpSettings::from_vars([
+            ("MCP_MAX_MESSAGE_SIZE".to_string(), "65536".to_string()),
+            ("MCP_TEMP_DIR".to_string(), "/var/tmp/just-mcp".to_string()),
+            ("MCP_DATA_DIR".to_string(), String::new()),
+            ("MCP_LOG_LEVEL".to_string(), "debug".to_string()),
+        ])
+        .unwrap();
+        assert_eq!(settings.max_message_size, Some(65536));
+        assert_eq!(settings.temp_dir, Some(PathBuf::from("/var/tmp/just-mcp")));
+        assert_eq!(settings.data_dir, None);
+        assert_eq!(settings.history_path(), None);
+
+        let error =
+            McpSettings::from_vars([("MCP_MAX_MESSAGE_SIZE".to_string(), "1MB".to_string())])
+                .unwrap_err();
+        assert!(error.to_string().contains("MCP_MAX_MESSAGE_SIZE"));
+    }
+
+    #[test]
+    fn test_fit_to_size() {
+        let mut value = serde_json::json!({
+            "recipe_name": "build",
+            "stdout": "o".repeat(5000),
+            "stderr": "e".repeat(1000),
+        });
+        assert!(fit_to_size(&mut value, 1000));
+        assert!(value.to_string().len() <= 1000);
+        assert_eq!(value["recipe_name"], "build");
+        assert!(
+            value["stdout"]
+                .as_str()
+                .unwrap()
+                .contains("bytes truncated]")
+        );
+
+        let mut many = serde_json::json!(vec!["short"; 100]);
+        assert!(!fit_to_size(&mut many, 100));
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::executor::CancelToken;
+
+/// Grace period used without `--shutdown-grace-period`.
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// How long cancelled executions get to unwind once their process groups
+/// are killed.
+const CANCEL_WAIT: Duration = Duration::from_secs(5);
+
+/// Tracks the recipe executions in flight across every session, so that a
+/// shutdown can stop new ones and wait for, or cancel, the rest.
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    state: Mutex<State>,
+    /// Woken whenever the last execution in flight finishes
+    idle: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    draining: bool,
+    next_id: u64,
+    running: HashMap<u64, CancelToken>,
+}
+
+/// How a drain ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Executions still running when the grace period ran out
+    pub cancelled: usize,
+    /// Executions that had not unwound after being cancelled
+    pub abandoned: usize,
+}
+
+impl Shutdown {
+    /// True once `drain` has started; new executions are refused.
+    pub fn is_draining(&self) -> bool {
+        self.state.lock().unwrap().draining
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().running.len()
+    }
+
+    /// Register an execution, cancelled through `cancel` if it outlives the
+    /// grace period. `None` once draining has started.
+    pub fn track(self: &Arc<Self>, cancel: CancelToken) -> Option<InFlight> {
+        let mut state = self.state.lock().unwrap();
+        if state.draining {
+            return None;
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.running.insert(id, cancel);
+        Some(InFlight {
+            shutdown: self.clone(),
+            id,
+        })
+    }
+
+    /// Refuse new executions, wait up to `grace` for those in flight to
+    /// finish, then cancel the rest, killing their process groups.
+    pub async fn drain(&self, grace: Duration) -> DrainReport {
+        self.state.lock().unwrap().draining = true;
+        let mut report = DrainReport::default();
+        if !self.wait_idle(grace).await {
+            let running: Vec<CancelToken> = self
+                .state
+                .lock()
+                .unwrap()
+                .running
+                .values()
+                .cloned()
+                .collect();
+            report.cancelled = running.len();
+            for cancel in running {
+                cancel.cancel();
+            }
+            if !self.wait_idle(CANCEL_WAIT).await {
+                report.abandoned = self.in_flight();
+            }
+        }
+        report
+    }
+
+    /// Wait until nothing is in flight; false if `timeout` passes first.
+    async fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let idle = self.idle.notified();
+            tokio::pin!(idle);
+            // Register before checking so a finish in between is not missed
+            idle.as_mut().enable();
+            if self.in_flight() == 0 {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                return self.in_flight() == 0;
+            }
+        }
+    }
+}
+
+/// An execution registered with `Shutdown::track`, unregistered on drop.
+#[derive(Debug)]
+pub struct InFlight {
+    shutdown: Arc<Shutdown>,
+    id: u64,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut state = self.shutdown.state.lock().unwrap();
+        state.running.remove(&self.id);
+        if state.running.is_empty() {
+            self.shutdown.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_executions() {
+        let shutdown = Arc::new(Shutdown::default());
+        let in_flight = shutdown.track(CancelToken::new()).unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(in_flight);
+        });
+        let report = shutdown.drain(Duration::from_secs(5)).await;
+        assert_eq!(report, DrainReport::default());
+        assert!(shutdown.is_draining());
+        assert!(shutdown.track(CancelToken::new()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_drain_cancels_after_grace_period() {
+        let shutdown = Arc::new(Shutdown::default());
+        let cancel = CancelToken::new();
+        let in_flight = shutdown.track(cancel.clone()).unwrap();
+        let watcher = cancel.clone();
+        tokio::spawn(async move {
+            while !watcher.is_cancelled() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            drop(in_flight);
+        });
+        let report = shutdown.drain(Duration::from_millis(50)).await;
+        assert!(cancel.is_cancelled());
+        assert_eq!(
+            report,
+            DrainReport {
+                cancelled: 1,
+                abandoned: 0
+            }
+        );
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+}
@@ -0,0 +1,264 @@
+use opentelemetry::KeyValue;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{ExporterBuildError, Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::{BatchSpanProcessor, SdkTracer, SdkTracerProvider};
+use sha2::{Digest, Sha256};
+use snafu::prelude::*;
+use std::sync::OnceLock;
+
+use crate::server_log::RedactingProcessor;
+
+/// `service.name` of the exported spans unless `OTEL_SERVICE_NAME` or
+/// `OTEL_RESOURCE_ATTRIBUTES` names the service.
+pub const SERVICE_NAME: &str = "just-mcp";
+
+/// Salt for `args_hash`. Unset, each process picks a random one, so
+/// digests only group runs until the server restarts.
+pub const ARGS_HASH_SALT_VAR: &str = "MCP_ARGS_HASH_SALT";
+
+/// `None` when no salt is configured and no random one could be picked.
+static ARGS_HASH_SALT: OnceLock<Option<Vec<u8>>> = OnceLock::new();
+
+#[derive(Debug, Snafu)]
+pub enum TelemetryError {
+    #[snafu(display(
+        "Unsupported OTLP protocol '{}', expected http/protobuf or http/json",
+        protocol
+    ))]
+    UnsupportedProtocol { protocol: String },
+
+    #[snafu(display("Failed to create the OTLP span exporter: {}", source))]
+    Exporter { source: ExporterBuildError },
+}
+
+pub type Result<T> = std::result::Result<T, TelemetryError>;
+
+/// Whether and how spans are exported, from the standard `OTEL_*`
+/// environment variables. The exporter reads the endpoint, headers and
+/// timeout itself, and the SDK the sampler, batching and resource ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetrySettings {
+    pub protocol: Protocol,
+    /// Neither `OTEL_SERVICE_NAME` nor a `service.name` resource attribute
+    /// is set
+    pub default_service_name: bool,
+}
+
+impl TelemetrySettings {
+    pub fn from_env() -> Result<Option<Self>> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// `None` when export is off: no OTLP endpoint is set and
+    /// `OTEL_TRACES_EXPORTER` does not ask for `otlp`, or the SDK is
+    /// disabled with `OTEL_SDK_DISABLED=true`. Empty values count as unset.
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Result<Option<Self>> {
+        let vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(_, value)| !value.trim().is_empty())
+            .collect();
+        let var = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.trim())
+        };
+
+        if var("OTEL_SDK_DISABLED").is_some_and(|value| value.eq_ignore_ascii_case("true")) {
+            return Ok(None);
+        }
+        let exporters = var("OTEL_TRACES_EXPORTER");
+        if exporters.is_some_and(|names| !names.split(',').any(|name| name.trim() == "otlp")) {
+            return Ok(None);
+        }
+        let endpoint = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_ENDPOINT"));
+        if endpoint.is_none() && exporters.is_none() {
+            return Ok(None);
+        }
+
+        let protocol = match var("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_PROTOCOL"))
+        {
+            None | Some("http/protobuf") => Protocol::HttpBinary,
+            Some("http/json") => Protocol::HttpJson,
+            Some(protocol) => {
+                return UnsupportedProtocolSnafu { protocol }.fail();
+            }
+        };
+        let default_service_name = var("OTEL_SERVICE_NAME").is_none()
+            && !var("OTEL_RESOURCE_ATTRIBUTES").is_some_and(|attributes| {
+                attributes
+                    .split(',')
+                    .any(|pair| pair.trim_start().starts_with("service.name="))
+            });
+        Ok(Some(Self {
+            protocol,
+            default_service_name,
+        }))
+    }
+}
+
+/// Exports the server's spans over OTLP in the background. Call
+/// `shutdown` before the process exits, or the last batch is lost.
+#[derive(Debug, Clone)]
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Telemetry {
+    pub fn new(settings: &TelemetrySettings) -> Result<Self> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_protocol(settings.protocol)
+            .build()
+            .context(ExporterSnafu)?;
+        let mut resource = Resource::builder()
+            .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")));
+        if settings.default_service_name {
+            resource = resource.with_service_name(SERVICE_NAME);
+        }
+        // Secrets are masked before spans leave the process
+        let processor = RedactingProcessor(BatchSpanProcessor::builder(exporter).build());
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(processor)
+            .with_resource(resource.build())
+            .build();
+        Ok(Self { provider })
+    }
+
+    /// Telemetry configured by the `OTEL_*` variables, if any.
+    pub fn from_env() -> Result<Option<Self>> {
+        TelemetrySettings::from_env()?
+            .map(|settings| Self::new(&settings))
+            .transpose()
+    }
+
+    pub fn tracer(&self) -> SdkTracer {
+        self.provider.tracer(SERVICE_NAME)
+    }
+
+    /// Export the spans still queued and stop.
+    pub fn shutdown(&self) {
+        if let Err(error) = self.provider.shutdown() {
+            tracing::warn!(%error, "failed to export the remaining spans");
+        }
+    }
+}
+
+/// Hex digest of a recipe's arguments, so executions with the same
+/// arguments can be grouped without exporting the arguments themselves.
+/// The digest is salted per deployment, so short or common arguments
+/// cannot be recovered by hashing guesses. `None` when there is no salt:
+/// an unsalted digest would give the arguments away.
+pub fn args_hash(args: &[String]) -> Option<String> {
+    let salt = ARGS_HASH_SALT.get_or_init(|| match std::env::var(ARGS_HASH_SALT_VAR) {
+        Ok(salt) if !salt.is_empty() => Some(salt.into_bytes()),
+        _ => {
+            let mut salt = vec![0; 32];
+            match getrandom::fill(&mut salt) {
+                Ok(()) => Some(salt),
+                Err(error) => {
+                    tracing::warn!(%error, "no random args_hash salt, leaving args_hash out");
+                    None
+                }
+            }
+        }
+    });
+    Some(salted_args_hash(salt.as_deref()?, args))
+}
+
+/// First 128 bits of the SHA-256 of `salt` and the length-prefixed `args`.
+fn salted_args_hash(salt: &[u8], args: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    for arg in args {
+        hasher.update((arg.len() as u64).to_le_bytes());
+        hasher.update(arg.as_bytes());
+    }
+    hasher.finalize()[..16]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(vars: &[(&str, &str)]) -> Result<Option<TelemetrySettings>> {
+        TelemetrySettings::from_vars(
+            vars.iter()
+                .map(|(var, value)| (var.to_string(), value.to_string())),
+        )
+    }
+
+    #[test]
+    fn test_settings_from_vars() {
+        assert_eq!(settings(&[]).unwrap(), None);
+        assert_eq!(
+            settings(&[("OTEL_EXPORTER_OTLP_ENDPOINT", "")]).unwrap(),
+            None
+        );
+
+        let enabled = settings(&[("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318")])
+            .unwrap()
+            .unwrap();
+        assert_eq!(enabled.protocol, Protocol::HttpBinary);
+        assert!(enabled.default_service_name);
+
+        let enabled = settings(&[
+            ("OTEL_TRACES_EXPORTER", "otlp"),
+            ("OTEL_EXPORTER_OTLP_PROTOCOL", "http/json"),
+            (
+                "OTEL_RESOURCE_ATTRIBUTES",
+                "deployment.environment=ci,service.name=mcp",
+            ),
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(enabled.protocol, Protocol::HttpJson);
+        assert!(!enabled.default_service_name);
+
+        for disabled in [
+            [
+                ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318"),
+                ("OTEL_SDK_DISABLED", "true"),
+            ],
+            [
+                ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318"),
+                ("OTEL_TRACES_EXPORTER", "none"),
+            ],
+        ] {
+            assert_eq!(settings(&disabled).unwrap(), None);
+        }
+
+        let error = settings(&[
+            (
+                "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                "http://collector:4317",
+            ),
+            ("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL", "grpc"),
+        ])
+        .unwrap_err();
+        assert!(matches!(error, TelemetryError::UnsupportedProtocol { .. }));
+    }
+
+    #[test]
+    fn test_args_hash() {
+        let args = vec!["prod".to_string(), "--force".to_string()];
+        let hash = args_hash(&args).unwrap();
+        assert_eq!(args_hash(&args.clone()), Some(hash.clone()));
+        assert_ne!(args_hash(&args[..1]), Some(hash.clone()));
+        assert_eq!(hash.len(), 32);
+
+        // The salt changes every digest, and argument boundaries count
+        let salted = salted_args_hash(b"deployment-a", &args);
+        assert_eq!(salted, salted_args_hash(b"deployment-a", &args));
+        assert_ne!(salted, salted_args_hash(b"deployment-b", &args));
+        assert_ne!(
+            salted_args_hash(b"deployment-a", &["ab".to_string(), "c".to_string()]),
+            salted_args_hash(b"deployment-a", &["a".to_string(), "bc".to_string()])
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use serde::Deserialize;
+use snafu::prelude::*;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Prefix of the environment variables setting a tool's timeout in seconds,
+/// e.g. `MCP_TOOL_TIMEOUT_RUN_RECIPE=300` for `run_recipe`.
+pub const TOOL_TIMEOUT_VAR_PREFIX: &str = "MCP_TOOL_TIMEOUT_";
+
+/// Key of the timeout for tools without one of their own.
+pub const DEFAULT_TIMEOUT_KEY: &str = "default";
+
+#[derive(Debug, Snafu)]
+pub enum TimeoutError {
+    #[snafu(display("Invalid {}: '{}' is not a number of seconds", var, value))]
+    InvalidSeconds { var: String, value: String },
+}
+
+pub type Result<T> = std::result::Result<T, TimeoutError>;
+
+/// How long each tool call may take, from the config's `[tool-timeouts]`
+/// table and `MCP_TOOL_TIMEOUT_*` variables. Keys are tool names, such as
+/// `run_recipe` or `just_build`, or `default`; 0 means no timeout.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct ToolTimeouts {
+    seconds: BTreeMap<String, u64>,
+}
+
+impl ToolTimeouts {
+    /// Set the timeout of `tool`, or of every tool for `default`.
+    pub fn set(&mut self, tool: impl Into<String>, seconds: u64) {
+        self.seconds.insert(tool.into(), seconds);
+    }
+
+    /// Override timeouts with `MCP_TOOL_TIMEOUT_<TOOL>` variables among
+    /// `vars`; `MCP_TOOL_TIMEOUT_DEFAULT` sets the default.
+    pub fn with_env_vars(
+        mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        for (var, value) in vars {
+            let Some(tool) = var.strip_prefix(TOOL_TIMEOUT_VAR_PREFIX) else {
+                continue;
+            };
+            let seconds = value.trim().parse().ok().context(InvalidSecondsSnafu {
+                var: var.clone(),
+                value: value.clone(),
+            })?;
+            self.set(tool.to_lowercase(), seconds);
+        }
+        Ok(self)
+    }
+
+    /// How long a call of `tool` may take, if it is limited.
+    pub fn get(&self, tool: &str) -> Option<Duration> {
+        self.seconds
+            .get(tool)
+            .or_else(|| self.seconds.get(DEFAULT_TIMEOUT_KEY))
+            .filter(|seconds| **seconds > 0)
+            .map(|seconds| Duration::from_secs(*seconds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_tool_timeouts() {
+        let mut timeouts = ToolTimeouts::default();
+        assert_eq!(timeouts.get("run_recipe"), None);
+
+        timeouts.set("run_recipe", 300);
+        timeouts.set(DEFAULT_TIMEOUT_KEY, 30);
+        timeouts.set("start_recipe", 0);
+        assert_eq!(timeouts.get("run_recipe"), Some(Duration::from_secs(300)));
+        assert_eq!(timeouts.get("list_recipes"), Some(Duration::from_secs(30)));
+        assert_eq!(timeouts.get("start_recipe"), None);
+    }
+
+    #[test]
+    fn test_env_vars_override_config() {
+        let mut timeouts = ToolTimeouts::default();
+        timeouts.set("validate_justfile", 60);
+        let timeouts = timeouts
+            .with_env_vars(vars(&[
+                ("MCP_TOOL_TIMEOUT_VALIDATE_JUSTFILE", "10"),
+                ("MCP_TOOL_TIMEOUT_JUST_BUILD", " 600 "),
+                ("MCP_LOG_LEVEL", "debug"),
+            ]))
+            .unwrap();
+        assert_eq!(
+            timeouts.get("validate_justfile"),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(timeouts.get("just_build"), Some(Duration::from_secs(600)));
+
+        let error = ToolTimeouts::default()
+            .with_env_vars(vars(&[("MCP_TOOL_TIMEOUT_RUN_RECIPE", "5m")]))
+            .unwrap_err();
+        assert!(error.to_string().contains("MCP_TOOL_TIMEOUT_RUN_RECIPE"));
+    }
+}
@@ -0,0 +1,66 @@
+use snafu::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::pki_types::pem::{self, PemObject};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+
+#[derive(Debug, Snafu)]
+pub enum TlsError {
+    #[snafu(display("Failed to read certificates from {}: {}", path.display(), source))]
+    CertificateRead { path: PathBuf, source: pem::Error },
+
+    #[snafu(display("No certificates found in {}", path.display()))]
+    NoCertificates { path: PathBuf },
+
+    #[snafu(display("Failed to read private key from {}: {}", path.display(), source))]
+    KeyRead { path: PathBuf, source: pem::Error },
+
+    #[snafu(display("Invalid TLS certificate or key: {}", source))]
+    InvalidConfig { source: rustls::Error },
+}
+
+pub type Result<T> = std::result::Result<T, TlsError>;
+
+/// How long a client has to finish the TLS handshake after connecting.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Acceptor terminating TLS with the PEM certificate chain in `cert`, leaf
+/// first, and the PEM private key in `key`.
+pub fn load_acceptor(cert: &Path, key: &Path) -> Result<TlsAcceptor> {
+    let certificates = CertificateDer::pem_file_iter(cert)
+        .and_then(|certificates| certificates.collect::<std::result::Result<Vec<_>, _>>())
+        .context(CertificateReadSnafu { path: cert })?;
+    ensure!(!certificates.is_empty(), NoCertificatesSnafu { path: cert });
+    let key = PrivateKeyDer::from_pem_file(key).context(KeyReadSnafu { path: key })?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| {
+            builder
+                .with_no_client_auth()
+                .with_single_cert(certificates, key)
+        })
+        .context(InvalidConfigSnafu)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cert = dir.path().join("cert.pem");
+        let key = dir.path().join("key.pem");
+        let error = load_acceptor(&cert, &key).err().unwrap();
+        assert!(matches!(error, TlsError::CertificateRead { .. }));
+
+        std::fs::write(&cert, "not a certificate\n").unwrap();
+        let error = load_acceptor(&cert, &key).err().unwrap();
+        assert!(matches!(error, TlsError::NoCertificates { .. }));
+    }
+}
@@ -0,0 +1,160 @@
+use rmcp::ServiceExt;
+use snafu::prelude::*;
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::TlsAcceptor;
+use tracing::Instrument;
+
+use crate::audit::AuditedStream;
+use crate::mcp_server::JustMcpServer;
+use crate::tls::HANDSHAKE_TIMEOUT;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum TransportError {
+    #[snafu(display("Failed to listen on {}: {}", address, source))]
+    Bind {
+        address: String,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("{} exists and is not a socket", path.display()))]
+    NotASocket { path: std::path::PathBuf },
+
+    #[snafu(display("Another server is already listening on {}", path.display()))]
+    AlreadyRunning { path: std::path::PathBuf },
+}
+
+pub type Result<T> = std::result::Result<T, TransportError>;
+
+/// How long a listener waits after failing to accept a connection.
+const ACCEPT_BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Log a failed accept and wait a little before the next. Accept fails
+/// when the process runs out of file descriptors or a client resets the
+/// connection before it is accepted; neither should stop the listener.
+pub(crate) async fn accept_failed(error: std::io::Error) {
+    tracing::warn!(%error, "failed to accept a connection");
+    tokio::time::sleep(ACCEPT_BACKOFF).await;
+}
+
+/// Protection for the listeners reachable over the network: the TCP
+/// transport and the HTTP endpoints.
+#[derive(Clone, Default)]
+pub struct NetworkOptions {
+    /// Bearer token HTTP clients must present. The TCP transport has no
+    /// headers to carry one.
+    pub auth_token: Option<String>,
+    /// Terminates TLS on every connection when set
+    pub tls: Option<TlsAcceptor>,
+}
+
+/// Serve MCP on a Unix domain socket, one session per connection, so
+/// several clients such as an editor and an agent share one server. A
+/// socket file left behind by an earlier server is replaced, but one a
+/// running server still accepts connections on is not.
+#[cfg(unix)]
+pub async fn serve_unix_socket(server: JustMcpServer, path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let address = path.display().to_string();
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        ensure!(metadata.file_type().is_socket(), NotASocketSnafu { path });
+        ensure!(
+            tokio::net::UnixStream::connect(path).await.is_err(),
+            AlreadyRunningSnafu { path }
+        );
+        std::fs::remove_file(path).context(BindSnafu { address: &address })?;
+    }
+    let listener = tokio::net::UnixListener::bind(path).context(BindSnafu { address: &address })?;
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                accept_failed(error).await;
+                continue;
+            }
+        };
+        spawn_session(&server, stream, address.clone());
+    }
+}
+
+/// Serve MCP over TCP with newline-delimited JSON-RPC, the framing of the
+/// stdio transport, one session per connection. Anyone who can connect
+/// can run recipes, so bind to a loopback address unless the network is
+/// trusted; for clients on other hosts, prefer the HTTP transport, which
+/// checks a bearer token.
+///
+/// With `tls`, connections are TLS from the first byte.
+pub async fn serve_tcp(
+    server: JustMcpServer,
+    address: SocketAddr,
+    tls: Option<TlsAcceptor>,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .context(BindSnafu {
+            address: address.to_string(),
+        })?;
+    loop {
+        let (stream, client) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                accept_failed(error).await;
+                continue;
+            }
+        };
+        // Responses are small and latency matters more than throughput
+        let _ = stream.set_nodelay(true);
+        let server = server.clone();
+        let tls = tls.clone();
+        tokio::spawn(async move {
+            let client = client.to_string();
+            let Some(tls) = tls else {
+                return spawn_session(&server, stream, client);
+            };
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.accept(stream)).await {
+                Ok(Ok(stream)) => spawn_session(&server, stream, client),
+                Ok(Err(error)) => tracing::warn!(%client, %error, "TLS handshake failed"),
+                Err(_) => tracing::warn!(%client, "TLS handshake timed out"),
+            }
+        });
+    }
+}
+
+/// Run a session for one connection until the client disconnects.
+fn spawn_session<S>(server: &JustMcpServer, stream: S, client: String)
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let session = server.new_session();
+    let guard = session.open_session(&client);
+    let span = tracing::info_span!("session", id = guard.id(), client = %client);
+    tokio::spawn(
+        async move {
+            tracing::info!("client connected");
+            match session.audit_log() {
+                Some(log) => {
+                    let stream = AuditedStream::new(stream, log, guard.id().to_string());
+                    run_session(session, stream).await;
+                }
+                None => run_session(session, stream).await,
+            }
+            drop(guard);
+        }
+        .instrument(span),
+    );
+}
+
+async fn run_session<S>(session: JustMcpServer, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    match session.serve(stream).await {
+        Ok(running) => {
+            let _ = running.waiting().await;
+            tracing::info!("client disconnected");
+        }
+        Err(error) => tracing::error!(%error, "session failed to start"),
+    }
+}
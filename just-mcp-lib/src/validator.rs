@@ -1,5 +1,7 @@
-use crate::Recipe;
+use crate::{Justfile, Parameter, ParameterType, Recipe, Span};
+use serde::Serialize;
 use snafu::prelude::*;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ValidationResult {
@@ -7,10 +9,33 @@ pub struct ValidationResult {
     pub errors: Vec<ValidationError>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    /// Name the error refers to: a parameter, or a dependency for dependency errors
     pub parameter: String,
     pub message: String,
+    /// Recipe the error was found in, for justfile-level validation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationErrorKind {
+    MissingArgument,
+    TooManyArguments,
+    InvalidType,
+    UnknownParameter,
+    ConflictingArgument,
+    UnknownDependency,
+    SelfDependency,
+    CircularDependency,
+    UndefinedVariable,
+    DuplicateRecipe,
+    DuplicateParameter,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -26,6 +51,7 @@ pub struct ParameterInfo {
     pub required: bool,
     pub default_value: Option<String>,
     pub description: Option<String>,
+    pub param_type: Option<ParameterType>,
 }
 
 #[derive(Debug, Snafu)]
@@ -38,33 +64,145 @@ pub type Result<T> = std::result::Result<T, ValidationSnafu>;
 
 /// Validate arguments against recipe parameters
 pub fn validate_arguments(recipe: &Recipe, args: &[String]) -> ValidationResult {
+    validate_named_arguments(recipe, args, &HashMap::new())
+}
+
+/// Validate a mix of positional and named arguments. Positional arguments
+/// bind to parameters in order; named arguments bind by parameter name.
+pub fn validate_named_arguments(
+    recipe: &Recipe,
+    args: &[String],
+    kwargs: &HashMap<String, String>,
+) -> ValidationResult {
     let mut errors = Vec::new();
     let params = &recipe.parameters;
 
-    // Check if we have too many arguments
     if args.len() > params.len() {
         errors.push(ValidationError {
+            kind: ValidationErrorKind::TooManyArguments,
             parameter: "<extra>".to_string(),
             message: format!(
                 "Too many arguments: expected at most {}, got {}",
                 params.len(),
                 args.len()
             ),
+            recipe: None,
+            span: None,
+        });
+    }
+
+    let mut unknown: Vec<&String> = kwargs
+        .keys()
+        .filter(|name| !params.iter().any(|param| &param.name == *name))
+        .collect();
+    unknown.sort();
+    for name in unknown {
+        errors.push(ValidationError {
+            kind: ValidationErrorKind::UnknownParameter,
+            parameter: name.clone(),
+            message: format!("Unknown parameter: {name}"),
+            recipe: None,
+            span: None,
         });
     }
 
-    // Check each parameter
     for (i, param) in params.iter().enumerate() {
-        if i >= args.len() {
-            // No argument provided for this parameter
-            if param.default_value.is_none() {
+        let value = match (args.get(i), kwargs.get(&param.name)) {
+            (Some(_), Some(_)) => {
                 errors.push(ValidationError {
+                    kind: ValidationErrorKind::ConflictingArgument,
                     parameter: param.name.clone(),
-                    message: format!("Missing required parameter: {}", param.name),
+                    message: format!(
+                        "Parameter {} is given both positionally and by name",
+                        param.name
+                    ),
+                    recipe: None,
+                    span: None,
+                });
+                continue;
+            }
+            (Some(value), None) | (None, Some(value)) => value,
+            (None, None) => {
+                if param.default_value.is_none() {
+                    errors.push(ValidationError {
+                        kind: ValidationErrorKind::MissingArgument,
+                        parameter: param.name.clone(),
+                        message: format!("Missing required parameter: {}", param.name),
+                        recipe: None,
+                        span: None,
+                    });
+                }
+                continue;
+            }
+        };
+
+        if let Some(parameter_type) = recipe.parameter_types.get(&param.name)
+            && !parameter_type.accepts(value)
+        {
+            errors.push(ValidationError {
+                kind: ValidationErrorKind::InvalidType,
+                parameter: param.name.clone(),
+                message: format!(
+                    "Invalid value for parameter {}: expected {}, got '{}'",
+                    param.name, parameter_type, value
+                ),
+                recipe: None,
+                span: None,
+            });
+        }
+    }
+
+    ValidationResult {
+        is_valid: errors.is_empty(),
+        errors,
+    }
+}
+
+/// Check a justfile for duplicate recipe and parameter names, problems in the
+/// dependency graph (unknown dependencies, self-dependencies and cycles) and
+/// unresolved `{{ name }}` references.
+pub fn validate_justfile(justfile: &Justfile) -> ValidationResult {
+    let mut errors = check_duplicates(justfile);
+    let recipes: HashMap<&str, &Recipe> = justfile
+        .recipes
+        .iter()
+        .map(|recipe| (recipe.name.as_str(), recipe))
+        .collect();
+
+    for recipe in &justfile.recipes {
+        for dependency in &recipe.dependencies {
+            if dependency == &recipe.name {
+                errors.push(ValidationError {
+                    kind: ValidationErrorKind::SelfDependency,
+                    parameter: dependency.clone(),
+                    message: format!("Recipe '{}' depends on itself", recipe.name),
+                    recipe: Some(recipe.name.clone()),
+                    span: Some(recipe.span),
+                });
+            } else if !recipes.contains_key(dependency.as_str()) {
+                errors.push(ValidationError {
+                    kind: ValidationErrorKind::UnknownDependency,
+                    parameter: dependency.clone(),
+                    message: format!(
+                        "Recipe '{}' depends on unknown recipe '{}'",
+                        recipe.name, dependency
+                    ),
+                    recipe: Some(recipe.name.clone()),
+                    span: Some(recipe.span),
                 });
             }
         }
-        // If an argument is provided, it's valid (we don't do type checking yet)
+    }
+
+    // Depth-first search; an edge back into the current path closes a cycle
+    let mut visited: HashMap<&str, bool> = HashMap::new();
+    for recipe in &justfile.recipes {
+        let mut path = Vec::new();
+        find_cycles(recipe, &recipes, &mut visited, &mut path, &mut errors);
+    }
+
+    for recipe in &justfile.recipes {
+        errors.extend(check_interpolations(recipe, justfile));
     }
 
     ValidationResult {
@@ -73,6 +211,257 @@ pub fn validate_arguments(recipe: &Recipe, args: &[String]) -> ValidationResult
     }
 }
 
+/// Report recipes defined more than once and parameters declared twice within
+/// a recipe. Recipes carrying a platform attribute may legitimately repeat.
+fn check_duplicates(justfile: &Justfile) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut first_definitions: HashMap<&str, &Recipe> = HashMap::new();
+
+    for recipe in &justfile.recipes {
+        if !recipe.has_platform_attribute() {
+            if let Some(first) = first_definitions.get(recipe.name.as_str()) {
+                errors.push(ValidationError {
+                    kind: ValidationErrorKind::DuplicateRecipe,
+                    parameter: recipe.name.clone(),
+                    message: format!(
+                        "Recipe '{}' is already defined on line {}",
+                        recipe.name, first.span.start_line
+                    ),
+                    recipe: Some(recipe.name.clone()),
+                    span: Some(recipe.span),
+                });
+            } else {
+                first_definitions.insert(&recipe.name, recipe);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for param in &recipe.parameters {
+            if !seen.insert(param.name.as_str()) {
+                errors.push(ValidationError {
+                    kind: ValidationErrorKind::DuplicateParameter,
+                    parameter: param.name.clone(),
+                    message: format!(
+                        "Recipe '{}' declares parameter '{}' more than once",
+                        recipe.name, param.name
+                    ),
+                    recipe: Some(recipe.name.clone()),
+                    span: Some(recipe.span),
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+/// Report `{{ name }}` references that are neither a parameter of the recipe
+/// nor a top-level variable, once per name.
+fn check_interpolations(recipe: &Recipe, justfile: &Justfile) -> Vec<ValidationError> {
+    let mut seen = HashSet::new();
+    interpolations(&recipe.body)
+        .into_iter()
+        .filter(|inner| is_identifier(inner))
+        .filter(|name| {
+            !recipe.parameters.iter().any(|param| param.name == *name)
+                && !justfile.variables.contains_key(*name)
+        })
+        .filter(|name| seen.insert(*name))
+        .map(|name| ValidationError {
+            kind: ValidationErrorKind::UndefinedVariable,
+            parameter: name.to_string(),
+            message: format!(
+                "Recipe '{}' references undefined variable '{}'",
+                recipe.name, name
+            ),
+            recipe: Some(recipe.name.clone()),
+            span: Some(recipe.span),
+        })
+        .collect()
+}
+
+/// The trimmed contents of every `{{ ... }}` in a recipe body. Only bare
+/// identifiers can be checked statically; anything else is an expression.
+fn interpolations(body: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        found.push(after[..end].trim());
+        rest = &after[end + 2..];
+    }
+    found
+}
+
+fn is_identifier(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Identifier-like words in an expression, e.g. `version` and `suffix` in
+/// `version + "-" + suffix`. Quoted strings are not excluded, so a name that
+/// only appears inside a string literal still counts as referenced.
+fn identifiers(expression: &str) -> impl Iterator<Item = &str> {
+    expression
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .filter(|word| !word.is_empty())
+}
+
+/// Top-level variables that are never referenced by a recipe body, another
+/// variable or a parameter default, and are not exported to recipe
+/// environments. Sorted by name.
+pub fn unused_variables(justfile: &Justfile) -> Vec<&str> {
+    if justfile.setting_enabled("export") {
+        return Vec::new();
+    }
+
+    let mut referenced: HashSet<&str> = HashSet::new();
+    for recipe in &justfile.recipes {
+        for inner in interpolations(&recipe.body) {
+            referenced.extend(identifiers(inner));
+        }
+        for param in &recipe.parameters {
+            if let Some(default) = &param.default_value {
+                referenced.extend(identifiers(default));
+            }
+        }
+    }
+    for (name, value) in &justfile.variables {
+        referenced.extend(identifiers(value).filter(|word| word != name));
+    }
+
+    let mut unused: Vec<&str> = justfile
+        .variables
+        .keys()
+        .map(String::as_str)
+        .filter(|name| !referenced.contains(name) && !justfile.exported.contains(*name))
+        .collect();
+    unused.sort();
+    unused
+}
+
+/// Recipe parameters that share a name with a top-level variable. Inside the
+/// recipe the parameter wins, which is easy to miss when reading the body.
+pub fn shadowed_parameters(justfile: &Justfile) -> Vec<(&Recipe, &Parameter)> {
+    justfile
+        .recipes
+        .iter()
+        .flat_map(|recipe| {
+            recipe
+                .parameters
+                .iter()
+                .filter(|param| justfile.variables.contains_key(&param.name))
+                .map(move |param| (recipe, param))
+        })
+        .collect()
+}
+
+/// Recipes that are neither a dependency of another recipe nor the target of
+/// an alias. The first recipe is the default entry point and never counts.
+pub fn unused_recipes(justfile: &Justfile) -> Vec<&Recipe> {
+    let referenced: HashSet<&str> = justfile
+        .recipes
+        .iter()
+        .flat_map(|recipe| {
+            recipe
+                .dependencies
+                .iter()
+                .filter(move |dependency| **dependency != recipe.name)
+        })
+        .chain(justfile.aliases.values())
+        .map(String::as_str)
+        .collect();
+
+    justfile
+        .recipes
+        .iter()
+        .skip(1)
+        .filter(|recipe| !referenced.contains(recipe.name.as_str()))
+        .collect()
+}
+
+/// `visited` maps a recipe to `true` while it is on the current path and to
+/// `false` once all of its dependencies have been explored.
+fn find_cycles<'a>(
+    recipe: &'a Recipe,
+    recipes: &HashMap<&str, &'a Recipe>,
+    visited: &mut HashMap<&'a str, bool>,
+    path: &mut Vec<&'a str>,
+    errors: &mut Vec<ValidationError>,
+) {
+    if visited.contains_key(recipe.name.as_str()) {
+        return;
+    }
+    visited.insert(&recipe.name, true);
+    path.push(&recipe.name);
+
+    for dependency in &recipe.dependencies {
+        // Self-dependencies and unknown names are reported separately
+        if dependency == &recipe.name {
+            continue;
+        }
+        let Some(next) = recipes.get(dependency.as_str()) else {
+            continue;
+        };
+        match visited.get(dependency.as_str()) {
+            Some(true) => {
+                let start = path.iter().position(|name| name == dependency).unwrap();
+                let mut cycle = path[start..].to_vec();
+                cycle.push(dependency);
+                errors.push(ValidationError {
+                    kind: ValidationErrorKind::CircularDependency,
+                    parameter: dependency.clone(),
+                    message: format!("Circular dependency: {}", cycle.join(" -> ")),
+                    recipe: Some(recipe.name.clone()),
+                    span: Some(recipe.span),
+                });
+            }
+            Some(false) => {}
+            None => find_cycles(next, recipes, visited, path, errors),
+        }
+    }
+
+    path.pop();
+    visited.insert(&recipe.name, false);
+}
+
+/// Recipe names close to `name` by edit distance, closest first (at most three).
+pub fn suggest_recipe_names(justfile: &Justfile, name: &str) -> Vec<String> {
+    let max_distance = (name.chars().count() / 3).max(2);
+    let mut candidates: Vec<(usize, &str)> = justfile
+        .recipes
+        .iter()
+        .map(|recipe| (levenshtein(name, &recipe.name), recipe.name.as_str()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .take(3)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 /// Get signature help for a recipe
 pub fn get_signature_help(recipe: &Recipe) -> SignatureHelp {
     let parameters = recipe
@@ -83,6 +472,7 @@ pub fn get_signature_help(recipe: &Recipe) -> SignatureHelp {
             required: param.default_value.is_none(),
             default_value: param.default_value.clone(),
             description: None, // Could be enhanced to parse parameter documentation
+            param_type: recipe.parameter_types.get(&param.name).cloned(),
         })
         .collect();
 
@@ -137,6 +527,9 @@ pub fn format_signature_help(help: &SignatureHelp) -> String {
         }
         for param in &help.parameters {
             result.push_str(&format!("\n  {}", param.name));
+            if let Some(ref param_type) = param.param_type {
+                result.push_str(&format!(": {param_type}"));
+            }
             if param.required {
                 result.push_str(" (required)");
             } else {
@@ -181,6 +574,7 @@ pub fn validate_with_help(recipe: &Recipe, args: &[String]) -> ValidationResult
 mod tests {
     use super::*;
     use crate::Parameter;
+    use crate::parser::parse_justfile_str;
 
     fn create_test_recipe(name: &str, params: Vec<Parameter>) -> Recipe {
         Recipe {
@@ -189,6 +583,7 @@ mod tests {
             documentation: Some(format!("Test recipe {}", name)),
             body: String::new(),
             dependencies: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -350,4 +745,287 @@ mod tests {
         assert!(result.errors[0].message.contains("Expected signature"));
         assert!(result.errors[0].message.contains("deploy(env)"));
     }
+
+    #[test]
+    fn test_validate_justfile_valid() {
+        let justfile =
+            parse_justfile_str("build:\n    cargo build\n\ntest: build\n    cargo test\n").unwrap();
+
+        let result = validate_justfile(&justfile);
+        assert!(result.is_valid);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn test_validate_justfile_unknown_and_self_dependency() {
+        let content = "test: build\n    cargo test\n\nloop: loop\n    echo loop\n";
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let result = validate_justfile(&justfile);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 2);
+
+        let unknown = &result.errors[0];
+        assert_eq!(unknown.kind, ValidationErrorKind::UnknownDependency);
+        assert_eq!(unknown.parameter, "build");
+        assert_eq!(unknown.recipe.as_deref(), Some("test"));
+        assert_eq!(
+            unknown.span,
+            Some(Span {
+                start_line: 1,
+                end_line: 2
+            })
+        );
+
+        let self_dep = &result.errors[1];
+        assert_eq!(self_dep.kind, ValidationErrorKind::SelfDependency);
+        assert_eq!(self_dep.recipe.as_deref(), Some("loop"));
+        assert_eq!(
+            self_dep.span,
+            Some(Span {
+                start_line: 4,
+                end_line: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_justfile_circular_dependency() {
+        let content = "a: b\n    echo a\nb: c\n    echo b\nc: a\n    echo c\n";
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let result = validate_justfile(&justfile);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(
+            result.errors[0].kind,
+            ValidationErrorKind::CircularDependency
+        );
+        assert_eq!(
+            result.errors[0].message,
+            "Circular dependency: a -> b -> c -> a"
+        );
+        assert_eq!(result.errors[0].recipe.as_deref(), Some("c"));
+        assert_eq!(
+            result.errors[0].span,
+            Some(Span {
+                start_line: 5,
+                end_line: 6
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_justfile_undefined_variables() {
+        let content = r#"
+version := "1.0.0"
+
+deploy env:
+    echo "{{ env }} {{version}} {{ target }}"
+    echo "{{ target }} {{ uppercase(env) }}"
+"#;
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let result = validate_justfile(&justfile);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(
+            result.errors[0].kind,
+            ValidationErrorKind::UndefinedVariable
+        );
+        assert_eq!(result.errors[0].parameter, "target");
+        assert_eq!(result.errors[0].recipe.as_deref(), Some("deploy"));
+    }
+
+    #[test]
+    fn test_validate_justfile_duplicates() {
+        let content = r#"
+build:
+    cargo build
+
+build:
+    make
+
+[linux]
+install:
+    cp bin /usr/local/bin
+
+[windows]
+install:
+    copy bin C:\\bin
+
+deploy env env:
+    echo {{ env }}
+"#;
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let result = validate_justfile(&justfile);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 2);
+
+        assert_eq!(result.errors[0].kind, ValidationErrorKind::DuplicateRecipe);
+        assert_eq!(result.errors[0].parameter, "build");
+        assert!(result.errors[0].message.contains("line 2"));
+        assert_eq!(
+            result.errors[0].span,
+            Some(Span {
+                start_line: 5,
+                end_line: 6
+            })
+        );
+
+        assert_eq!(
+            result.errors[1].kind,
+            ValidationErrorKind::DuplicateParameter
+        );
+        assert_eq!(result.errors[1].parameter, "env");
+        assert_eq!(result.errors[1].recipe.as_deref(), Some("deploy"));
+    }
+
+    #[test]
+    fn test_validate_arguments_types() {
+        let content = r#"
+# @param port: int
+# @param env: enum(dev,prod)
+serve port env="dev":
+    ./serve {{ port }} {{ env }}
+"#;
+        let justfile = parse_justfile_str(content).unwrap();
+        let recipe = &justfile.recipes[0];
+
+        let result = validate_arguments(recipe, &["8080".to_string(), "prod".to_string()]);
+        assert!(result.is_valid);
+
+        let result = validate_arguments(recipe, &["http".to_string(), "qa".to_string()]);
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.errors[0].kind, ValidationErrorKind::InvalidType);
+        assert_eq!(result.errors[0].parameter, "port");
+        assert!(result.errors[0].message.contains("expected int"));
+        assert!(result.errors[1].message.contains("expected enum(dev,prod)"));
+
+        let help = get_signature_help(recipe);
+        assert_eq!(help.parameters[0].param_type, Some(ParameterType::Int));
+        assert!(format_signature_help(&help).contains("port: int (required)"));
+    }
+
+    #[test]
+    fn test_unused_variables() {
+        let content = r#"
+version := "1.0.0"
+suffix := "beta"
+full := version + "-" + suffix
+unused := "x"
+export TOKEN := "secret"
+port := "8080"
+
+serve p=port:
+    echo "{{ full }} {{ p }}"
+"#;
+        let justfile = parse_justfile_str(content).unwrap();
+
+        assert_eq!(unused_variables(&justfile), vec!["unused"]);
+    }
+
+    #[test]
+    fn test_unused_recipes() {
+        let content = r#"
+alias t := test
+
+default: build
+
+build:
+    cargo build
+
+test:
+    cargo test
+
+_orphan:
+    echo unused
+"#;
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let unused: Vec<&str> = unused_recipes(&justfile)
+            .iter()
+            .map(|recipe| recipe.name.as_str())
+            .collect();
+        assert_eq!(unused, vec!["_orphan"]);
+    }
+
+    #[test]
+    fn test_shadowed_parameters() {
+        let content = r#"
+env := "dev"
+
+deploy env target:
+    echo "{{ env }} {{ target }}"
+
+build:
+    echo "{{ env }}"
+"#;
+        let justfile = parse_justfile_str(content).unwrap();
+
+        let shadowed = shadowed_parameters(&justfile);
+        assert_eq!(shadowed.len(), 1);
+        assert_eq!(shadowed[0].0.name, "deploy");
+        assert_eq!(shadowed[0].1.name, "env");
+    }
+
+    #[test]
+    fn test_suggest_recipe_names() {
+        let content = "build:\n    cargo build\nbench:\n    cargo bench\ndeploy:\n    ./deploy\n";
+        let justfile = parse_justfile_str(content).unwrap();
+
+        assert_eq!(levenshtein("buidl", "build"), 2);
+        assert_eq!(suggest_recipe_names(&justfile, "buidl"), vec!["build"]);
+        assert_eq!(suggest_recipe_names(&justfile, "bnech"), vec!["bench"]);
+        assert_eq!(suggest_recipe_names(&justfile, "depoly"), vec!["deploy"]);
+        assert!(suggest_recipe_names(&justfile, "xyz").is_empty());
+    }
+
+    #[test]
+    fn test_validate_named_arguments() {
+        let params = vec![
+            Parameter {
+                name: "env".to_string(),
+                default_value: None,
+            },
+            Parameter {
+                name: "target".to_string(),
+                default_value: Some("prod".to_string()),
+            },
+        ];
+        let recipe = create_test_recipe("deploy", params);
+        let kwargs = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        // Named only, and positional mixed with named
+        assert!(validate_named_arguments(&recipe, &[], &kwargs(&[("env", "dev")])).is_valid);
+        assert!(
+            validate_named_arguments(&recipe, &["dev".to_string()], &kwargs(&[("target", "qa")]))
+                .is_valid
+        );
+
+        let result = validate_named_arguments(&recipe, &[], &kwargs(&[("region", "eu")]));
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(result.errors[0].kind, ValidationErrorKind::UnknownParameter);
+        assert_eq!(result.errors[0].parameter, "region");
+        assert_eq!(result.errors[1].kind, ValidationErrorKind::MissingArgument);
+        assert_eq!(result.errors[1].parameter, "env");
+
+        let result =
+            validate_named_arguments(&recipe, &["dev".to_string()], &kwargs(&[("env", "qa")]));
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(
+            result.errors[0].kind,
+            ValidationErrorKind::ConflictingArgument
+        );
+        assert_eq!(result.errors[0].parameter, "env");
+    }
 }
@@ -0,0 +1,535 @@
+use notify::{Event, EventKind, PollWatcher, RecursiveMode};
+use regex::Regex;
+use snafu::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use crate::parser::parse_justfile_str;
+use crate::{Recipe, Span};
+
+/// How often watched files are polled where the OS's file notifications
+/// are unavailable, and how often the set of watched files is updated.
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long files must be quiet before their changes are reported, so an
+/// editor's write, rename and chmod arrive as one event.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Files that keep changing are reported at least this often.
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// `import 'path'`, `import? 'path'` and `mod name 'path'` statements.
+static IMPORT_PATH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)^(?:import\??|mod\??\s+[A-Za-z_][A-Za-z0-9_-]*)\s+['"]([^'"]+)['"]"#).unwrap()
+});
+
+/// `mod name` without a path, which loads `name.just` or `name/mod.just`.
+static BARE_MOD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^mod\??\s+([A-Za-z_][A-Za-z0-9_-]*)\s*$").unwrap());
+
+#[derive(Debug, Snafu)]
+pub enum WatcherError {
+    #[snafu(display("Failed to start watching files: {}", source))]
+    Start { source: notify::Error },
+
+    #[snafu(display("Failed to watch {}: {}", path.display(), source))]
+    Watch {
+        path: PathBuf,
+        source: notify::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, WatcherError>;
+
+/// The files to watch, by what they are to the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchTargets {
+    /// Justfiles; the files they import are watched too
+    pub justfiles: Vec<PathBuf>,
+    /// Dotenv files recipes get variables from
+    pub env_files: Vec<PathBuf>,
+}
+
+/// A file imported by a watched justfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedFile {
+    pub path: PathBuf,
+    /// The watched justfile importing it, maybe through other imports
+    pub justfile: PathBuf,
+}
+
+/// The watched files that were created, modified or removed before they
+/// were quiet for the debounce time. Paths are as given in `WatchTargets`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchEvent {
+    pub justfiles: Vec<PathBuf>,
+    pub imports: Vec<ImportedFile>,
+    pub env_files: Vec<PathBuf>,
+}
+
+impl WatchEvent {
+    pub fn is_empty(&self) -> bool {
+        self.justfiles.is_empty() && self.imports.is_empty() && self.env_files.is_empty()
+    }
+
+    /// Justfiles that changed themselves or through one of their imports.
+    pub fn affected_justfiles(&self) -> Vec<PathBuf> {
+        let mut justfiles = self.justfiles.clone();
+        for import in &self.imports {
+            if !justfiles.contains(&import.justfile) {
+                justfiles.push(import.justfile.clone());
+            }
+        }
+        justfiles
+    }
+}
+
+/// What a `WatchEvent` changed for the client.
+#[derive(Debug, Default, PartialEq)]
+pub struct Changes {
+    /// A recipe was added, removed, or had its signature or docs changed
+    pub recipes_changed: bool,
+    /// A justfile appeared or disappeared
+    pub files_changed: bool,
+    /// Justfiles whose contents were modified, themselves or through an
+    /// import
+    pub modified: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        !self.recipes_changed && !self.files_changed && self.modified.is_empty()
+    }
+}
+
+/// The recipe interfaces of the published justfiles, to tell which watch
+/// events change the tool list. `None` when the justfile could not be read
+/// or parsed.
+#[derive(Debug, Default)]
+pub struct RecipeSnapshots {
+    recipes: HashMap<PathBuf, Option<Vec<Recipe>>>,
+}
+
+impl RecipeSnapshots {
+    pub fn new(paths: &[PathBuf]) -> Self {
+        let recipes = paths
+            .iter()
+            .map(|path| (path.clone(), recipe_interfaces(path)))
+            .collect();
+        RecipeSnapshots { recipes }
+    }
+
+    /// What `event` changed, given that `paths` are the published
+    /// justfiles now. Only justfiles that are new or that `event` affects
+    /// are read again.
+    pub fn update(&mut self, paths: &[PathBuf], event: &WatchEvent) -> Changes {
+        let modified = event.affected_justfiles();
+        let mut changes = Changes {
+            files_changed: paths.len() != self.recipes.len()
+                || paths.iter().any(|path| !self.recipes.contains_key(path)),
+            ..Changes::default()
+        };
+
+        let mut recipes = HashMap::new();
+        for path in paths {
+            let current = match self.recipes.remove(path) {
+                Some(previous) if !modified.contains(path) => previous,
+                Some(previous) => {
+                    let current = recipe_interfaces(path);
+                    changes.recipes_changed |= current != previous;
+                    current
+                }
+                None => {
+                    changes.recipes_changed = true;
+                    recipe_interfaces(path)
+                }
+            };
+            recipes.insert(path.clone(), current);
+        }
+        // Whatever was not visited above is no longer published
+        changes.recipes_changed |= !self.recipes.is_empty();
+
+        self.recipes = recipes;
+        changes.modified = modified;
+        changes
+    }
+}
+
+/// The recipes of the justfile at `path` as they show up in tool listings;
+/// bodies and spans change on every edit and are ignored.
+fn recipe_interfaces(path: &Path) -> Option<Vec<Recipe>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let justfile = parse_justfile_str(&content).ok()?;
+    let interfaces = justfile.recipes.into_iter().map(|recipe| Recipe {
+        body: String::new(),
+        span: Span::default(),
+        ..recipe
+    });
+    Some(interfaces.collect())
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Justfile,
+    Import { justfile: PathBuf },
+    EnvFile,
+}
+
+/// A watched file: what it is and its path as given.
+type Targets = HashMap<PathBuf, (Kind, PathBuf)>;
+
+/// Watches justfiles, the files they import and env files with the OS's
+/// file notifications, or by polling where those are unavailable, and sends
+/// one debounced `WatchEvent` per burst of changes.
+pub struct Watcher {
+    watcher: Box<dyn notify::Watcher + Send>,
+    targets: Arc<Mutex<Targets>>,
+    /// Directories watched for the targets in them
+    dirs: HashSet<PathBuf>,
+}
+
+impl Watcher {
+    /// A watcher with nothing to watch yet, and the receiver its events go
+    /// to. `poll_interval` is only used when falling back to polling.
+    pub fn new(
+        debounce: Duration,
+        poll_interval: Duration,
+    ) -> Result<(Self, UnboundedReceiver<WatchEvent>)> {
+        let (raw_sender, raw_receiver) = mpsc::channel::<PathBuf>();
+        let handler = move |result: notify::Result<Event>| {
+            if let Ok(event) = result
+                && !matches!(event.kind, EventKind::Access(_))
+            {
+                for path in event.paths {
+                    let _ = raw_sender.send(path);
+                }
+            }
+        };
+        let watcher: Box<dyn notify::Watcher + Send> =
+            match notify::recommended_watcher(handler.clone()) {
+                Ok(watcher) => Box::new(watcher),
+                Err(_) => {
+                    let config = notify::Config::default().with_poll_interval(poll_interval);
+                    Box::new(PollWatcher::new(handler, config).context(StartSnafu)?)
+                }
+            };
+
+        let targets = Arc::new(Mutex::new(Targets::new()));
+        let (sender, receiver) = unbounded_channel();
+        let debounce_targets = targets.clone();
+        std::thread::spawn(move || {
+            debounce_events(raw_receiver, debounce_targets, debounce, sender)
+        });
+
+        let watcher = Self {
+            watcher,
+            targets,
+            dirs: HashSet::new(),
+        };
+        Ok((watcher, receiver))
+    }
+
+    /// Watch `targets` instead of the previous ones. Imports are read from
+    /// the justfiles again, so call this after they change.
+    pub fn watch(&mut self, targets: &WatchTargets) -> Result<()> {
+        let mut watched = Targets::new();
+        let mut add = |path: &Path, kind: Kind| {
+            let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+            watched
+                .entry(absolute)
+                .or_insert_with(|| (kind, path.to_path_buf()));
+        };
+        for justfile in &targets.justfiles {
+            add(justfile, Kind::Justfile);
+        }
+        for justfile in &targets.justfiles {
+            for import in imported_files(justfile) {
+                let justfile = justfile.clone();
+                add(&import, Kind::Import { justfile });
+            }
+        }
+        for env_file in &targets.env_files {
+            add(env_file, Kind::EnvFile);
+        }
+
+        // Directories are watched rather than files, so files that are
+        // replaced by renaming or created later are still seen
+        let dirs: HashSet<PathBuf> = watched
+            .keys()
+            .filter_map(|path| path.parent())
+            .filter(|dir| dir.is_dir())
+            .map(Path::to_path_buf)
+            .collect();
+        *self.targets.lock().unwrap() = watched;
+        for dir in self.dirs.difference(&dirs) {
+            let _ = self.watcher.unwatch(dir);
+        }
+        let added: Vec<PathBuf> = dirs.difference(&self.dirs).cloned().collect();
+        self.dirs.retain(|dir| dirs.contains(dir));
+        for dir in added {
+            self.watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .context(WatchSnafu { path: &dir })?;
+            self.dirs.insert(dir);
+        }
+        Ok(())
+    }
+}
+
+/// Collect changed paths until the watched ones among them have been quiet
+/// for `debounce`, then send them as one event.
+fn debounce_events(
+    raw: mpsc::Receiver<PathBuf>,
+    targets: Arc<Mutex<Targets>>,
+    debounce: Duration,
+    sender: UnboundedSender<WatchEvent>,
+) {
+    let is_watched = |path: &PathBuf| targets.lock().unwrap().contains_key(path);
+    while let Ok(path) = raw.recv() {
+        if !is_watched(&path) {
+            continue;
+        }
+        let started = Instant::now();
+        let mut paths = BTreeSet::from([path]);
+        // Changes to other files in the same directories do not delay it
+        let mut quiet_until = started + debounce;
+        loop {
+            let now = Instant::now();
+            if now >= quiet_until || now >= started + MAX_DELAY {
+                break;
+            }
+            match raw.recv_timeout(quiet_until - now) {
+                Ok(path) if is_watched(&path) => {
+                    paths.insert(path);
+                    quiet_until = Instant::now() + debounce;
+                }
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let event = classify(&targets.lock().unwrap(), paths);
+        if !event.is_empty() && sender.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+fn classify(targets: &Targets, paths: BTreeSet<PathBuf>) -> WatchEvent {
+    let mut event = WatchEvent::default();
+    for path in paths {
+        match targets.get(&path) {
+            Some((Kind::Justfile, path)) => event.justfiles.push(path.clone()),
+            Some((Kind::Import { justfile }, path)) => event.imports.push(ImportedFile {
+                path: path.clone(),
+                justfile: justfile.clone(),
+            }),
+            Some((Kind::EnvFile, path)) => event.env_files.push(path.clone()),
+            None => {}
+        }
+    }
+    event
+}
+
+/// Files `justfile` imports with `import` or loads with `mod`, and the
+/// files those import in turn. Modules only yield the files that exist.
+pub fn imported_files(justfile: &Path) -> Vec<PathBuf> {
+    let mut imports = Vec::new();
+    let mut pending = vec![justfile.to_path_buf()];
+    let mut seen = HashSet::from([justfile.to_path_buf()]);
+    while let Some(path) = pending.pop() {
+        let Ok(source) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let explicit = IMPORT_PATH.captures_iter(&source).flat_map(|captures| {
+            let path = dir.join(&captures[1]);
+            if path.is_dir() {
+                module_files(&path)
+            } else {
+                vec![path]
+            }
+        });
+        let modules = BARE_MOD.captures_iter(&source).flat_map(|captures| {
+            let name = &captures[1];
+            let file = Some(dir.join(format!("{name}.just"))).filter(|path| path.is_file());
+            file.into_iter().chain(module_files(&dir.join(name)))
+        });
+        for import in explicit.chain(modules) {
+            if seen.insert(import.clone()) {
+                imports.push(import.clone());
+                pending.push(import);
+            }
+        }
+    }
+    imports
+}
+
+/// The files in a module directory a module can be loaded from.
+fn module_files(dir: &Path) -> Vec<PathBuf> {
+    ["mod.just", "justfile", "Justfile", ".justfile"]
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Size and modification time of a file; `None` when it does not exist.
+pub(crate) type Stamp = Option<(Option<SystemTime>, u64)>;
+
+pub(crate) fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_imported_files() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("ci")).unwrap();
+        fs::write(
+            root.join("justfile"),
+            "import 'common.just'\nimport? \"missing.just\"\nmod ci\nmod? docs\n\nbuild:\n    cargo build\n",
+        )
+        .unwrap();
+        fs::write(root.join("common.just"), "import 'nested.just'\n").unwrap();
+        fs::write(root.join("nested.just"), "import 'common.just'\n").unwrap();
+        fs::write(root.join("ci/mod.just"), "test:\n    cargo test\n").unwrap();
+
+        let mut imports = imported_files(&root.join("justfile"));
+        imports.sort();
+        assert_eq!(
+            imports,
+            vec![
+                root.join("ci/mod.just"),
+                root.join("common.just"),
+                root.join("missing.just"),
+                root.join("nested.just"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watcher_reports_debounced_changes() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let justfile = root.join("justfile");
+        let env = root.join(".env");
+        fs::write(&justfile, "import 'common.just'\n").unwrap();
+        fs::write(root.join("common.just"), "build:\n    cargo build\n").unwrap();
+        fs::write(&env, "API_KEY=old\n").unwrap();
+
+        let (mut watcher, mut events) =
+            Watcher::new(DEFAULT_DEBOUNCE, Duration::from_millis(100)).unwrap();
+        watcher
+            .watch(&WatchTargets {
+                justfiles: vec![justfile.clone()],
+                env_files: vec![env.clone()],
+            })
+            .unwrap();
+
+        fs::write(root.join("unrelated.txt"), "ignored").unwrap();
+        fs::write(&env, "API_KEY=rotated\n").unwrap();
+        fs::write(root.join("common.just"), "test:\n    cargo test\n").unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.env_files, vec![env]);
+        assert_eq!(
+            event.imports,
+            vec![ImportedFile {
+                path: root.join("common.just"),
+                justfile: justfile.clone(),
+            }]
+        );
+        assert_eq!(event.affected_justfiles(), vec![justfile]);
+    }
+
+    fn modified(paths: &[&PathBuf]) -> WatchEvent {
+        WatchEvent {
+            justfiles: paths.iter().map(|path| path.to_path_buf()).collect(),
+            ..WatchEvent::default()
+        }
+    }
+
+    #[test]
+    fn test_body_edit_is_a_modification_only() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("justfile");
+        fs::write(&path, "build:\n    cargo build\n").unwrap();
+
+        let paths = vec![path.clone()];
+        let mut snapshots = RecipeSnapshots::new(&paths);
+        assert!(snapshots.update(&paths, &WatchEvent::default()).is_empty());
+        fs::write(&path, "build:\n    cargo build --release\n").unwrap();
+
+        let changes = snapshots.update(&paths, &modified(&[&path]));
+        assert!(!changes.recipes_changed);
+        assert!(!changes.files_changed);
+        assert_eq!(changes.modified, vec![path]);
+    }
+
+    #[test]
+    fn test_added_recipe_changes_recipes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("justfile");
+        fs::write(&path, "build:\n    cargo build\n").unwrap();
+
+        let paths = vec![path.clone()];
+        let mut snapshots = RecipeSnapshots::new(&paths);
+        fs::write(&path, "build:\n    cargo build\n\ntest:\n    cargo test\n").unwrap();
+
+        let changes = snapshots.update(&paths, &modified(&[&path]));
+        assert!(changes.recipes_changed);
+        assert_eq!(changes.modified, vec![path]);
+    }
+
+    #[test]
+    fn test_edit_through_an_import_changes_recipes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("justfile");
+        fs::write(&path, "import 'common.just'\n").unwrap();
+
+        let paths = vec![path.clone()];
+        let mut snapshots = RecipeSnapshots::new(&paths);
+        let event = WatchEvent {
+            imports: vec![ImportedFile {
+                path: dir.path().join("common.just"),
+                justfile: path.clone(),
+            }],
+            ..WatchEvent::default()
+        };
+        let changes = snapshots.update(&paths, &event);
+        assert!(!changes.files_changed);
+        assert_eq!(changes.modified, vec![path]);
+    }
+
+    #[test]
+    fn test_new_and_removed_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("justfile");
+        fs::write(&path, "build:\n    cargo build\n").unwrap();
+
+        let mut snapshots = RecipeSnapshots::new(&[]);
+        let paths = vec![path.clone()];
+        let changes = snapshots.update(&paths, &modified(&[&path]));
+        assert!(changes.files_changed);
+        assert!(changes.recipes_changed);
+
+        let changes = snapshots.update(&[], &modified(&[&path]));
+        assert!(changes.files_changed);
+        assert!(changes.recipes_changed);
+    }
+}
@@ -1,72 +1,505 @@
-use clap::{Arg, Command};
-use just_mcp_lib::mcp_server::JustMcpServer;
+use clap::{Arg, ArgMatches, Command, ValueEnum};
+use clap_complete::engine::{ArgValueCompleter, CompletionCandidate};
+use clap_complete::env::{CompleteEnv, Shells};
+use just_mcp_lib::audit::{AuditLog, AuditedStream};
+use just_mcp_lib::auth;
+use just_mcp_lib::config::ServerConfig;
+use just_mcp_lib::diff::unified_diff;
+use just_mcp_lib::docgen::{self, DocFormat, recipe_table};
+use just_mcp_lib::doctor;
+use just_mcp_lib::environment::{McpEnvironment, validate_mcp_environment};
+use just_mcp_lib::executor::{
+    ExecutionOptions, ExitStrategy, OutputStream, execute_recipe_with_output,
+};
+use just_mcp_lib::formatter::format_justfile_str;
+use just_mcp_lib::history::{self, ExecutionHistory, ExecutionStatus, HistoryFilter};
+use just_mcp_lib::http::{self, McpEndpoint};
+use just_mcp_lib::lints::LintConfig;
+use just_mcp_lib::mcp_server::{
+    ExecutionHistoryList, JustMcpServer, JustfileInfo, ValidationReport,
+};
+use just_mcp_lib::parser::parse_justfile;
+use just_mcp_lib::policy::RecipePolicy;
+use just_mcp_lib::redact::{self, Redactor};
+use just_mcp_lib::roots::{self, find_justfile_upward};
+use just_mcp_lib::secrets::{self, SecretsConfig};
+use just_mcp_lib::server_log::{self, LogFormat};
+use just_mcp_lib::settings::McpSettings;
+use just_mcp_lib::shutdown::DEFAULT_GRACE_PERIOD;
+use just_mcp_lib::telemetry::Telemetry;
+use just_mcp_lib::tls;
+use just_mcp_lib::transport::{self, NetworkOptions};
+use just_mcp_lib::watcher::DEFAULT_WATCH_INTERVAL;
 use just_mcp_lib::JustfileRegistry;
 use rmcp::{ServiceExt, transport::stdio};
 use std::error::Error;
-use std::path::Path;
+use std::ffi::OsStr;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::Duration;
+use tracing::Level;
+
+/// Address `serve --transport tcp` listens on without `--bind`.
+const DEFAULT_TCP_ADDRESS: &str = "127.0.0.1:8377";
+
+/// Address `serve --transport http` and `sse` listen on without `--bind`.
+const DEFAULT_HTTP_ADDRESS: &str = "127.0.0.1:8378";
+
+/// Socket `serve --daemon` listens on without `--bind`, in the working
+/// directory.
+const DAEMON_SOCKET: &str = ".just-mcp.sock";
+
+/// How `just-mcp serve` talks to clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Transport {
+    /// Standard input and output, for a single client
+    Stdio,
+    /// Unix domain socket at --bind PATH, one session per connection
+    Unix,
+    /// TCP on --bind ADDR, one session per connection
+    Tcp,
+    /// Streamable HTTP at /mcp on --bind ADDR, one session per client
+    Http,
+    /// Server-sent events at /sse on --bind ADDR, one session per client
+    Sse,
+}
+
+/// The running transport, until its clients are gone or it fails.
+type Serving = Pin<Box<dyn Future<Output = Result<(), Box<dyn Error>>>>>;
+
+/// Shells `just-mcp completions` writes scripts for.
+const COMPLETION_SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let matches = Command::new("just-mcp")
-        .version(env!("CARGO_PKG_VERSION"))
-        .author("just-mcp contributors")
-        .about("MCP Server for Justfile Integration")
-        .arg(
-            Arg::new("working-dir")
-                .short('d')
-                .long("directory")
-                .value_name("DIR")
-                .help("Working directory for the MCP server")
-                .default_value("."),
-        )
-        .arg(
-            Arg::new("stdio")
-                .long("stdio")
-                .help("Run as MCP server using stdio transport")
-                .action(clap::ArgAction::SetTrue),
-        )
-        .arg(
-            Arg::new("allow")
-                .long("allow")
-                .value_name("JUSTFILE")
-                .help("Register a justfile path for execution (repeatable; omit for permissive mode)")
-                .action(clap::ArgAction::Append),
-        )
-        .get_matches();
+    // Answers completion requests from the scripts `completions` writes
+    CompleteEnv::with_factory(cli).complete();
 
-    let working_dir = matches.get_one::<String>("working-dir").unwrap();
-    let working_path = Path::new(working_dir);
+    let matches = cli().get_matches();
+
+    let telemetry = init_logging(&matches)?;
+
+    if let Some(completions) = matches.subcommand_matches("completions") {
+        let shell = completions.get_one::<String>("shell").unwrap();
+        return write_completions(shell);
+    }
 
-    // Build registry from --allow flags; empty = permissive mode
-    let registry = match matches.get_many::<String>("allow") {
+    // --config replaces the user and project config files
+    let cli_dirs: Vec<PathBuf> = matches
+        .get_many::<String>("working-dir")
+        .unwrap_or_default()
+        .map(PathBuf::from)
+        .collect();
+    let cli_dir = cli_dirs.first().cloned();
+    let config_files = match matches.get_one::<String>("config") {
+        Some(path) => vec![PathBuf::from(path)],
+        None => ServerConfig::discover(cli_dir.as_deref().unwrap_or(Path::new("."))),
+    };
+    // Before loading the config, so a broken one is diagnosed rather than fatal
+    if let Some(json) = matches
+        .subcommand_matches("doctor")
+        .map(|doctor| doctor.get_flag("json"))
+    {
+        let working_dir = cli_dir.unwrap_or_else(|| PathBuf::from("."));
+        let justfile = matches.get_one::<String>("justfile").map(PathBuf::from);
+        let checks = doctor::diagnose(&config_files, &working_dir, justfile.as_deref());
+        if json {
+            println!("{}", serde_json::to_string_pretty(&checks)?);
+        } else {
+            print!("{}", doctor::to_text(&checks));
+        }
+        std::process::exit(if doctor::is_healthy(&checks) { 0 } else { 1 });
+    }
+    let mut config = ServerConfig::load_layered(&config_files)?;
+    let working_dir = cli_dir
+        .or_else(|| config.directory.clone())
+        .unwrap_or_else(|| PathBuf::from("."));
+    let working_path = working_dir.as_path();
+    let search_boundary = config.search_boundary.clone();
+    let boundary = search_boundary.as_deref();
+    let justfile = match matches.get_one::<String>("justfile").map(PathBuf::from) {
+        Some(path) => Some(std::path::absolute(path)?),
+        None => config.justfile.clone(),
+    };
+    let profile = matches
+        .get_one::<String>("profile")
+        .cloned()
+        .or_else(|| config.profile.clone());
+    if let Some(name) = &profile {
+        config.check_profile(name)?;
+    }
+    // Only serving and `history` use the MCP_* settings; the offline
+    // subcommands neither check them nor create their directories
+    let offline = matches!(
+        matches.subcommand_name(),
+        Some("list" | "run" | "validate" | "fmt" | "docs" | "schema")
+    );
+    let settings = if offline {
+        McpSettings::default()
+    } else {
+        validate_mcp_environment(&McpEnvironment::with_process_env(), &[])?;
+        let settings = McpSettings::from_env()?;
+        settings.create_dirs()?;
+        settings
+    };
+    let mut env_sources = config.env_sources();
+    if let Some(temp_dir) = &settings.temp_dir {
+        env_sources
+            .defaults
+            .insert("TMPDIR".to_string(), temp_dir.display().to_string());
+    }
+    let (mut environment, profiles) = env_sources.load()?;
+    let secret_patterns = config
+        .secret_patterns
+        .clone()
+        .unwrap_or_else(redact::default_secret_patterns);
+    server_log::set_redactor(
+        Redactor::new(&secret_patterns)
+            .with_variables(std::env::vars())
+            .with_variables(&environment)
+            .with_variables(profiles.values().flatten()),
+    );
+    if let Some(patterns) = matches.get_one::<String>("allow-recipes") {
+        config
+            .recipes
+            .allow
+            .extend(RecipePolicy::parse_patterns(patterns));
+    }
+    if let Some(patterns) = matches.get_one::<String>("deny-recipes") {
+        config
+            .recipes
+            .deny
+            .extend(RecipePolicy::parse_patterns(patterns));
+    }
+    config.recipes.hide_blocked |= matches.get_flag("hide-blocked-recipes");
+    if let Some(max) = matches.get_one::<usize>("max-concurrent-executions") {
+        config.limits.max_concurrent_executions = Some(*max);
+    }
+    if let Some(max) = matches.get_one::<usize>("tool-calls-per-minute") {
+        config.limits.tool_calls_per_minute = Some(*max);
+    }
+    for rule in config.lints.unknown_rules() {
+        tracing::warn!(rule, "ignoring unknown lint rule");
+    }
+
+    if let Some(list) = matches.subcommand_matches("list") {
+        let path = locate_justfile(justfile.as_deref(), working_path, boundary)?;
+        return list_recipes(&path, list.get_flag("json"));
+    }
+    if let Some(run) = matches.subcommand_matches("run") {
+        let recipe = run.get_one::<String>("recipe").unwrap();
+        let args: Vec<String> = run.get_many("args").unwrap_or_default().cloned().collect();
+        let path = locate_justfile(justfile.as_deref(), working_path, boundary)?;
+        if config.dotenv_stack {
+            let mut dotenv = McpEnvironment::new();
+            dotenv.load_dotenv_stack(working_path, profile.as_deref())?;
+            for (name, value) in dotenv.variables() {
+                environment
+                    .entry(name.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        if let Some(name) = &profile {
+            environment.extend(profiles[name].clone());
+        }
+        let options = ExecutionOptions {
+            env: environment,
+            inherit_env: config.inherit_env,
+            ..Default::default()
+        };
+        std::process::exit(run_recipe(&path, recipe, &args, options, &config.secrets)?);
+    }
+    if let Some(validate) = matches.subcommand_matches("validate") {
+        let path = match validate.get_one::<String>("path").map(Path::new) {
+            Some(dir) if dir.is_dir() => locate_justfile(None, dir, boundary)?,
+            Some(path) => path.to_path_buf(),
+            None => locate_justfile(justfile.as_deref(), working_path, boundary)?,
+        };
+        let valid = validate_justfile(&path, &config.lints, validate.get_flag("json"))?;
+        std::process::exit(if valid { 0 } else { 1 });
+    }
+    if let Some(fmt) = matches.subcommand_matches("fmt") {
+        let path = match fmt.get_one::<String>("path").map(Path::new) {
+            Some(dir) if dir.is_dir() => locate_justfile(None, dir, boundary)?,
+            Some(path) => path.to_path_buf(),
+            None => locate_justfile(justfile.as_deref(), working_path, boundary)?,
+        };
+        let formatted = format_file(&path, fmt.get_flag("check"))?;
+        std::process::exit(if formatted { 0 } else { 1 });
+    }
+    if let Some(docs) = matches.subcommand_matches("docs") {
+        let path = match docs.get_one::<String>("path").map(Path::new) {
+            Some(dir) if dir.is_dir() => locate_justfile(None, dir, boundary)?,
+            Some(path) => path.to_path_buf(),
+            None => locate_justfile(justfile.as_deref(), working_path, boundary)?,
+        };
+        return write_docs(&path, docs);
+    }
+    if let Some(history) = matches.subcommand_matches("history") {
+        return print_history(&settings, history);
+    }
+
+    if let Some(path) = &justfile {
+        locate_justfile(Some(path), working_path, boundary)?;
+    }
+
+    // Build registry from --allow flags, else the config's justfiles; empty = permissive mode
+    let allowed: Option<Vec<PathBuf>> = match matches.get_many::<String>("allow") {
+        Some(paths) => Some(paths.map(PathBuf::from).collect()),
+        None => (!config.justfiles.is_empty()).then(|| config.justfiles.clone()),
+    };
+    let registry = match allowed {
         Some(paths) => {
-            let reg = JustfileRegistry::from_paths(paths.map(|p| Path::new(p)));
-            eprintln!(
-                "just-mcp: strict mode — {} registered justfile(s)",
-                reg.len()
-            );
+            let reg = JustfileRegistry::from_paths(paths);
+            tracing::info!(justfiles = reg.len(), "strict mode");
             reg
         }
         None => {
-            eprintln!("just-mcp: permissive mode — all justfiles accessible");
+            tracing::info!("permissive mode, all justfiles accessible");
             JustfileRegistry::permissive()
         }
     };
 
-    if matches.get_flag("stdio") {
-        // Run as MCP server
-        eprintln!(
-            "Starting just-mcp MCP server in directory: {}",
-            working_path.display()
+    // Watching is off unless --watch or a watch interval turns it on
+    let watch_interval = matches.get_one::<u64>("watch-interval").copied();
+    let watch_interval = match watch_interval.or(config.watch_interval).unwrap_or(0) {
+        // --watch wins over a config file that turns watching off
+        0 if matches.get_flag("watch") => Some(DEFAULT_WATCH_INTERVAL),
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    };
+    let audit_log = matches
+        .get_one::<String>("audit-log")
+        .map(PathBuf::from)
+        .or_else(|| config.audit_log.clone())
+        .map(|path| AuditLog::open(&path))
+        .transpose()?;
+    let history = match settings.history_path() {
+        Some(path) => ExecutionHistory::open(&path, history::DEFAULT_CAPACITY)?,
+        None => ExecutionHistory::default(),
+    };
+    // MCP_TOOL_TIMEOUT_<TOOL> variables override the config's [tool-timeouts]
+    let tool_timeouts = config.tool_timeouts.with_env_vars(std::env::vars())?;
+    let server = JustMcpServer::with_registry(working_path, registry)
+        .with_lint_config(config.lints)
+        .with_watch_interval(watch_interval)
+        .with_recipe_policy(config.recipes)
+        .with_env_policy(config.inherit_env)
+        .with_audit_log(audit_log)
+        .with_session_limits(config.limits)
+        .with_tool_timeouts(tool_timeouts)
+        .with_max_message_size(settings.max_message_size)
+        .with_secret_patterns(secret_patterns)
+        .with_history(history)
+        .with_environment(environment)
+        .with_profiles(profiles, profile)
+        .with_env_sources(env_sources)
+        .with_dotenv_stack(config.dotenv_stack)
+        .with_secret_provider(config.secrets.provider())
+        .with_secret_env(config.secrets.env)
+        .with_justfile(justfile)
+        .with_search_boundary(search_boundary)
+        .with_project_roots(if cli_dirs.len() > 1 {
+            roots::project_roots(&cli_dirs)
+        } else {
+            Vec::new()
+        })
+        .with_parameter_elicitation(
+            matches.get_flag("elicit-parameters") || config.elicit_parameters,
         );
 
-        let server = JustMcpServer::with_registry(working_path, registry);
+    if let Some(schema) = matches.subcommand_matches("schema") {
+        let tools = server.tools();
+        let output = match schema.get_one::<String>("tool") {
+            Some(name) => {
+                let tool = tools
+                    .into_iter()
+                    .find(|tool| tool.name == *name)
+                    .ok_or_else(|| format!("no tool named '{name}'"))?;
+                serde_json::to_value(tool)?
+            }
+            None => serde_json::json!({ "tools": tools }),
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let auth_token = matches
+        .get_one::<String>("auth-token")
+        .cloned()
+        .or_else(|| std::env::var(auth::AUTH_TOKEN_VAR).ok())
+        .filter(|token| !token.is_empty());
+    let tls_files = match (
+        matches.get_one::<String>("tls-cert").map(PathBuf::from),
+        matches.get_one::<String>("tls-key").map(PathBuf::from),
+    ) {
+        (Some(cert), Some(key)) => Some((cert, key)),
+        _ => config.tls_cert.clone().zip(config.tls_key.clone()),
+    };
+    let network = NetworkOptions {
+        auth_token,
+        tls: match tls_files {
+            Some((cert, key)) => Some(tls::load_acceptor(&cert, &key)?),
+            None => None,
+        },
+    };
+
+    let serve = match matches.subcommand_matches("serve") {
+        Some(serve) => {
+            let daemon = serve.get_flag("daemon") || config.daemon;
+            let transport = match serve.get_one::<Transport>("transport") {
+                Some(transport) => *transport,
+                None => match config.transport.as_deref() {
+                    Some(name) => Transport::from_str(name, true)
+                        .map_err(|_| format!("invalid transport '{name}' in config"))?,
+                    None if daemon => Transport::Unix,
+                    None => Transport::Stdio,
+                },
+            };
+            if daemon && transport == Transport::Stdio {
+                return Err("--daemon needs a transport other than stdio".into());
+            }
+            let bind = serve
+                .get_one::<String>("bind")
+                .cloned()
+                .or(config.bind)
+                .or_else(|| {
+                    (daemon && transport == Transport::Unix)
+                        .then(|| working_path.join(DAEMON_SOCKET).display().to_string())
+                });
+            let http_bind = serve
+                .get_one::<String>("http-bind")
+                .cloned()
+                .or(config.http_bind);
+            if let Some(http_bind) = http_bind {
+                let address: SocketAddr = http_bind
+                    .parse()
+                    .map_err(|_| format!("invalid HTTP address '{http_bind}'"))?;
+                tracing::info!(%address, "serving /healthz, /readyz and /metrics");
+                let server = server.clone();
+                let network = network.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = http::serve_http(server, address, network).await {
+                        tracing::error!(%error, "HTTP endpoints stopped");
+                    }
+                });
+            }
+            Some((transport, bind))
+        }
+        None => deprecated_transport(&matches),
+    };
+
+    if let Some((transport, bind)) = serve {
+        let grace_period = matches
+            .get_one::<u64>("shutdown-grace-period")
+            .copied()
+            .or(config.shutdown_grace_period)
+            .map_or(DEFAULT_GRACE_PERIOD, Duration::from_secs);
+        let mut socket_file = None;
+        let serving: Serving = match (transport, bind) {
+            (Transport::Stdio, Some(_)) => {
+                return Err("--bind is not used with --transport stdio".into());
+            }
+            (Transport::Stdio, None) => {
+                tracing::info!(
+                    directory = %working_path.display(),
+                    "starting MCP server on stdio"
+                );
+
+                // Start the MCP server with stdio transport
+                let session = server.open_session("stdio");
+                let running_service = match server.audit_log() {
+                    Some(log) => {
+                        let (stdin, stdout) = stdio();
+                        let id = session.id().to_string();
+                        let reader = AuditedStream::new(stdin, log.clone(), &id);
+                        let writer = AuditedStream::new(stdout, log, id);
+                        server.clone().serve((reader, writer)).await?
+                    }
+                    None => server.clone().serve(stdio()).await?,
+                };
 
-        // Start the MCP server with stdio transport
-        let running_service = server.serve(stdio()).await?;
+                // Keep the server running
+                Box::pin(async move {
+                    running_service.waiting().await?;
+                    drop(session);
+                    Ok(())
+                })
+            }
+            (Transport::Unix, bind) => {
+                let socket = bind.ok_or("--transport unix needs --bind PATH")?;
+                tracing::info!(
+                    socket,
+                    directory = %working_path.display(),
+                    "starting MCP server on a Unix socket"
+                );
+                socket_file = Some(PathBuf::from(&socket));
+                Box::pin(serve_socket(server.clone(), PathBuf::from(socket)))
+            }
+            (Transport::Tcp, bind) => {
+                let bind = bind.unwrap_or_else(|| DEFAULT_TCP_ADDRESS.to_string());
+                let address: SocketAddr = bind
+                    .parse()
+                    .map_err(|_| format!("invalid TCP address '{bind}'"))?;
+                if network.auth_token.is_some() {
+                    return Err("the tcp transport cannot check the auth token; \
+                                use --transport http, or unset --auth-token and MCP_AUTH_TOKEN"
+                        .into());
+                }
+                warn_if_exposed(address, None);
+                tracing::info!(
+                    %address,
+                    directory = %working_path.display(),
+                    "starting MCP server on tcp"
+                );
+                let (server, tls) = (server.clone(), network.tls);
+                Box::pin(async move { Ok(transport::serve_tcp(server, address, tls).await?) })
+            }
+            (Transport::Http | Transport::Sse, bind) => {
+                let bind = bind.unwrap_or_else(|| DEFAULT_HTTP_ADDRESS.to_string());
+                let address: SocketAddr = bind
+                    .parse()
+                    .map_err(|_| format!("invalid HTTP address '{bind}'"))?;
+                warn_if_exposed(address, network.auth_token.as_deref());
+                if server.audit_log().is_some() {
+                    tracing::warn!("--audit-log only records stdio, unix and tcp sessions");
+                }
+                let (endpoint, path) = if transport == Transport::Http {
+                    (McpEndpoint::StreamableHttp, http::MCP_PATH)
+                } else {
+                    (McpEndpoint::Sse, http::SSE_PATH)
+                };
+                tracing::info!(
+                    %address,
+                    path,
+                    directory = %working_path.display(),
+                    "starting MCP server on http"
+                );
+                let router = http::router(
+                    server.clone(),
+                    Some(endpoint),
+                    network.auth_token.as_deref(),
+                );
+                Box::pin(async move { Ok(http::serve(router, address, network.tls).await?) })
+            }
+        };
 
-        // Keep the server running
-        running_service.waiting().await?;
+        // Stop on a signal, or when the stdio client disconnects
+        tokio::select! {
+            result = serving => result?,
+            signal = shutdown_signal() => tracing::info!(signal, "shutting down"),
+        }
+        server.shutdown(grace_period).await;
+        if let Some(path) = socket_file {
+            let _ = std::fs::remove_file(path);
+        }
+        if let Some(telemetry) = &telemetry {
+            telemetry.shutdown();
+        }
+        // A blocking read of stdin would keep the runtime from shutting
+        // down, so leave without waiting for it
+        std::process::exit(0);
     } else {
         // Show usage information
         println!("just-mcp v{}", env!("CARGO_PKG_VERSION"));
@@ -74,23 +507,710 @@ async fn main() -> Result<(), Box<dyn Error>> {
         println!();
         println!("Usage:");
         println!(
-            "  {} --stdio                    Run as MCP server with stdio transport",
+            "  {} serve                      Run as MCP server with stdio transport",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} --directory <DIR> serve    Run MCP server in specific directory",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} serve --transport unix --bind <PATH>",
+            env!("CARGO_PKG_NAME")
+        );
+        println!("                                Serve several clients on a Unix domain socket");
+        println!(
+            "  {} serve --transport tcp [--bind <ADDR>]",
+            env!("CARGO_PKG_NAME")
+        );
+        println!("                                Serve clients over TCP (default 127.0.0.1:8377)");
+        println!(
+            "  {} serve --transport http|sse [--bind <ADDR>]",
             env!("CARGO_PKG_NAME")
         );
+        println!("                                HTTP at /mcp or SSE at /sse (port 8378)");
         println!(
-            "  {} --directory <DIR> --stdio  Run MCP server in specific directory",
+            "  {} serve --daemon             Keep serving clients on ./.just-mcp.sock",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} list [--json]              Print the recipes as just-mcp parses them",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} run <RECIPE> [ARGS...]     Run a recipe with just-mcp's executor",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} validate [PATH] [--json]   Lint a justfile; exits non-zero on errors",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} fmt [PATH] [--check]       Format a justfile; --check prints the diff instead",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} docs [PATH] [-o FILE]      Print Markdown or HTML docs for a justfile",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} history [--json]           Print past recipe executions, newest first",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} doctor [--json]            Check the config, justfile, shell and just install",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} schema [TOOL]              Print the tools' input and output JSON Schemas",
+            env!("CARGO_PKG_NAME")
+        );
+        println!(
+            "  {} completions <SHELL>        Print a bash, zsh, fish or powershell completion script",
             env!("CARGO_PKG_NAME")
         );
         println!();
         println!("MCP Tools Available:");
         println!("  list_recipes      - List all available recipes in the justfile");
+        println!("  list_justfiles    - List the justfiles in the working directory tree by id");
+        println!("  list_workspace_recipes - List the recipes of every justfile in the tree");
+        println!("  set_working_directory - Switch this session to another project directory");
         println!("  run_recipe        - Execute a specific recipe with optional arguments");
+        println!("  run_sequence      - Run several recipes in order, stopping at the first failure");
+        println!("  start_recipe      - Start a recipe in the background and return an execution id");
+        println!("  get_execution_status - Poll the state and output so far of a started recipe");
+        println!("  get_execution_result - Get the result of a started recipe once it finishes");
+        println!("  tail_execution_log - Get the last lines or a byte range of a started recipe's output");
+        println!("  cancel_execution  - Cancel a started recipe, killing its process group");
+        println!("  execution_history - List past executions, filtered by recipe and status");
+        println!("  list_sessions     - List the clients connected to the server");
+        println!("  get_environment   - Show the environment recipes see, with secrets masked");
+        println!("  set_session_environment - Set variables passed to later recipe runs in this session");
+        println!("  set_profile       - Switch the config profile applied to later recipe runs in this session");
+        println!("  dry_run_recipe    - Preview the commands a recipe would run, without running it");
+        println!("  explain_recipe    - Show a recipe's body with interpolations resolved");
         println!("  get_recipe_info   - Get detailed information about a specific recipe");
+        println!("  get_recipe_signature - Get the parameters, defaults and docs for calling a recipe");
+        println!("  search_recipes    - Search recipe names, documentation, and bodies");
         println!("  validate_justfile - Validate the justfile for syntax and semantic errors");
+        println!("  dependency_graph  - Get the recipe dependency graph (JSON, DOT, or Mermaid)");
+        println!("  document_justfile - Generate Markdown or HTML docs for the justfile (optionally JUSTFILE.md)");
+        println!("  import_makefile   - Convert a Makefile into a justfile draft");
+        println!("  init_justfile     - Scaffold a starter justfile for the detected project type");
+        println!("  create_recipe     - Append a new recipe to the justfile and return the diff");
+        println!("  generate_recipe   - Have the client's model write a recipe for a goal (sampling)");
+        println!("  update_recipe     - Edit a recipe in place (dry_run returns the diff only)");
+        println!("  delete_recipe     - Delete a recipe (force when other recipes depend on it)");
+        println!("  rename_recipe     - Rename a recipe and update references to it");
+        println!("  set_variable      - Update or add a top-level variable assignment");
+        println!("  format_justfile   - Format the justfile (check_only reports the diff)");
+        println!("  just_<recipe>     - Run a recipe directly, one tool per public recipe");
         println!();
         println!("Example usage with MCP client:");
-        println!("  {} --stdio | your-mcp-client", env!("CARGO_PKG_NAME"));
+        println!("  {} serve | your-mcp-client", env!("CARGO_PKG_NAME"));
+    }
+
+    Ok(())
+}
+
+fn cli() -> Command {
+    Command::new("just-mcp")
+        .version(env!("CARGO_PKG_VERSION"))
+        .author("just-mcp contributors")
+        .about("MCP Server for Justfile Integration")
+        .arg(
+            Arg::new("working-dir")
+                .short('d')
+                .long("directory")
+                .value_name("DIR")
+                .help("Working directory for the MCP server (default: the config's `directory`, else .); repeat to serve several projects")
+                .action(clap::ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("justfile")
+                .short('f')
+                .long("justfile")
+                .value_name("FILE")
+                .help("Use this justfile, e.g. build.just, instead of looking for one in the working directory")
+                .global(true),
+        )
+        .arg(
+            Arg::new("stdio")
+                .long("stdio")
+                .help("Deprecated alias of `serve`")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("socket")
+                .long("socket")
+                .value_name("PATH")
+                .help("Deprecated alias of `serve --transport unix --bind PATH`"),
+        )
+        .arg(
+            Arg::new("tcp")
+                .long("tcp")
+                .value_name("ADDR")
+                .help("Deprecated alias of `serve --transport tcp --bind ADDR`")
+                .value_parser(clap::value_parser!(SocketAddr))
+                .conflicts_with("socket"),
+        )
+        .arg(
+            Arg::new("allow")
+                .long("allow")
+                .value_name("JUSTFILE")
+                .help("Register a justfile path for execution (repeatable; omit for permissive mode)")
+                .action(clap::ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Load server configuration from this TOML file instead of the user and project config files")
+                .global(true),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .help("Apply this config profile's env files and variables to recipe runs, e.g. 'staging'")
+                .global(true),
+        )
+        .arg(
+            Arg::new("watch-interval")
+                .long("watch-interval")
+                .value_name("SECONDS")
+                .help("Watch justfiles, checking for changes every SECONDS (0 disables watching)")
+                .value_parser(clap::value_parser!(u64))
+                .global(true),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .help("Reload justfiles when they change on disk, checking every 2 seconds unless --watch-interval is given")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("elicit-parameters")
+                .long("elicit-parameters")
+                .help("Ask the user for required parameters missing from run_recipe calls")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("allow-recipes")
+                .long("allow-recipes")
+                .value_name("PATTERNS")
+                .help("Only let clients run recipes matching these comma-separated patterns, e.g. 'build,test,lint-*'")
+                .global(true),
+        )
+        .arg(
+            Arg::new("deny-recipes")
+                .long("deny-recipes")
+                .value_name("PATTERNS")
+                .help("Never let clients run recipes matching these comma-separated patterns, e.g. 'deploy*,db-*'")
+                .global(true),
+        )
+        .arg(
+            Arg::new("hide-blocked-recipes")
+                .long("hide-blocked-recipes")
+                .help("Leave recipes the allow/deny patterns block out of listings")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("max-concurrent-executions")
+                .long("max-concurrent-executions")
+                .value_name("N")
+                .help("Recipe executions each client session may have running at once")
+                .value_parser(clap::value_parser!(usize))
+                .global(true),
+        )
+        .arg(
+            Arg::new("tool-calls-per-minute")
+                .long("tool-calls-per-minute")
+                .value_name("N")
+                .help("Tool calls each client session may make per minute")
+                .value_parser(clap::value_parser!(usize))
+                .global(true),
+        )
+        .arg(
+            Arg::new("shutdown-grace-period")
+                .long("shutdown-grace-period")
+                .value_name("SECONDS")
+                .help("Seconds running recipes get to finish on SIGINT/SIGTERM before they are killed [default: 10]")
+                .value_parser(clap::value_parser!(u64))
+                .global(true),
+        )
+        .arg(
+            Arg::new("auth-token")
+                .long("auth-token")
+                .value_name("TOKEN")
+                .help("Require this bearer token for the HTTP and SSE transports and /metrics (default: $MCP_AUTH_TOKEN)")
+                .global(true),
+        )
+        .arg(
+            Arg::new("tls-cert")
+                .long("tls-cert")
+                .value_name("FILE")
+                .help("Serve TCP and HTTP over TLS with this PEM certificate chain")
+                .requires("tls-key")
+                .global(true),
+        )
+        .arg(
+            Arg::new("tls-key")
+                .long("tls-key")
+                .value_name("FILE")
+                .help("PEM private key for --tls-cert")
+                .requires("tls-cert")
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .help("Server log level: error, warn, info, debug or trace (default: $MCP_LOG_LEVEL, else info)")
+                .value_parser(clap::value_parser!(Level))
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("Server log format: pretty or json [default: pretty]")
+                .value_parser(clap::value_parser!(LogFormat))
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_name("FILE")
+                .help("Append the server log to this file instead of stderr")
+                .global(true),
+        )
+        .arg(
+            Arg::new("audit-log")
+                .long("audit-log")
+                .value_name("FILE")
+                .help("Append every JSON-RPC message, secrets masked and long output cut, to this JSON Lines file")
+                .global(true),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Run the MCP server")
+                .arg(
+                    Arg::new("transport")
+                        .long("transport")
+                        .help("How clients connect (default: the config's `transport`, else stdio)")
+                        .value_parser(clap::value_parser!(Transport)),
+                )
+                .arg(
+                    Arg::new("bind")
+                        .long("bind")
+                        .value_name("ADDR|PATH")
+                        .help("Address (tcp 127.0.0.1:8377, http and sse 127.0.0.1:8378 by default) or Unix socket path to listen on"),
+                )
+                .arg(
+                    Arg::new("http-bind")
+                        .long("http-bind")
+                        .value_name("ADDR")
+                        .help("Also serve the /healthz, /readyz and /metrics HTTP endpoints on this address"),
+                )
+                .arg(
+                    Arg::new("daemon")
+                        .long("daemon")
+                        .help("Keep serving clients one after another and side by side, on a Unix socket at ./.just-mcp.sock unless --transport or --bind says otherwise")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("Print the recipes as just-mcp parses them, without starting a server")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print JSON, as the list_recipes tool returns it")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Run a recipe with just-mcp's executor, without starting a server")
+                .arg(
+                    Arg::new("recipe")
+                        .required(true)
+                        .help("Recipe to run")
+                        .add(ArgValueCompleter::new(complete_recipe)),
+                )
+                .arg(
+                    Arg::new("args")
+                        .num_args(0..)
+                        .trailing_var_arg(true)
+                        .allow_hyphen_values(true)
+                        .help("Arguments for the recipe's parameters"),
+                ),
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("Validate and lint a justfile, exiting non-zero when it has errors")
+                .arg(
+                    Arg::new("path")
+                        .help("Justfile, or directory containing one (default: --directory)"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print JSON, as the validate_justfile tool returns it")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("fmt")
+                .about("Format a justfile in place, or with --check report whether it is formatted")
+                .arg(
+                    Arg::new("path")
+                        .help("Justfile, or directory containing one (default: --directory)"),
+                )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Print the diff instead of writing, exiting non-zero when the justfile is not formatted")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("docs")
+                .about("Generate Markdown or HTML documentation for a justfile, as the document_justfile tool does")
+                .arg(
+                    Arg::new("path")
+                        .help("Justfile, or directory containing one (default: --directory)"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Documentation format [default: markdown]")
+                        .value_parser(DocFormat::ALL.map(DocFormat::as_str)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("Write to this file instead of stdout"),
+                )
+                .arg(
+                    Arg::new("include-private")
+                        .long("include-private")
+                        .help("Include [private] and _-prefixed recipes")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Print past recipe executions kept in MCP_DATA_DIR, newest first")
+                .arg(
+                    Arg::new("recipe")
+                        .long("recipe")
+                        .help("Only executions of this recipe"),
+                )
+                .arg(
+                    Arg::new("status")
+                        .long("status")
+                        .help("Only executions with this status")
+                        .value_parser(ExecutionStatus::ALL.map(ExecutionStatus::as_str)),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .help("Most executions to print [default: 20]")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print JSON, as the execution_history tool returns it")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check the setup: config, justfile, shell, just, dotenv and TLS files")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the checks as JSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Print the tools with their input and output JSON Schemas, as tools/list returns them")
+                .arg(
+                    Arg::new("tool")
+                        .help("Print only this tool, e.g. run_recipe or just_build"),
+                ),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script, completing recipe names for `run`")
+                .arg(
+                    Arg::new("shell")
+                        .required(true)
+                        .value_parser(COMPLETION_SHELLS),
+                ),
+        )
+}
+
+/// `just-mcp completions`: the script registering just-mcp's completer
+/// with `shell`.
+fn write_completions(shell: &str) -> Result<(), Box<dyn Error>> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell)
+        .ok_or_else(|| format!("unsupported shell '{shell}'"))?;
+    let bin = env!("CARGO_PKG_NAME");
+    completer.write_registration("COMPLETE", bin, bin, bin, &mut std::io::stdout())?;
+    Ok(())
+}
+
+/// Recipe names for `run`, from the justfile in the current directory.
+fn complete_recipe(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let Some(justfile) =
+        find_justfile_upward(Path::new("."), None).and_then(|path| parse_justfile(&path).ok())
+    else {
+        return Vec::new();
+    };
+    justfile
+        .recipes
+        .iter()
+        .filter(|recipe| !recipe.is_private() && recipe.name.starts_with(current.as_ref()))
+        .map(|recipe| {
+            let summary = recipe
+                .documentation
+                .as_deref()
+                .and_then(|doc| doc.lines().next());
+            CompletionCandidate::new(&recipe.name).help(summary.map(|line| line.to_owned().into()))
+        })
+        .collect()
+}
+
+/// Warn when `address` can be reached from other hosts and no auth token
+/// protects it.
+fn warn_if_exposed(address: SocketAddr, auth_token: Option<&str>) {
+    if !address.ip().is_loopback() && auth_token.is_none() {
+        tracing::warn!(
+            %address,
+            "listening on a non-loopback address; anyone who can connect can run recipes"
+        );
     }
+}
+
+/// The transport chosen with the flags `serve` replaced, if any, warning
+/// that they are deprecated.
+fn deprecated_transport(matches: &ArgMatches) -> Option<(Transport, Option<String>)> {
+    let (flag, transport, bind) = if let Some(address) = matches.get_one::<SocketAddr>("tcp") {
+        ("--tcp", Transport::Tcp, Some(address.to_string()))
+    } else if let Some(path) = matches.get_one::<String>("socket") {
+        ("--socket", Transport::Unix, Some(path.clone()))
+    } else if matches.get_flag("stdio") {
+        ("--stdio", Transport::Stdio, None)
+    } else {
+        return None;
+    };
+    let name = transport.to_possible_value()?.get_name().to_string();
+    let bind_flag = if bind.is_some() { " --bind" } else { "" };
+    tracing::warn!("{flag} is deprecated; use `just-mcp serve --transport {name}{bind_flag}`");
+    Some((transport, bind))
+}
 
+/// Send the server's own log to stderr or `--log-file`, at `--log-level`,
+/// else `MCP_LOG_LEVEL`, else info, and its spans to the OTLP endpoint set
+/// with the `OTEL_*` variables, if any.
+fn init_logging(matches: &ArgMatches) -> Result<Option<Telemetry>, Box<dyn Error>> {
+    let filter = server_log::log_filter(matches.get_one::<Level>("log-level").copied())
+        .map_err(|error| format!("invalid {}: {error}", server_log::LOG_LEVEL_VAR))?;
+    let format = matches
+        .get_one::<LogFormat>("log-format")
+        .copied()
+        .unwrap_or_default();
+    let writer: Box<dyn std::io::Write + Send> = match matches.get_one::<String>("log-file") {
+        Some(path) => Box::new(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|error| format!("cannot open log file {path}: {error}"))?,
+        ),
+        None => Box::new(std::io::stderr()),
+    };
+    let telemetry = Telemetry::from_env()?;
+    let tracer = telemetry.as_ref().map(Telemetry::tracer);
+    server_log::init(filter, format, writer, tracer);
+    Ok(telemetry)
+}
+
+/// The `--justfile`, else the justfile in `dir` or the nearest parent
+/// directory up to `boundary`.
+fn locate_justfile(
+    explicit: Option<&Path>,
+    dir: &Path,
+    boundary: Option<&Path>,
+) -> Result<PathBuf, Box<dyn Error>> {
+    match explicit {
+        Some(path) if path.is_file() => Ok(path.to_path_buf()),
+        Some(path) => Err(format!("justfile {} not found", path.display()).into()),
+        None => find_justfile_upward(dir, boundary)
+            .ok_or_else(|| format!("no justfile found in {}", dir.display()).into()),
+    }
+}
+
+/// `just-mcp list`: the justfile as just-mcp's parser sees it.
+fn list_recipes(path: &Path, json: bool) -> Result<(), Box<dyn Error>> {
+    let justfile = parse_justfile(path)?;
+    if json {
+        let info = JustfileInfo::from_justfile(path, &justfile);
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        print!("{}", recipe_table(&justfile));
+    }
+    Ok(())
+}
+
+/// `just-mcp run`: run a recipe with the internal executor, streaming its
+/// output and echoed commands as just does, and return its exit code.
+/// Secrets the recipe needs that are not in its environment are read from
+/// the keyring.
+fn run_recipe(
+    path: &Path,
+    recipe: &str,
+    args: &[String],
+    mut options: ExecutionOptions,
+    secrets_config: &SecretsConfig,
+) -> Result<i32, Box<dyn Error>> {
+    let justfile = parse_justfile(path)?;
+    let working_dir = path.parent().unwrap_or(Path::new("."));
+    let mut names = secrets::recipe_secrets(&justfile, recipe);
+    names.extend(secrets_config.env.iter().cloned());
+    names.retain(|name| !options.env.contains_key(name) && std::env::var_os(name).is_none());
+    let provider = secrets_config.provider();
+    options.env.extend(secrets::resolve(&provider, names)?);
+    let options = ExecutionOptions {
+        echo: true,
+        exit_strategy: ExitStrategy::Just,
+        ..options
+    };
+    let result =
+        execute_recipe_with_output(&justfile, recipe, args, working_dir, &options, &|entry| {
+            match entry.stream {
+                OutputStream::Stdout => println!("{}", entry.line),
+                OutputStream::Stderr | OutputStream::Command => eprintln!("{}", entry.line),
+            }
+        })?;
+    Ok(result.exit_code)
+}
+
+/// `just-mcp docs`: documentation for the justfile at `path`, on stdout or
+/// in `--output`.
+fn write_docs(path: &Path, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let justfile = parse_justfile(path)?;
+    let format = matches
+        .get_one::<String>("format")
+        .and_then(|name| DocFormat::parse(name))
+        .unwrap_or_default();
+    let docs = docgen::generate(
+        &justfile,
+        &docgen::default_title(&std::path::absolute(path)?),
+        matches.get_flag("include-private"),
+        format,
+    );
+    match matches.get_one::<String>("output") {
+        Some(output) => std::fs::write(output, docs)
+            .map_err(|error| format!("cannot write {output}: {error}"))?,
+        None => print!("{docs}"),
+    }
     Ok(())
 }
+
+/// `just-mcp history`: the executions recorded in `MCP_DATA_DIR`.
+fn print_history(settings: &McpSettings, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let path = settings
+        .history_path()
+        .ok_or("MCP_DATA_DIR is not set, so no execution history is kept")?;
+    let history = ExecutionHistory::open(&path, history::DEFAULT_CAPACITY)?;
+    let executions = history.query(&HistoryFilter {
+        recipe_name: matches.get_one::<String>("recipe").cloned(),
+        status: matches
+            .get_one::<String>("status")
+            .and_then(|name| ExecutionStatus::parse(name)),
+        limit: matches.get_one::<usize>("limit").copied(),
+    })?;
+    if matches.get_flag("json") {
+        let list = ExecutionHistoryList { executions };
+        println!("{}", serde_json::to_string_pretty(&list)?);
+    } else {
+        print!("{}", history::history_table(&executions));
+    }
+    Ok(())
+}
+
+/// `just-mcp fmt`: write the justfile in canonical form, or with `check`
+/// print the diff to it; true when the justfile was already formatted or
+/// has been rewritten.
+fn format_file(path: &Path, check: bool) -> Result<bool, Box<dyn Error>> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|error| format!("cannot read {}: {error}", path.display()))?;
+    let formatted = format_justfile_str(&source)?;
+    if formatted == source {
+        return Ok(true);
+    }
+    if check {
+        let label = path.display().to_string();
+        print!("{}", unified_diff(&source, &formatted, &label));
+        eprintln!("{label} is not formatted");
+        return Ok(false);
+    }
+    std::fs::write(path, &formatted)?;
+    eprintln!("formatted {}", path.display());
+    Ok(true)
+}
+
+/// `just-mcp validate`: print the validator and lint diagnostics for a
+/// justfile; true when it has no errors.
+fn validate_justfile(path: &Path, lints: &LintConfig, json: bool) -> Result<bool, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let report = ValidationReport::check(path, &content, lints);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.to_text());
+    }
+    Ok(report.is_valid)
+}
+
+#[cfg(unix)]
+async fn serve_socket(server: JustMcpServer, path: PathBuf) -> Result<(), Box<dyn Error>> {
+    Ok(transport::serve_unix_socket(server, &path).await?)
+}
+
+#[cfg(not(unix))]
+async fn serve_socket(_server: JustMcpServer, _path: PathBuf) -> Result<(), Box<dyn Error>> {
+    Err("--socket is only supported on Unix".into())
+}
+
+/// Wait for SIGINT (Ctrl-C) or, on Unix, SIGTERM and return its name.
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            return tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            };
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+    "SIGINT"
+}
@@ -50,7 +50,7 @@ async fn test_mcp_server_basic() {
         );
     }
 
-    // Start our MCP server process using the built binary
+    // Start our MCP server process through the deprecated --stdio alias of serve
     let mut server = Command::new("cargo")
         .args(["run", "--", "--stdio"])
         .stdin(Stdio::piped())
@@ -171,3 +171,21 @@ fn test_server_config_source() {
     let info = env.get_environment_info();
     assert!(info.get("sources").unwrap().contains("ServerConfig"));
 }
+
+#[test]
+fn test_load_env_file_reports_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    let env_file = temp_dir.path().join(".env");
+    std::fs::write(&env_file, "JUST_MCP_DIFF_TEST_ADDED=1\n").unwrap();
+
+    let mut env = McpEnvironment::new();
+    let diff = env.load_env_file(&env_file).unwrap();
+    assert_eq!(diff.added["JUST_MCP_DIFF_TEST_ADDED"], "1");
+    assert!(diff.removed.is_empty());
+    // The server's own environment is left alone
+    assert!(std::env::var("JUST_MCP_DIFF_TEST_ADDED").is_err());
+    assert_eq!(
+        env.origin("JUST_MCP_DIFF_TEST_ADDED").unwrap().source,
+        format!("EnvFile({})", env_file.display())
+    );
+}
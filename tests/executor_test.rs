@@ -1,4 +1,6 @@
-use just_mcp_lib::executor::execute_recipe;
+use just_mcp_lib::executor::{
+    ExecutionOptions, ExitStrategy, OutputStream, execute_recipe, execute_recipe_with_options,
+};
 use just_mcp_lib::parser::parse_justfile_str;
 use tempfile::TempDir;
 
@@ -229,3 +231,142 @@ multi:
     assert!(result.stdout.contains("Second command"));
     assert!(result.stdout.contains("Third command"));
 }
+
+#[test]
+fn test_execute_recipe_transcript_interleaves_streams() {
+    let content = r#"
+setup:
+    echo "setup out"
+
+mixed: setup
+    echo "first out"
+    echo "then err" >&2
+    echo "last out"
+"#;
+
+    let justfile = parse_justfile_str(content).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let options = ExecutionOptions {
+        transcript: true,
+        ..Default::default()
+    };
+
+    let result =
+        execute_recipe_with_options(&justfile, "mixed", &[], temp_dir.path(), &options).unwrap();
+
+    let lines: Vec<(OutputStream, &str)> = result
+        .transcript
+        .iter()
+        .map(|entry| (entry.stream, entry.line.as_str()))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (OutputStream::Stdout, "setup out"),
+            (OutputStream::Stdout, "first out"),
+            (OutputStream::Stderr, "then err"),
+            (OutputStream::Stdout, "last out"),
+        ]
+    );
+    assert!(
+        result
+            .transcript
+            .windows(2)
+            .all(|pair| pair[0].elapsed_us <= pair[1].elapsed_us)
+    );
+
+    // Transcript is opt-in
+    let result = execute_recipe(&justfile, "mixed", &[], temp_dir.path()).unwrap();
+    assert!(result.transcript.is_empty());
+}
+
+#[test]
+fn test_execute_recipe_echo_respects_quiet_markers() {
+    let content = r#"
+set quiet
+
+loud:
+    echo "hidden command"
+    @echo "shown command"
+
+@whisper:
+    echo "also hidden"
+"#;
+
+    let justfile = parse_justfile_str(content).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let options = ExecutionOptions {
+        transcript: true,
+        echo: true,
+        ..Default::default()
+    };
+
+    let result =
+        execute_recipe_with_options(&justfile, "loud", &[], temp_dir.path(), &options).unwrap();
+
+    let commands: Vec<&str> = result
+        .transcript
+        .iter()
+        .filter(|entry| entry.stream == OutputStream::Command)
+        .map(|entry| entry.line.as_str())
+        .collect();
+    assert_eq!(commands, vec![r#"echo "shown command""#]);
+    assert!(result.stderr.contains(r#"echo "shown command""#));
+
+    let result =
+        execute_recipe_with_options(&justfile, "whisper", &[], temp_dir.path(), &options).unwrap();
+    assert!(result.stderr.is_empty());
+}
+
+#[test]
+fn test_execute_recipe_exit_strategies() {
+    let content = r#"
+broken:
+    false
+
+flaky:
+    -false
+    echo "after ignored"
+    exit 3
+    echo "after failure"
+
+uses_broken: broken
+    echo "main body"
+"#;
+
+    let justfile = parse_justfile_str(content).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let run = |recipe: &str, exit_strategy: ExitStrategy| {
+        let options = ExecutionOptions {
+            exit_strategy,
+            ..Default::default()
+        };
+        execute_recipe_with_options(&justfile, recipe, &[], temp_dir.path(), &options).unwrap()
+    };
+
+    // Fail-fast stops at the first failure, even for '-' lines
+    let result = run("flaky", ExitStrategy::FailFast);
+    assert_eq!(result.exit_code, 1);
+    assert!(!result.stdout.contains("after ignored"));
+
+    // Just-compatible mode ignores '-' lines and stops at the next failure
+    let result = run("flaky", ExitStrategy::Just);
+    assert_eq!(result.exit_code, 3);
+    assert!(result.stdout.contains("after ignored"));
+    assert!(!result.stdout.contains("after failure"));
+    assert_eq!(result.failures.len(), 2);
+    assert!(result.failures[0].ignored);
+
+    // Keep-going runs everything and collects every failure
+    let result = run("flaky", ExitStrategy::KeepGoing);
+    assert_eq!(result.exit_code, 3);
+    assert!(result.stdout.contains("after failure"));
+    assert_eq!(result.failures.len(), 2);
+
+    // A failed dependency only lets the recipe run when keeping going
+    let result = run("uses_broken", ExitStrategy::FailFast);
+    assert_ne!(result.exit_code, 0);
+    assert!(!result.stdout.contains("main body"));
+    let result = run("uses_broken", ExitStrategy::KeepGoing);
+    assert!(result.stdout.contains("main body"));
+}
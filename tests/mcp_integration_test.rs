@@ -32,25 +32,65 @@ with the official Rust MCP SDK (rmcp) using proper async service patterns.
 */
 
 use rmcp::{
-    ServiceExt,
-    model::CallToolRequestParam,
+    ClientHandler, RoleClient, ServiceExt,
+    model::{
+        CallToolRequestParam, ClientCapabilities, ClientInfo, Content,
+        CreateElicitationRequestParam, CreateElicitationResult, CreateMessageRequestParam,
+        CreateMessageResult, ElicitationAction, ErrorData, GetPromptRequestParam, ListRootsResult,
+        LoggingLevel, LoggingMessageNotificationParam, PromptMessageContent,
+        ReadResourceRequestParam, ResourceContents, Role, Root, SamplingMessage,
+        SetLevelRequestParam, Tool, ToolAnnotations,
+    },
+    service::{NotificationContext, RequestContext, RunningService},
     transport::{ConfigureCommandExt, TokioChildProcess},
 };
 use serde_json::{Map, Value};
 use std::borrow::Cow;
+use std::path::Path;
+use tokio::sync::mpsc;
 use tokio::time::{Duration, timeout};
 
+/// `just-mcp serve` on stdio, working in `dir`, with `extra_args` after it.
+fn server_command(dir: &Path, extra_args: &[&str]) -> tokio::process::Command {
+    tokio::process::Command::new("cargo").configure(|cmd| {
+        cmd.args(["run", "--", "serve", "--directory"]);
+        cmd.arg(dir);
+        cmd.args(extra_args);
+    })
+}
+
+/// `client` connected to a server started with `server_command`.
+async fn serve_client<C: ClientHandler>(
+    client: C,
+    dir: &Path,
+    extra_args: &[&str],
+) -> RunningService<RoleClient, C> {
+    let transport = TokioChildProcess::new(server_command(dir, extra_args))
+        .expect("Failed to create transport");
+    client
+        .serve(transport)
+        .await
+        .expect("Failed to initialize client")
+}
+
+/// A plain client connected to a server started with `server_command`.
+async fn spawn_client(dir: &Path, extra_args: &[&str]) -> RunningService<RoleClient, ()> {
+    serve_client((), dir, extra_args).await
+}
+
+fn list_tool_annotations<'a>(tools: &'a [Tool], name: &str) -> &'a ToolAnnotations {
+    tools
+        .iter()
+        .find(|t| t.name == name)
+        .and_then(|t| t.annotations.as_ref())
+        .expect("Expected tool annotations")
+}
+
 #[tokio::test]
 async fn test_mcp_server_integration() {
     // Create transport for our MCP server as child process
-    let transport =
-        TokioChildProcess::new(tokio::process::Command::new("cargo").configure(|cmd| {
-            cmd.args(["run", "--", "--stdio"]);
-        }))
-        .expect("Failed to create transport");
-
     // Create client and initialize (initialization is automatic with .serve())
-    let client = ().serve(transport).await.expect("Failed to initialize client");
+    let client = spawn_client(Path::new("."), &[]).await;
 
     // Test listing tools
     let tools = timeout(Duration::from_secs(10), client.list_all_tools())
@@ -64,6 +104,7 @@ async fn test_mcp_server_integration() {
     assert!(tool_names.contains(&"run_recipe"));
     assert!(tool_names.contains(&"get_recipe_info"));
     assert!(tool_names.contains(&"validate_justfile"));
+    assert!(tool_names.contains(&"dependency_graph"));
 
     // Test calling list_recipes
     let list_result = timeout(
@@ -86,6 +127,29 @@ async fn test_mcp_server_integration() {
     assert!(content_str.text.contains("hello"));
     assert!(content_str.text.contains("write_file"));
 
+    // Read-only tools are annotated so clients can skip confirmation
+    let annotations = list_tool_annotations(&tools, "list_recipes");
+    assert_eq!(annotations.read_only_hint, Some(true));
+    let annotations = list_tool_annotations(&tools, "run_recipe");
+    assert_eq!(annotations.destructive_hint, Some(true));
+
+    // JSON tools declare an output schema and return matching structured content
+    let list_tool = tools.iter().find(|t| t.name == "list_recipes").unwrap();
+    let output_schema = list_tool
+        .output_schema
+        .as_ref()
+        .expect("Expected list_recipes to declare an output schema");
+    assert!(output_schema["properties"]["recipes"].is_object());
+    let structured = list_result
+        .structured_content
+        .as_ref()
+        .expect("Expected structured content");
+    assert!(structured["recipes"].is_array());
+    assert_eq!(
+        structured,
+        &serde_json::from_str::<Value>(&content_str.text).unwrap()
+    );
+
     // Test calling hello recipe with default parameter
     let hello_result = timeout(
         Duration::from_secs(10),
@@ -190,13 +254,7 @@ async fn test_mcp_server_integration() {
 #[tokio::test]
 async fn test_get_recipe_info() {
     // Create transport and client
-    let transport =
-        TokioChildProcess::new(tokio::process::Command::new("cargo").configure(|cmd| {
-            cmd.args(["run", "--", "--stdio"]);
-        }))
-        .expect("Failed to create transport");
-
-    let client = ().serve(transport).await.expect("Failed to initialize client");
+    let client = spawn_client(Path::new("."), &[]).await;
 
     // Test get_recipe_info for hello recipe
     let info_result = timeout(
@@ -229,3 +287,3156 @@ async fn test_get_recipe_info() {
     // Cleanup
     client.cancel().await.expect("Failed to cancel client");
 }
+
+#[tokio::test]
+async fn test_get_recipe_signature() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("get_recipe_signature"),
+            arguments: serde_json::json!({ "recipe_name": "write_file" })
+                .as_object()
+                .cloned(),
+        }),
+    )
+    .await
+    .expect("Get recipe signature timed out")
+    .expect("Failed to get recipe signature");
+
+    let signature = result
+        .structured_content
+        .expect("Expected structured content");
+    assert!(
+        signature["signature"]
+            .as_str()
+            .unwrap()
+            .starts_with("write_file(filename, content=")
+    );
+    assert_eq!(
+        signature["documentation"],
+        "Write content to a specified file"
+    );
+    let parameters = signature["parameters"].as_array().unwrap();
+    assert_eq!(parameters[0]["name"], "filename");
+    assert_eq!(parameters[0]["required"], true);
+    assert_eq!(parameters[1]["required"], false);
+    assert_eq!(parameters[1]["default_value"], "Hello from just-mcp!");
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_validate_justfile_report() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let validate_result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("validate_justfile"),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert(
+                    "justfile_path".to_string(),
+                    Value::String("test-fixtures/sample.justfile".to_string()),
+                );
+                map
+            }),
+        }),
+    )
+    .await
+    .expect("Validate justfile timed out")
+    .expect("Failed to validate justfile");
+
+    let content_str = match &validate_result.content[0].raw {
+        rmcp::model::RawContent::Text(text) => text,
+        _ => panic!("Expected text content"),
+    };
+    let report: serde_json::Value =
+        serde_json::from_str(&content_str.text).expect("Failed to parse report JSON");
+
+    // is_valid is computed from the error count
+    let error_count = report["summary"]["error_count"]
+        .as_u64()
+        .expect("Expected summary.error_count");
+    assert_eq!(report["is_valid"].as_bool(), Some(error_count == 0));
+    assert!(report["summary"]["recipe_count"].as_u64().unwrap() > 0);
+    assert!(report["recipes"].is_array());
+    assert!(report["global"].is_array());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_per_recipe_tools() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let tools = timeout(Duration::from_secs(10), client.list_all_tools())
+        .await
+        .expect("List tools timed out")
+        .expect("Failed to list tools");
+
+    // Each recipe is exposed as a tool with a schema built from its parameters
+    let hello = tools
+        .iter()
+        .find(|t| t.name == "just_hello")
+        .expect("Expected a just_hello tool");
+    assert_eq!(hello.input_schema["properties"]["name"]["default"], "World");
+
+    let hello_result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("just_hello"),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert("name".to_string(), Value::String("MCP".to_string()));
+                map
+            }),
+        }),
+    )
+    .await
+    .expect("just_hello timed out")
+    .expect("Failed to call just_hello");
+
+    let content_str = match &hello_result.content[0].raw {
+        rmcp::model::RawContent::Text(text) => text,
+        _ => panic!("Expected text content"),
+    };
+    let result_json: serde_json::Value =
+        serde_json::from_str(&content_str.text).expect("Failed to parse result JSON");
+    assert!(
+        result_json["stdout"]
+            .as_str()
+            .unwrap()
+            .contains("Hello, MCP!")
+    );
+
+    // Unknown named arguments are rejected before execution
+    let bad_result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("just_hello"),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert("nmae".to_string(), Value::String("MCP".to_string()));
+                map
+            }),
+        }),
+    )
+    .await
+    .expect("just_hello timed out");
+    assert!(bad_result.is_err());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_private_recipe_tools() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "build:\n    echo build\n\n_helper:\n    echo helper\n\n[private]\nsecret:\n    echo secret\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let tools = timeout(Duration::from_secs(10), client.list_all_tools())
+        .await
+        .expect("List tools timed out")
+        .expect("Failed to list tools");
+    assert!(tools.iter().any(|t| t.name == "just_build"));
+
+    // Private recipes are neither listed nor callable, and fail like a missing tool
+    for name in ["just__helper", "just_secret", "just_missing"] {
+        assert!(!tools.iter().any(|t| t.name == name));
+        let error = timeout(
+            Duration::from_secs(10),
+            client.peer().call_tool(CallToolRequestParam {
+                name: Cow::Borrowed(name),
+                arguments: None,
+            }),
+        )
+        .await
+        .expect("Tool call timed out")
+        .expect_err("Expected the tool to be unknown");
+        assert!(error.to_string().contains("tool not found"), "{error}");
+    }
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_justfile_resource() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let resources = timeout(Duration::from_secs(10), client.list_all_resources())
+        .await
+        .expect("List resources timed out")
+        .expect("Failed to list resources");
+
+    let resource = resources
+        .iter()
+        .find(|r| r.uri.starts_with("justfile://") && r.uri.ends_with("justfile"))
+        .expect("Expected the default justfile to be published");
+
+    let result = timeout(
+        Duration::from_secs(10),
+        client.read_resource(ReadResourceRequestParam {
+            uri: resource.uri.clone(),
+        }),
+    )
+    .await
+    .expect("Read resource timed out")
+    .expect("Failed to read resource");
+
+    // Raw justfile text followed by its parsed structure
+    assert_eq!(result.contents.len(), 2);
+    let texts: Vec<&str> = result
+        .contents
+        .iter()
+        .map(|contents| match contents {
+            ResourceContents::TextResourceContents { text, .. } => text.as_str(),
+            _ => panic!("Expected text contents"),
+        })
+        .collect();
+    assert!(texts[0].contains("hello name="));
+    let info: serde_json::Value =
+        serde_json::from_str(texts[1]).expect("Failed to parse resource JSON");
+    assert!(
+        info["recipes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|recipe| recipe["name"] == "hello")
+    );
+
+    // Unknown URIs are rejected
+    let missing = timeout(
+        Duration::from_secs(10),
+        client.read_resource(ReadResourceRequestParam {
+            uri: "file:///etc/passwd".to_string(),
+        }),
+    )
+    .await
+    .expect("Read resource timed out");
+    assert!(missing.is_err());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_dependency_graph() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let graph_result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("dependency_graph"),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert(
+                    "justfile_path".to_string(),
+                    Value::String("test-fixtures/sample.justfile".to_string()),
+                );
+                map.insert("format".to_string(), Value::String("dot".to_string()));
+                map
+            }),
+        }),
+    )
+    .await
+    .expect("Dependency graph timed out")
+    .expect("Failed to get dependency graph");
+
+    let content_str = match &graph_result.content[0].raw {
+        rmcp::model::RawContent::Text(text) => text,
+        _ => panic!("Expected text content"),
+    };
+    assert!(content_str.text.starts_with("digraph justfile {"));
+    assert!(content_str.text.contains("\"test\" -> \"build\";"));
+    assert!(content_str.text.contains("\"deploy\" -> \"test\";"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_prompts() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let prompts = timeout(Duration::from_secs(10), client.list_all_prompts())
+        .await
+        .expect("List prompts timed out")
+        .expect("Failed to list prompts");
+    let prompt_names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+    assert!(prompt_names.contains(&"write_recipe"));
+    assert!(prompt_names.contains(&"explain_recipe"));
+
+    let explain = timeout(
+        Duration::from_secs(10),
+        client.get_prompt(GetPromptRequestParam {
+            name: "explain_recipe".to_string(),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert("name".to_string(), Value::String("hello".to_string()));
+                map
+            }),
+        }),
+    )
+    .await
+    .expect("Get prompt timed out")
+    .expect("Failed to get prompt");
+
+    assert_eq!(explain.messages.len(), 1);
+    let text = match &explain.messages[0].content {
+        PromptMessageContent::Text { text } => text,
+        _ => panic!("Expected text prompt"),
+    };
+    assert!(text.contains("`hello` recipe"));
+    assert!(text.contains("hello name="));
+
+    // Required arguments are enforced
+    let missing = timeout(
+        Duration::from_secs(10),
+        client.get_prompt(GetPromptRequestParam {
+            name: "write_recipe".to_string(),
+            arguments: None,
+        }),
+    )
+    .await
+    .expect("Get prompt timed out");
+    assert!(missing.is_err());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+/// Client that forwards list-changed notifications to the test.
+struct ChangeListener(mpsc::UnboundedSender<&'static str>);
+
+impl ClientHandler for ChangeListener {
+    async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        let _ = self.0.send("tools");
+    }
+}
+
+#[tokio::test]
+async fn test_tool_list_changed_notification() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    std::fs::write(&justfile, "build:\n    echo build\n").unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let client = serve_client(ChangeListener(tx), dir.path(), &["--watch-interval", "1"]).await;
+
+    let tools = timeout(Duration::from_secs(10), client.list_all_tools())
+        .await
+        .expect("List tools timed out")
+        .expect("Failed to list tools");
+    assert!(!tools.iter().any(|t| t.name == "just_test"));
+
+    // Give the watcher time to take its first snapshot, then add a recipe
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    std::fs::write(
+        &justfile,
+        "build:\n    echo build\n\ntest:\n    echo test\n",
+    )
+    .unwrap();
+
+    let notification = timeout(Duration::from_secs(10), rx.recv())
+        .await
+        .expect("No tools/list_changed notification");
+    assert_eq!(notification, Some("tools"));
+
+    let tools = timeout(Duration::from_secs(10), client.list_all_tools())
+        .await
+        .expect("List tools timed out")
+        .expect("Failed to list tools");
+    assert!(tools.iter().any(|t| t.name == "just_test"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_watching_is_opt_in() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    std::fs::write(&justfile, "build:\n    echo build\n").unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let client = serve_client(ChangeListener(tx), dir.path(), &[]).await;
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    std::fs::write(
+        &justfile,
+        "build:\n    echo build\n\ntest:\n    echo test\n",
+    )
+    .unwrap();
+
+    // Without --watch nothing is sent, but the next call sees the edit
+    let notification = timeout(Duration::from_secs(3), rx.recv()).await;
+    assert!(notification.is_err(), "unexpected {notification:?}");
+    let tools = timeout(Duration::from_secs(10), client.list_all_tools())
+        .await
+        .expect("List tools timed out")
+        .expect("Failed to list tools");
+    assert!(tools.iter().any(|t| t.name == "just_test"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+/// Client that forwards the `event` of each log message to the test.
+struct LogCollector(mpsc::UnboundedSender<String>);
+
+impl ClientHandler for LogCollector {
+    async fn on_logging_message(
+        &self,
+        params: LoggingMessageNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        if let Some(event) = params.data["event"].as_str() {
+            let _ = self.0.send(event.to_string());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_logging_notifications() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let client = serve_client(LogCollector(tx), Path::new("."), &[]).await;
+
+    client
+        .set_level(SetLevelRequestParam {
+            level: LoggingLevel::Debug,
+        })
+        .await
+        .expect("Failed to set log level");
+
+    timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert(
+                    "recipe_name".to_string(),
+                    Value::String("hello_simple".to_string()),
+                );
+                map
+            }),
+        }),
+    )
+    .await
+    .expect("Run recipe timed out")
+    .expect("Failed to run recipe");
+
+    let mut events = Vec::new();
+    while !events.iter().any(|event| event == "recipe_finished") {
+        let event = timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("Expected a recipe_finished log message")
+            .expect("Log channel closed");
+        events.push(event);
+    }
+    let started = events.iter().position(|event| event == "recipe_started");
+    let finished = events.iter().position(|event| event == "recipe_finished");
+    assert!(events.iter().any(|event| event == "justfile_loaded"));
+    assert!(started.expect("Expected recipe_started") < finished.unwrap());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+/// Client that advertises a single workspace root.
+struct RootsClient(String);
+
+impl ClientHandler for RootsClient {
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        Ok(ListRootsResult {
+            roots: vec![Root {
+                uri: self.0.clone(),
+                name: None,
+            }],
+        })
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities::builder().enable_roots().build(),
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_client_roots() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "from_root:\n    echo from root\n",
+    )
+    .unwrap();
+
+    let roots = RootsClient(format!("file://{}", dir.path().display()));
+    let client = serve_client(roots, Path::new("."), &[]).await;
+
+    // Roots are fetched in the background after initialization
+    let mut found = false;
+    for _ in 0..20 {
+        let tools = timeout(Duration::from_secs(10), client.list_all_tools())
+            .await
+            .expect("List tools timed out")
+            .expect("Failed to list tools");
+        if tools.iter().any(|t| t.name == "just_from_root") {
+            found = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    assert!(found, "Expected recipes from the client's root");
+
+    let result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("just_from_root"),
+            arguments: None,
+        }),
+    )
+    .await
+    .expect("just_from_root timed out")
+    .expect("Failed to call just_from_root");
+
+    let content_str = match &result.content[0].raw {
+        rmcp::model::RawContent::Text(text) => text,
+        _ => panic!("Expected text content"),
+    };
+    assert!(content_str.text.contains("from root"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_create_recipe() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    std::fs::write(&justfile, "build:\n    cargo build\n").unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let create = |name: &str| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("create_recipe"),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert("name".to_string(), Value::String(name.to_string()));
+                map.insert(
+                    "documentation".to_string(),
+                    Value::String("Run clippy".to_string()),
+                );
+                map.insert(
+                    "dependencies".to_string(),
+                    Value::Array(vec![Value::String("build".to_string())]),
+                );
+                map.insert(
+                    "body".to_string(),
+                    Value::String("cargo clippy".to_string()),
+                );
+                map
+            }),
+        })
+    };
+
+    let result = timeout(Duration::from_secs(10), create("lint"))
+        .await
+        .expect("Create recipe timed out")
+        .expect("Failed to create recipe");
+    let structured = result
+        .structured_content
+        .expect("Expected structured content");
+    let diff = structured["diff"].as_str().unwrap();
+    assert!(diff.contains("+# Run clippy\n+lint: build\n+    cargo clippy\n"));
+    assert_eq!(structured["written"], true);
+    assert_eq!(
+        std::fs::read_to_string(&justfile).unwrap(),
+        "build:\n    cargo build\n\n# Run clippy\nlint: build\n    cargo clippy\n"
+    );
+
+    // Existing names are refused and the file is left alone
+    let error = timeout(Duration::from_secs(10), create("build"))
+        .await
+        .expect("Create recipe timed out")
+        .expect_err("Expected duplicate recipe to be refused");
+    assert!(error.to_string().contains("already exists"));
+    assert!(
+        std::fs::read_to_string(&justfile)
+            .unwrap()
+            .starts_with("build:\n    cargo build\n\n# Run clippy")
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_update_recipe() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    let original = "# Build it\nbuild:\n    cargo build\n";
+    std::fs::write(&justfile, original).unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let update = |dry_run: bool| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("update_recipe"),
+            arguments: Some({
+                let mut map = Map::new();
+                map.insert(
+                    "recipe_name".to_string(),
+                    Value::String("build".to_string()),
+                );
+                map.insert(
+                    "body".to_string(),
+                    Value::String("cargo build --release".to_string()),
+                );
+                map.insert("dry_run".to_string(), Value::Bool(dry_run));
+                map
+            }),
+        })
+    };
+
+    // A dry run only reports the diff
+    let result = timeout(Duration::from_secs(10), update(true))
+        .await
+        .expect("Update recipe timed out")
+        .expect("Failed to update recipe");
+    let structured = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(structured["written"], false);
+    assert!(
+        structured["diff"]
+            .as_str()
+            .unwrap()
+            .contains("-    cargo build\n+    cargo build --release\n")
+    );
+    assert_eq!(std::fs::read_to_string(&justfile).unwrap(), original);
+
+    let result = timeout(Duration::from_secs(10), update(false))
+        .await
+        .expect("Update recipe timed out")
+        .expect("Failed to update recipe");
+    assert_eq!(result.structured_content.unwrap()["written"], true);
+    assert_eq!(
+        std::fs::read_to_string(&justfile).unwrap(),
+        "# Build it\nbuild:\n    cargo build --release\n"
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_delete_and_rename_recipe() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    std::fs::write(
+        &justfile,
+        "alias b := build\n\nbuild:\n    cargo build\n\ntest: build\n    cargo test\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let call = |name: &'static str, arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    // `test` depends on `build`, so deleting it needs force
+    let error = timeout(
+        Duration::from_secs(10),
+        call(
+            "delete_recipe",
+            serde_json::json!({ "recipe_name": "build" }),
+        ),
+    )
+    .await
+    .expect("Delete recipe timed out")
+    .expect_err("Expected delete to be refused");
+    assert!(error.to_string().contains("dependency of test"));
+
+    timeout(
+        Duration::from_secs(10),
+        call(
+            "rename_recipe",
+            serde_json::json!({ "recipe_name": "build", "new_name": "compile" }),
+        ),
+    )
+    .await
+    .expect("Rename recipe timed out")
+    .expect("Failed to rename recipe");
+    assert_eq!(
+        std::fs::read_to_string(&justfile).unwrap(),
+        "alias b := compile\n\ncompile:\n    cargo build\n\ntest: compile\n    cargo test\n"
+    );
+
+    timeout(
+        Duration::from_secs(10),
+        call(
+            "delete_recipe",
+            serde_json::json!({ "recipe_name": "compile", "force": true }),
+        ),
+    )
+    .await
+    .expect("Delete recipe timed out")
+    .expect("Failed to delete recipe");
+    assert_eq!(
+        std::fs::read_to_string(&justfile).unwrap(),
+        "test:\n    cargo test\n"
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_set_variable() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    std::fs::write(
+        &justfile,
+        "version := \"1.0.0\"\n\nbuild:\n    echo {{ version }}\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let call = |arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("set_variable"),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    timeout(
+        Duration::from_secs(10),
+        call(serde_json::json!({ "name": "version", "value": "1.2.0" })),
+    )
+    .await
+    .expect("Set variable timed out")
+    .expect("Failed to set variable");
+    timeout(
+        Duration::from_secs(10),
+        call(serde_json::json!({ "name": "home", "value": "env_var('HOME')", "raw": true })),
+    )
+    .await
+    .expect("Set variable timed out")
+    .expect("Failed to set variable");
+
+    assert_eq!(
+        std::fs::read_to_string(&justfile).unwrap(),
+        "version := \"1.2.0\"\nhome := env_var('HOME')\n\nbuild:\n    echo {{ version }}\n"
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_format_justfile() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    let original = "version:=\"1.0\"\nbuild  :\n  cargo build\n";
+    std::fs::write(&justfile, original).unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let format = |check_only: bool| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("format_justfile"),
+            arguments: serde_json::json!({ "check_only": check_only })
+                .as_object()
+                .cloned(),
+        })
+    };
+
+    let result = timeout(Duration::from_secs(10), format(true))
+        .await
+        .expect("Format timed out")
+        .expect("Failed to check formatting");
+    let structured = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(structured["formatted"], false);
+    assert_eq!(structured["written"], false);
+    assert!(
+        structured["diff"]
+            .as_str()
+            .unwrap()
+            .contains("-version:=\"1.0\"\n-build  :\n-  cargo build\n+version := \"1.0\"\n+\n+build:\n+    cargo build\n")
+    );
+    assert_eq!(std::fs::read_to_string(&justfile).unwrap(), original);
+
+    timeout(Duration::from_secs(10), format(false))
+        .await
+        .expect("Format timed out")
+        .expect("Failed to format");
+    assert_eq!(
+        std::fs::read_to_string(&justfile).unwrap(),
+        "version := \"1.0\"\n\nbuild:\n    cargo build\n"
+    );
+
+    let result = timeout(Duration::from_secs(10), format(true))
+        .await
+        .expect("Format timed out")
+        .expect("Failed to check formatting");
+    assert_eq!(result.structured_content.unwrap()["formatted"], true);
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_dry_run_recipe() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("dry_run_recipe"),
+            arguments: serde_json::json!({
+                "recipe_name": "deploy",
+                "args": "[\"staging\"]",
+                "justfile_path": "test-fixtures/sample.justfile",
+            })
+            .as_object()
+            .cloned(),
+        }),
+    )
+    .await
+    .expect("Dry run timed out")
+    .expect("Failed to dry run recipe");
+
+    let plan = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(
+        plan["order"],
+        serde_json::json!(["build", "build", "test", "deploy"])
+    );
+    assert_eq!(
+        plan["steps"][3]["commands"][1]["command"],
+        "./scripts/deploy.sh staging production"
+    );
+    assert_eq!(plan["shell"], serde_json::json!(["sh", "-c"]));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_explain_recipe_tool() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("explain_recipe"),
+            arguments: serde_json::json!({
+                "recipe_name": "deploy",
+                "args": "[\"staging\"]",
+                "justfile_path": "test-fixtures/sample.justfile",
+            })
+            .as_object()
+            .cloned(),
+        }),
+    )
+    .await
+    .expect("Explain recipe timed out")
+    .expect("Failed to explain recipe");
+
+    let explanation = result
+        .structured_content
+        .expect("Expected structured content");
+    assert!(
+        explanation["body"]
+            .as_str()
+            .unwrap()
+            .contains("echo \"Deploying to staging with target production\"")
+    );
+    assert_eq!(explanation["arguments"]["target"], "production");
+    assert_eq!(
+        explanation["dependencies"],
+        serde_json::json!(["build", "build", "test"])
+    );
+    assert_eq!(explanation["documentation"], "Deploy to environment");
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_search_recipes() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let search = |arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("search_recipes"),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = timeout(
+        Duration::from_secs(10),
+        search(serde_json::json!({
+            "query": "CARGO",
+            "justfile_path": "test-fixtures/sample.justfile",
+        })),
+    )
+    .await
+    .expect("Search timed out")
+    .expect("Failed to search recipes");
+    let results = result
+        .structured_content
+        .expect("Expected structured content");
+    let names: Vec<&str> = results["matches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|found| found["recipe_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["build", "test", "lint", "serve", "clean"]);
+    assert_eq!(
+        results["matches"][2]["lines"][1]["text"],
+        "cargo fmt --check"
+    );
+
+    let error = timeout(
+        Duration::from_secs(10),
+        search(serde_json::json!({
+            "query": "(",
+            "regex": true,
+            "justfile_path": "test-fixtures/sample.justfile",
+        })),
+    )
+    .await
+    .expect("Search timed out")
+    .expect_err("Expected invalid pattern to fail");
+    assert!(error.to_string().contains("Invalid search pattern"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_list_justfiles() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "build:\n    cargo build\n").unwrap();
+    for sub in ["api", "web"] {
+        std::fs::create_dir(dir.path().join(sub)).unwrap();
+    }
+    std::fs::write(
+        dir.path().join("api/justfile"),
+        "serve:\n    ./serve\n\nmigrate:\n    ./migrate\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("web/justfile"), "dev:\n    npm run dev\n").unwrap();
+    // Ignored by .gitignore
+    std::fs::create_dir(dir.path().join("vendor")).unwrap();
+    std::fs::write(dir.path().join("vendor/justfile"), "vendored:\n    true\n").unwrap();
+    std::fs::write(dir.path().join(".gitignore"), "vendor/\n").unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let call = |name: &'static str, arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call("list_justfiles", serde_json::json!({ "ignore": ["web"] })),
+    )
+    .await
+    .expect("List justfiles timed out")
+    .expect("Failed to list justfiles");
+    let list = result
+        .structured_content
+        .expect("Expected structured content");
+    let entries: Vec<(&str, u64)> = list["justfiles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| {
+            (
+                entry["id"].as_str().unwrap(),
+                entry["recipe_count"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(entries, vec![(".", 1), ("api", 2)]);
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call("list_workspace_recipes", serde_json::json!({})),
+    )
+    .await
+    .expect("List workspace recipes timed out")
+    .expect("Failed to list workspace recipes");
+    let recipes = result
+        .structured_content
+        .expect("Expected structured content");
+    let recipes: Vec<(&str, &str)> = recipes["recipes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|recipe| {
+            (
+                recipe["justfile_id"].as_str().unwrap(),
+                recipe["name"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        recipes,
+        vec![
+            (".", "build"),
+            ("api", "serve"),
+            ("api", "migrate"),
+            ("web", "dev"),
+        ]
+    );
+
+    // The id selects the justfile in other tools
+    let result = timeout(
+        Duration::from_secs(10),
+        call(
+            "list_recipes",
+            serde_json::json!({ "justfile_path": "api" }),
+        ),
+    )
+    .await
+    .expect("List recipes timed out")
+    .expect("Failed to list recipes");
+    let info = result
+        .structured_content
+        .expect("Expected structured content");
+    let names: Vec<&str> = info["recipes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|recipe| recipe["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["serve", "migrate"]);
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_start_recipe_and_poll() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "slow:\n    echo started\n    sleep 1\n    echo done\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let call = |name: &'static str, arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call("start_recipe", serde_json::json!({ "recipe_name": "slow" })),
+    )
+    .await
+    .expect("Start recipe timed out")
+    .expect("Failed to start recipe");
+    let started = result
+        .structured_content
+        .expect("Expected structured content");
+    let execution_id = started["execution_id"].as_str().unwrap().to_string();
+
+    // Poll until the recipe finishes
+    let mut status = Value::Null;
+    for _ in 0..100 {
+        let result = call(
+            "get_execution_status",
+            serde_json::json!({ "execution_id": execution_id }),
+        )
+        .await
+        .expect("Failed to get execution status");
+        status = result
+            .structured_content
+            .expect("Expected structured content");
+        if status["state"] != "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status["state"], "finished");
+    assert_eq!(status["recipe_name"], "slow");
+    assert_eq!(status["output_lines"], 2);
+    assert_eq!(status["output"][1]["line"], "done");
+
+    let result = call(
+        "get_execution_status",
+        serde_json::json!({ "execution_id": execution_id, "since": 1 }),
+    )
+    .await
+    .expect("Failed to get execution status");
+    let status = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(status["output"].as_array().unwrap().len(), 1);
+
+    let result = call(
+        "get_execution_result",
+        serde_json::json!({ "execution_id": execution_id }),
+    )
+    .await
+    .expect("Failed to get execution result");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["exit_code"], 0);
+    assert_eq!(output["stdout"], "started\n\ndone\n");
+
+    let result = call(
+        "tail_execution_log",
+        serde_json::json!({ "execution_id": execution_id, "lines": 1 }),
+    )
+    .await
+    .expect("Failed to tail execution log");
+    let tail = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(tail["text"], "done\n");
+    assert_eq!(tail["offset"], 8);
+    assert_eq!(tail["total_bytes"], 13);
+
+    let result = call(
+        "tail_execution_log",
+        serde_json::json!({ "execution_id": execution_id, "offset": 0, "length": 7 }),
+    )
+    .await
+    .expect("Failed to tail execution log");
+    assert_eq!(result.structured_content.unwrap()["text"], "started");
+
+    let error = call(
+        "get_execution_result",
+        serde_json::json!({ "execution_id": "exec-999" }),
+    )
+    .await
+    .expect_err("Expected unknown execution id to fail");
+    assert!(error.to_string().contains("Unknown execution id"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_cancel_execution() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "hang:\n    echo waiting\n    sleep 30\n    echo unreachable\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let call = |name: &'static str, arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call("start_recipe", serde_json::json!({ "recipe_name": "hang" })),
+    )
+    .await
+    .expect("Start recipe timed out")
+    .expect("Failed to start recipe");
+    let execution_id = result
+        .structured_content
+        .expect("Expected structured content")["execution_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Wait until the recipe is inside `sleep 30`
+    for _ in 0..50 {
+        let result = call(
+            "get_execution_status",
+            serde_json::json!({ "execution_id": execution_id }),
+        )
+        .await
+        .expect("Failed to get execution status");
+        if result.structured_content.unwrap()["output_lines"] == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call(
+            "cancel_execution",
+            serde_json::json!({ "execution_id": execution_id }),
+        ),
+    )
+    .await
+    .expect("Cancel timed out")
+    .expect("Failed to cancel execution");
+    let status = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(status["state"], "cancelled");
+
+    let error = call(
+        "get_execution_result",
+        serde_json::json!({ "execution_id": execution_id }),
+    )
+    .await
+    .expect_err("Expected cancelled execution to have no result");
+    assert!(error.to_string().contains("was cancelled"));
+
+    let error = call(
+        "cancel_execution",
+        serde_json::json!({ "execution_id": execution_id }),
+    )
+    .await
+    .expect_err("Expected a second cancel to fail");
+    assert!(error.to_string().contains("is not running"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_execution_history() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "deploy env:\n    echo deploying {{ env }}\n\ncheck:\n    exit 3\n\nlogin token:\n    true\n",
+    )
+    .unwrap();
+    let data_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+
+    let mut command = server_command(dir.path(), &[]);
+    command.env("MCP_DATA_DIR", data_dir.path());
+    command.env("REGISTRY_TOKEN", "tok-3f9a2c71");
+    let transport = TokioChildProcess::new(command).expect("Failed to create transport");
+    let client = ().serve(transport).await.expect("Failed to initialize client");
+
+    let call = |name: &'static str, arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    for arguments in [
+        serde_json::json!({ "recipe_name": "deploy", "args": "[\"staging\"]" }),
+        serde_json::json!({ "recipe_name": "check" }),
+        serde_json::json!({ "recipe_name": "deploy", "args": "[\"prod\"]" }),
+        serde_json::json!({ "recipe_name": "login", "args": "[\"tok-3f9a2c71\"]" }),
+    ] {
+        timeout(Duration::from_secs(10), call("run_recipe", arguments))
+            .await
+            .expect("Run recipe timed out")
+            .expect("Failed to run recipe");
+    }
+
+    let result = call(
+        "execution_history",
+        serde_json::json!({ "recipe_name": "deploy" }),
+    )
+    .await
+    .expect("Failed to get execution history");
+    let history = result
+        .structured_content
+        .expect("Expected structured content");
+    let deploys = history["executions"].as_array().unwrap();
+    assert_eq!(deploys.len(), 2);
+    // Newest first
+    assert_eq!(deploys[0]["args"], serde_json::json!(["prod"]));
+    assert_eq!(deploys[0]["status"], "succeeded");
+    assert!(deploys[0]["started_at_ms"].as_u64().unwrap() > 0);
+    assert!(
+        deploys[0]["stdout"]
+            .as_str()
+            .unwrap()
+            .contains("deploying prod")
+    );
+
+    let result = call(
+        "execution_history",
+        serde_json::json!({ "status": "failed" }),
+    )
+    .await
+    .expect("Failed to get execution history");
+    let history = result
+        .structured_content
+        .expect("Expected structured content");
+    let failed = history["executions"].as_array().unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["recipe_name"], "check");
+    assert_eq!(failed[0]["exit_code"], 3);
+
+    // Secrets passed as arguments are masked
+    let result = call(
+        "execution_history",
+        serde_json::json!({ "recipe_name": "login" }),
+    )
+    .await
+    .expect("Failed to get execution history");
+    let history = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(
+        history["executions"][0]["args"],
+        serde_json::json!(["****"])
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+
+    // The database outlives the server and is readable from the CLI
+    let output = std::process::Command::new("cargo")
+        .args([
+            "run", "--quiet", "--", "history", "--recipe", "deploy", "--json",
+        ])
+        .env("MCP_DATA_DIR", data_dir.path())
+        .output()
+        .expect("Failed to run just-mcp history");
+    assert!(output.status.success());
+    let history: Value = serde_json::from_slice(&output.stdout).expect("Expected JSON");
+    assert_eq!(history["executions"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        history["executions"][1]["args"],
+        serde_json::json!(["staging"])
+    );
+}
+
+#[tokio::test]
+async fn test_recipe_outputs_as_resources() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "build:\n    mkdir -p dist\n    echo hello > dist/app.txt\n    printf '\\000\\001' > dist/app.bin\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: serde_json::json!({ "recipe_name": "build", "outputs": ["dist/*"] })
+                .as_object()
+                .cloned(),
+        }),
+    )
+    .await
+    .expect("Run recipe timed out")
+    .expect("Failed to run recipe");
+
+    let content = serde_json::to_value(&result.content).unwrap();
+    let links: Vec<&str> = content
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|content| content["type"] == "resource_link")
+        .map(|link| link["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(links, vec!["dist/app.bin", "dist/app.txt"]);
+
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    let artifacts = output["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts[0]["mime_type"], "application/octet-stream");
+    assert_eq!(artifacts[1]["mime_type"], "text/plain");
+    assert_eq!(artifacts[1]["size"], 6);
+
+    let resources = timeout(Duration::from_secs(10), client.list_all_resources())
+        .await
+        .expect("List resources timed out")
+        .expect("Failed to list resources");
+    for artifact in artifacts {
+        let uri = artifact["uri"].as_str().unwrap();
+        assert!(resources.iter().any(|resource| resource.uri == uri));
+    }
+
+    let read = |uri: &str| {
+        client.read_resource(ReadResourceRequestParam {
+            uri: uri.to_string(),
+        })
+    };
+    let text = read(artifacts[1]["uri"].as_str().unwrap())
+        .await
+        .expect("Failed to read text artifact");
+    assert!(matches!(
+        &text.contents[0],
+        ResourceContents::TextResourceContents { text, .. } if text == "hello\n"
+    ));
+    let binary = read(artifacts[0]["uri"].as_str().unwrap())
+        .await
+        .expect("Failed to read binary artifact");
+    assert!(matches!(
+        &binary.contents[0],
+        ResourceContents::BlobResourceContents { blob, .. } if blob == "AAE="
+    ));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_get_environment() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "set dotenv-load\n\ngreet:\n    echo $GREETING\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join(".env"), "GREETING=hello\n").unwrap();
+
+    let mut command = server_command(dir.path(), &[]);
+    command
+        .env("MCP_LOG_LEVEL", "debug")
+        .env("DEPLOY_TOKEN", "hunter2")
+        .env_remove("GREETING");
+    let transport = TokioChildProcess::new(command).expect("Failed to create transport");
+    let client = ().serve(transport).await.expect("Failed to initialize client");
+
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("get_environment"),
+            arguments: serde_json::json!({ "names": ["DEPLOY_TOKEN", "GREETING"] })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .expect("Failed to get environment");
+    let report = result
+        .structured_content
+        .expect("Expected structured content");
+
+    assert_eq!(report["mcp_variables"]["MCP_LOG_LEVEL"], "debug");
+    // Secret values never leave the server
+    assert_eq!(report["variables"]["DEPLOY_TOKEN"], "****");
+    assert!(!report.to_string().contains("hunter2"));
+    assert_eq!(report["variables"]["GREETING"], Value::Null);
+
+    let dotenv = &report["dotenv"];
+    assert_eq!(dotenv["load_enabled"], true);
+    assert_eq!(dotenv["exists"], true);
+    assert_eq!(dotenv["variables"], serde_json::json!(["GREETING"]));
+    assert_eq!(
+        dotenv["missing_from_environment"],
+        serde_json::json!(["GREETING"])
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_set_session_environment() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "greet:\n    echo \"${GREETING:-unset}\"\n",
+    )
+    .unwrap();
+
+    let mut command = server_command(dir.path(), &[]);
+    command.env_remove("GREETING");
+    let transport = TokioChildProcess::new(command).expect("Failed to create transport");
+    let client = ().serve(transport).await.expect("Failed to initialize client");
+
+    let call = |name: &'static str, arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+    let greet = || async {
+        let result = timeout(
+            Duration::from_secs(10),
+            call("run_recipe", serde_json::json!({ "recipe_name": "greet" })),
+        )
+        .await
+        .expect("Run recipe timed out")
+        .expect("Failed to run recipe");
+        let output = result
+            .structured_content
+            .expect("Expected structured content");
+        output["stdout"].as_str().unwrap().trim().to_string()
+    };
+
+    let result = call(
+        "set_session_environment",
+        serde_json::json!({ "variables": { "GREETING": "hello", "API_TOKEN": "s3cret" } }),
+    )
+    .await
+    .expect("Failed to set session environment");
+    let session = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(session["variables"]["GREETING"], "hello");
+    assert_eq!(session["variables"]["API_TOKEN"], "****");
+    assert_eq!(greet().await, "hello");
+
+    let result = call(
+        "get_environment",
+        serde_json::json!({ "names": ["GREETING"] }),
+    )
+    .await
+    .expect("Failed to get environment");
+    let report = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(report["variables"]["GREETING"], "hello");
+
+    // A null value unsets the variable again
+    call(
+        "set_session_environment",
+        serde_json::json!({ "variables": { "GREETING": null } }),
+    )
+    .await
+    .expect("Failed to set session environment");
+    assert_eq!(greet().await, "unset");
+
+    let result = call(
+        "set_session_environment",
+        serde_json::json!({ "variables": { "BAD=NAME": "x" } }),
+    )
+    .await;
+    assert!(result.is_err());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_set_profile() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "where:\n    echo \"${API_URL:-unset}\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("just-mcp.toml"),
+        "[profiles.dev.env]\nAPI_URL = \"http://localhost\"\n\n[profiles.staging]\nenv-files = [\".env.staging\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join(".env.staging"),
+        "API_URL=https://staging.example.com\n",
+    )
+    .unwrap();
+
+    let mut command = server_command(dir.path(), &["--profile", "dev"]);
+    command.env_remove("API_URL");
+    let transport = TokioChildProcess::new(command).expect("Failed to create transport");
+    let client = ().serve(transport).await.expect("Failed to initialize client");
+
+    let call = |name: &'static str, arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+    let where_ = || async {
+        let result = timeout(
+            Duration::from_secs(10),
+            call("run_recipe", serde_json::json!({ "recipe_name": "where" })),
+        )
+        .await
+        .expect("Run recipe timed out")
+        .expect("Failed to run recipe");
+        let output = result
+            .structured_content
+            .expect("Expected structured content");
+        output["stdout"].as_str().unwrap().trim().to_string()
+    };
+
+    assert_eq!(where_().await, "http://localhost");
+
+    let result = call("set_profile", serde_json::json!({ "name": "staging" }))
+        .await
+        .expect("Failed to set profile");
+    let profile = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(profile["active"], "staging");
+    assert_eq!(profile["profiles"], serde_json::json!(["dev", "staging"]));
+    assert_eq!(where_().await, "https://staging.example.com");
+
+    call("set_profile", serde_json::json!({}))
+        .await
+        .expect("Failed to clear profile");
+    assert_eq!(where_().await, "unset");
+
+    let result = call("set_profile", serde_json::json!({ "name": "prod" })).await;
+    assert!(result.is_err());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_env_file_reload() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "region:\n    echo \"$DEPLOY_REGION\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("just-mcp.toml"),
+        "env-files = [\".env.mcp\"]\n",
+    )
+    .unwrap();
+    let env_file = dir.path().join(".env.mcp");
+    std::fs::write(&env_file, "DEPLOY_REGION=eu-west-1\n").unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let client = serve_client(LogCollector(tx), dir.path(), &["--watch-interval", "1"]).await;
+
+    let region = || async {
+        let result = timeout(
+            Duration::from_secs(10),
+            client.peer().call_tool(CallToolRequestParam {
+                name: Cow::Borrowed("run_recipe"),
+                arguments: serde_json::json!({ "recipe_name": "region" })
+                    .as_object()
+                    .cloned(),
+            }),
+        )
+        .await
+        .expect("Run recipe timed out")
+        .expect("Failed to run recipe");
+        let output = result
+            .structured_content
+            .expect("Expected structured content");
+        output["stdout"].as_str().unwrap().trim().to_string()
+    };
+
+    assert_eq!(region().await, "eu-west-1");
+
+    std::fs::write(&env_file, "DEPLOY_REGION=eu-central-1\n").unwrap();
+    loop {
+        let event = timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("Expected an env_reloaded log message")
+            .expect("Log channel closed");
+        if event == "env_reloaded" {
+            break;
+        }
+    }
+    assert_eq!(region().await, "eu-central-1");
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_justfile_found_in_parent_directory() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "here:\n    pwd\n").unwrap();
+    let app = dir.path().join("crates").join("app");
+    std::fs::create_dir_all(&app).unwrap();
+
+    let client = spawn_client(&app, &[]).await;
+
+    let result = timeout(
+        Duration::from_secs(10),
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: serde_json::json!({ "recipe_name": "here" })
+                .as_object()
+                .cloned(),
+        }),
+    )
+    .await
+    .expect("Run recipe timed out")
+    .expect("Failed to run recipe");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(
+        output["justfile"],
+        dir.path().join("justfile").display().to_string()
+    );
+    assert_eq!(
+        output["stdout"].as_str().unwrap().trim(),
+        dir.path().display().to_string()
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_run_recipe_args_array() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "greet name greeting=\"Hello\":\n    echo \"{{ greeting }}, {{ name }}!\"\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    // Arrays and the legacy stringified form are both accepted
+    for (args, expected) in [
+        (serde_json::json!(["Claude", "Hi"]), "Hi, Claude!"),
+        (serde_json::json!("[\"Claude\"]"), "Hello, Claude!"),
+    ] {
+        let result = timeout(
+            Duration::from_secs(10),
+            client.peer().call_tool(CallToolRequestParam {
+                name: Cow::Borrowed("run_recipe"),
+                arguments: serde_json::json!({ "recipe_name": "greet", "args": args })
+                    .as_object()
+                    .cloned(),
+            }),
+        )
+        .await
+        .expect("Run recipe timed out")
+        .expect("Failed to run recipe");
+        let output = result
+            .structured_content
+            .expect("Expected structured content");
+        assert_eq!(output["stdout"].as_str().unwrap().trim(), expected);
+    }
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_run_sequence() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "build target:\n    echo building {{ target }}\n\ncheck:\n    exit 2\n\ndeploy:\n    echo deployed\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let call = |arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_sequence"),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+    let steps = serde_json::json!([
+        { "recipe": "build", "args": ["web"] },
+        { "recipe": "check" },
+        { "recipe": "deploy" },
+    ]);
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call(serde_json::json!({ "steps": steps })),
+    )
+    .await
+    .expect("Run sequence timed out")
+    .expect("Failed to run sequence");
+    assert_eq!(result.is_error, Some(true));
+    let sequence = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(sequence["success"], false);
+    let statuses: Vec<&str> = sequence["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, vec!["succeeded", "failed", "skipped"]);
+    assert_eq!(
+        sequence["steps"][0]["output"]["stdout"]
+            .as_str()
+            .unwrap()
+            .trim(),
+        "building web"
+    );
+    assert_eq!(sequence["steps"][1]["output"]["exit_code"], 2);
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call(serde_json::json!({ "steps": steps, "stop_on_failure": false })),
+    )
+    .await
+    .expect("Run sequence timed out")
+    .expect("Failed to run sequence");
+    let sequence = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(sequence["steps"][2]["status"], "succeeded");
+
+    // Unknown recipes are reported before anything runs
+    let result = call(serde_json::json!({
+        "steps": [{ "recipe": "build", "args": ["web"] }, { "recipe": "missing" }]
+    }))
+    .await;
+    assert!(result.is_err());
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+/// Client whose model always replies with the same text.
+struct SamplingClient(&'static str);
+
+impl ClientHandler for SamplingClient {
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        let prompt = match &params.messages[0].content.raw {
+            rmcp::model::RawContent::Text(text) => text.text.clone(),
+            _ => panic!("Expected text content"),
+        };
+        assert!(prompt.contains("that runs clippy"));
+        Ok(CreateMessageResult {
+            model: "test-model".to_string(),
+            stop_reason: Some(CreateMessageResult::STOP_REASON_END_TURN.to_string()),
+            message: SamplingMessage {
+                role: Role::Assistant,
+                content: Content::text(self.0),
+            },
+        })
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities::builder().enable_sampling().build(),
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_generate_recipe() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    std::fs::write(&justfile, "build:\n    cargo build\n").unwrap();
+
+    let sampling = SamplingClient(
+        "Sure:\n\n```just\n# Run clippy\nlint: build\n  cargo clippy -- -D warnings\n```\n",
+    );
+    let client = serve_client(sampling, dir.path(), &[]).await;
+
+    let call = |arguments: Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("generate_recipe"),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call(serde_json::json!({ "goal": "runs clippy" })),
+    )
+    .await
+    .expect("Generate recipe timed out")
+    .expect("Failed to generate recipe");
+    let generated = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(generated["recipe_name"], "lint");
+    assert_eq!(generated["model"], "test-model");
+    // Re-indented to match the justfile
+    assert_eq!(
+        generated["recipe"],
+        "# Run clippy\nlint: build\n    cargo clippy -- -D warnings"
+    );
+    assert_eq!(generated["edit"]["written"], false);
+    assert_eq!(
+        std::fs::read_to_string(&justfile).unwrap(),
+        "build:\n    cargo build\n"
+    );
+
+    let result = timeout(
+        Duration::from_secs(10),
+        call(serde_json::json!({ "goal": "runs clippy", "name": "clippy", "write": true })),
+    )
+    .await
+    .expect("Generate recipe timed out")
+    .expect("Failed to generate recipe");
+    let generated = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(generated["edit"]["written"], true);
+    assert!(
+        std::fs::read_to_string(&justfile)
+            .unwrap()
+            .ends_with("\n\n# Run clippy\nclippy: build\n    cargo clippy -- -D warnings\n")
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_generate_recipe_requires_sampling() {
+    let client = spawn_client(Path::new("."), &[]).await;
+
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("generate_recipe"),
+            arguments: serde_json::json!({ "goal": "runs clippy" })
+                .as_object()
+                .cloned(),
+        })
+        .await;
+    let error = result.expect_err("Expected an error without sampling");
+    assert!(error.to_string().contains("does not support sampling"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_document_justfile() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "# Build it\n[group('dev')]\nbuild:\n    cargo build\n\n# Ship it\ndeploy env: build\n    ./deploy {{ env }}\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("document_justfile"),
+            arguments: serde_json::json!({ "write": true }).as_object().cloned(),
+        })
+        .await
+        .expect("Failed to document justfile");
+    let docs = result
+        .structured_content
+        .expect("Expected structured content");
+    let markdown = docs["markdown"].as_str().unwrap();
+    assert!(markdown.contains("### dev\n"));
+    assert!(markdown.contains("```mermaid\n"));
+    assert!(markdown.contains("just deploy <env>"));
+
+    let docs_path = dir.path().join("JUSTFILE.md");
+    assert_eq!(docs["written_to"], docs_path.display().to_string());
+    assert_eq!(std::fs::read_to_string(docs_path).unwrap(), markdown);
+
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("document_justfile"),
+            arguments: serde_json::json!({ "format": "html" }).as_object().cloned(),
+        })
+        .await
+        .expect("Failed to document justfile");
+    let docs = result
+        .structured_content
+        .expect("Expected structured content");
+    assert!(docs.get("markdown").is_none());
+    assert!(docs.get("written_to").is_none());
+    let html = docs["html"].as_str().unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h3>dev</h3>\n"));
+    assert!(html.contains("<pre><code>just deploy &lt;env&gt;</code></pre>"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_import_makefile() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("Makefile"),
+        "NAME = world\n\n.PHONY: greet\n\n# Say hello\ngreet: prepare\n\techo hello $(NAME)\n\nprepare:\n\t@echo preparing\n\n%.o: %.c\n\tcc -c $<\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = call("import_makefile", serde_json::json!({ "write": true }))
+        .await
+        .expect("Failed to import Makefile");
+    let imported = result
+        .structured_content
+        .expect("Expected structured content");
+    let justfile = imported["justfile"].as_str().unwrap();
+    assert!(justfile.contains("NAME := \"world\"\n"));
+    assert!(justfile.contains("# Say hello\ngreet: prepare\n    echo hello {{ NAME }}\n"));
+    assert_eq!(imported["untranslated"][0]["text"], "%.o: %.c");
+
+    let justfile_path = dir.path().join("justfile");
+    assert_eq!(imported["written_to"], justfile_path.display().to_string());
+
+    // The written draft is the justfile the server now uses
+    let result = call("run_recipe", serde_json::json!({ "recipe_name": "greet" }))
+        .await
+        .expect("Failed to run imported recipe");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert!(output["stdout"].as_str().unwrap().contains("hello world"));
+
+    // An existing justfile is never overwritten
+    let error = call("import_makefile", serde_json::json!({ "write": true }))
+        .await
+        .expect_err("Expected an existing justfile to be kept");
+    assert!(error.to_string().contains("already exists"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_init_justfile() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"demo\"\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = call("init_justfile", serde_json::json!({}))
+        .await
+        .expect("Failed to scaffold justfile");
+    let scaffolded = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(scaffolded["project_type"], "rust");
+    assert_eq!(scaffolded["detected"], serde_json::json!(["rust"]));
+    assert_eq!(scaffolded["overwritten"], false);
+    let justfile_path = dir.path().join("justfile");
+    assert_eq!(
+        std::fs::read_to_string(&justfile_path).unwrap(),
+        scaffolded["justfile"].as_str().unwrap()
+    );
+
+    let result = call("list_recipes", serde_json::json!({}))
+        .await
+        .expect("Failed to list recipes");
+    let info = result
+        .structured_content
+        .expect("Expected structured content");
+    let names: Vec<&str> = info["recipes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|recipe| recipe["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"build") && names.contains(&"test"));
+
+    // Existing justfiles are kept unless forced
+    let error = call("init_justfile", serde_json::json!({ "project_type": "go" }))
+        .await
+        .expect_err("Expected the existing justfile to be kept");
+    assert!(error.to_string().contains("already exists"));
+
+    let result = call(
+        "init_justfile",
+        serde_json::json!({ "project_type": "go", "force": true }),
+    )
+    .await
+    .expect("Failed to replace justfile");
+    let scaffolded = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(scaffolded["overwritten"], true);
+    assert!(
+        std::fs::read_to_string(&justfile_path)
+            .unwrap()
+            .contains("go test ./...")
+    );
+
+    // Paths outside the working directory and the client's roots are refused
+    let outside = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let outside_justfile = outside.path().join("justfile");
+    std::fs::write(&outside_justfile, "build:\n    echo build\n").unwrap();
+    let error = call(
+        "init_justfile",
+        serde_json::json!({ "directory": outside.path(), "force": true }),
+    )
+    .await
+    .expect_err("Expected an absolute directory outside the roots to be refused");
+    assert!(
+        error
+            .to_string()
+            .contains("is outside the working directory")
+    );
+    let relative = Path::new("..")
+        .join(outside.path().file_name().unwrap())
+        .join("justfile");
+    let error = call(
+        "update_recipe",
+        serde_json::json!({
+            "justfile_path": relative,
+            "recipe_name": "build",
+            "body": "echo replaced",
+        }),
+    )
+    .await
+    .expect_err("Expected a .. path outside the roots to be refused");
+    assert!(
+        error
+            .to_string()
+            .contains("is outside the working directory")
+    );
+    assert_eq!(
+        std::fs::read_to_string(&outside_justfile).unwrap(),
+        "build:\n    echo build\n"
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_list_recipes_pagination() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "[group('test')]\nunit:\n    echo unit\n\nbuild:\n    echo build\n\n[group('deploy')]\nship:\n    echo ship\n\n[group('deploy')]\nrelease:\n    echo release\n\nclean:\n    echo clean\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+    let list = |arguments: serde_json::Value| {
+        let peer = client.peer().clone();
+        async move {
+            peer.call_tool(CallToolRequestParam {
+                name: Cow::Borrowed("list_recipes"),
+                arguments: arguments.as_object().cloned(),
+            })
+            .await
+            .map(|result| {
+                result
+                    .structured_content
+                    .expect("Expected structured content")
+            })
+        }
+    };
+
+    let first = list(serde_json::json!({ "page_size": 2, "sort": "name" }))
+        .await
+        .expect("Failed to list first page");
+    let names: Vec<&str> = first["recipes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|recipe| recipe["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["build", "clean"]);
+    assert_eq!(first["total_recipes"], 5);
+    assert_eq!(first["next_cursor"], "2");
+
+    let last = list(serde_json::json!({
+        "cursor": "4",
+        "page_size": 2,
+        "sort": "name",
+        "names_only": true,
+    }))
+    .await
+    .expect("Failed to list last page");
+    assert_eq!(last["names"], serde_json::json!(["unit"]));
+    assert_eq!(last["recipes"], serde_json::json!([]));
+    assert!(last.get("next_cursor").is_none());
+
+    let grouped = list(serde_json::json!({ "sort": "group", "names_only": true }))
+        .await
+        .expect("Failed to list by group");
+    assert_eq!(
+        grouped["names"],
+        serde_json::json!(["release", "ship", "unit", "build", "clean"])
+    );
+    assert!(grouped.get("next_cursor").is_none());
+
+    let error = list(serde_json::json!({ "cursor": "bogus" }))
+        .await
+        .expect_err("Expected an invalid cursor to fail");
+    assert!(error.to_string().contains("Invalid cursor"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_set_working_directory() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    for (project, recipe) in [("api", "serve"), ("web", "bundle")] {
+        std::fs::create_dir(dir.path().join(project)).unwrap();
+        std::fs::write(
+            dir.path().join(project).join("justfile"),
+            format!("{recipe}:\n    echo {recipe}\n"),
+        )
+        .unwrap();
+    }
+    let root = dir.path().canonicalize().unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+    let switch = |path: &str| call("set_working_directory", serde_json::json!({ "path": path }));
+    let recipe_names = |result: rmcp::model::CallToolResult| -> Vec<String> {
+        let info = result
+            .structured_content
+            .expect("Expected structured content");
+        info["recipes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|recipe| recipe["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let result = switch("api").await.expect("Failed to switch to api");
+    let switched = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(switched["path"], root.join("api").display().to_string());
+    assert_eq!(
+        switched["justfile"],
+        root.join("api/justfile").display().to_string()
+    );
+    let result = call("list_recipes", serde_json::json!({}))
+        .await
+        .expect("Failed to list api recipes");
+    assert_eq!(recipe_names(result), vec!["serve"]);
+
+    // Relative paths resolve against the current directory
+    switch("../web").await.expect("Failed to switch to web");
+    let result = call("list_recipes", serde_json::json!({}))
+        .await
+        .expect("Failed to list web recipes");
+    assert_eq!(recipe_names(result), vec!["bundle"]);
+
+    // Directories outside the allowed roots are refused
+    let error = switch("../..")
+        .await
+        .expect_err("Expected a directory outside the roots to be refused");
+    assert!(error.to_string().contains("not a directory inside"));
+    let error = switch("missing")
+        .await
+        .expect_err("Expected a missing directory to be refused");
+    assert!(error.to_string().contains("not a directory inside"));
+
+    let result = call("set_working_directory", serde_json::json!({}))
+        .await
+        .expect("Failed to reset working directory");
+    let reset = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(reset["justfile"], serde_json::Value::Null);
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_recipe_policy() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "build:\n    echo building\n\ndeploy:\n    echo deploying\n\nrelease: deploy\n    echo released\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &["--deny-recipes", "deploy*"]).await;
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = call("list_recipes", serde_json::json!({}))
+        .await
+        .expect("Failed to list recipes");
+    let info = result
+        .structured_content
+        .expect("Expected structured content");
+    let blocked: Vec<(&str, bool)> = info["recipes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|recipe| {
+            (
+                recipe["name"].as_str().unwrap(),
+                recipe["blocked"].as_bool().unwrap_or(false),
+            )
+        })
+        .collect();
+    assert_eq!(
+        blocked,
+        vec![("build", false), ("deploy", true), ("release", true)]
+    );
+
+    let result = call("run_recipe", serde_json::json!({ "recipe_name": "build" }))
+        .await
+        .expect("Failed to run build");
+    assert_eq!(result.is_error, Some(false));
+
+    let error = call("run_recipe", serde_json::json!({ "recipe_name": "deploy" }))
+        .await
+        .expect_err("Expected deploy to be blocked");
+    assert!(
+        error
+            .to_string()
+            .contains("blocked by the server's recipe policy")
+    );
+    // Blocked through its dependency
+    let error = call(
+        "start_recipe",
+        serde_json::json!({ "recipe_name": "release" }),
+    )
+    .await
+    .expect_err("Expected release to be blocked");
+    assert!(error.to_string().contains("it depends on 'deploy'"));
+
+    let tools = client
+        .peer()
+        .list_all_tools()
+        .await
+        .expect("Failed to list tools");
+    assert!(tools.iter().any(|tool| tool.name == "just_build"));
+    assert!(!tools.iter().any(|tool| tool.name == "just_deploy"));
+    assert!(!tools.iter().any(|tool| tool.name == "update_recipe"));
+
+    // Edits could get around the policy, so they are refused
+    let source = std::fs::read_to_string(dir.path().join("justfile")).unwrap();
+    let error = call(
+        "rename_recipe",
+        serde_json::json!({ "recipe_name": "deploy", "new_name": "ship" }),
+    )
+    .await
+    .expect_err("Expected renaming deploy to be refused");
+    assert!(
+        error
+            .to_string()
+            .contains("disabled while a recipe policy is set")
+    );
+    let error = call(
+        "update_recipe",
+        serde_json::json!({ "recipe_name": "build", "body": "echo deploying" }),
+    )
+    .await
+    .expect_err("Expected updating build to be refused");
+    assert!(
+        error
+            .to_string()
+            .contains("disabled while a recipe policy is set")
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("justfile")).unwrap(),
+        source
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_project_config_file() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "greet:\n    echo \"$GREETING from $SOURCE\"\n\ndeploy:\n    echo deploying\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join(".env.mcp"), "GREETING=hi\nSOURCE=file\n").unwrap();
+    std::fs::write(
+        dir.path().join("just-mcp.toml"),
+        "env-files = [\".env.mcp\"]\n\n[env]\nSOURCE = \"config\"\n\n[recipes]\ndeny = [\"deploy\"]\n",
+    )
+    .unwrap();
+
+    let client = spawn_client(dir.path(), &[]).await;
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = call("run_recipe", serde_json::json!({ "recipe_name": "greet" }))
+        .await
+        .expect("Failed to run greet");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"], "hi from config\n");
+
+    let error = call("run_recipe", serde_json::json!({ "recipe_name": "deploy" }))
+        .await
+        .expect_err("Expected deploy to be denied by the config");
+    assert!(error.to_string().contains("blocked"));
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_explicit_justfile() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::create_dir(dir.path().join("ci")).unwrap();
+    let justfile = dir.path().join("ci").join("build.just");
+    std::fs::write(&justfile, "where:\n    basename \"$PWD\"\n").unwrap();
+    // Not the default justfile candidate in the working directory
+    std::fs::write(dir.path().join("justfile"), "other:\n    echo other\n").unwrap();
+
+    let client = spawn_client(dir.path(), &["--justfile", justfile.to_str().unwrap()]).await;
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let result = call("list_recipes", serde_json::json!({ "names_only": true }))
+        .await
+        .expect("Failed to list recipes");
+    let info = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(info["names"], serde_json::json!(["where"]));
+    assert_eq!(info["path"], justfile.display().to_string());
+
+    // Recipes run in the justfile's directory
+    let result = call("run_recipe", serde_json::json!({ "recipe_name": "where" }))
+        .await
+        .expect("Failed to run where");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"], "ci\n");
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_multiple_project_roots() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    for (project, recipe) in [("api", "serve"), ("web", "bundle")] {
+        std::fs::create_dir(dir.path().join(project)).unwrap();
+        std::fs::write(
+            dir.path().join(project).join("justfile"),
+            format!("{recipe}:\n    echo {project} {recipe}\n"),
+        )
+        .unwrap();
+    }
+
+    let web = dir.path().join("web");
+    let client = spawn_client(
+        &dir.path().join("api"),
+        &["--directory", web.to_str().unwrap()],
+    )
+    .await;
+    let call = |name: &'static str, arguments: serde_json::Value| {
+        client.peer().call_tool(CallToolRequestParam {
+            name: Cow::Borrowed(name),
+            arguments: arguments.as_object().cloned(),
+        })
+    };
+
+    let tools = client
+        .peer()
+        .list_all_tools()
+        .await
+        .expect("Failed to list tools");
+    let recipe_tools: Vec<&str> = tools
+        .iter()
+        .map(|tool| tool.name.as_ref())
+        .filter(|name| name.starts_with("just_"))
+        .collect();
+    assert_eq!(recipe_tools, vec!["just_api__serve", "just_web__bundle"]);
+
+    let result = call("just_web__bundle", serde_json::json!({}))
+        .await
+        .expect("Failed to run the web bundle tool");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"], "web bundle\n");
+
+    // Root names work as justfile_path
+    let result = call(
+        "run_recipe",
+        serde_json::json!({ "recipe_name": "serve", "justfile_path": "api" }),
+    )
+    .await
+    .expect("Failed to run api serve");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"], "api serve\n");
+
+    let result = call("list_justfiles", serde_json::json!({}))
+        .await
+        .expect("Failed to list justfiles");
+    let list = result
+        .structured_content
+        .expect("Expected structured content");
+    let ids: Vec<&str> = list["justfiles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec!["api", "web"]);
+
+    let resources = client
+        .peer()
+        .list_all_resources()
+        .await
+        .expect("Failed to list resources");
+    let names: Vec<&str> = resources
+        .iter()
+        .map(|resource| resource.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["api/justfile", "web/justfile"]);
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+}
+
+#[tokio::test]
+async fn test_list_subcommand() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "# Deploy the app\ndeploy env port=\"8080\":\n    echo {{ env }} {{ port }}\n",
+    )
+    .unwrap();
+    let list = |json: bool| {
+        let mut cmd = std::process::Command::new("cargo");
+        cmd.args(["run", "--quiet", "--", "list", "--directory"]);
+        cmd.arg(dir.path());
+        if json {
+            cmd.arg("--json");
+        }
+        let output = cmd.output().expect("Failed to run just-mcp list");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        list(false),
+        "RECIPE  PARAMETERS         DESCRIPTION\ndeploy  <env> [port=8080]  Deploy the app\n"
+    );
+    let info: Value = serde_json::from_str(&list(true)).expect("Expected JSON");
+    assert_eq!(info["total_recipes"], 1);
+    assert_eq!(info["recipes"][0]["name"], "deploy");
+    assert_eq!(info["recipes"][0]["parameters"][1]["default_value"], "8080");
+
+    // Offline subcommands ignore the MCP_* settings the server checks
+    let data_dir = dir.path().join("data");
+    let output = std::process::Command::new("cargo")
+        .args(["run", "--quiet", "--", "list", "--directory"])
+        .arg(dir.path())
+        .env("MCP_TIMEOUT_SECONDS", "soon")
+        .env("MCP_DATA_DIR", &data_dir)
+        .output()
+        .expect("Failed to run just-mcp list");
+    assert!(output.status.success());
+    assert!(!data_dir.exists());
+}
+
+#[tokio::test]
+async fn test_run_subcommand() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "greet name:\n    echo hello {{ name }}\n\nfail:\n    exit 3\n",
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let mut cmd = std::process::Command::new("cargo");
+        cmd.args(["run", "--quiet", "--", "--directory"]);
+        cmd.arg(dir.path());
+        cmd.arg("run");
+        cmd.args(args);
+        cmd.output().expect("Failed to run just-mcp run")
+    };
+
+    let output = run(&["greet", "bob"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello bob\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("echo hello bob"));
+
+    // The recipe's exit code becomes just-mcp's
+    assert_eq!(run(&["fail"]).status.code(), Some(3));
+    assert!(!run(&["missing"]).status.success());
+}
+
+#[tokio::test]
+async fn test_validate_subcommand() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    let validate = |args: &[&str]| {
+        let mut cmd = std::process::Command::new("cargo");
+        cmd.args(["run", "--quiet", "--", "validate"]);
+        cmd.arg(&justfile);
+        cmd.args(args);
+        cmd.output().expect("Failed to run just-mcp validate")
+    };
+
+    std::fs::write(&justfile, "# Build it\nbuild:\n    cargo build\n").unwrap();
+    let output = validate(&[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(0 errors, 0 warnings)"));
+
+    std::fs::write(&justfile, "# Build it\nbuild: missing\n    cargo build\n").unwrap();
+    let output = validate(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains(": error[unknown-dependency]: "), "{text}");
+
+    let output = validate(&["--json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let report: Value = serde_json::from_slice(&output.stdout).expect("Expected JSON");
+    assert_eq!(report["is_valid"], false);
+    assert_eq!(report["summary"]["error_count"], 1);
+}
+
+#[tokio::test]
+async fn test_fmt_subcommand() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let justfile = dir.path().join("justfile");
+    let fmt = |args: &[&str]| {
+        let mut cmd = std::process::Command::new("cargo");
+        cmd.args(["run", "--quiet", "--", "fmt"]);
+        cmd.args(args);
+        cmd.arg(dir.path());
+        cmd.output().expect("Failed to run just-mcp fmt")
+    };
+
+    let original = "version:=\"1.0\"\nbuild  :\n  cargo build\n";
+    std::fs::write(&justfile, original).unwrap();
+    let output = fmt(&["--check"]);
+    assert_eq!(output.status.code(), Some(1));
+    let diff = String::from_utf8_lossy(&output.stdout);
+    assert!(diff.contains("+version := \"1.0\"\n"), "{diff}");
+    assert_eq!(std::fs::read_to_string(&justfile).unwrap(), original);
+
+    let output = fmt(&[]);
+    assert!(output.status.success());
+    let formatted = "version := \"1.0\"\n\nbuild:\n    cargo build\n";
+    assert_eq!(std::fs::read_to_string(&justfile).unwrap(), formatted);
+
+    let output = fmt(&["--check"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[tokio::test]
+async fn test_docs_subcommand() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "# Build it\nbuild:\n    cargo build\n\n[private]\n_clean:\n    rm -rf target\n",
+    )
+    .unwrap();
+    let docs = |args: &[&str]| {
+        let mut cmd = std::process::Command::new("cargo");
+        cmd.args(["run", "--quiet", "--", "docs"]);
+        cmd.arg(dir.path());
+        cmd.args(args);
+        cmd.output().expect("Failed to run just-mcp docs")
+    };
+
+    let output = docs(&[]);
+    assert!(output.status.success());
+    let markdown = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        markdown.contains("| [`build`](#build) | Build it |"),
+        "{markdown}"
+    );
+    assert!(!markdown.contains("_clean"));
+
+    let html_path = dir.path().join("recipes.html");
+    let output = docs(&[
+        "--format",
+        "html",
+        "--include-private",
+        "--output",
+        html_path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let html = std::fs::read_to_string(html_path).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<section id=\"_clean\">"));
+}
+
+/// Client that answers elicitation requests with fixed content, or declines
+/// them when there is none.
+struct ElicitingClient(Option<Value>);
+
+impl ClientHandler for ElicitingClient {
+    async fn create_elicitation(
+        &self,
+        request: CreateElicitationRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateElicitationResult, ErrorData> {
+        assert!(
+            request
+                .message
+                .contains("Recipe `greet` needs: name, greeting")
+        );
+        assert!(request.message.contains("Greet someone"));
+        let schema = Value::Object(request.requested_schema);
+        assert_eq!(schema["required"], serde_json::json!(["name"]));
+        assert_eq!(schema["properties"]["greeting"]["default"], "hello");
+        Ok(CreateElicitationResult {
+            action: match self.0 {
+                Some(_) => ElicitationAction::Accept,
+                None => ElicitationAction::Decline,
+            },
+            content: self.0.clone(),
+        })
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            // Built from JSON since the capability's builder differs between rmcp versions
+            capabilities: serde_json::from_value(serde_json::json!({ "elicitation": {} })).unwrap(),
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_run_recipe_elicits_missing_parameters() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(
+        dir.path().join("justfile"),
+        "# Greet someone\ngreet name greeting=\"hello\":\n    echo {{ greeting }} {{ name }}\n",
+    )
+    .unwrap();
+
+    for (content, expected) in [
+        (
+            Some(serde_json::json!({ "name": "Claude" })),
+            Ok("hello Claude"),
+        ),
+        (None, Err("declined")),
+    ] {
+        let client = serve_client(
+            ElicitingClient(content),
+            dir.path(),
+            &["--elicit-parameters"],
+        )
+        .await;
+
+        let result = client
+            .peer()
+            .call_tool(CallToolRequestParam {
+                name: Cow::Borrowed("run_recipe"),
+                arguments: serde_json::json!({ "recipe_name": "greet" })
+                    .as_object()
+                    .cloned(),
+            })
+            .await;
+        match expected {
+            Ok(stdout) => {
+                let output = result
+                    .expect("Failed to run recipe")
+                    .structured_content
+                    .expect("Expected structured content");
+                assert_eq!(output["stdout"].as_str().unwrap().trim(), stdout);
+            }
+            Err(message) => {
+                let error = result.expect_err("Expected the declined run to fail");
+                assert!(error.to_string().contains(message));
+            }
+        }
+
+        // Cleanup
+        client.cancel().await.expect("Failed to cancel client");
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_socket_sessions() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "hello:\n    echo hello\n").unwrap();
+    let socket = dir.path().join("just-mcp.sock");
+
+    let mut server = tokio::process::Command::new("cargo")
+        .args(["run", "--", "--directory"])
+        .arg(dir.path())
+        .arg("--socket")
+        .arg(&socket)
+        .kill_on_drop(true)
+        .spawn()
+        .expect("Failed to start server");
+    // Building the server can take a while
+    timeout(Duration::from_secs(120), async {
+        while !socket.exists() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("Server did not create the socket");
+
+    let connect = || async {
+        let stream = tokio::net::UnixStream::connect(&socket)
+            .await
+            .expect("Failed to connect to socket");
+        ().serve(stream).await.expect("Failed to initialize client")
+    };
+    let editor = connect().await;
+    let agent = connect().await;
+
+    editor
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("set_session_environment"),
+            arguments: serde_json::json!({ "variables": { "JUST_MCP_CLIENT": "editor" } })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .expect("Failed to set session environment");
+
+    // Each connection has its own session
+    for (client, expected) in [
+        (&editor, serde_json::json!("editor")),
+        (&agent, Value::Null),
+    ] {
+        let result = client
+            .peer()
+            .call_tool(CallToolRequestParam {
+                name: Cow::Borrowed("get_environment"),
+                arguments: serde_json::json!({ "names": ["JUST_MCP_CLIENT"] })
+                    .as_object()
+                    .cloned(),
+            })
+            .await
+            .expect("Failed to get environment");
+        let report = result
+            .structured_content
+            .expect("Expected structured content");
+        assert_eq!(report["variables"]["JUST_MCP_CLIENT"], expected);
+    }
+
+    let result = agent
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: serde_json::json!({ "recipe_name": "hello" })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .expect("Failed to run recipe over the socket");
+    assert_eq!(result.is_error, Some(false));
+
+    // Cleanup
+    editor.cancel().await.expect("Failed to cancel client");
+    agent.cancel().await.expect("Failed to cancel client");
+    server.kill().await.expect("Failed to stop server");
+}
+
+#[tokio::test]
+async fn test_tcp_transport() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "hello:\n    echo hello\n").unwrap();
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("Failed to find a free port");
+
+    let mut server = tokio::process::Command::new("cargo")
+        .args(["run", "--", "--directory"])
+        .arg(dir.path())
+        .args(["serve", "--transport", "tcp", "--bind"])
+        .arg(address.to_string())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("Failed to start server");
+    // Building the server can take a while
+    let stream = timeout(Duration::from_secs(120), async {
+        loop {
+            match tokio::net::TcpStream::connect(address).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+            }
+        }
+    })
+    .await
+    .expect("Server did not listen on the address");
+
+    let client = ().serve(stream).await.expect("Failed to initialize client");
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: serde_json::json!({ "recipe_name": "hello" })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .expect("Failed to run recipe over TCP");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"].as_str().unwrap().trim(), "hello");
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+    server.kill().await.expect("Failed to stop server");
+}
+
+#[test]
+fn test_tcp_transport_refuses_auth_token() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "hello:\n    echo hello\n").unwrap();
+
+    // A token would protect nothing: TCP has no header to carry it
+    let output = std::process::Command::new("cargo")
+        .args(["run", "--quiet", "--", "--directory"])
+        .arg(dir.path())
+        .args(["serve", "--transport", "tcp", "--bind", "0.0.0.0:0"])
+        .env("MCP_AUTH_TOKEN", "s3cret-token")
+        .output()
+        .expect("Failed to run just-mcp serve");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("the tcp transport cannot check the auth token"),
+        "{stderr}"
+    );
+}
+
+/// Wait until a server started with `cargo run` accepts connections on
+/// `address`.
+async fn wait_for_listener(address: std::net::SocketAddr) {
+    // Building the server can take a while
+    timeout(Duration::from_secs(120), async {
+        while tokio::net::TcpStream::connect(address).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("Server did not listen on the address");
+}
+
+/// Start `serve --transport <transport>` on a free port.
+fn spawn_http_server(
+    dir: &std::path::Path,
+    transport: &str,
+    extra_args: &[&str],
+) -> (tokio::process::Child, std::net::SocketAddr) {
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("Failed to find a free port");
+    let server = tokio::process::Command::new("cargo")
+        .args(["run", "--", "--directory"])
+        .arg(dir)
+        .args(["serve", "--transport", transport, "--bind"])
+        .arg(address.to_string())
+        .args(extra_args)
+        .kill_on_drop(true)
+        .spawn()
+        .expect("Failed to start server");
+    (server, address)
+}
+
+/// The status line of a plain `GET path` on `address`.
+async fn http_status_line(address: std::net::SocketAddr, path: &str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::TcpStream::connect(address)
+        .await
+        .expect("Failed to connect");
+    stream
+        .write_all(
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .as_bytes(),
+        )
+        .await
+        .expect("Failed to send request");
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .await
+        .expect("Failed to read response");
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+#[tokio::test]
+async fn test_streamable_http_transport() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "hello:\n    echo hello\n").unwrap();
+    let (mut server, address) = spawn_http_server(dir.path(), "http", &[]);
+    wait_for_listener(address).await;
+
+    let transport =
+        rmcp::transport::StreamableHttpClientTransport::from_uri(format!("http://{address}/mcp"));
+    let client = ().serve(transport).await.expect("Failed to initialize client");
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: serde_json::json!({ "recipe_name": "hello" })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .expect("Failed to run recipe over HTTP");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"].as_str().unwrap().trim(), "hello");
+
+    // The probes share the listener
+    assert_eq!(
+        http_status_line(address, "/healthz").await,
+        "HTTP/1.1 200 OK"
+    );
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+    server.kill().await.expect("Failed to stop server");
+}
+
+#[tokio::test]
+async fn test_sse_transport() {
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "hello:\n    echo hello\n").unwrap();
+    let (mut server, address) = spawn_http_server(dir.path(), "sse", &[]);
+    wait_for_listener(address).await;
+
+    let transport = rmcp::transport::SseClientTransport::start(format!("http://{address}/sse"))
+        .await
+        .expect("Failed to open the event stream");
+    let client = ().serve(transport).await.expect("Failed to initialize client");
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: serde_json::json!({ "recipe_name": "hello" })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .expect("Failed to run recipe over SSE");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"].as_str().unwrap().trim(), "hello");
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+    server.kill().await.expect("Failed to stop server");
+}
+
+#[tokio::test]
+async fn test_http_transport_auth_token() {
+    use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
+
+    let dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("justfile"), "hello:\n    echo hello\n").unwrap();
+    let (mut server, address) =
+        spawn_http_server(dir.path(), "http", &["--auth-token", "s3cret-token"]);
+    wait_for_listener(address).await;
+    let uri = format!("http://{address}/mcp");
+
+    // Refused without the token, or with the wrong one
+    let transport = rmcp::transport::StreamableHttpClientTransport::from_uri(uri.clone());
+    assert!(().serve(transport).await.is_err());
+    let transport = rmcp::transport::StreamableHttpClientTransport::from_config(
+        StreamableHttpClientTransportConfig::with_uri(uri.clone()).auth_header("guess"),
+    );
+    assert!(().serve(transport).await.is_err());
+    assert_eq!(
+        http_status_line(address, "/healthz").await,
+        "HTTP/1.1 200 OK"
+    );
+
+    let transport = rmcp::transport::StreamableHttpClientTransport::from_config(
+        StreamableHttpClientTransportConfig::with_uri(uri).auth_header("s3cret-token"),
+    );
+    let client = ().serve(transport).await.expect("Failed to initialize client");
+    let result = client
+        .peer()
+        .call_tool(CallToolRequestParam {
+            name: Cow::Borrowed("run_recipe"),
+            arguments: serde_json::json!({ "recipe_name": "hello" })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .expect("Failed to run recipe with the token");
+    let output = result
+        .structured_content
+        .expect("Expected structured content");
+    assert_eq!(output["stdout"].as_str().unwrap().trim(), "hello");
+
+    // Cleanup
+    client.cancel().await.expect("Failed to cancel client");
+    server.kill().await.expect("Failed to stop server");
+}
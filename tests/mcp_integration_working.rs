@@ -22,7 +22,7 @@ async fn test_mcp_server_integration_working() {
     // Create transport for our MCP server as child process
     let transport =
         TokioChildProcess::new(tokio::process::Command::new("cargo").configure(|cmd| {
-            cmd.args(["run", "--", "--stdio"]);
+            cmd.args(["run", "--", "serve"]);
         }))
         .expect("Failed to create transport");
 
@@ -166,7 +166,7 @@ async fn test_get_recipe_info_working() {
     // Create transport and client
     let transport =
         TokioChildProcess::new(tokio::process::Command::new("cargo").configure(|cmd| {
-            cmd.args(["run", "--", "--stdio"]);
+            cmd.args(["run", "--", "serve"]);
         }))
         .expect("Failed to create transport");
 
@@ -1,4 +1,5 @@
-use just_mcp_lib::mcp_server::JustMcpServer;
+use just_mcp_lib::mcp_server::{JustMcpServer, recipe_annotations, recipe_input_schema};
+use just_mcp_lib::parser::parse_justfile_str;
 use rmcp::ServerHandler;
 use std::path::PathBuf;
 
@@ -25,3 +26,61 @@ fn test_mcp_server_with_working_directory() {
     let info = server.get_info();
     assert!(info.instructions.is_some());
 }
+
+#[test]
+fn test_recipe_input_schema() {
+    let justfile = parse_justfile_str(
+        "# @param port: int\nserve host port=\"8080\":\n    ./serve {{ host }} {{ port }}\n",
+    )
+    .unwrap();
+
+    let schema = recipe_input_schema(&justfile.recipes[0]);
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["properties"]["host"]["type"], "string");
+    assert_eq!(schema["properties"]["port"]["type"], "integer");
+    assert_eq!(schema["properties"]["port"]["default"], 8080);
+    assert_eq!(schema["required"], serde_json::json!(["host"]));
+
+    let justfile = parse_justfile_str(
+        "# @param debug: bool\n# @param ratio: float\ntune debug=\"false\" ratio=\"0.5\":\n    ./tune\n",
+    )
+    .unwrap();
+    let schema = recipe_input_schema(&justfile.recipes[0]);
+    assert_eq!(schema["properties"]["debug"]["default"], false);
+    assert_eq!(schema["properties"]["ratio"]["default"], 0.5);
+}
+
+#[test]
+fn test_recipe_annotations() {
+    let justfile = parse_justfile_str(
+        "[confirm]\ndeploy:\n    ./deploy\n\nclean:\n    rm -rf ~/.cache/app\n\nbuild:\n    cargo build\n",
+    )
+    .unwrap();
+
+    let hints: Vec<Option<bool>> = justfile
+        .recipes
+        .iter()
+        .map(|recipe| recipe_annotations(recipe).destructive_hint)
+        .collect();
+    assert_eq!(hints, vec![Some(true), Some(true), Some(false)]);
+    assert_eq!(
+        recipe_annotations(&justfile.recipes[2]).read_only_hint,
+        Some(false)
+    );
+}
+
+#[test]
+fn test_redactor_masks_config_secrets() {
+    let server = JustMcpServer::new(std::env::temp_dir())
+        .with_secret_patterns(vec!["*_TOKEN".to_string()])
+        .with_environment(std::collections::BTreeMap::from([
+            ("DEPLOY_TOKEN".to_string(), "tok-123456".to_string()),
+            ("REGION".to_string(), "eu-west-1".to_string()),
+        ]));
+    assert_eq!(
+        server
+            .redactor()
+            .redact("deploying to eu-west-1 with tok-123456"),
+        "deploying to eu-west-1 with ****"
+    );
+}
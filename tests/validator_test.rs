@@ -10,6 +10,7 @@ fn create_test_recipe(name: &str, params: Vec<Parameter>, doc: Option<&str>) ->
         documentation: doc.map(|s| s.to_string()),
         body: String::new(),
         dependencies: Vec::new(),
+        ..Default::default()
     }
 }
 